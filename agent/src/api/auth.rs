@@ -781,6 +781,8 @@ mod tests {
             observations: std::collections::HashMap::new(),
             streak: grey_api::Streak::default(),
             debounce: None,
+            interval: None,
+            blocked_by: Vec::new(),
             retired: false,
         };
 
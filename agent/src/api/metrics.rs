@@ -0,0 +1,139 @@
+use actix_web::{HttpRequest, HttpResponse, Result, http::header, web};
+use chrono::Utc;
+use grey_api::ApiError;
+
+use super::AppState;
+use super::auth::{resolve_auth_context, retain_visible_crons, retain_visible_probes};
+use crate::metrics::{self, OPENMETRICS_CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE};
+use crate::state::{CronStore, ProbeStore};
+
+/// `GET /metrics` on the UI listener — served only when metrics are enabled without a dedicated
+/// `metrics.listen` address, so moving them to their own listener takes them off the status page.
+pub async fn get_metrics(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse> {
+    if !data.state.get_config().metrics.served_by_ui() {
+        return Ok(ApiError::not_found("Metrics are not served on this address.").into());
+    }
+
+    scrape(req, data).await
+}
+
+/// `GET /metrics` — the probe, cron and cluster metrics the requesting scraper may see, in the
+/// Prometheus text format, or OpenMetrics when its `Accept` header asks for it. Probes and crons are
+/// filtered by their `visible` filters exactly as for `/api/v1/probes`, so a scraper presenting no
+/// bearer token only sees what is public. Peer counts and gossip traffic carry no identities and are
+/// always included, while each peer's own health is reserved for administrators (as for
+/// `/api/v1/admin/cluster/peers`).
+pub async fn scrape(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let ctx = match resolve_auth_context(&req, &data).await {
+        Ok(ctx) => ctx,
+        Err(err) => return Ok(err.into()),
+    };
+    let config = data.state.get_config();
+    let now = Utc::now();
+
+    let mut probes: Vec<grey_api::Probe> = data.state.get_probe_states().await?.into_values().collect();
+    retain_visible_probes(&config, &ctx, &mut probes);
+    probes.sort_by_key(|p| p.name.clone());
+
+    let mut crons: Vec<grey_api::Cron> = data.state.get_cron_states().await?.into_values().collect();
+    retain_visible_crons(&config, &ctx, &mut crons);
+    crons.sort_by_key(|c| c.name.clone());
+
+    let mut peers = data.state.get_peers().await?;
+    peers.retain(|peer| !peer.current);
+    peers.sort_by_key(|p| p.id.clone());
+
+    let mut families = metrics::probe_families(&probes, now);
+    families.extend(metrics::cron_families(&crons, now));
+    families.extend(metrics::cluster_families(&peers, &data.state.gossip_stats(), ctx.admin));
+
+    let open_metrics = req
+        .headers()
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .is_some_and(|accept| accept.contains("application/openmetrics-text"));
+
+    Ok(HttpResponse::Ok()
+        .content_type(if open_metrics { OPENMETRICS_CONTENT_TYPE } else { PROMETHEUS_CONTENT_TYPE })
+        .body(metrics::render(&families, open_metrics)))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::body::MessageBody;
+    use actix_web::http::StatusCode;
+    use actix_web::test::TestRequest;
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::state::State;
+
+    async fn app_state(dir: &std::path::Path, metrics: &str) -> web::Data<AppState> {
+        let config = format!(
+            "ui:\n  enabled: true\n  listen: 127.0.0.1:0\nmetrics: {metrics}\nprobes:\n  - name: web.public\n    policy: {{ interval: 60s, timeout: 5s }}\n    target: !Http\n      url: https://example.com\n    tags: {{ service: Web }}\n  - name: web.secret\n    policy: {{ interval: 60s, timeout: 5s }}\n    target: !Http\n      url: https://example.com\n    visible: auth.admin\nstate: {}\n",
+            dir.join("state.redb").display().to_string().replace('\\', "/")
+        );
+        let config_path = dir.join("config.yml");
+        tokio::fs::write(&config_path, config).await.unwrap();
+        let state = State::new(&config_path).await.unwrap();
+        for probe in state.get_config().probes.iter() {
+            state.update_probe_config(probe).await.unwrap();
+            state
+                .update_probe_state(&probe.name, crate::result::ProbeResult::test())
+                .await
+                .unwrap();
+        }
+
+        web::Data::new(AppState::new(state))
+    }
+
+    #[actix_web::test]
+    async fn test_get_metrics() {
+        let dir = tempdir().unwrap();
+        let data = app_state(dir.path(), "{ enabled: true }").await;
+
+        let resp = get_metrics(TestRequest::default().to_http_request(), data)
+            .await
+            .expect("Failed to get metrics");
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get("content-type").and_then(|v| v.to_str().ok()),
+            Some(PROMETHEUS_CONTENT_TYPE)
+        );
+        let body = String::from_utf8(resp.into_body().try_into_bytes().unwrap().to_vec()).unwrap();
+
+        assert!(body.contains("grey_probe_up{probe=\"web.public\",service=\"Web\"} 1\n"), "{body}");
+        assert!(!body.contains("web.secret"), "hidden probes aren't exported: {body}");
+        assert!(body.contains("# TYPE grey_gossip_messages_sent_total counter\n"), "{body}");
+        assert!(!body.contains("grey_cluster_peer_health"), "peer identities are admin-only: {body}");
+    }
+
+    #[actix_web::test]
+    async fn test_get_metrics_negotiates_openmetrics() {
+        let dir = tempdir().unwrap();
+        let data = app_state(dir.path(), "{ enabled: true }").await;
+
+        let req = TestRequest::default()
+            .insert_header((header::ACCEPT, "application/openmetrics-text; version=1.0.0"))
+            .to_http_request();
+        let resp = get_metrics(req, data).await.expect("Failed to get metrics");
+        assert_eq!(
+            resp.headers().get("content-type").and_then(|v| v.to_str().ok()),
+            Some(OPENMETRICS_CONTENT_TYPE)
+        );
+        let body = String::from_utf8(resp.into_body().try_into_bytes().unwrap().to_vec()).unwrap();
+        assert!(body.ends_with("# EOF\n"), "{body}");
+    }
+
+    #[actix_web::test]
+    async fn test_get_metrics_not_served_by_ui() {
+        for metrics in ["{ enabled: false }", "{ enabled: true, listen: \"127.0.0.1:0\" }"] {
+            let dir = tempdir().unwrap();
+            let data = app_state(dir.path(), metrics).await;
+            let resp = get_metrics(TestRequest::default().to_http_request(), data)
+                .await
+                .expect("Failed to get metrics");
+            assert_eq!(resp.status(), StatusCode::NOT_FOUND, "metrics: {metrics}");
+        }
+    }
+}
@@ -13,8 +13,11 @@ mod cluster;
 mod config;
 mod cron;
 mod incidents;
+mod metrics;
 mod page;
 mod probes;
+mod slos;
+mod snapshot;
 mod trace;
 
 // Embed the dist directory at compile time
@@ -120,6 +123,8 @@ pub fn create_app() -> App<
         .route("/auth/callback", web::get().to(page::index))
         .route("/auth/logout", web::get().to(page::index))
         .route("/api/v1/probes", web::get().to(probes::get_probes))
+        .route("/api/v1/probes/{name}/history", web::get().to(probes::get_probe_history))
+        .route("/api/v1/probes/{name}/samples", web::get().to(probes::get_probe_samples))
         .route("/api/v1/crons", web::get().to(cron::get_crons))
         // Public cron check-in: a scheduled job reports its status here (POST with a JSON body, or
         // GET with query parameters). This is a separate ingest endpoint from the UI's `/crons` read
@@ -127,6 +132,7 @@ pub fn create_app() -> App<
         .route("/api/v1/cron/{name}/check-in", web::get().to(cron::report_checkin_get))
         .route("/api/v1/cron/{name}/check-in", web::post().to(cron::report_checkin_post))
         .route("/api/v1/incidents", web::get().to(incidents::get_incidents))
+        .route("/api/v1/slos", web::get().to(slos::get_slos))
         // Public login endpoints: the SPA fetches the provider's authorization endpoint, then hands
         // the resulting authorization code here for the agent to exchange with its client secret.
         .route("/api/v1/auth/metadata", web::get().to(auth::metadata))
@@ -140,6 +146,8 @@ pub fn create_app() -> App<
                 // Cluster topology is operator-only: it exposes peer addresses and health, so it
                 // lives behind the admin gate rather than being surfaced to public viewers.
                 .route("/cluster/peers", web::get().to(cluster::get_peers))
+                // A backup of the node's state database, for restoring with `grey state import`.
+                .route("/state/snapshot", web::get().to(snapshot::get_snapshot))
                 .route("/incidents", web::get().to(admin::list_incidents))
                 .route("/incidents", web::post().to(admin::create_incident))
                 .route("/incidents/{id}", web::get().to(admin::get_incident))
@@ -149,6 +157,9 @@ pub fn create_app() -> App<
                 .route("/incidents/{id}/updates/{uid}", web::put().to(admin::put_update))
                 .route("/incidents/{id}/updates/{uid}", web::delete().to(admin::delete_update)),
         )
+        // Scraped by Prometheus rather than browsed, so it sits outside `/api/`; the handler only
+        // answers when metrics aren't moved to a dedicated listener (see `start_metrics_server`).
+        .route("/metrics", web::get().to(metrics::get_metrics))
         .route("/robots.txt", web::get().to(robots))
         .route("/static/{filename:.*}", web::get().to(serve_static));
 
@@ -422,6 +433,23 @@ mod tests {
     }
 }
 
+/// Serves `/metrics` on its own `metrics.listen` address, keeping it off the status page's listener.
+pub async fn start_metrics_server(state: State, listen_addr: String) -> Result<(), Box<dyn std::error::Error>> {
+    let state = AppState::new(state);
+
+    Ok(HttpServer::new(move || {
+        App::new()
+            .wrap(from_fn(trace::trace_requests))
+            .route("/metrics", web::get().to(metrics::scrape))
+            .app_data(web::Data::new(state.clone()))
+    })
+    .workers(1)
+    .bind(&listen_addr)?
+    .run()
+    .await
+    .map_err(|e| format!("{}", e))?)
+}
+
 pub async fn start_server(state: State) -> Result<(), Box<dyn std::error::Error>> {
     let state = AppState::new(state);
 
@@ -32,6 +32,10 @@ pub async fn index(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpRe
     super::auth::retain_visible_crons(&config, &ctx, &mut crons);
     crons.sort_by_key(|c| c.name.clone());
 
+    let slos = super::slos::visible_slos(&data.state, &config, &ctx)
+        .await
+        .unwrap_or_default();
+
     // Only the first page of publicly visible incidents is server-rendered for unauthenticated
     // viewers; the client paginates for older ones.
     let incidents = data
@@ -58,6 +62,7 @@ pub async fn index(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpRe
         config: (&config.ui).into(),
         probes,
         crons,
+        slos,
         // Cluster topology is operator-only: it is never part of the server-rendered payload and is
         // fetched client-side once an administrator has signed in, so it can't leak to anonymous
         // viewers via the page's hydration data.
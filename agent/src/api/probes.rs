@@ -1,8 +1,57 @@
-use actix_web::{HttpRequest, HttpResponse, Result, web};
+use actix_web::{HttpRequest, HttpResponse, Result, http::header, web};
+use chrono::{DateTime, SecondsFormat, Utc};
+use grey_api::{ApiError, HistoryResolution, ProbeHistory, ProbeSample, ProbeSamples};
+use serde::Deserialize;
 
 use super::AppState;
 use super::auth::{resolve_auth_context, retain_visible_probes};
-use crate::state::ProbeStore;
+use crate::state::{HistoryStore, ProbeStore, SampleStore, finest_retained_resolution};
+
+/// How far back a history request reaches when it doesn't specify `from`.
+const DEFAULT_HISTORY_SPAN_DAYS: i64 = 30;
+
+/// Query parameters for a probe's history. The values are kept as strings and validated by hand so
+/// that a malformed one yields a clean 400 rather than a generic deserialization error.
+#[derive(Debug, Default, Deserialize)]
+pub struct HistoryQuery {
+    /// The start of the range, as an RFC 3339 timestamp or Unix seconds. Defaults to 30 days before
+    /// `to`.
+    #[serde(default)]
+    pub from: Option<String>,
+    /// The end of the range, as an RFC 3339 timestamp or Unix seconds. Defaults to now.
+    #[serde(default)]
+    pub to: Option<String>,
+    /// `hour`, `day` or `month`. Defaults to the finest resolution still retained as far back as
+    /// `from`.
+    #[serde(default)]
+    pub resolution: Option<String>,
+}
+
+/// Query parameters for a probe's raw samples, validated by hand as for [`HistoryQuery`].
+#[derive(Debug, Default, Deserialize)]
+pub struct SamplesQuery {
+    /// The start of the range, as an RFC 3339 timestamp or Unix seconds. Defaults to the oldest
+    /// sample kept.
+    #[serde(default)]
+    pub from: Option<String>,
+    /// The end of the range, as an RFC 3339 timestamp or Unix seconds. Defaults to now.
+    #[serde(default)]
+    pub to: Option<String>,
+    /// The most samples to return, keeping the most recent. Defaults to every sample in the range.
+    #[serde(default)]
+    pub limit: Option<String>,
+    /// `json`, `ndjson` or `csv`. Defaults to `json`.
+    #[serde(default)]
+    pub format: Option<String>,
+}
+
+/// The formats a probe's samples can be exported in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SampleFormat {
+    Json,
+    Ndjson,
+    Csv,
+}
 
 /// `GET /api/v1/probes` — the probes the requesting viewer may see, sorted by name. Public: an
 /// anonymous viewer sees every probe whose `visible` filter permits it (the default permits
@@ -25,6 +74,210 @@ pub async fn get_probes(req: HttpRequest, data: web::Data<AppState>) -> Result<H
     Ok(HttpResponse::Ok().json(probes))
 }
 
+/// `GET /api/v1/probes/{name}/history?from=&to=&resolution=` — the probe's long-term availability
+/// history, with its per-observer observations, at hourly (kept for a week), daily (kept for 13
+/// months) or monthly resolution. Visibility is enforced as for `/api/v1/probes`: a probe the viewer
+/// may not see is reported as not found.
+pub async fn get_probe_history(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+    query: web::Query<HistoryQuery>,
+) -> Result<HttpResponse> {
+    let ctx = match resolve_auth_context(&req, &data).await {
+        Ok(ctx) => ctx,
+        Err(err) => return Ok(err.into()),
+    };
+    let config = data.state.get_config();
+    let name = path.into_inner();
+
+    let now = Utc::now();
+    let to = match query.to.as_deref().map(parse_time) {
+        None => now,
+        Some(Some(to)) => to,
+        Some(None) => return Ok(invalid_time("to")),
+    };
+    let from = match query.from.as_deref().map(parse_time) {
+        None => to - chrono::Duration::days(DEFAULT_HISTORY_SPAN_DAYS),
+        Some(Some(from)) => from,
+        Some(None) => return Ok(invalid_time("from")),
+    };
+    if from > to {
+        return Ok(ApiError::bad_request("The history range's 'from' must not be after its 'to'.").into());
+    }
+
+    let resolution = match query.resolution.as_deref().map(str::parse::<HistoryResolution>) {
+        None => finest_retained_resolution(from, now),
+        Some(Ok(resolution)) => resolution,
+        Some(Err(err)) => return Ok(ApiError::bad_request(err).into()),
+    };
+
+    let mut probes: Vec<grey_api::Probe> = data.state.get_probe_state(&name).await?.into_iter().collect();
+    retain_visible_probes(&config, &ctx, &mut probes);
+    if probes.is_empty() {
+        return Ok(ApiError::not_found("The probe you requested could not be found.").into());
+    }
+
+    let history = data.state.get_probe_history(&name, resolution, from, to).await?;
+
+    Ok(HttpResponse::Ok().json(ProbeHistory {
+        name,
+        resolution,
+        from,
+        to,
+        history: history.into_iter().map(Into::into).collect(),
+    }))
+}
+
+/// `GET /api/v1/probes/{name}/samples?from=&to=&limit=&format=` — the raw results this node has kept
+/// for a probe with a `samples` log, oldest first: as JSON, or exported as NDJSON (one sample per
+/// line) or CSV. Visibility is enforced as for `/api/v1/probes`, and a probe which keeps no sample log
+/// is reported as not found.
+pub async fn get_probe_samples(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+    query: web::Query<SamplesQuery>,
+) -> Result<HttpResponse> {
+    let ctx = match resolve_auth_context(&req, &data).await {
+        Ok(ctx) => ctx,
+        Err(err) => return Ok(err.into()),
+    };
+    let config = data.state.get_config();
+    let name = path.into_inner();
+
+    let to = match query.to.as_deref().map(parse_time) {
+        None => Utc::now(),
+        Some(Some(to)) => to,
+        Some(None) => return Ok(invalid_time("to")),
+    };
+    let from = match query.from.as_deref().map(parse_time) {
+        None => DateTime::<Utc>::MIN_UTC,
+        Some(Some(from)) => from,
+        Some(None) => return Ok(invalid_time("from")),
+    };
+    if from > to {
+        return Ok(ApiError::bad_request("The samples range's 'from' must not be after its 'to'.").into());
+    }
+
+    let limit = match query.limit.as_deref().map(str::parse::<usize>) {
+        None => None,
+        Some(Ok(limit)) => Some(limit),
+        Some(Err(_)) => {
+            return Ok(ApiError::bad_request("The samples 'limit' must be a non-negative whole number.").into());
+        }
+    };
+
+    let format = match query.format.as_deref() {
+        None | Some("json") => SampleFormat::Json,
+        Some("ndjson") => SampleFormat::Ndjson,
+        Some("csv") => SampleFormat::Csv,
+        Some(other) => {
+            return Ok(ApiError::bad_request(format!(
+                "'{other}' is not a samples format, expected 'json', 'ndjson' or 'csv'."
+            ))
+            .into());
+        }
+    };
+
+    let mut probes: Vec<grey_api::Probe> = data.state.get_probe_state(&name).await?.into_iter().collect();
+    retain_visible_probes(&config, &ctx, &mut probes);
+    if probes.is_empty() {
+        return Ok(ApiError::not_found("The probe you requested could not be found.").into());
+    }
+    if !config.probes.iter().any(|probe| probe.name == name && probe.samples.is_some()) {
+        return Ok(ApiError::not_found(
+            "This probe does not keep a sample log; configure its `samples` option to record one.",
+        )
+        .into());
+    }
+
+    let samples = data.state.get_probe_samples(&name, from, to, limit).await?;
+
+    let (content_type, extension, body) = match format {
+        SampleFormat::Json => {
+            return Ok(HttpResponse::Ok().json(ProbeSamples {
+                name,
+                observer: data.state.node_id().to_string(),
+                samples,
+            }));
+        }
+        SampleFormat::Ndjson => ("application/x-ndjson", "ndjson", samples_to_ndjson(&samples)?),
+        SampleFormat::Csv => ("text/csv; charset=utf-8", "csv", samples_to_csv(&samples)),
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type(content_type)
+        .insert_header((
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}.samples.{extension}\"", name.replace('"', "")),
+        ))
+        .body(body))
+}
+
+/// Renders samples as newline-delimited JSON, one sample per line.
+fn samples_to_ndjson(samples: &[ProbeSample]) -> Result<String, serde_json::Error> {
+    let mut out = String::new();
+    for sample in samples {
+        out.push_str(&serde_json::to_string(sample)?);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// Renders samples as CSV with a header row. The failing checks are joined into a single column as
+/// `check: message` pairs separated by `; `.
+fn samples_to_csv(samples: &[ProbeSample]) -> String {
+    let mut out = String::from("start_time,duration_ms,pass,degraded,retries,scheduling_delay_ms,message,failed_checks\n");
+    for sample in samples {
+        let failed_checks = sample
+            .failed_checks()
+            .map(|(check, message)| match message {
+                Some(message) => format!("{check}: {message}"),
+                None => check.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("; ");
+
+        out.push_str(&format!(
+            "{},{},{},{},{},{},{},{}\n",
+            sample.start_time.to_rfc3339_opts(SecondsFormat::Micros, true),
+            sample.duration.as_millis(),
+            sample.pass,
+            sample.degraded,
+            sample.retries,
+            sample.scheduling_delay.as_millis(),
+            csv_field(&sample.message),
+            csv_field(&failed_checks),
+        ));
+    }
+    out
+}
+
+/// Quotes a CSV field when it contains a delimiter, quote or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Parses a timestamp given as either RFC 3339 or Unix seconds.
+fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    match value.parse::<i64>() {
+        Ok(seconds) => DateTime::from_timestamp(seconds, 0),
+        Err(_) => DateTime::parse_from_rfc3339(value).ok().map(|t| t.to_utc()),
+    }
+}
+
+fn invalid_time(parameter: &str) -> HttpResponse {
+    ApiError::bad_request(format!(
+        "The requested range's '{parameter}' must be an RFC 3339 timestamp or a number of Unix seconds."
+    ))
+    .into()
+}
+
 #[cfg(test)]
 mod tests {
     use actix_web::body::MessageBody;
@@ -51,6 +304,182 @@ mod tests {
         assert_eq!(probes.len(), 1);
     }
 
+    fn history_query(from: Option<&str>, to: Option<&str>, resolution: Option<&str>) -> web::Query<HistoryQuery> {
+        web::Query(HistoryQuery {
+            from: from.map(str::to_string),
+            to: to.map(str::to_string),
+            resolution: resolution.map(str::to_string),
+        })
+    }
+
+    #[actix_web::test]
+    async fn test_get_probe_history() {
+        let temp_dir = tempdir().unwrap();
+        let app_state = AppState::test(temp_dir.path().to_path_buf()).await;
+        let name = app_state.state.get_config().probes[0].name.clone();
+        let data = web::Data::new(app_state);
+
+        let resp = get_probe_history(
+            TestRequest::default().to_http_request(),
+            data.clone(),
+            web::Path::from(name.clone()),
+            history_query(None, None, None),
+        )
+        .await
+        .expect("Failed to get probe history");
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body_bytes = resp.into_body().try_into_bytes().unwrap();
+        let history: ProbeHistory = serde_json::from_slice(&body_bytes).unwrap();
+        assert_eq!(history.name, name);
+        assert_eq!(history.resolution, HistoryResolution::Day, "30 days back is beyond the hourly tier");
+
+        let resp = get_probe_history(
+            TestRequest::default().to_http_request(),
+            data.clone(),
+            web::Path::from(name.clone()),
+            history_query(Some("2020-01-01T00:00:00Z"), None, Some("hour")),
+        )
+        .await
+        .unwrap();
+        let body_bytes = resp.into_body().try_into_bytes().unwrap();
+        let history: ProbeHistory = serde_json::from_slice(&body_bytes).unwrap();
+        assert_eq!(history.history.len(), 1, "the current hour is reported before any rollup");
+        assert_eq!(history.history[0].total().total_samples, 1);
+        assert!(history.history[0].latency.p99.is_some(), "each bucket reports its latency percentiles");
+        assert_eq!(history.history[0].observer_latency.len(), 1);
+        assert!(
+            history.history[0].observer_latency.values().all(|latency| latency.p99.is_some()),
+            "and those of each of its observers"
+        );
+
+        for (query, status) in [
+            (history_query(Some("yesterday"), None, None), StatusCode::BAD_REQUEST),
+            (history_query(Some("200"), Some("100"), None), StatusCode::BAD_REQUEST),
+            (history_query(None, None, Some("week")), StatusCode::BAD_REQUEST),
+        ] {
+            let resp = get_probe_history(
+                TestRequest::default().to_http_request(),
+                data.clone(),
+                web::Path::from(name.clone()),
+                query,
+            )
+            .await
+            .unwrap();
+            assert_eq!(resp.status(), status);
+        }
+
+        let resp = get_probe_history(
+            TestRequest::default().to_http_request(),
+            data,
+            web::Path::from("missing".to_string()),
+            history_query(None, None, None),
+        )
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    fn samples_query(format: Option<&str>, limit: Option<&str>) -> web::Query<SamplesQuery> {
+        web::Query(SamplesQuery {
+            from: None,
+            to: None,
+            limit: limit.map(str::to_string),
+            format: format.map(str::to_string),
+        })
+    }
+
+    #[actix_web::test]
+    async fn test_get_probe_samples() {
+        let dir = tempdir().unwrap();
+        let config = format!(
+            "ui:\n  enabled: true\n  listen: 127.0.0.1:0\nprobes:\n  - name: logged\n    policy: {{ interval: 60s, timeout: 5s }}\n    target: !Http\n      url: https://example.com\n    samples: {{ count: 100 }}\n  - name: unlogged\n    policy: {{ interval: 60s, timeout: 5s }}\n    target: !Http\n      url: https://example.com\nstate: {}\n",
+            dir.path().join("state.redb").display().to_string().replace('\\', "/")
+        );
+        let config_path = dir.path().join("config.yml");
+        tokio::fs::write(&config_path, config).await.unwrap();
+        let state = State::new(&config_path).await.unwrap();
+        let retention = state.get_config().probes[0].samples.clone().unwrap();
+
+        let now = Utc::now();
+        for (minutes_ago, pass) in [(2, true), (1, false)] {
+            let mut result = crate::result::ProbeResult::test();
+            result.start_time = now - chrono::Duration::minutes(minutes_ago);
+            result.pass = pass;
+            result.message = "Probe failed, twice".into();
+            state.record_probe_sample("logged", &result.to_sample(), &retention).await.unwrap();
+        }
+        let data = web::Data::new(AppState::new(state));
+
+        let resp = get_probe_samples(
+            TestRequest::default().to_http_request(),
+            data.clone(),
+            web::Path::from("logged".to_string()),
+            samples_query(None, Some("1")),
+        )
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body_bytes = resp.into_body().try_into_bytes().unwrap();
+        let samples: ProbeSamples = serde_json::from_slice(&body_bytes).unwrap();
+        assert_eq!(samples.samples.len(), 1, "the limit keeps the most recent sample");
+        assert!(!samples.samples[0].pass);
+
+        let resp = get_probe_samples(
+            TestRequest::default().to_http_request(),
+            data.clone(),
+            web::Path::from("logged".to_string()),
+            samples_query(Some("ndjson"), None),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            resp.headers().get("content-type").and_then(|v| v.to_str().ok()),
+            Some("application/x-ndjson")
+        );
+        let body = String::from_utf8(resp.into_body().try_into_bytes().unwrap().to_vec()).unwrap();
+        let lines: Vec<ProbeSample> = body.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].start_time < lines[1].start_time, "samples are oldest first");
+
+        let resp = get_probe_samples(
+            TestRequest::default().to_http_request(),
+            data.clone(),
+            web::Path::from("logged".to_string()),
+            samples_query(Some("csv"), None),
+        )
+        .await
+        .unwrap();
+        let body = String::from_utf8(resp.into_body().try_into_bytes().unwrap().to_vec()).unwrap();
+        let rows: Vec<&str> = body.lines().collect();
+        assert_eq!(rows[0], "start_time,duration_ms,pass,degraded,retries,scheduling_delay_ms,message,failed_checks");
+        assert_eq!(rows.len(), 3);
+        assert!(rows[2].contains(",false,false,0,0,\"Probe failed, twice\","), "{}", rows[2]);
+
+        for (name, query, status) in [
+            ("unlogged", samples_query(None, None), StatusCode::NOT_FOUND),
+            ("missing", samples_query(None, None), StatusCode::NOT_FOUND),
+            ("logged", samples_query(Some("xml"), None), StatusCode::BAD_REQUEST),
+            ("logged", samples_query(None, Some("-1")), StatusCode::BAD_REQUEST),
+        ] {
+            let resp = get_probe_samples(
+                TestRequest::default().to_http_request(),
+                data.clone(),
+                web::Path::from(name.to_string()),
+                query,
+            )
+            .await
+            .unwrap();
+            assert_eq!(resp.status(), status, "{name}");
+        }
+    }
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a, b"), "\"a, b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
     /// A probe restricted with `visible: auth.admin` is omitted from the public listing for an
     /// anonymous viewer (no bearer token), while an unrestricted probe is returned.
     #[actix_web::test]
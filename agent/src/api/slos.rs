@@ -0,0 +1,91 @@
+use actix_web::{HttpRequest, HttpResponse, Result, web};
+use chrono::Utc;
+
+use super::AppState;
+use super::auth::{AuthContext, resolve_auth_context};
+use crate::Config;
+use crate::slo::evaluate_slos;
+use crate::state::State;
+
+/// `GET /api/v1/slos` — the service level objectives the requesting viewer may see, sorted by name,
+/// with their error budgets and burn rates. Visibility follows each SLO's `visible` filter, as for
+/// `/api/v1/probes`. A target the viewer may not see still counts towards its objectives, but is
+/// left out of their `targets`.
+pub async fn get_slos(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let ctx = match resolve_auth_context(&req, &data).await {
+        Ok(ctx) => ctx,
+        Err(err) => return Ok(err.into()),
+    };
+    let config = data.state.get_config();
+
+    let slos = visible_slos(&data.state, &config, &ctx).await?;
+    Ok(HttpResponse::Ok().json(slos))
+}
+
+/// The SLOs the viewer may see, evaluated now and sorted by name, naming only the targets the viewer
+/// may also see.
+pub(super) async fn visible_slos(
+    state: &State,
+    config: &Config,
+    ctx: &AuthContext,
+) -> Result<Vec<grey_api::Slo>, Box<dyn std::error::Error>> {
+    let visible = config.slos.iter().filter(|slo| ctx.can_see(&slo.visible));
+    let mut slos = evaluate_slos(state, config, visible, Utc::now()).await?;
+    for slo in slos.iter_mut() {
+        slo.targets.retain(|name| target_visible(config, ctx, name));
+    }
+    slos.sort_by_key(|slo| slo.name.clone());
+
+    Ok(slos)
+}
+
+/// Whether the viewer may see the probe or cron with the given name.
+fn target_visible(config: &Config, ctx: &AuthContext, name: &str) -> bool {
+    config
+        .probes
+        .iter()
+        .find(|probe| probe.name == name)
+        .map(|probe| ctx.can_see(&probe.visible))
+        .or_else(|| {
+            config
+                .crons
+                .iter()
+                .find(|cron| cron.name == name)
+                .map(|cron| ctx.can_see(&cron.visible))
+        })
+        .unwrap_or(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::body::MessageBody;
+    use actix_web::http::StatusCode;
+    use actix_web::test::TestRequest;
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn test_get_slos() {
+        let dir = tempdir().unwrap();
+        let config = format!(
+            "ui:\n  enabled: true\n  listen: 127.0.0.1:0\nprobes:\n  - name: web.public\n    policy: {{ interval: 60s, timeout: 5s }}\n    target: !Http\n      url: https://example.com\n    tags: {{ service: Web }}\n  - name: web.secret\n    policy: {{ interval: 60s, timeout: 5s }}\n    target: !Http\n      url: https://example.com\n    tags: {{ service: Web }}\n    visible: auth.admin\nslos:\n  - name: web\n    selector: tags.service == \"Web\"\n    objective: 99.9\n  - name: internal\n    selector: \"true\"\n    objective: 99\n    visible: auth.admin\nstate: {}\n",
+            dir.path().join("state.redb").display().to_string().replace('\\', "/")
+        );
+        let config_path = dir.path().join("config.yml");
+        tokio::fs::write(&config_path, config).await.unwrap();
+        let app_state = AppState::new(State::new(&config_path).await.unwrap());
+
+        let resp = get_slos(TestRequest::default().to_http_request(), web::Data::new(app_state))
+            .await
+            .expect("Failed to get SLOs");
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body_bytes = resp.into_body().try_into_bytes().unwrap();
+        let slos: Vec<grey_api::Slo> = serde_json::from_slice(&body_bytes).unwrap();
+
+        assert_eq!(slos.len(), 1, "the admin-only SLO is hidden from anonymous viewers");
+        assert_eq!(slos[0].name, "web");
+        assert_eq!(slos[0].targets, vec!["web.public"], "hidden targets aren't named");
+        assert_eq!(slos[0].burn_rates.len(), 2);
+    }
+}
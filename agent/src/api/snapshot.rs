@@ -0,0 +1,75 @@
+use actix_web::{HttpResponse, Result, http::header, web};
+use grey_api::ApiError;
+use serde::Deserialize;
+
+use super::AppState;
+use crate::state::{ArchiveFormat, ArchiveStore};
+
+/// Query parameters for a state snapshot, validated by hand so that an unknown format yields a clean
+/// 400 rather than a generic deserialization error.
+#[derive(Debug, Default, Deserialize)]
+pub struct SnapshotQuery {
+    /// `json` or `msgpack`. Defaults to `json`.
+    #[serde(default)]
+    pub format: Option<String>,
+}
+
+/// `GET /api/v1/admin/state/snapshot?format=` — an archive of every probe, cron and incident record
+/// in this node's state database, as written by `grey state export` and read by `grey state import`.
+///
+/// The archive carries the full probe history and hidden incidents, so this endpoint is
+/// operator-only and lives behind the admin authentication gate (see [`super::create_app`]).
+pub async fn get_snapshot(data: web::Data<AppState>, query: web::Query<SnapshotQuery>) -> Result<HttpResponse> {
+    let format = match query.format.as_deref().map(str::parse::<ArchiveFormat>) {
+        None => ArchiveFormat::default(),
+        Some(Ok(format)) => format,
+        Some(Err(err)) => return Ok(ApiError::bad_request(err).into()),
+    };
+
+    let archive = data.state.export_archive().await?;
+    let body = archive.to_vec(format)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header((
+            header::CONTENT_DISPOSITION,
+            format!(
+                "attachment; filename=\"grey-state-{}.{}\"",
+                archive.exported_at.format("%Y%m%dT%H%M%SZ"),
+                format.extension()
+            ),
+        ))
+        .body(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::body::MessageBody;
+    use actix_web::http::StatusCode;
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::state::StateArchive;
+
+    #[actix_web::test]
+    async fn test_get_snapshot() {
+        let temp_dir = tempdir().unwrap();
+        let data = web::Data::new(AppState::test(temp_dir.path().to_path_buf()).await);
+
+        for (format, content_type) in [("json", "application/json"), ("msgpack", "application/msgpack")] {
+            let query = web::Query(SnapshotQuery { format: Some(format.into()) });
+            let resp = get_snapshot(data.clone(), query).await.expect("a snapshot");
+            assert_eq!(resp.status(), StatusCode::OK);
+            assert_eq!(resp.headers().get("content-type").and_then(|v| v.to_str().ok()), Some(content_type));
+
+            let body = resp.into_body().try_into_bytes().unwrap();
+            let archive = StateArchive::from_slice(&body).unwrap();
+            assert_eq!(archive.node_id, data.state.node_id().to_string());
+            assert_eq!(archive.probes.len(), 1);
+        }
+
+        let query = web::Query(SnapshotQuery { format: Some("xml".into()) });
+        let resp = get_snapshot(data, query).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,280 @@
+use chrono::{DateTime, Datelike, Timelike, Utc};
+use grey_api::ProbeHistoryBucket;
+use serde::{Deserialize, Serialize};
+
+use crate::Sample;
+
+/// Learns how long a probe's runs usually take from its history, so that its checks can catch an
+/// unusually slow run without a hardcoded latency threshold.
+///
+/// The baseline is fitted to the probe's completed buckets in the hourly history tier, which
+/// reaches back a week, before each run, and the run's latency is compared against it, exposing
+/// `baseline.latency_zscore` (how many standard deviations slower than the baseline's mean the run
+/// was), `baseline.anomalous` (whether that exceeds the `threshold`), `baseline.latency_mean` and
+/// `baseline.latency_stddev`. Until the history holds `min_samples` runs the z-score is `null` and
+/// no run is anomalous.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BaselineConfig {
+    /// How the history is weighted to form the baseline.
+    #[serde(default)]
+    pub model: BaselineModel,
+
+    /// How quickly the `ewma` model forgets older runs: a run this long ago counts half as much as
+    /// one in the last hour.
+    #[serde(default = "default_half_life", with = "humantime_serde")]
+    pub half_life: std::time::Duration,
+
+    /// The z-score above which a run is `anomalous`.
+    #[serde(default = "default_threshold")]
+    pub threshold: f64,
+
+    /// The fewest runs the baseline must be fitted to before runs are scored against it.
+    #[serde(default = "default_min_samples")]
+    pub min_samples: u64,
+}
+
+fn default_half_life() -> std::time::Duration {
+    std::time::Duration::from_secs(6 * 60 * 60)
+}
+
+fn default_threshold() -> f64 {
+    3.0
+}
+
+fn default_min_samples() -> u64 {
+    30
+}
+
+/// How a probe's history is weighted to form its latency baseline.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BaselineModel {
+    /// An exponentially weighted mean and standard deviation of every retained run, which tracks
+    /// gradual drift.
+    #[default]
+    Ewma,
+    /// A seasonal profile: only runs from the same hour of the same weekday, for probes whose
+    /// latency follows a weekly traffic pattern.
+    Hourly,
+}
+
+/// A probe's usual latency, as fitted by a [`BaselineConfig`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LatencyBaseline {
+    /// The mean latency, in milliseconds.
+    pub mean_ms: f64,
+    /// The latency's standard deviation, in milliseconds.
+    pub std_dev_ms: f64,
+}
+
+impl LatencyBaseline {
+    /// How many standard deviations slower than the mean `latency` is. The standard deviation is
+    /// floored at a millisecond, so that a perfectly steady probe doesn't read every jitter as an
+    /// anomaly.
+    pub fn zscore(&self, latency: std::time::Duration) -> f64 {
+        (latency.as_secs_f64() * 1000.0 - self.mean_ms) / self.std_dev_ms.max(1.0)
+    }
+}
+
+impl BaselineConfig {
+    /// The start of the hourly history the baseline is fitted to at `now`: the same hour a week
+    /// earlier, which is as far back as the hourly tier reaches.
+    pub fn history_since(now: DateTime<Utc>) -> DateTime<Utc> {
+        now - chrono::Duration::weeks(1) - chrono::Duration::hours(1)
+    }
+
+    /// The most runs the baseline can be fitted to for a probe run every `interval`. The `ewma` model
+    /// has every completed hour of the week the hourly tier reaches back, but the `hourly` model only
+    /// ever has one: the same hour a week earlier.
+    pub fn max_samples(&self, interval: std::time::Duration) -> u64 {
+        let hours = match self.model {
+            BaselineModel::Ewma => 7 * 24,
+            BaselineModel::Hourly => 1,
+        };
+        hours * 3600 / interval.as_secs().max(1)
+    }
+
+    /// Fits the baseline at `now` to the runs recorded in `history`, or `None` while it holds fewer
+    /// than `min_samples` of them. The bucket for the current hour is still filling up, so it is
+    /// left out.
+    pub fn fit(&self, history: &[ProbeHistoryBucket], now: DateTime<Utc>) -> Option<LatencyBaseline> {
+        let half_life_hours = self.half_life.as_secs_f64() / 3600.0;
+
+        let mut samples = 0;
+        let mut total_weight = 0.0;
+        let mut buckets = Vec::new();
+        for bucket in history {
+            let age = now - bucket.start_time;
+            if age < chrono::Duration::hours(1) {
+                continue;
+            }
+
+            let weight = match self.model {
+                BaselineModel::Ewma if half_life_hours > 0.0 => {
+                    0.5f64.powf(age.num_seconds() as f64 / 3600.0 / half_life_hours)
+                }
+                BaselineModel::Ewma => 1.0,
+                BaselineModel::Hourly
+                    if bucket.start_time.weekday() == now.weekday() && bucket.start_time.hour() == now.hour() =>
+                {
+                    1.0
+                }
+                BaselineModel::Hourly => continue,
+            };
+
+            let sketch = bucket.total().latency_sketch;
+            let Some((mean, variance)) = sketch.moments() else {
+                continue;
+            };
+
+            let weight = weight * sketch.count() as f64;
+            samples += sketch.count();
+            total_weight += weight;
+            buckets.push((weight, mean, variance));
+        }
+
+        if samples < self.min_samples || total_weight <= 0.0 {
+            return None;
+        }
+
+        // The pooled variance is the (weighted) mean of each bucket's own variance, plus the spread of
+        // the buckets' means around the overall mean.
+        let mean_ms = buckets.iter().map(|(w, mean, _)| w * mean).sum::<f64>() / total_weight;
+        let variance = buckets
+            .iter()
+            .map(|(w, mean, variance)| w * (variance + (mean - mean_ms).powi(2)))
+            .sum::<f64>()
+            / total_weight;
+
+        Some(LatencyBaseline {
+            mean_ms,
+            std_dev_ms: variance.sqrt(),
+        })
+    }
+
+    /// Scores a run which took `latency` against the fitted `baseline`, adding the `baseline.*`
+    /// fields to `sample`.
+    pub fn observe(&self, baseline: Option<LatencyBaseline>, latency: std::time::Duration, sample: Sample) -> Sample {
+        let zscore = baseline.map(|baseline| baseline.zscore(latency));
+        let duration = |ms: f64| chrono::Duration::microseconds((ms * 1000.0) as i64);
+
+        sample
+            .with("baseline.latency_zscore", zscore)
+            .with("baseline.anomalous", zscore.is_some_and(|zscore| zscore > self.threshold))
+            .with("baseline.latency_mean", baseline.map(|b| duration(b.mean_ms)))
+            .with("baseline.latency_stddev", baseline.map(|b| duration(b.std_dev_ms)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SampleValue;
+    use grey_api::Observation;
+
+    fn bucket(start_time: DateTime<Utc>, latencies_ms: impl IntoIterator<Item = u64>) -> ProbeHistoryBucket {
+        let mut observation = Observation::default();
+        for ms in latencies_ms {
+            observation.add_sample(true, 0, std::time::Duration::from_millis(ms));
+        }
+
+        ProbeHistoryBucket {
+            start_time,
+            pass: true,
+            degraded: false,
+            message: String::new(),
+            validations: Default::default(),
+            observations: [("observer1".to_string(), observation)].into_iter().collect(),
+        }
+    }
+
+    fn config(model: BaselineModel) -> BaselineConfig {
+        BaselineConfig {
+            model,
+            half_life: default_half_life(),
+            threshold: default_threshold(),
+            min_samples: 4,
+        }
+    }
+
+    #[test]
+    fn test_fit_ewma() {
+        let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let history = vec![
+            bucket(now - chrono::Duration::hours(12), [200, 200]),
+            bucket(now - chrono::Duration::hours(2), [100, 100]),
+            bucket(now - chrono::Duration::minutes(10), [5_000; 10]),
+        ];
+
+        let baseline = config(BaselineModel::Ewma).fit(&history, now).expect("a baseline");
+        // Twelve hours is two half-lives, so the older runs count a quarter as much as a run from the
+        // last hour would, the newer ones (a third of a half-life old) a little more than three
+        // quarters, and the current hour is ignored.
+        let recent = 0.5f64.powf(2.0 / 6.0);
+        let expected = (100.0 * recent + 200.0 * 0.25) / (recent + 0.25);
+        assert!((baseline.mean_ms - expected).abs() <= 2.0, "{baseline:?}");
+        assert!(baseline.std_dev_ms > 30.0 && baseline.std_dev_ms < 50.0, "{baseline:?}");
+
+        assert_eq!(BaselineConfig { min_samples: 5, ..config(BaselineModel::Ewma) }.fit(&history, now), None);
+        assert_eq!(config(BaselineModel::Ewma).fit(&[], now), None);
+    }
+
+    #[test]
+    fn test_fit_hourly() {
+        let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let history = vec![
+            bucket(now - chrono::Duration::weeks(1), [1_000, 1_200]),
+            bucket(now - chrono::Duration::hours(167), [100, 100]),
+            bucket(now - chrono::Duration::hours(24), [100, 100]),
+            bucket(now - chrono::Duration::hours(2), [100, 100]),
+        ];
+
+        let baseline = BaselineConfig { min_samples: 2, ..config(BaselineModel::Hourly) }
+            .fit(&history, now)
+            .expect("a baseline");
+        assert!((baseline.mean_ms - 1_100.0).abs() <= 11.0, "{baseline:?}");
+        assert!((baseline.std_dev_ms - 100.0).abs() <= 11.0, "{baseline:?}");
+    }
+
+    #[test]
+    fn test_max_samples() {
+        let minutely = std::time::Duration::from_secs(60);
+        assert_eq!(config(BaselineModel::Ewma).max_samples(minutely), 7 * 24 * 60);
+        assert_eq!(config(BaselineModel::Hourly).max_samples(minutely), 60);
+        assert_eq!(config(BaselineModel::Hourly).max_samples(std::time::Duration::from_secs(300)), 12);
+        assert_eq!(config(BaselineModel::Hourly).max_samples(std::time::Duration::from_millis(500)), 3600);
+    }
+
+    #[test]
+    fn test_observe() {
+        let config = config(BaselineModel::Ewma);
+        let baseline = LatencyBaseline { mean_ms: 100.0, std_dev_ms: 10.0 };
+
+        let sample = config.observe(Some(baseline), std::time::Duration::from_millis(150), Sample::default());
+        match sample.get("baseline.latency_zscore") {
+            SampleValue::Double(zscore) => assert!((zscore - 5.0).abs() < 1e-9, "{zscore}"),
+            other => panic!("expected a z-score, got {other:?}"),
+        }
+        assert_eq!(sample.get("baseline.anomalous"), &SampleValue::Bool(true));
+        assert_eq!(sample.get("baseline.latency_mean"), &SampleValue::Duration(chrono::Duration::milliseconds(100)));
+
+        let sample = config.observe(Some(baseline), std::time::Duration::from_millis(50), Sample::default());
+        assert_eq!(sample.get("baseline.anomalous"), &SampleValue::Bool(false));
+
+        let sample = config.observe(None, std::time::Duration::from_millis(150), Sample::default());
+        assert_eq!(sample.get("baseline.latency_zscore"), &SampleValue::None);
+        assert_eq!(sample.get("baseline.anomalous"), &SampleValue::Bool(false));
+    }
+
+    #[test]
+    fn test_deserialize_defaults() {
+        let config: BaselineConfig = serde_yaml::from_str("model: hourly").unwrap();
+        assert_eq!(config.model, BaselineModel::Hourly);
+        assert_eq!(config.half_life, default_half_life());
+        assert_eq!(config.threshold, 3.0);
+        assert_eq!(config.min_samples, 30);
+
+        let config: BaselineConfig = serde_yaml::from_str("{}").unwrap();
+        assert_eq!(config.model, BaselineModel::Ewma);
+    }
+}
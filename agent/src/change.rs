@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    Sample, SampleValue,
+    state::{ChangeStore, State},
+};
+
+/// Watches one of a probe's sample fields for changes between runs, such as a vendor's status JSON
+/// or a DNS TXT record being modified.
+///
+/// Each run hashes the field and compares it with the hash the previous run recorded in the
+/// replicated [`State`], exposing the result as `change.changed`, `change.hash`,
+/// `change.previous_hash` and `change.since` so that checks like `change.changed == false` hold
+/// across restarts and nodes.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ChangeDetection {
+    /// The sample field to watch, such as `http.body` or `dns.answers`.
+    pub field: String,
+}
+
+impl ChangeDetection {
+    /// The hex-encoded SHA-256 hash of the watched field's value in `sample`.
+    pub fn hash(&self, sample: &Sample) -> String {
+        let value = match sample.get(&self.field) {
+            SampleValue::String(value) => value.clone(),
+            value => serde_json::to_string(value).unwrap_or_else(|_| value.to_string()),
+        };
+
+        hex::encode(Sha256::digest(value.as_bytes()))
+    }
+
+    /// Records the watched field's hash for the probe named `probe`, adding the `change.*` fields to
+    /// `sample`.
+    pub async fn observe(
+        &self,
+        state: &State,
+        probe: &str,
+        sample: Sample,
+        run_started: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Sample, Box<dyn std::error::Error>> {
+        let hash = self.hash(&sample);
+        let change = state.observe_content_hash(probe, &self.field, &hash, run_started).await?;
+
+        Ok(sample
+            .with("change.changed", change.changed)
+            .with("change.hash", hash)
+            .with("change.previous_hash", change.previous_hash)
+            .with("change.since", change.since))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash() {
+        let detection = ChangeDetection {
+            field: "http.body".into(),
+        };
+
+        let a = detection.hash(&Sample::default().with("http.body", "a"));
+        assert_eq!(a, detection.hash(&Sample::default().with("http.body", "a").with("http.status", 200)));
+        assert_ne!(a, detection.hash(&Sample::default().with("http.body", "b")));
+        assert_ne!(
+            detection.hash(&Sample::default().with("http.body", vec!["a", "b"])),
+            detection.hash(&Sample::default().with("http.body", vec!["b", "a"])),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_observe() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = State::test(dir.path().to_path_buf()).await;
+        let detection = ChangeDetection {
+            field: "dns.answers".into(),
+        };
+
+        let sample = Sample::default().with("dns.answers", vec!["v=spf1 -all"]);
+        let first = detection
+            .observe(&state, "txt", sample.clone(), chrono::Utc::now())
+            .await
+            .unwrap();
+        assert_eq!(first.get("change.changed"), &SampleValue::Bool(false));
+        assert_eq!(first.get("change.previous_hash"), &SampleValue::None);
+
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        let modified = Sample::default().with("dns.answers", vec!["v=spf1 include:example.com -all"]);
+        let second = detection
+            .observe(&state, "txt", modified, chrono::Utc::now())
+            .await
+            .unwrap();
+        assert_eq!(second.get("change.changed"), &SampleValue::Bool(true));
+        assert_eq!(second.get("change.previous_hash"), first.get("change.hash"));
+    }
+}
@@ -1,4 +1,5 @@
-//! Helpers for working with probe `checks` (filt-rs expressions).
+//! Probe `checks`: either `filt-rs` expressions or, with the `scripts` feature, `!Script` checks
+//! written in JavaScript — and the helpers used to report their failures.
 //!
 //! When a check fails it is far more useful to see *what the probe actually
 //! observed* than to be told the expression didn't match. This module walks a
@@ -11,7 +12,11 @@ use filt_rs::{
     UnaryOperator,
 };
 
+use serde::{Deserialize, Serialize, de::Visitor};
+
 use crate::Sample;
+#[cfg(feature = "scripts")]
+use crate::js::{ScriptCheck, ScriptCheckOutcome};
 
 /// The maximum number of fields to enumerate in a failure summary before the
 /// remainder are collapsed into an "and N more" suffix.
@@ -21,6 +26,196 @@ const DEFAULT_MAX_FIELDS: usize = 6;
 /// is truncated with an ellipsis.
 const DEFAULT_MAX_VALUE_LEN: usize = 64;
 
+/// A single entry in a probe's `checks`: a `filt-rs` expression, written as a plain string (or as a
+/// `!Filter` to give it a severity), or a `!Script` check for assertions which are awkward to
+/// express in `filt-rs`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Check {
+    Filter(FilterCheck),
+    #[cfg(feature = "scripts")]
+    Script(ScriptCheck),
+}
+
+/// How a failing check affects its probe: a `critical` check fails it, while a `warning` check only
+/// marks it as degraded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckSeverity {
+    #[default]
+    Critical,
+    Warning,
+}
+
+/// A `filt-rs` expression check along with its severity.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FilterCheck {
+    pub filter: Filter,
+    #[serde(default)]
+    pub severity: CheckSeverity,
+}
+
+/// Why a check failed: the public message reported as its validation result, and any operator-only
+/// detail which is kept to telemetry (see the probe runner).
+#[derive(Debug)]
+pub struct CheckFailure {
+    pub message: String,
+    pub detail: Option<String>,
+}
+
+impl Check {
+    /// The check as it was written in the configuration.
+    pub fn raw(&self) -> &str {
+        match self {
+            Check::Filter(check) => check.filter.raw(),
+            #[cfg(feature = "scripts")]
+            Check::Script(script) => &script.code,
+        }
+    }
+
+    /// Whether a failure of this check fails its probe or only marks it as degraded.
+    pub fn severity(&self) -> CheckSeverity {
+        match self {
+            Check::Filter(check) => check.severity,
+            #[cfg(feature = "scripts")]
+            Check::Script(script) => script.severity,
+        }
+    }
+
+    /// Evaluates the check against `sample`, describing the failure if it doesn't pass.
+    pub async fn evaluate(&self, sample: &Sample) -> Result<(), CheckFailure> {
+        match self {
+            Check::Filter(FilterCheck { filter, .. }) => match filter.matches(sample) {
+                Ok(true) => Ok(()),
+                Ok(false) => Err(CheckFailure {
+                    message: unmatched_message(filter, sample),
+                    detail: None,
+                }),
+                Err(e) => Err(CheckFailure {
+                    message: evaluation_error_message(filter, sample),
+                    detail: Some(e.to_string()),
+                }),
+            },
+            #[cfg(feature = "scripts")]
+            Check::Script(script) => match script.run(sample).await {
+                Ok(ScriptCheckOutcome::Passed) => Ok(()),
+                Ok(ScriptCheckOutcome::Failed { fields }) => Err(CheckFailure {
+                    message: unmatched_fields_message(&fields, sample),
+                    detail: None,
+                }),
+                // The script's author chose the message it threw, so it is reported as-is.
+                Ok(ScriptCheckOutcome::Threw { message, fields }) => {
+                    let observed = render_fields(&fields, sample, DEFAULT_MAX_FIELDS, DEFAULT_MAX_VALUE_LEN);
+                    Err(CheckFailure {
+                        message: if observed.is_empty() {
+                            message
+                        } else {
+                            format!("{message}\n{observed}")
+                        },
+                        detail: None,
+                    })
+                }
+                Err(e) => Err(CheckFailure {
+                    message: "The check could not be evaluated.".to_string(),
+                    detail: Some(e.to_string()),
+                }),
+            },
+        }
+    }
+}
+
+impl From<Filter> for Check {
+    fn from(filter: Filter) -> Self {
+        Check::Filter(FilterCheck {
+            filter,
+            severity: CheckSeverity::Critical,
+        })
+    }
+}
+
+impl std::fmt::Display for Check {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Check::Filter(check) => write!(f, "{}", check.filter),
+            #[cfg(feature = "scripts")]
+            Check::Script(script) => write!(f, "{}", script),
+        }
+    }
+}
+
+impl Serialize for Check {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            Check::Filter(FilterCheck {
+                filter,
+                severity: CheckSeverity::Critical,
+            }) => filter.serialize(serializer),
+            Check::Filter(check) => serializer.serialize_newtype_variant("Check", 0, "Filter", check),
+            #[cfg(feature = "scripts")]
+            Check::Script(script) => serializer.serialize_newtype_variant("Check", 1, "Script", script),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Check {
+    /// Plain strings are parsed as `filt-rs` expressions, `!Filter` tagged maps as expressions with
+    /// a severity and `!Script` tagged maps as script checks, with any of them being rejected at
+    /// config-load time if it doesn't parse.
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(CheckVisitor)
+    }
+}
+
+struct CheckVisitor;
+impl<'de> Visitor<'de> for CheckVisitor {
+    type Value = Check;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a filt-rs expression, a !Filter check or a !Script check")
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Filter::new(value).map(Check::from).map_err(E::custom)
+    }
+
+    fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
+    where
+        A: serde::de::EnumAccess<'de>,
+    {
+        use serde::de::{Error, VariantAccess};
+
+        let (variant, access): (String, _) = data.variant()?;
+        match variant.as_str() {
+            "Filter" => access.newtype_variant::<FilterCheck>().map(Check::Filter),
+            #[cfg(feature = "scripts")]
+            "Script" => {
+                let script: ScriptCheck = access.newtype_variant()?;
+                script.validate().map_err(A::Error::custom)?;
+                Ok(Check::Script(script))
+            }
+            #[cfg(not(feature = "scripts"))]
+            "Script" => {
+                let _ = access;
+                Err(A::Error::custom(
+                    "!Script checks require grey to be built with the `scripts` feature",
+                ))
+            }
+            other => {
+                let _ = access;
+                Err(A::Error::unknown_variant(other, &["Filter", "Script"]))
+            }
+        }
+    }
+}
+
 /// An [`ExprVisitor`] that collects, in order of first appearance and without
 /// duplicates, the names of every sample field (property) a check references.
 ///
@@ -114,7 +309,17 @@ fn observed_fields_with_limits(
     max_fields: usize,
     max_value_len: usize,
 ) -> String {
-    let fields = referenced_fields(check);
+    render_fields(&referenced_fields(check), sample, max_fields, max_value_len)
+}
+
+/// Renders the given sample fields and the values they held, one per line, capped at `max_fields`
+/// fields of `max_value_len` characters each.
+fn render_fields<F: AsRef<str>>(
+    fields: &[F],
+    sample: &Sample,
+    max_fields: usize,
+    max_value_len: usize,
+) -> String {
     if fields.is_empty() {
         return String::new();
     }
@@ -123,7 +328,8 @@ fn observed_fields_with_limits(
         .iter()
         .take(max_fields)
         .map(|field| {
-            let value = truncate(&sample.get(*field).to_string(), max_value_len);
+            let field = field.as_ref();
+            let value = truncate(&sample.get(field).to_string(), max_value_len);
             format!("{field}={value}")
         })
         .collect();
@@ -143,7 +349,17 @@ fn observed_fields_with_limits(
 /// the check references no fields there is nothing useful to add, so a terse
 /// generic note is used instead.
 pub fn unmatched_message(check: &Filter, sample: &Sample) -> String {
-    let observed = observed_fields(check, sample);
+    unmatched(observed_fields(check, sample))
+}
+
+/// [`unmatched_message`] for a check whose consulted fields were recorded as it ran (such as a
+/// script check), rather than read from its expression.
+#[cfg_attr(not(feature = "scripts"), allow(dead_code))]
+pub fn unmatched_fields_message<F: AsRef<str>>(fields: &[F], sample: &Sample) -> String {
+    unmatched(render_fields(fields, sample, DEFAULT_MAX_FIELDS, DEFAULT_MAX_VALUE_LEN))
+}
+
+fn unmatched(observed: String) -> String {
     if observed.is_empty() {
         "The check did not pass.".to_string()
     } else {
@@ -316,4 +532,46 @@ mod tests {
             "The check could not be evaluated."
         );
     }
+
+    #[tokio::test]
+    async fn filter_checks_evaluate_with_the_unmatched_message() {
+        let sample = Sample::default().with("http.status", 503);
+        let check = Check::from(filter("http.status == 200"));
+        let failure = check.evaluate(&sample).await.expect_err("the check should fail");
+        assert_eq!(failure.message, "http.status=503");
+        assert!(failure.detail.is_none());
+    }
+
+    #[cfg(feature = "scripts")]
+    #[tokio::test]
+    async fn script_checks_report_the_fields_they_read() {
+        let check: Check = serde_yaml::from_str(
+            "!Script\nname: statuses match\ncode: return sample[\"http.status\"] === sample[\"expected.status\"]\n",
+        )
+        .unwrap();
+
+        let sample = Sample::default().with("http.status", 503).with("expected.status", 200);
+        let failure = check.evaluate(&sample).await.expect_err("the check should fail");
+        assert_eq!(failure.message, "http.status=503\nexpected.status=200");
+
+        let sample = Sample::default().with("http.status", 200).with("expected.status", 200);
+        assert!(check.evaluate(&sample).await.is_ok());
+    }
+
+    #[cfg(feature = "scripts")]
+    #[tokio::test]
+    async fn script_checks_report_thrown_messages() {
+        let check: Check =
+            serde_yaml::from_str("!Script\nname: body\ncode: throw new Error(\"the body was empty\")\n")
+                .unwrap();
+        assert_eq!(check.to_string(), "body");
+
+        assert!(
+            serde_yaml::from_str::<Check>("!Script\ncode: return true\n").is_err(),
+            "script checks must be named"
+        );
+
+        let failure = check.evaluate(&Sample::default()).await.expect_err("the check should fail");
+        assert_eq!(failure.message, "the body was empty");
+    }
 }
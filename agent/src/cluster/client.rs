@@ -20,6 +20,8 @@ where
     /// The in-memory membership registry: discovered peers, per-address link health, and the
     /// failure detector. Shared with the rest of the process (e.g. the API) behind an [`Arc`].
     membership: Arc<Membership<S::Id, T::Address>>,
+    /// Running totals of the gossip messages exchanged, shared with the metrics endpoint.
+    stats: Arc<GossipStats>,
 
     seed_peers: Vec<String>,
     /// How frequently the seed peers are re-resolved by the background resolver loop.
@@ -45,6 +47,7 @@ where
             store,
             transport,
             membership,
+            stats: Arc::new(GossipStats::default()),
 
             gossip_factor: 1,
             gossip_interval: std::time::Duration::from_secs(10),
@@ -82,6 +85,10 @@ where
         }
     }
 
+    pub fn with_stats(self, stats: Arc<GossipStats>) -> Self {
+        Self { stats, ..self }
+    }
+
     pub async fn run(&self) {
         tokio::join!(self.gossip_loop(), self.receive_loop(), self.resolve_loop());
     }
@@ -178,7 +185,6 @@ where
             // target: a failure to reach one peer must not prevent the remaining targets (including
             // the seeds) from being gossiped this round.
             if let Err(err) = self
-                .transport
                 .send(addr.clone(), Message::Syn(syn_meta, digest.clone()))
                 .instrument(span.clone())
                 .await
@@ -194,7 +200,6 @@ where
                 let member_meta =
                     span.in_scope(|| MessageMetadata::new(self_id.clone()).with_trace_context());
                 if let Err(err) = self
-                    .transport
                     .send(addr.clone(), Message::MemberGossip(member_meta, sample.clone()))
                     .instrument(span)
                     .await
@@ -280,6 +285,7 @@ where
         loop {
             match self.transport.try_receive().await {
                 Ok(Some((addr, msg))) => {
+                    self.stats.record_received(msg.kind());
                     let meta = msg.metadata();
                     let span = info_span!(
                         "gossip.receive",
@@ -306,7 +312,7 @@ where
                     // now awaits the next datagram, so this no longer busy-polls.
                 }
                 Err(err) => {
-                    // Handle error
+                    self.stats.record_malformed();
                     warn!(
                         "Malformed gossip message received, ignoring (make sure all Grey instances in the cluster are running the same major version): {err:?}"
                     );
@@ -316,6 +322,21 @@ where
         }
     }
 
+    /// Sends a message through the transport, counting it (or its failure) in the gossip stats.
+    async fn send(
+        &self,
+        addr: T::Address,
+        msg: Message<S::Id, S::State>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let kind = msg.kind();
+        let result = self.transport.send(addr, msg).await;
+        match &result {
+            Ok(()) => self.stats.record_sent(kind),
+            Err(_) => self.stats.record_send_failure(kind),
+        }
+        result
+    }
+
     async fn handle_message(
         &self,
         self_id: S::Id,
@@ -335,13 +356,12 @@ where
                         .map_err(|e| format!("Failed to compute diff for peer {}: {e:?}", meta.from))?;
                     let digest = self.store.digest().await
                         .map_err(|e| format!("Failed to compute digest for node: {e:?}"))?;
-                    self.transport
-                        .send(
-                            addr.clone(),
-                            Message::SynAck(MessageMetadata::new(self_id.clone()).with_trace_context(), digest, delta),
-                        )
-                        .await
-                        .map_err(|e| format!("Failed to send synack gossip message to peer {} at {addr}: {e:?}", meta.from))?;
+                    self.send(
+                        addr.clone(),
+                        Message::SynAck(MessageMetadata::new(self_id.clone()).with_trace_context(), digest, delta),
+                    )
+                    .await
+                    .map_err(|e| format!("Failed to send synack gossip message to peer {} at {addr}: {e:?}", meta.from))?;
                     trace!("Sent synack to {} at {}", meta.from, addr);
                 }
                 Message::SynAck(meta, digest, diff) => {
@@ -352,8 +372,7 @@ where
                         .map_err(|e| format!("Failed to compute diff for peer {}: {e:?}", meta.from))?;
                     self.store.apply(diff).await?;
 
-                    self.transport
-                        .send(addr.clone(), Message::Ack(MessageMetadata::new(self_id.clone()).with_trace_context(), delta))
+                    self.send(addr.clone(), Message::Ack(MessageMetadata::new(self_id.clone()).with_trace_context(), delta))
                         .await
                         .map_err(|e| format!("Failed to send ack gossip message to peer {} at {addr}: {e:?}", meta.from))?;
 
@@ -424,11 +443,15 @@ mod tests {
         let store2 = InMemoryGossipStore::<_, _, LastWriteWinsValue<String>>::new(node2, node2);
         store2.update("test", LastWriteWinsValue::new("value2".to_string())).await;
 
+        let stats1 = Arc::new(GossipStats::default());
+        let stats2 = Arc::new(GossipStats::default());
         let client1 = GossipClient::new(store1.clone(), transport1, test_membership(node1))
-            .with_gossip_interval(Duration::from_millis(10));
+            .with_gossip_interval(Duration::from_millis(10))
+            .with_stats(stats1.clone());
         let client2 = GossipClient::new(store2.clone(), transport2, test_membership(node2))
             .with_gossip_interval(Duration::from_millis(10))
-            .with_seed_peers(vec![node1.to_string()]);
+            .with_seed_peers(vec![node1.to_string()])
+            .with_stats(stats2.clone());
 
         {
             let local_set = tokio::task::LocalSet::new();
@@ -451,6 +474,11 @@ mod tests {
 
         assert_eq!(store1.get(&node2, "test").await.unwrap().value, "value2");
         assert_eq!(store2.get(&node1, "test").await.unwrap().value, "value1");
+
+        // Node 2 opens the handshake with its seed, and node 1 answers it.
+        assert!(stats2.sent().get("syn").copied().unwrap_or_default() > 0);
+        assert!(stats1.received().get("syn").copied().unwrap_or_default() > 0);
+        assert!(stats1.sent().get("synack").copied().unwrap_or_default() > 0);
     }
 
     // ---- Multi-node mock network (for discovery and unidirectional-link tests) -------------------
@@ -5,6 +5,7 @@ mod helpers;
 mod membership;
 mod message;
 mod node;
+mod stats;
 mod versioned;
 mod store;
 mod transport;
@@ -17,6 +18,7 @@ pub use helpers::*;
 pub use membership::*;
 pub use message::*;
 pub use node::*;
+pub use stats::*;
 pub use versioned::*;
 pub use store::*;
 pub use transport::*;
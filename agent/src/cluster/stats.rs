@@ -0,0 +1,75 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Running totals of the gossip traffic this node has exchanged, keyed by message kind (see
+/// [`super::Message::kind`]). They live for the lifetime of the process and are exported by the
+/// metrics endpoint as counters, so they are never reset.
+#[derive(Debug, Default)]
+pub struct GossipStats {
+    sent: Mutex<BTreeMap<&'static str, u64>>,
+    send_failures: Mutex<BTreeMap<&'static str, u64>>,
+    received: Mutex<BTreeMap<&'static str, u64>>,
+    malformed: AtomicU64,
+}
+
+impl GossipStats {
+    /// Records a message of the given kind handed to the transport successfully.
+    pub fn record_sent(&self, kind: &'static str) {
+        *self.sent.lock().unwrap().entry(kind).or_default() += 1;
+    }
+
+    /// Records a message of the given kind which the transport failed to send.
+    pub fn record_send_failure(&self, kind: &'static str) {
+        *self.send_failures.lock().unwrap().entry(kind).or_default() += 1;
+    }
+
+    /// Records a message of the given kind received from a peer.
+    pub fn record_received(&self, kind: &'static str) {
+        *self.received.lock().unwrap().entry(kind).or_default() += 1;
+    }
+
+    /// Records an inbound datagram which could not be decoded as a gossip message.
+    pub fn record_malformed(&self) {
+        self.malformed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn sent(&self) -> BTreeMap<&'static str, u64> {
+        self.sent.lock().unwrap().clone()
+    }
+
+    pub fn send_failures(&self) -> BTreeMap<&'static str, u64> {
+        self.send_failures.lock().unwrap().clone()
+    }
+
+    pub fn received(&self) -> BTreeMap<&'static str, u64> {
+        self.received.lock().unwrap().clone()
+    }
+
+    pub fn malformed(&self) -> u64 {
+        self.malformed.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_by_kind() {
+        let stats = GossipStats::default();
+        stats.record_sent("syn");
+        stats.record_sent("syn");
+        stats.record_sent("members");
+        stats.record_send_failure("syn");
+        stats.record_received("ack");
+        stats.record_malformed();
+
+        assert_eq!(stats.sent().get("syn"), Some(&2));
+        assert_eq!(stats.sent().get("members"), Some(&1));
+        assert_eq!(stats.send_failures().get("syn"), Some(&1));
+        assert_eq!(stats.received().get("ack"), Some(&1));
+        assert_eq!(stats.received().get("syn"), None);
+        assert_eq!(stats.malformed(), 1);
+    }
+}
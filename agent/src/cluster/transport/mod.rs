@@ -6,6 +6,8 @@ pub use udp::UdpGossipTransport;
 
 #[cfg(test)]
 pub use tests::InMemoryGossipTransport;
+#[cfg(test)]
+pub use udp::MAX_DATAGRAM_SIZE;
 
 pub trait GossipTransport<Id: Eq + Hash, State: Versioned> {
     type Address;
@@ -6,7 +6,7 @@ use crate::cluster::transport::encryption::{EncryptionKeyProvider, EncryptionPro
 
 /// Largest UDP datagram payload we will ever receive (IPv4: 65535 - 20-byte IP header - 8-byte UDP
 /// header). Used to size the receive buffer; the per-message send limit is configurable.
-const MAX_DATAGRAM_SIZE: usize = 65507;
+pub const MAX_DATAGRAM_SIZE: usize = 65507;
 
 /// Grey's gossip magic, a throwback to the `#888` brand colour. It occupies the upper 10 bits of a
 /// 2-byte protocol header; the lower 6 bits carry the protocol version (0–63). Every Grey 2.0
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use std::path::PathBuf;
 use std::time::SystemTime;
@@ -8,6 +8,7 @@ use tracing::Level;
 use tracing_batteries::prelude::*;
 
 use crate::Probe;
+use crate::slo::SloConfig;
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Config {
     #[serde(default)]
@@ -21,15 +22,38 @@ pub struct Config {
     #[serde(default)]
     pub webhooks: Vec<WebhookConfig>,
 
+    /// Service level objectives measured over the probes and crons their selectors match, reported
+    /// in the API and UI and alerted on through `slo.*` webhook events.
+    #[serde(default)]
+    pub slos: Vec<SloConfig>,
+
     #[serde(default)]
     pub ui: UiConfig,
 
     #[serde(default)]
     pub cluster: ClusterConfig,
 
+    /// Limits on how many probes may run at once, so a burst of heavy probes can't starve the rest.
+    #[serde(default)]
+    pub concurrency: ConcurrencyConfig,
+
+    /// The Prometheus/OpenMetrics scrape endpoint, exposing probe, cron and cluster metrics.
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+
+    /// A directory of shared ES modules which `!Script` probes may `import` by their path relative to
+    /// it, resolved relative to the configuration file.
+    #[serde(default)]
+    pub script_modules: Option<PathBuf>,
+
     #[serde(rename = "state")]
     #[serde(default = "default::state")]
     pub state: PathBuf,
+
+    /// The files and directories (besides the configuration file itself) which were read while
+    /// loading this configuration; the config reloader watches these for changes too.
+    #[serde(skip)]
+    pub sources: Vec<PathBuf>,
 }
 
 /// Configuration for a "deadman's switch" cron monitor. A scheduled job reports check-ins to the
@@ -77,6 +101,11 @@ pub struct CronConfig {
     /// must persist before it is reported (see [`AlertingConfig`]).
     #[serde(default)]
     pub alerting: AlertingConfig,
+
+    /// The probes or crons this cron depends on. While any of them is unhealthy, a failure of this
+    /// cron is attributed to the upstream outage and no webhook is sent for it.
+    #[serde(default)]
+    pub depends_on: Vec<String>,
 }
 
 /// Controls how state-change alerts (webhook notifications) behave for a probe or cron.
@@ -127,6 +156,31 @@ fn default_alerting_debounce() -> chrono::Duration {
     chrono::Duration::minutes(5)
 }
 
+/// Keeps a probe's individual results in a bounded, node-local log, so that intermittent failures can
+/// be investigated with their exact timing after the probe's history has folded them into hourly
+/// buckets. At least one of `count` and `max_age` must be set; when both are, a sample is dropped as
+/// soon as either limit is reached.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SampleLogConfig {
+    /// The most samples to keep, dropping the oldest first.
+    #[serde(default)]
+    pub count: Option<usize>,
+
+    /// How long to keep each sample for.
+    #[serde(default, with = "humantime_serde::option")]
+    pub max_age: Option<std::time::Duration>,
+}
+
+impl SampleLogConfig {
+    /// The oldest sample start still retained at `now`, or `None` when samples are only limited by
+    /// count.
+    pub fn retained_since(&self, now: chrono::DateTime<chrono::Utc>) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.max_age
+            .and_then(|age| chrono::Duration::from_std(age).ok())
+            .and_then(|age| now.checked_sub_signed(age))
+    }
+}
+
 impl CronConfig {
     /// The schedule this cron declares, preferring an explicit crontab `schedule` over `interval`.
     /// (Config validation guarantees exactly one is set; the fallback is purely defensive.)
@@ -162,7 +216,7 @@ impl CronConfig {
     }
 }
 
-/// A webhook endpoint notified when a probe or cron changes state. The agent posts the JSON event
+/// A webhook endpoint notified when a probe or cron changes state, or an SLO burns its error budget. The agent posts the JSON event
 /// payload to `endpoint`, signs it with `secret` (see the HMAC scheme on [`WebhookConfig::secret`]),
 /// and only delivers events for which `filter` evaluates to true.
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -194,8 +248,9 @@ pub struct WebhookConfig {
     /// A `filt-rs` expression — the same language as probe `checks` — evaluated against each event to
     /// decide whether it is delivered to this endpoint. The available fields are documented in
     /// `docs/guide/webhooks.md` (`event`, `entity.type`, `entity.name`, `entity.tags.<key>`,
-    /// `state.current`, `state.previous`, `state.healthy`, `state.was_healthy`, and
-    /// `state.availability`). Defaults to matching every event.
+    /// `state.current`, `state.previous`, `state.healthy`, `state.was_healthy`,
+    /// `state.availability`, `slo.objective` and `slo.budget_remaining`). Defaults to matching every
+    /// event.
     #[serde(default = "default_webhook_filter")]
     pub filter: filt_rs::Filter,
 
@@ -237,9 +292,14 @@ impl Config {
             ],
             crons: vec![],
             webhooks: vec![],
+            slos: vec![],
             ui: UiConfig::default(),
             cluster: ClusterConfig::default(),
+            concurrency: ConcurrencyConfig::default(),
+            metrics: MetricsConfig::default(),
+            script_modules: None,
             state: temp_dir.join("test_state.redb"),
+            sources: vec![],
         }
     }
 
@@ -251,12 +311,51 @@ impl Config {
             err
         })?;
 
-        let config: Self = serde_yaml::from_str(&config)?;
+        let mut config: Self = serde_yaml::from_str(&config)?;
+        #[cfg(feature = "scripts")]
+        config.load_scripts(path).await?;
+        config.validate_probes()?;
         config.validate_crons()?;
+        config.validate_dependencies()?;
         config.validate_webhooks()?;
+        config.validate_slos()?;
+        config.concurrency.validate()?;
+        config.metrics.validate(&config.ui)?;
         Ok(config)
     }
 
+    /// Reads the code of every `!Script` probe which references a `file`, along with the shared
+    /// `script_modules`, resolving both relative to the configuration file at `path`.
+    #[cfg(feature = "scripts")]
+    async fn load_scripts(&mut self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let base = path.parent().unwrap_or(Path::new("."));
+
+        let modules = match &self.script_modules {
+            Some(dir) => {
+                let dir = base.join(dir);
+                let modules = crate::js::ScriptModules::load(&dir).await?;
+                self.sources.push(dir);
+                Some(modules)
+            }
+            None => None,
+        };
+
+        for probe in self.probes.iter_mut() {
+            if let crate::targets::TargetType::Script(script) = &mut probe.target {
+                script
+                    .load(base, modules.clone())
+                    .await
+                    .map_err(|e| format!("Probe '{}' has an invalid script: {}", probe.name, e))?;
+
+                if let Some(file) = &script.file {
+                    self.sources.push(base.join(file));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     /// Validates each webhook's destination: an endpoint must be present and an absolute `http(s)`
     /// URL, so a typo fails the load rather than silently dropping every notification. The `filter`
     /// expression is already validated during deserialization (it is a parsed [`filt_rs::Filter`]).
@@ -283,6 +382,155 @@ impl Config {
         Ok(())
     }
 
+    /// Validates each SLO's objective and alerts, and that no two SLOs share a name — the name keys
+    /// an SLO's webhook events and its entry in the API.
+    fn validate_slos(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let mut names = HashSet::new();
+        for slo in &self.slos {
+            if !names.insert(slo.name.as_str()) {
+                return Err(format!("More than one SLO is named '{}'; SLO names must be unique.", slo.name).into());
+            }
+
+            slo.validate()?;
+        }
+        Ok(())
+    }
+
+    /// Validates that each probe's policy declares exactly one of `interval` / `schedule` and that any
+    /// crontab expression parses, mirroring the cron validation below, and that any failing interval
+    /// and its backoff are usable. The active window and timezone are already validated during
+    /// deserialization.
+    fn validate_probes(&self) -> Result<(), Box<dyn std::error::Error>> {
+        for probe in &self.probes {
+            match (&probe.policy.schedule, probe.policy.interval) {
+                (Some(_), Some(_)) => {
+                    return Err(format!(
+                        "Probe '{}' sets both `interval` and `schedule` in its policy; set exactly one.",
+                        probe.name
+                    )
+                    .into());
+                }
+                (None, None) => {
+                    return Err(format!(
+                        "Probe '{}' must set either `interval` or `schedule` in its policy.",
+                        probe.name
+                    )
+                    .into());
+                }
+                (Some(expr), None) => {
+                    if !grey_api::CronSchedule::Cron(expr.clone()).is_valid() {
+                        return Err(format!(
+                            "Probe '{}' has an invalid crontab `schedule`: '{expr}'.",
+                            probe.name
+                        )
+                        .into());
+                    }
+                }
+                (None, Some(_)) => {}
+            }
+
+            match (probe.policy.failing_interval, &probe.policy.failing_backoff) {
+                (Some(interval), _) if interval.is_zero() => {
+                    return Err(format!(
+                        "Probe '{}' has a zero `failing_interval`; it must be a positive duration.",
+                        probe.name
+                    )
+                    .into());
+                }
+                (None, Some(_)) => {
+                    return Err(format!(
+                        "Probe '{}' sets a `failing_backoff` without a `failing_interval` to back off from.",
+                        probe.name
+                    )
+                    .into());
+                }
+                (_, Some(backoff)) if backoff.multiplier.is_nan() || backoff.multiplier < 1.0 => {
+                    return Err(format!(
+                        "Probe '{}' has a `failing_backoff` multiplier of {}; it must be at least 1.",
+                        probe.name, backoff.multiplier
+                    )
+                    .into());
+                }
+                _ => {}
+            }
+
+            if probe.policy.attempt_timeout.is_some_and(|t| t.is_zero()) {
+                return Err(format!(
+                    "Probe '{}' has a zero `attempt_timeout`; it must be a positive duration.",
+                    probe.name
+                )
+                .into());
+            }
+
+            if let Some(samples) = &probe.samples {
+                if samples.count.is_none() && samples.max_age.is_none() {
+                    return Err(format!(
+                        "Probe '{}' keeps a sample log without limiting it; set its `samples.count`, `samples.max_age` or both.",
+                        probe.name
+                    )
+                    .into());
+                }
+
+                if samples.count == Some(0) || samples.max_age.is_some_and(|age| age.is_zero()) {
+                    return Err(format!(
+                        "Probe '{}' has a zero `samples` limit; remove the `samples` log instead to disable it.",
+                        probe.name
+                    )
+                    .into());
+                }
+            }
+
+            if let Some(baseline) = &probe.baseline {
+                if baseline.threshold.is_nan() || baseline.threshold <= 0.0 {
+                    return Err(format!(
+                        "Probe '{}' has a `baseline` threshold of {}; it must be a positive z-score.",
+                        probe.name, baseline.threshold
+                    )
+                    .into());
+                }
+
+                if baseline.half_life.is_zero() {
+                    return Err(format!(
+                        "Probe '{}' has a zero `baseline.half_life`; it must be a positive duration.",
+                        probe.name
+                    )
+                    .into());
+                }
+
+                if let Some(interval) = probe.policy.interval
+                    && baseline.min_samples > baseline.max_samples(interval)
+                {
+                    return Err(format!(
+                        "Probe '{}' has a `baseline.min_samples` of {}, but its baseline can only be fitted to {} runs every {}.",
+                        probe.name,
+                        baseline.min_samples,
+                        baseline.max_samples(interval),
+                        humantime::format_duration(interval)
+                    )
+                    .into());
+                }
+            }
+
+            if let crate::targets::TargetType::Http(http) = &probe.target
+                && let Some(html) = &http.html
+            {
+                html.validate()
+                    .map_err(|e| format!("Probe '{}' has invalid `html` options: {e}", probe.name))?;
+            }
+
+            if let Some(delay) = &probe.policy.retry_delay {
+                if delay.multiplier.is_nan() || delay.multiplier < 1.0 {
+                    return Err(format!(
+                        "Probe '{}' has a `retry_delay` multiplier of {}; it must be at least 1.",
+                        probe.name, delay.multiplier
+                    )
+                    .into());
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Validates that each cron declares exactly one of `interval` / `schedule`, that any crontab
     /// expression parses, and that no cron shares a name with a probe — so a misconfiguration fails
     /// the load rather than silently misbehaving. The name check is what lets gossip key replicated
@@ -328,10 +576,56 @@ impl Config {
         Ok(())
     }
 
+    /// The `depends_on` list of every configured probe and cron, keyed by name.
+    pub fn dependencies(&self) -> HashMap<&str, &[String]> {
+        self.probes
+            .iter()
+            .map(|probe| (probe.name.as_str(), probe.depends_on.as_slice()))
+            .chain(self.crons.iter().map(|cron| (cron.name.as_str(), cron.depends_on.as_slice())))
+            .collect()
+    }
+
+    /// Validates that every `depends_on` entry names a configured probe or cron, and that no entity
+    /// depends on itself, directly or transitively. A cycle would let failing entities blame one
+    /// another, suppressing every alert between them.
+    fn validate_dependencies(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let dependencies = self.dependencies();
+        for (name, depends_on) in &dependencies {
+            if let Some(unknown) = depends_on.iter().find(|d| !dependencies.contains_key(d.as_str())) {
+                return Err(format!(
+                    "'{name}' depends on '{unknown}', which is not a configured probe or cron."
+                )
+                .into());
+            }
+        }
+
+        for (name, depends_on) in &dependencies {
+            let mut pending: Vec<&str> = depends_on.iter().map(String::as_str).collect();
+            let mut visited = HashSet::new();
+            while let Some(next) = pending.pop() {
+                if next == *name {
+                    return Err(format!(
+                        "'{name}' depends on itself through `depends_on`; dependencies must not form a cycle."
+                    )
+                    .into());
+                }
+
+                if visited.insert(next) {
+                    pending.extend(dependencies[next].iter().map(String::as_str));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     #[tracing::instrument(name = "config.reload", level=Level::DEBUG, skip(path), err(Debug))]
+    /// Reloads the configuration if the file at `path`, or any of the `sources` the current
+    /// configuration was loaded from (see [`Config::sources`]), has been modified since `last_modified`.
     pub async fn load_if_modified_since(
         path: &Path,
         last_modified: SystemTime,
+        sources: &[PathBuf],
     ) -> Result<Option<(Config, SystemTime)>, Box<dyn std::error::Error>> {
         let metadata = tokio::fs::metadata(path).await.map_err(|e| {
             error!(name: "config.reload", { config.path=%path.display(), exception = %e }, "Failed to get metadata for {}: {}", path.display(), e);
@@ -339,7 +633,11 @@ impl Config {
             err
         })?;
 
-        let modified = metadata.modified()?;
+        let mut modified = metadata.modified()?;
+        for source in sources {
+            modified = modified.max(latest_modification(source).await);
+        }
+
         if modified > last_modified {
             let config = Self::load_from_path(path).await?;
             Ok(Some((config, modified)))
@@ -349,6 +647,33 @@ impl Config {
     }
 }
 
+/// The most recent modification time of `path` or, for a directory, of anything beneath it. Entries
+/// which can't be read are skipped: a missing script fails the reload of the configuration which
+/// references it instead.
+async fn latest_modification(path: &Path) -> SystemTime {
+    let mut latest = SystemTime::UNIX_EPOCH;
+    let mut pending = vec![path.to_path_buf()];
+    while let Some(path) = pending.pop() {
+        let Ok(metadata) = tokio::fs::metadata(&path).await else {
+            continue;
+        };
+
+        if let Ok(modified) = metadata.modified() {
+            latest = latest.max(modified);
+        }
+
+        if metadata.is_dir()
+            && let Ok(mut entries) = tokio::fs::read_dir(&path).await
+        {
+            while let Ok(Some(entry)) = entries.next_entry().await {
+                pending.push(entry.path());
+            }
+        }
+    }
+
+    latest
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct UiConfig {
     #[serde(default)]
@@ -432,6 +757,95 @@ fn default_admin_acl() -> filt_rs::Filter {
     filt_rs::Filter::new("false").expect("the deny-all ACL expression must parse")
 }
 
+/// Caps on the number of probe runs in flight at once. Every limit is optional; a run which would
+/// exceed any limit that applies to it waits in a queue shared by all probes (see
+/// [`crate::limiter::ConcurrencyLimiter`]).
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct ConcurrencyConfig {
+    /// The most probes which may run at once across the whole agent.
+    #[serde(default)]
+    pub max_concurrent_probes: Option<usize>,
+
+    /// The most probes sharing a value of the given tag which may run at once, e.g. `service: 2`
+    /// allows two probes of each service to run together.
+    #[serde(default)]
+    pub per_tag: HashMap<String, usize>,
+
+    /// The most probes targeting the same host which may run at once. DNS and script probes aren't
+    /// bound to a single host and are exempt.
+    #[serde(default)]
+    pub per_host: Option<usize>,
+}
+
+impl ConcurrencyConfig {
+    /// The limits which apply to a run of `probe`, as `(key, limit)` pairs where probes sharing a key
+    /// share its limit.
+    pub fn limits_for(&self, probe: &Probe) -> Vec<(String, usize)> {
+        let mut limits = Vec::new();
+        if let Some(max) = self.max_concurrent_probes {
+            limits.push(("global".to_string(), max));
+        }
+
+        for (tag, max) in &self.per_tag {
+            if let Some(value) = probe.tags.get(tag) {
+                limits.push((format!("tag:{tag}={value}"), *max));
+            }
+        }
+
+        if let (Some(max), Some(host)) = (self.per_host, probe.target.host()) {
+            limits.push((format!("host:{host}"), max));
+        }
+
+        limits
+    }
+
+    /// Validates that no limit is zero, which would stall every probe it applies to forever.
+    fn validate(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if self.max_concurrent_probes == Some(0) || self.per_host == Some(0) {
+            return Err("Concurrency limits must allow at least one probe to run; remove the limit rather than setting it to 0.".into());
+        }
+
+        if let Some((tag, _)) = self.per_tag.iter().find(|(_, max)| **max == 0) {
+            return Err(format!(
+                "The concurrency limit for the '{tag}' tag must allow at least one probe to run."
+            )
+            .into());
+        }
+
+        Ok(())
+    }
+}
+
+/// Where the `/metrics` scrape endpoint is served. It is off by default; once enabled it is served
+/// alongside the status page, or on a listener of its own when `listen` is set, which keeps it off a
+/// publicly exposed status page.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct MetricsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// A dedicated `host:port` to serve `/metrics` on instead of the UI listener. Like `ui.listen`,
+    /// a change only takes effect once the agent restarts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub listen: Option<String>,
+}
+
+impl MetricsConfig {
+    /// Whether `/metrics` is served by the UI listener (rather than not at all, or on its own).
+    pub fn served_by_ui(&self) -> bool {
+        self.enabled && self.listen.is_none()
+    }
+
+    /// Validates that enabled metrics have somewhere to be served from.
+    fn validate(&self, ui: &UiConfig) -> Result<(), Box<dyn std::error::Error>> {
+        if self.served_by_ui() && !ui.enabled {
+            return Err("Metrics are enabled without a `metrics.listen` address, but the UI (which would serve them) is disabled; set `metrics.listen` or enable the UI.".into());
+        }
+
+        Ok(())
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct ClusterConfig {
     #[serde(default)]
@@ -639,6 +1053,66 @@ mod tests {
         assert!(Config::load_from_path(&ok).await.is_ok());
     }
 
+    /// A probe's policy must declare exactly one of `interval` / `schedule`, with a parseable crontab,
+    /// and any active window must be well-formed.
+    #[tokio::test]
+    async fn rejects_invalid_probe_schedules() {
+        let dir = tempfile::tempdir().unwrap();
+        let probe = |policy: &str| {
+            format!("probes:\n  - name: p\n    policy: {{ timeout: 2s, {policy} }}\n    target: !Http\n      url: https://example.com\n")
+        };
+
+        let cases = [
+            // Invalid crontab expression.
+            probe("schedule: 'not a cron'"),
+            // Neither interval nor schedule.
+            probe("retries: 3"),
+            // Both interval and schedule.
+            probe("interval: 5s, schedule: '* * * * *'"),
+            // Malformed active hours.
+            probe("interval: 5s, active_hours: '9-5'"),
+            // Unknown timezone.
+            probe("interval: 5s, timezone: Mars/Olympus_Mons"),
+            // Unknown weekday.
+            probe("interval: 5s, active_days: [someday]"),
+            // A zero failing interval.
+            probe("interval: 5s, failing_interval: 0s"),
+            // A backoff with nothing to back off from.
+            probe("interval: 5s, failing_backoff: { max: 1m }"),
+            // A backoff which would shrink the interval.
+            probe("interval: 5s, failing_interval: 1s, failing_backoff: { multiplier: 0.5, max: 1m }"),
+            // A zero per-attempt timeout.
+            probe("interval: 5s, attempt_timeout: 0s"),
+            // A retry delay which would shrink between attempts.
+            probe("interval: 5s, retry_delay: { initial: 1s, multiplier: 0.5 }"),
+            // An unparseable retry condition.
+            probe("interval: 5s, retry_on: 'error.kind =='"),
+        ];
+
+        for (i, body) in cases.iter().enumerate() {
+            let path = dir.path().join(format!("bad-probe-{i}.yml"));
+            tokio::fs::write(&path, body).await.unwrap();
+            assert!(
+                Config::load_from_path(&path).await.is_err(),
+                "probe config #{i} should be rejected: {body}"
+            );
+        }
+
+        // A business-hours crontab probe loads.
+        let ok = dir.path().join("ok.yml");
+        tokio::fs::write(
+            &ok,
+            probe("schedule: '*/15 * * * *', active_hours: '09:00-17:00', active_days: [mon, fri], timezone: Europe/London"),
+        )
+        .await
+        .unwrap();
+        let config = Config::load_from_path(&ok).await.expect("a scheduled probe should load");
+        let policy = &config.probes[0].policy;
+        assert_eq!(policy.schedule.as_deref(), Some("*/15 * * * *"));
+        assert_eq!(policy.active_days, vec![chrono::Weekday::Mon, chrono::Weekday::Fri]);
+        assert_eq!(policy.timezone, Some(chrono_tz::Europe::London));
+    }
+
     /// The shipped `webhooks` example must parse through the real configuration loader, guarding the
     /// example against drift and exercising the `WebhookConfig` (filt-rs filter + humantime timeout)
     /// deserialization.
@@ -760,6 +1234,265 @@ mod tests {
             .unwrap();
         assert!(Config::load_from_path(&ok).await.is_ok());
     }
+
+    /// `depends_on` must name configured entities and must not loop back on itself.
+    #[tokio::test]
+    async fn rejects_invalid_dependencies() {
+        let dir = tempfile::tempdir().unwrap();
+        let probe = |name: &str, depends_on: &str| {
+            format!("  - name: {name}\n    policy: {{ interval: 5s, timeout: 2s }}\n    target: !Http\n      url: https://example.com\n    depends_on: [{depends_on}]\n")
+        };
+
+        let cases = [
+            // An unknown dependency.
+            format!("probes:\n{}", probe("web", "missing")),
+            // A probe depending on itself.
+            format!("probes:\n{}", probe("web", "web")),
+            // A cycle through a cron.
+            format!("probes:\n{}crons:\n  - name: job\n    interval: 1h\n    depends_on: [web]\n", probe("web", "job")),
+        ];
+
+        for (i, body) in cases.iter().enumerate() {
+            let path = dir.path().join(format!("bad-deps-{i}.yml"));
+            tokio::fs::write(&path, body).await.unwrap();
+            assert!(
+                Config::load_from_path(&path).await.is_err(),
+                "dependency config #{i} should be rejected: {body}"
+            );
+        }
+
+        // A chain (web -> lb, job -> lb) loads.
+        let ok = dir.path().join("ok.yml");
+        tokio::fs::write(
+            &ok,
+            format!("probes:\n{}{}crons:\n  - name: job\n    interval: 1h\n    depends_on: [lb]\n", probe("lb", ""), probe("web", "lb")),
+        )
+        .await
+        .unwrap();
+        let config = Config::load_from_path(&ok).await.expect("a dependency chain should load");
+        assert_eq!(config.dependencies()["web"], ["lb".to_string()]);
+        assert_eq!(config.dependencies()["job"], ["lb".to_string()]);
+    }
+
+    /// SLOs default their window and burn rate alerts, and a bad objective or a repeated name fails
+    /// the load.
+    #[tokio::test]
+    async fn loads_and_validates_slos() {
+        let dir = tempfile::tempdir().unwrap();
+        let slo = |name: &str, objective: &str| {
+            format!("  - name: {name}\n    selector: tags.service == \"Web\"\n    objective: {objective}\n")
+        };
+
+        let ok = dir.path().join("ok.yml");
+        tokio::fs::write(&ok, format!("slos:\n{}", slo("web", "99.9"))).await.unwrap();
+        let config = Config::load_from_path(&ok).await.expect("a valid SLO should load");
+        assert_eq!(config.slos[0].window, std::time::Duration::from_secs(30 * 24 * 3600));
+        assert_eq!(config.slos[0].alerts.len(), 2);
+
+        for (i, body) in [
+            format!("slos:\n{}", slo("web", "100")),
+            format!("slos:\n{}", slo("web", "-1")),
+            format!("slos:\n{}{}", slo("web", "99"), slo("web", "99.9")),
+        ]
+        .iter()
+        .enumerate()
+        {
+            let path = dir.path().join(format!("bad-slo-{i}.yml"));
+            tokio::fs::write(&path, body).await.unwrap();
+            assert!(Config::load_from_path(&path).await.is_err(), "SLO config #{i} should be rejected: {body}");
+        }
+    }
+
+    /// Metrics need a listener: either their own, or the UI's.
+    #[tokio::test]
+    async fn validates_metrics_listener() {
+        let dir = tempfile::tempdir().unwrap();
+        for (i, (body, valid)) in [
+            ("metrics: { enabled: true }\nui: { enabled: true }\n", true),
+            ("metrics: { enabled: true, listen: \"127.0.0.1:9090\" }\n", true),
+            ("metrics: { enabled: true }\n", false),
+        ]
+        .iter()
+        .enumerate()
+        {
+            let path = dir.path().join(format!("metrics-{i}.yml"));
+            tokio::fs::write(&path, body).await.unwrap();
+            assert_eq!(Config::load_from_path(&path).await.is_ok(), *valid, "metrics config #{i}: {body}");
+        }
+    }
+
+    /// A probe's sample log must be bounded by a non-zero count, age or both.
+    #[tokio::test]
+    async fn validates_sample_logs() {
+        let dir = tempfile::tempdir().unwrap();
+        let probe = |samples: &str| {
+            format!("probes:\n  - name: p\n    policy: {{ interval: 5s, timeout: 2s }}\n    target: !Http\n      url: https://example.com\n    samples: {samples}\n")
+        };
+
+        for (i, (samples, valid)) in [
+            ("{ count: 1000 }", true),
+            ("{ max_age: 7d }", true),
+            ("{ count: 100, max_age: 1h }", true),
+            ("{}", false),
+            ("{ count: 0 }", false),
+            ("{ max_age: 0s }", false),
+        ]
+        .iter()
+        .enumerate()
+        {
+            let path = dir.path().join(format!("samples-{i}.yml"));
+            tokio::fs::write(&path, probe(samples)).await.unwrap();
+            assert_eq!(Config::load_from_path(&path).await.is_ok(), *valid, "samples config #{i}: {samples}");
+        }
+    }
+
+    /// A probe's latency baseline needs a positive threshold and half-life, and must be able to reach
+    /// its `min_samples` at the probe's interval.
+    #[tokio::test]
+    async fn validates_latency_baselines() {
+        let dir = tempfile::tempdir().unwrap();
+        let probe = |baseline: &str| {
+            format!("probes:
+  - name: p
+    policy: {{ interval: 5s, timeout: 2s }}
+    target: !Http
+      url: https://example.com
+    baseline: {baseline}
+")
+        };
+
+        for (i, (baseline, valid)) in [
+            ("{}", true),
+            ("{ model: hourly, threshold: 2.5 }", true),
+            ("{ model: ewma, half_life: 1d, min_samples: 100 }", true),
+            ("{ model: weekly }", false),
+            ("{ threshold: 0 }", false),
+            ("{ half_life: 0s }", false),
+            ("{ model: hourly, min_samples: 720 }", true),
+            ("{ model: hourly, min_samples: 721 }", false),
+            ("{ model: ewma, min_samples: 120960 }", true),
+            ("{ model: ewma, min_samples: 120961 }", false),
+        ]
+        .iter()
+        .enumerate()
+        {
+            let path = dir.path().join(format!("baseline-{i}.yml"));
+            tokio::fs::write(&path, probe(baseline)).await.unwrap();
+            assert_eq!(Config::load_from_path(&path).await.is_ok(), *valid, "baseline config #{i}: {baseline}");
+        }
+    }
+
+    /// An HTTP target's HTML selectors are parsed when the configuration is loaded.
+    #[tokio::test]
+    async fn validates_html_options() {
+        let dir = tempfile::tempdir().unwrap();
+        let probe = |html: &str| {
+            format!("probes:
+  - name: p
+    policy: {{ interval: 5s, timeout: 2s }}
+    target: !Http
+      url: https://example.com
+      html: {html}
+")
+        };
+
+        for (i, (html, valid)) in [
+            ("{ selectors: { hero: h1.hero } }", true),
+            ("{ check_assets: true, max_assets: 20 }", true),
+            ("{ selectors: { hero: 'h1[' } }", false),
+            ("{ check_assets: true, max_assets: 0 }", false),
+        ]
+        .iter()
+        .enumerate()
+        {
+            let path = dir.path().join(format!("html-{i}.yml"));
+            tokio::fs::write(&path, probe(html)).await.unwrap();
+            assert_eq!(Config::load_from_path(&path).await.is_ok(), *valid, "html config #{i}: {html}");
+        }
+    }
+
+    /// Script files and shared modules are loaded relative to the configuration file, and editing
+    /// either one triggers a reload even though the configuration file itself is unchanged.
+    #[cfg(feature = "scripts")]
+    #[tokio::test]
+    async fn reloads_when_script_sources_change() {
+        let dir = tempfile::tempdir().unwrap();
+        tokio::fs::create_dir_all(dir.path().join("modules/lib")).await.unwrap();
+        tokio::fs::write(dir.path().join("modules/lib/util.js"), "export const x = 1;")
+            .await
+            .unwrap();
+        tokio::fs::write(dir.path().join("probe.js"), "output.ok = true;")
+            .await
+            .unwrap();
+
+        let path = dir.path().join("config.yml");
+        tokio::fs::write(
+            &path,
+            "script_modules: modules\nprobes:\n  - name: script\n    policy: { interval: 5s, timeout: 2s }\n    target: !Script\n      file: probe.js\n",
+        )
+        .await
+        .unwrap();
+
+        let (config, loaded_at) = Config::load_if_modified_since(&path, SystemTime::UNIX_EPOCH, &[])
+            .await
+            .unwrap()
+            .expect("the config to load");
+        assert_eq!(config.sources.len(), 2, "the script file and module directory are watched");
+        assert!(
+            Config::load_if_modified_since(&path, loaded_at + std::time::Duration::from_secs(1), &config.sources)
+                .await
+                .unwrap()
+                .is_none(),
+            "nothing has changed yet"
+        );
+
+        // Editing a module bumps its modification time past the last load.
+        let module = std::fs::File::options()
+            .write(true)
+            .open(dir.path().join("modules/lib/util.js"))
+            .unwrap();
+        module
+            .set_modified(loaded_at + std::time::Duration::from_secs(2))
+            .unwrap();
+        let (reloaded, _) = Config::load_if_modified_since(&path, loaded_at + std::time::Duration::from_secs(1), &config.sources)
+            .await
+            .unwrap()
+            .expect("the module edit to trigger a reload");
+        assert_eq!(reloaded.probes, config.probes);
+
+        // A script which can't be read fails the load.
+        tokio::fs::remove_file(dir.path().join("probe.js")).await.unwrap();
+        assert!(Config::load_from_path(&path).await.is_err());
+    }
+
+    #[test]
+    fn concurrency_limits_apply_by_tag_and_host() {
+        let mut probe = Probe::test();
+        probe.tags.insert("service".into(), "billing".into());
+        probe.target = serde_yaml::from_str("!Http\nurl: https://API.example.com:8443/health\n")
+            .expect("the target to parse");
+
+        let concurrency = ConcurrencyConfig {
+            max_concurrent_probes: Some(8),
+            per_tag: [("service".to_string(), 2), ("team".to_string(), 1)].into(),
+            per_host: Some(1),
+        };
+
+        assert_eq!(
+            concurrency.limits_for(&probe),
+            vec![
+                ("global".to_string(), 8),
+                ("tag:service=billing".to_string(), 2),
+                ("host:api.example.com".to_string(), 1),
+            ]
+        );
+        assert!(ConcurrencyConfig::default().limits_for(&probe).is_empty());
+        assert!(
+            ConcurrencyConfig { per_host: Some(0), ..Default::default() }
+                .validate()
+                .is_err()
+        );
+    }
 }
 
 mod default {
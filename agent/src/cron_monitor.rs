@@ -123,6 +123,7 @@ mod tests {
             tags: Default::default(),
             visible: crate::config::default_visible_filter(),
             alerting: Default::default(),
+            depends_on: Vec::new(),
         }
     }
 
@@ -5,6 +5,7 @@ use std::sync::{Arc, atomic::AtomicBool};
 use tracing_batteries::prelude::opentelemetry::trace::SpanKind as OpenTelemetrySpanKind;
 use tracing_batteries::prelude::*;
 
+use crate::limiter::ConcurrencyLimiter;
 use crate::probe_runner::ProbeRunner;
 use crate::state::{ProbeStore, State};
 use crate::{Probe, cluster};
@@ -12,10 +13,12 @@ use crate::{Probe, cluster};
 pub struct Engine {
     state: State,
     probes: Arc<RwLock<HashMap<String, Arc<ProbeRunner>>>>,
+    limiter: ConcurrencyLimiter,
 }
 
 impl Engine {
     pub fn new(state: State) -> Self {
+        let limiter = ConcurrencyLimiter::default();
         let probes: HashMap<String, Arc<ProbeRunner>> = state
             .get_config()
             .probes
@@ -23,7 +26,10 @@ impl Engine {
             .map(|probe| {
                 (
                     probe.name.clone(),
-                    Arc::new(ProbeRunner::new(probe.clone(), state.clone())),
+                    Arc::new(
+                        ProbeRunner::new(probe.clone(), state.clone())
+                            .with_limiter(limiter.clone()),
+                    ),
                 )
             })
             .collect();
@@ -31,6 +37,7 @@ impl Engine {
         Self {
             state,
             probes: Arc::new(RwLock::new(probes)),
+            limiter,
         }
     }
 
@@ -111,13 +118,27 @@ impl Engine {
                     .with_gossip_factor(config.cluster.gossip_factor)
                     .with_gossip_interval(config.cluster.gossip_interval)
                     .with_seed_resolve_interval(config.cluster.peer_resolve_interval)
-                    .with_seed_peers(config.cluster.peers.clone());
+                    .with_seed_peers(config.cluster.peers.clone())
+                    .with_stats(self.state.gossip_stats());
 
             tokio::task::spawn_local(async move {
                 cluster_client.run().await;
             });
         }
 
+        if let Some(listen) = self.state.get_config().metrics.listen.clone()
+            && self.state.get_config().metrics.enabled
+        {
+            info!("Starting metrics endpoint on http://{listen}/metrics");
+
+            let state = self.state.clone();
+            tokio::task::spawn_local(async move {
+                if let Err(err) = crate::api::start_metrics_server(state, listen).await {
+                    error!(name: "metrics.server", { exception = err }, "Failed to serve metrics: {err}");
+                }
+            });
+        }
+
         if self.state.get_config().ui.enabled {
             info!(
                 "Starting web UI on http://{}",
@@ -153,6 +174,7 @@ impl Engine {
     fn start_config_reloader(&self) {
         let state = self.state.clone();
         let probes = self.probes.clone();
+        let limiter = self.limiter.clone();
         tokio::task::spawn_local(async move {
             let mut current_probes = state.get_config().probes.clone();
             loop {
@@ -202,8 +224,10 @@ impl Engine {
                             // New probe has been added
                             let name = name.to_string();
                             info!(name: "config.reload.probe", { probe.name=name, action = "add" }, "Added configuration for probe {}", name);
-                            let probe =
-                                Arc::new(ProbeRunner::new(new_probe.clone(), state.clone()));
+                            let probe = Arc::new(
+                                ProbeRunner::new(new_probe.clone(), state.clone())
+                                    .with_limiter(limiter.clone()),
+                            );
 
                             probes
                                 .write()
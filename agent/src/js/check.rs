@@ -0,0 +1,240 @@
+use std::{cell::RefCell, rc::Rc};
+
+use boa_engine::{
+    Context, JsError, JsNativeError, JsResult, JsValue, Script, Source,
+    builtins::promise::PromiseState, js_string,
+};
+use serde::{Deserialize, Serialize};
+
+use super::{JobQueue, ScriptLimits, ToJs, TraceLogger, limit_exceeded, script_error};
+use crate::Sample;
+use crate::checks::CheckSeverity;
+
+/// Wraps the sample handed to a check in a proxy which records the fields the check reads, so that
+/// a failure can report the values it saw just as a `filt-rs` check's does.
+const TRACK_READS: &str = r#"(function (fields) {
+    const read = [];
+    const sample = new Proxy(fields, {
+        get(target, key) {
+            if (typeof key === "string" && !read.includes(key)) {
+                read.push(key);
+            }
+
+            return target[key];
+        },
+    });
+
+    return { sample, read };
+})"#;
+
+/// A probe check written in JavaScript, for assertions which are awkward to express in `filt-rs`
+/// such as comparing two fields, date arithmetic or parsing a body.
+///
+/// The `code` is the body of an async function which receives the probe's sample as `sample`,
+/// keyed by field name (`sample["http.status"]`), with datetimes as `Date`s and durations in
+/// milliseconds. The check passes when it returns a truthy value and fails when it returns a falsy
+/// one, or throws, in which case the error's message is reported.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ScriptCheck {
+    /// The name the check is reported under. It is required because the check's results are
+    /// keyed by it in every gossiped observation, which its code could easily outgrow.
+    pub name: String,
+    pub code: String,
+    /// The budgets bounding how much work each evaluation of the check may do.
+    #[serde(default)]
+    pub limits: ScriptLimits,
+    /// Whether a failure fails the probe (`critical`) or only marks it as degraded (`warning`).
+    #[serde(default)]
+    pub severity: CheckSeverity,
+}
+
+/// How a script check concluded, along with the sample fields it read on the way.
+#[derive(Debug, PartialEq)]
+pub(crate) enum ScriptCheckOutcome {
+    Passed,
+    Failed { fields: Vec<String> },
+    Threw { message: String, fields: Vec<String> },
+}
+
+impl ScriptCheck {
+    /// Parses the check's code, so that syntax errors are reported when the configuration is
+    /// loaded rather than whenever the check runs.
+    pub fn validate(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let context = &mut Context::default();
+        Script::parse(Source::from_bytes(&self.source()), None, context)
+            .map_err(|e| format!("The script check '{}' is invalid: {}", self, e))?;
+        Ok(())
+    }
+
+    /// Evaluates the check against `sample`.
+    pub(crate) async fn run(
+        &self,
+        sample: &Sample,
+    ) -> Result<ScriptCheckOutcome, Box<dyn std::error::Error>> {
+        let executor = Rc::new(JobQueue::new());
+        let context = &mut super::budgeted().job_executor(executor.clone()).build()?;
+
+        boa_runtime::register(
+            (boa_runtime::extensions::ConsoleExtension(TraceLogger),),
+            None,
+            context,
+        )?;
+        super::web::register(context)?;
+        self.limits.apply(context);
+
+        let check = context.eval(Source::from_bytes(&self.source()))?;
+        let track = context.eval(Source::from_bytes(TRACK_READS))?;
+        let fields = sample.to_js(context)?;
+        let tracked = callable(&track)?.call(&JsValue::undefined(), &[fields], context)?;
+        let tracked = tracked
+            .as_object()
+            .ok_or_else(|| JsNativeError::typ().with_message("expected the tracked sample"))?;
+        let proxy = tracked.get(js_string!("sample"), context)?;
+        let read = tracked.get(js_string!("read"), context)?;
+
+        let result = callable(&check)?
+            .call(&JsValue::undefined(), &[proxy], context)
+            .map_err(script_error)?;
+
+        {
+            let jobs = RefCell::new(&mut *context);
+            let finished = match self.limits.max_duration {
+                Some(limit) => tokio::time::timeout(limit, executor.run_jobs_async(&jobs))
+                    .await
+                    .map_err(|_| self.limits.duration_exceeded(limit))?,
+                None => executor.run_jobs_async(&jobs).await,
+            };
+            finished.map_err(script_error)?;
+        }
+
+        let fields = read_fields(&read, context)?;
+        let promise = result
+            .as_promise()
+            .ok_or_else(|| JsNativeError::typ().with_message("expected the check to return a promise"))?;
+
+        match promise.state() {
+            PromiseState::Fulfilled(value) if value.to_boolean() => Ok(ScriptCheckOutcome::Passed),
+            PromiseState::Fulfilled(_) => Ok(ScriptCheckOutcome::Failed { fields }),
+            PromiseState::Rejected(err) => {
+                let error = JsError::from_opaque(err.clone());
+                if let Ok(native) = error.try_native(context)
+                    && limit_exceeded(&native.into())
+                {
+                    return Err(format!("script.limit_exceeded: {}", err.display()).into());
+                }
+
+                let message = match err.as_object() {
+                    Some(err) if err.has_property(js_string!("message"), context)? => {
+                        err.get(js_string!("message"), context)?
+                    }
+                    _ => err.clone(),
+                };
+
+                Ok(ScriptCheckOutcome::Threw {
+                    message: message.to_string(context)?.to_std_string_lossy(),
+                    fields,
+                })
+            }
+            PromiseState::Pending => Err("The check awaits unresolvable promises.".into()),
+        }
+    }
+
+    fn source(&self) -> String {
+        format!("(async function (sample) {{\n{}\n}})", self.code)
+    }
+}
+
+impl std::fmt::Display for ScriptCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+fn callable(value: &JsValue) -> JsResult<boa_engine::JsObject> {
+    value
+        .as_callable()
+        .map(|callable| callable.clone())
+        .ok_or_else(|| JsNativeError::typ().with_message("expected a function").into())
+}
+
+fn read_fields(read: &JsValue, context: &mut Context) -> JsResult<Vec<String>> {
+    let Some(read) = read.as_object() else {
+        return Ok(Vec::new());
+    };
+
+    let length = read.get(js_string!("length"), context)?.to_length(context)? as usize;
+    (0..length)
+        .map(|i| Ok(read.get(i, context)?.to_string(context)?.to_std_string_lossy()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(code: &str) -> ScriptCheck {
+        ScriptCheck {
+            name: "check".into(),
+            code: code.into(),
+            limits: ScriptLimits::default(),
+            severity: CheckSeverity::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_passes_and_fails() {
+        let sample = Sample::default()
+            .with("http.header.etag", "abc")
+            .with("http.header.x-etag", "abc")
+            .with("http.status", 200);
+
+        assert_eq!(
+            check(r#"return sample["http.header.etag"] === sample["http.header.x-etag"]"#)
+                .run(&sample)
+                .await
+                .unwrap(),
+            ScriptCheckOutcome::Passed
+        );
+
+        assert_eq!(
+            check(r#"return sample["http.status"] === 404 && sample["http.body"] !== null"#)
+                .run(&sample)
+                .await
+                .unwrap(),
+            ScriptCheckOutcome::Failed {
+                fields: vec!["http.status".into()]
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_throws() {
+        let sample = Sample::default().with("http.body", "{\"ok\": false}");
+
+        assert_eq!(
+            check(r#"if (!JSON.parse(sample["http.body"]).ok) { throw new Error("the service is not ok"); } return true;"#)
+                .run(&sample)
+                .await
+                .unwrap(),
+            ScriptCheckOutcome::Threw {
+                message: "the service is not ok".into(),
+                fields: vec!["http.body".into()]
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_limits() {
+        let err = check("while (true) {}")
+            .run(&Sample::default())
+            .await
+            .expect_err("the loop budget should stop the check");
+        assert!(err.to_string().starts_with("script.limit_exceeded"), "{err}");
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(check("return true").validate().is_ok());
+        assert!(check("return (").validate().is_err());
+    }
+}
@@ -49,11 +49,11 @@ impl Logger for TraceLogger {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use boa_engine::{Context, Source};
+    use boa_engine::Source;
 
     #[test]
     fn test_console_logging() {
-        let mut context = Context::default();
+        let mut context = crate::js::budgeted().build().unwrap();
         boa_runtime::register((boa_runtime::extensions::ConsoleExtension(TraceLogger),), None, &mut context).unwrap();
         
         context
@@ -1,35 +1,171 @@
-use boa_engine::{Context, JsData, JsObject, JsResult};
+use boa_engine::{
+    Context, JsData, JsError, JsObject, JsResult, JsString, JsValue, NativeFunction, js_string,
+};
 use boa_gc::{Finalize, Trace};
 use boa_runtime::fetch::{Fetcher, request::JsRequest, response::JsResponse};
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use serde::{Deserialize, Serialize};
+use std::{cell::RefCell, collections::HashMap, rc::Rc, time::Duration};
+use tokio::sync::Notify;
+use tracing_batteries::prelude::opentelemetry::trace::SpanKind as OpenTelemetrySpanKind;
+use tracing::Instrument;
 use tracing_batteries::prelude::*;
 
 use crate::version;
 
+/// The HTTP client configuration used for a script's `fetch` calls.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct FetchOptions {
+    /// How long each request may take, including reading its response body. Individual requests
+    /// can set a shorter deadline with `AbortSignal.timeout(ms)`.
+    #[serde(default, with = "humantime_serde::option")]
+    pub timeout: Option<Duration>,
+
+    /// PEM encoded CA certificates to trust in addition to the system's roots.
+    #[serde(default)]
+    pub ca_cert: Option<String>,
+
+    /// A PEM encoded client certificate chain and private key presented to servers requesting
+    /// mutual TLS.
+    #[serde(default)]
+    pub client_identity: Option<String>,
+
+    /// The proxy every request is sent through, e.g. `http://proxy.internal:3128`.
+    #[serde(default)]
+    pub proxy: Option<String>,
+
+    /// Disables verification of the certificates presented by servers.
+    #[serde(default)]
+    pub no_verify: bool,
+}
+
+impl FetchOptions {
+    /// Builds the client these options describe.
+    pub fn client(&self) -> Result<reqwest::Client, Box<dyn std::error::Error>> {
+        let mut builder = reqwest::ClientBuilder::new()
+            .user_agent(version!("SierraSoftworks/grey@v"))
+            .danger_accept_invalid_certs(self.no_verify);
+
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+
+        if let Some(pem) = &self.ca_cert {
+            let certs = reqwest::Certificate::from_pem_bundle(pem.as_bytes())
+                .map_err(|e| format!("The fetch 'ca_cert' is not a valid PEM bundle: {e}"))?;
+            if certs.is_empty() {
+                return Err(
+                    "The fetch 'ca_cert' did not contain any PEM encoded certificates.".into(),
+                );
+            }
+
+            for cert in certs {
+                builder = builder.add_root_certificate(cert);
+            }
+        }
+
+        if let Some(pem) = &self.client_identity {
+            builder = builder.identity(reqwest::Identity::from_pem(pem.as_bytes()).map_err(|e| {
+                format!("The fetch 'client_identity' is not a valid PEM certificate and key: {e}")
+            })?);
+        }
+
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(
+                reqwest::Proxy::all(proxy)
+                    .map_err(|e| format!("The fetch 'proxy' '{proxy}' is not valid: {e}"))?,
+            );
+        }
+
+        Ok(builder.build()?)
+    }
+}
+
 #[derive(Clone, Debug, Trace, Finalize, JsData, Default)]
 pub(crate) struct ReqwestFetcher {
     #[unsafe_ignore_trace]
     client: reqwest::Client,
 }
 
+impl ReqwestFetcher {
+    /// A fetcher sending its requests through `client` (see [`FetchOptions::client`]).
+    pub fn new(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+}
+
 impl Fetcher for ReqwestFetcher {
     async fn fetch(
         self: Rc<Self>,
         request: JsRequest,
-        _signal: Option<JsObject>,
-        _context: &RefCell<&mut Context>,
+        signal: Option<JsObject>,
+        context: &RefCell<&mut Context>,
     ) -> JsResult<JsResponse> {
-        let client = self.client.clone();
-
-        use boa_engine::{JsError, JsString};
-
         let request = request.into_inner();
         let url = request.uri().to_string();
-        let req = client
-            .request(request.method().clone(), &url)
-            .header("User-Agent", version!("SierraSoftworks/grey@v"));
+        let uri = request.uri();
+
+        let span = info_span!(
+            "script.fetch",
+            otel.kind = ?OpenTelemetrySpanKind::Client,
+            otel.status_code = EmptyField,
+            http.request.method = %request.method(),
+            url.full = %url,
+            server.address = uri.host().unwrap_or_default(),
+            server.port = uri.port_u16().or_else(|| match uri.scheme_str() {
+                Some("https") => Some(443),
+                Some("http") => Some(80),
+                _ => None,
+            }),
+            http.request.body.size = request.body().len(),
+            http.response.status_code = EmptyField,
+            http.response.body.size = EmptyField,
+            error.type = EmptyField,
+        );
+
+        let response = async {
+            let exchange = self.exchange(&request, &url);
+            match signal {
+                Some(signal) => {
+                    tokio::select! {
+                        response = exchange => response,
+                        reason = aborted(&signal, context) => Err(reason),
+                    }
+                }
+                None => exchange.await,
+            }
+        }
+        .instrument(span.clone())
+        .await;
+
+        match &response {
+            Ok(response) => {
+                span.record("http.response.status_code", response.status().as_u16())
+                    .record("http.response.body.size", response.body().len());
+                if response.status().is_server_error() {
+                    span.record("otel.status_code", "Error")
+                        .record("error.type", response.status().as_str());
+                }
+            }
+            Err(err) => {
+                span.record("otel.status_code", "Error")
+                    .record("error.type", display(err));
+            }
+        }
+
+        response.map(|response| JsResponse::basic(JsString::from(url), response))
+    }
+}
+
+impl ReqwestFetcher {
+    /// Sends `request` and reads its response, within the current (`script.fetch`) span.
+    async fn exchange(
+        &self,
+        request: &http::Request<Vec<u8>>,
+        url: &str,
+    ) -> JsResult<http::Response<Vec<u8>>> {
+        let req = self.client.request(request.method().clone(), url);
 
-        // Inject trace headers automatically
+        // Inject trace headers automatically, parenting downstream spans to this request's span.
         let mut trace_headers = HashMap::new();
         tracing_batteries::prelude::opentelemetry::global::get_text_map_propagator(|p| {
             p.inject_context(&Span::current().context(), &mut trace_headers)
@@ -44,7 +180,7 @@ impl Fetcher for ReqwestFetcher {
             .build()
             .map_err(JsError::from_rust)?;
 
-        let resp = client.execute(req).await.map_err(JsError::from_rust)?;
+        let resp = self.client.execute(req).await.map_err(JsError::from_rust)?;
 
         let status = resp.status();
         let headers = resp.headers().clone();
@@ -57,18 +193,77 @@ impl Fetcher for ReqwestFetcher {
             }
         }
 
-        builder
-            .body(bytes.to_vec())
-            .map_err(JsError::from_rust)
-            .map(|response| JsResponse::basic(JsString::from(url), response))
+        builder.body(bytes.to_vec()).map_err(JsError::from_rust)
     }
 }
 
+/// Resolves with the signal's `reason` once `signal` has been aborted, so the request it was given
+/// to can be abandoned. An `abort` listener added to the signal wakes this as soon as the script's
+/// `abort()` call, or the timer behind `AbortSignal.timeout`, trips it.
+async fn aborted(signal: &JsObject, context: &RefCell<&mut Context>) -> JsError {
+    let notify = Rc::new(Notify::new());
+    let listening = {
+        let context = &mut context.borrow_mut();
+        if let Some(reason) = abort_reason(signal, context) {
+            return reason;
+        }
+
+        let listener = NativeFunction::from_copy_closure_with_captures(
+            |_, _, listener: &AbortListener, _| {
+                listener.notify.notify_one();
+                Ok(JsValue::undefined())
+            },
+            AbortListener { notify: notify.clone() },
+        )
+        .to_js_function(context.realm());
+
+        let added = signal
+            .get(js_string!("addEventListener"), context)
+            .ok()
+            .and_then(|add| {
+                Some(add.as_callable()?.call(
+                    &signal.clone().into(),
+                    &[js_string!("abort").into(), listener.into()],
+                    context,
+                ))
+            });
+
+        matches!(added, Some(Ok(_)))
+    };
+
+    // Something other than an `AbortSignal` was passed, which can never abort the request.
+    if !listening {
+        return std::future::pending().await;
+    }
+
+    loop {
+        notify.notified().await;
+        if let Some(reason) = abort_reason(signal, &mut context.borrow_mut()) {
+            return reason;
+        }
+    }
+}
+
+/// The `reason` `signal` was aborted with, or `None` while it hasn't been.
+fn abort_reason(signal: &JsObject, context: &mut Context) -> Option<JsError> {
+    signal
+        .get(js_string!("aborted"), context)
+        .is_ok_and(|aborted| aborted.to_boolean())
+        .then(|| JsError::from_opaque(signal.get(js_string!("reason"), context).unwrap_or_default()))
+}
+
+/// The native `abort` listener [`aborted`] adds to a request's signal, waking the request.
+#[derive(Trace, Finalize)]
+struct AbortListener {
+    #[unsafe_ignore_trace]
+    notify: Rc<Notify>,
+}
+
 #[cfg(test)]
 #[cfg(not(feature = "pure_tests"))]
 mod tests {
     use super::*;
-    use boa_engine::{Context, Source};
+    use boa_engine::Source;
     use boa_engine::builtins::promise::PromiseState;
     use boa_engine::job::JobExecutor;
     use crate::js::JobQueue;
@@ -88,7 +283,7 @@ mod tests {
         let mock_url = format!("{}/test", mock_server.uri());
 
         let job_queue = Rc::new(JobQueue::new());
-        let mut context = Context::builder()
+        let mut context = crate::js::budgeted()
             .job_executor(job_queue.clone())
             .build().unwrap();
 
@@ -122,4 +317,68 @@ mod tests {
             }
         }
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_fetch_abort_signal() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/slow"))
+            .respond_with(ResponseTemplate::new(200).set_delay(std::time::Duration::from_secs(10)))
+            .mount(&mock_server)
+            .await;
+
+        let job_queue = Rc::new(JobQueue::new());
+        let mut context = crate::js::budgeted()
+            .job_executor(job_queue.clone())
+            .build().unwrap();
+        crate::js::setup_runtime(
+            &mut context,
+            ReqwestFetcher::default(),
+            crate::js::SessionStorage::default(),
+            None,
+            crate::js::NetPolicy::default(),
+            crate::js::ScriptResults::default(),
+            vec![],
+        ).unwrap();
+
+        let script = format!(r#"
+            async function test() {{
+                const controller = new AbortController();
+                setTimeout(() => controller.abort(), 20);
+                const aborted = await fetch('{url}', {{ signal: controller.signal }}).then(() => "completed", (e) => e.name);
+                const timedOut = await fetch('{url}', {{ signal: AbortSignal.timeout(20) }}).then(() => "completed", (e) => e.name);
+                const already = await fetch('{url}', {{ signal: AbortSignal.abort() }}).then(() => "completed", (e) => e.name);
+                return `${{aborted}} ${{timedOut}} ${{already}}`;
+            }}
+            test();
+        "#, url = format!("{}/slow", mock_server.uri()));
+
+        let started = std::time::Instant::now();
+        let result = context.eval(Source::from_bytes(script.as_bytes())).unwrap();
+        let promise = result.as_promise().expect("Expected a Promise");
+        job_queue.run_jobs_async(&RefCell::new(&mut context)).await.unwrap();
+        assert!(started.elapsed() < std::time::Duration::from_secs(5), "the requests should be abandoned");
+        match promise.state() {
+            PromiseState::Fulfilled(value) => {
+                assert_eq!(value.to_string(&mut context).unwrap().to_std_string_lossy(), "AbortError TimeoutError AbortError");
+            }
+            _ => panic!("Expected the script to complete"),
+        }
+    }
+
+    #[test]
+    fn test_fetch_options_client() {
+        FetchOptions::default().client().expect("the default options to build a client");
+
+        let options: FetchOptions = serde_yaml::from_str("timeout: 5s\nproxy: http://proxy.internal:3128\nno_verify: true\n").unwrap();
+        assert_eq!(options.timeout, Some(std::time::Duration::from_secs(5)));
+        options.client().expect("a proxied client to build");
+
+        let invalid = FetchOptions {
+            ca_cert: Some("not a certificate".into()),
+            ..Default::default()
+        };
+        assert!(invalid.client().is_err(), "a CA bundle without certificates should be rejected");
+    }
+}
@@ -0,0 +1,79 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+thread_local! {
+    static ALLOCATED: Cell<usize> = const { Cell::new(0) };
+}
+
+/// The system allocator, counting the bytes each thread holds so that a script's thread can be held
+/// to its `max_heap_size` (see [`super::ScriptLimits`]).
+///
+/// Memory is attributed to the thread which allocated it and only released from that thread's count
+/// when the same thread frees it, so the count is an estimate: it errs high for buffers handed off
+/// to another thread, and low (but never below zero) for buffers received from one.
+pub struct HeapTracker;
+
+impl HeapTracker {
+    /// The number of bytes the current thread has allocated and not yet freed.
+    pub fn allocated() -> usize {
+        ALLOCATED.try_with(Cell::get).unwrap_or_default()
+    }
+
+    fn record(allocated: usize, freed: usize) {
+        let _ = ALLOCATED.try_with(|count| {
+            count.set(count.get().saturating_add(allocated).saturating_sub(freed))
+        });
+    }
+}
+
+unsafe impl GlobalAlloc for HeapTracker {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc(layout) };
+        if !ptr.is_null() {
+            Self::record(layout.size(), 0);
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc_zeroed(layout) };
+        if !ptr.is_null() {
+            Self::record(layout.size(), 0);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) };
+        Self::record(0, layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new = unsafe { System.realloc(ptr, layout, new_size) };
+        if !new.is_null() {
+            Self::record(new_size, layout.size());
+        }
+        new
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allocations_are_counted_per_thread() {
+        std::thread::spawn(|| {
+            let before = HeapTracker::allocated();
+            let buffer = vec![0u8; 1 << 20];
+            assert!(HeapTracker::allocated() >= before + (1 << 20));
+
+            drop(buffer);
+            assert!(HeapTracker::allocated() < before + (1 << 20));
+        })
+        .join()
+        .unwrap();
+    }
+}
@@ -1,5 +1,5 @@
 use boa_engine::{
-    Context, JsResult,
+    Context, JsNativeError, JsResult,
     context::time::JsInstant,
     job::{GenericJob, Job, JobExecutor, NativeAsyncJob, PromiseJob, TimeoutJob},
 };
@@ -18,6 +18,7 @@ pub(crate) struct JobQueue {
     promise_jobs: RefCell<VecDeque<PromiseJob>>,
     timeout_jobs: RefCell<BTreeMap<JsInstant, TimeoutJob>>,
     generic_jobs: RefCell<VecDeque<GenericJob>>,
+    max_heap_size: Option<u64>,
 }
 
 impl JobQueue {
@@ -27,10 +28,30 @@ impl JobQueue {
             promise_jobs: RefCell::default(),
             timeout_jobs: RefCell::default(),
             generic_jobs: RefCell::default(),
+            max_heap_size: None,
         }
     }
 
-    fn drain_timeout_jobs(&self, context: &mut Context) {
+    /// Stops the run once its thread holds more than `limit` bytes, checked between jobs.
+    pub fn with_max_heap_size(mut self, limit: u64) -> Self {
+        self.max_heap_size = Some(limit);
+        self
+    }
+
+    fn check_heap(&self) -> JsResult<()> {
+        match self.max_heap_size {
+            Some(limit) if super::HeapTracker::allocated() as u64 > limit => {
+                Err(JsNativeError::runtime_limit()
+                    .with_message(format!(
+                        "the script holds more than its {limit} byte heap budget"
+                    ))
+                    .into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn drain_timeout_jobs(&self, context: &mut Context) -> JsResult<()> {
         let now = context.clock().now();
 
         let mut timeouts_borrow = self.timeout_jobs.borrow_mut();
@@ -40,30 +61,27 @@ impl JobQueue {
         drop(timeouts_borrow);
 
         for job in jobs_to_run.into_values() {
-            if let Err(e) = job.call(context) {
-                tracing::error!("Uncaught {e}");
-            }
+            uncaught(job.call(context))?;
         }
+
+        Ok(())
     }
 
-    fn drain_jobs(&self, context: &mut Context) {
+    fn drain_jobs(&self, context: &mut Context) -> JsResult<()> {
         // Run the timeout jobs first.
-        self.drain_timeout_jobs(context);
+        self.drain_timeout_jobs(context)?;
 
         let job = self.generic_jobs.borrow_mut().pop_front();
         if let Some(generic) = job {
-            if let Err(err) = generic.call(context) {
-                tracing::error!("Uncaught {err}");
-            }
+            uncaught(generic.call(context))?;
         }
 
         let jobs = std::mem::take(&mut *self.promise_jobs.borrow_mut());
         for job in jobs {
-            if let Err(e) = job.call(context) {
-                tracing::error!("Uncaught {e}");
-            }
+            uncaught(job.call(context))?;
         }
         context.clear_kept_objects();
+        Ok(())
     }
 }
 
@@ -112,13 +130,28 @@ impl JobExecutor for JobQueue {
             // We have some jobs pending on the microtask queue. Try to poll the pending
             // tasks once to see if any of them finished, and run the pending microtasks
             // otherwise.
-            if let Some(Err(err)) = future::poll_once(group.next()).await.flatten() {
-                tracing::error!("Uncaught {err}");
+            if let Some(result) = future::poll_once(group.next()).await.flatten() {
+                uncaught(result)?;
             };
 
             // Only one macrotask can be executed before the next drain of the microtask queue.
-            self.drain_jobs(&mut context.borrow_mut());
+            self.drain_jobs(&mut context.borrow_mut())?;
+            self.check_heap()?;
             tokio::task::yield_now().await
         }
     }
 }
+
+/// Logs an error which escaped a job without being handled by the script. Errors raised because the
+/// script exceeded one of its budgets (see [`super::ScriptLimits`]) are returned instead, aborting
+/// the run.
+fn uncaught(result: JsResult<impl Sized>) -> JsResult<()> {
+    match result {
+        Err(err) if super::limits::limit_exceeded(&err) => Err(err),
+        Err(err) => {
+            tracing::error!("Uncaught {err}");
+            Ok(())
+        }
+        Ok(_) => Ok(()),
+    }
+}
@@ -0,0 +1,125 @@
+use std::time::Duration;
+
+use boa_engine::{Context, JsError, JsNativeErrorKind, context::ContextBuilder};
+use serde::{Deserialize, Serialize};
+
+/// The budgets bounding how much work a single script run may do. Exceeding any of them aborts the
+/// run with a `script.limit_exceeded` error.
+///
+/// Each run executes on its own thread, so a script which never yields can't hold up the agent's
+/// other probes, and a run which is cancelled or outlives its `max_duration` is abandoned at once. The
+/// engine can't be interrupted mid-statement though, so an abandoned run keeps its thread busy until
+/// it next yields or exhausts its `max_instructions` budget. The heap budget is checked each time the
+/// script yields, leaving a synchronous stretch of script bounded by its instruction budget alone.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ScriptLimits {
+    /// The most iterations any single loop may run for. Defaults to 1,000,000.
+    #[serde(default = "default_max_loop_iterations")]
+    pub max_loop_iterations: u64,
+
+    /// The deepest the script's call stack may grow (defaults to the engine's limit of 512).
+    #[serde(default)]
+    pub max_recursion_depth: Option<usize>,
+
+    /// The most values the engine's stack may hold at once, bounding the memory a script can pin
+    /// through deeply nested calls and large temporaries (defaults to the engine's limit).
+    #[serde(default)]
+    pub max_stack_size: Option<usize>,
+
+    /// The most instructions the engine may execute over the whole run, across every loop, callback
+    /// and promise job. Defaults to 100,000,000.
+    #[serde(default = "default_max_instructions")]
+    pub max_instructions: usize,
+
+    /// The most memory, in bytes, the script's thread may hold. Defaults to 256 MiB.
+    #[serde(default = "default_max_heap_size")]
+    pub max_heap_size: u64,
+
+    /// How long the script may run for in total, including time spent awaiting timers and requests.
+    /// When unset, only the probe policy's `timeout` applies.
+    #[serde(default, with = "humantime_serde::option")]
+    pub max_duration: Option<Duration>,
+}
+
+impl Default for ScriptLimits {
+    fn default() -> Self {
+        Self {
+            max_loop_iterations: default_max_loop_iterations(),
+            max_recursion_depth: None,
+            max_stack_size: None,
+            max_instructions: default_max_instructions(),
+            max_heap_size: default_max_heap_size(),
+            max_duration: None,
+        }
+    }
+}
+
+impl ScriptLimits {
+    /// Gives the context being built its total instruction budget.
+    pub fn budget(&self, builder: ContextBuilder) -> ContextBuilder {
+        builder.instructions_remaining(self.max_instructions)
+    }
+
+    /// Applies the engine-enforced budgets to `context`.
+    pub fn apply(&self, context: &mut Context) {
+        let limits = context.runtime_limits_mut();
+        limits.set_loop_iteration_limit(self.max_loop_iterations);
+
+        if let Some(depth) = self.max_recursion_depth {
+            limits.set_recursion_limit(depth);
+        }
+
+        if let Some(size) = self.max_stack_size {
+            limits.set_stack_size_limit(size);
+        }
+    }
+
+    /// The error reported when a run outlives its `max_duration`.
+    pub fn duration_exceeded(&self, limit: Duration) -> Box<dyn std::error::Error> {
+        format!(
+            "script.limit_exceeded: the script ran for longer than its {} limit",
+            humantime::format_duration(limit)
+        )
+        .into()
+    }
+}
+
+/// A builder for contexts which don't run under a script target's own [`ScriptLimits`], such as
+/// those evaluating checks, bounding them by the default instruction budget.
+pub fn budgeted() -> ContextBuilder {
+    ScriptLimits::default().budget(Context::builder())
+}
+
+/// Whether `err` was raised by the engine because the script exceeded one of its budgets. These
+/// errors can't be caught by the script itself.
+pub fn limit_exceeded(err: &JsError) -> bool {
+    err.as_native()
+        .is_some_and(|native| {
+            matches!(
+                native.kind,
+                JsNativeErrorKind::RuntimeLimit | JsNativeErrorKind::NoInstructionsRemain
+            )
+        })
+}
+
+/// Converts a script error into the one reported for the run, marking budget overruns as
+/// `script.limit_exceeded` so they can be told apart from failures of the script's own logic.
+pub fn script_error(err: JsError) -> Box<dyn std::error::Error> {
+    if limit_exceeded(&err) {
+        format!("script.limit_exceeded: {err}").into()
+    } else {
+        err.into()
+    }
+}
+
+fn default_max_loop_iterations() -> u64 {
+    1_000_000
+}
+
+fn default_max_instructions() -> usize {
+    100_000_000
+}
+
+fn default_max_heap_size() -> u64 {
+    256 * 1024 * 1024
+}
@@ -1,13 +1,28 @@
+mod check;
 mod console;
 mod fetch;
+mod heap;
 mod job_queue;
+mod limits;
+mod modules;
+mod net;
+mod persistent_storage;
 mod runtime;
 mod storage;
+mod testing;
 mod to_sample;
+mod web;
 
+pub(crate) use check::{ScriptCheck, ScriptCheckOutcome};
 pub(crate) use console::TraceLogger;
-pub(crate) use fetch::ReqwestFetcher;
+pub(crate) use fetch::{FetchOptions, ReqwestFetcher};
+pub(crate) use heap::HeapTracker;
 pub(crate) use job_queue::JobQueue;
+pub(crate) use limits::{ScriptLimits, budgeted, limit_exceeded, script_error};
+pub(crate) use modules::ScriptModules;
+pub(crate) use net::NetPolicy;
+pub(crate) use persistent_storage::ScriptStorage;
 pub(crate) use runtime::setup_runtime;
 pub(crate) use storage::SessionStorage;
-pub(crate) use to_sample::JsInto;
+pub(crate) use testing::ScriptResults;
+pub(crate) use to_sample::{JsInto, ToJs};
@@ -0,0 +1,93 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use boa_engine::{Context, JsResult, Module, Source, module::SimpleModuleLoader};
+
+/// The shared ES modules configured through `script_modules`, which scripts may `import` by their
+/// path relative to the modules directory (e.g. `import { getAccessToken } from "lib/azure.js"`).
+///
+/// The modules are read once when the configuration is loaded, so every run sees the same code
+/// and an edit is picked up (like any other configuration change) by the config reloader.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptModules {
+    root: PathBuf,
+    files: Arc<BTreeMap<PathBuf, String>>,
+}
+
+impl ScriptModules {
+    /// Reads every `.js` and `.mjs` file beneath `dir`, failing if any of them doesn't parse so that a
+    /// broken module is reported when the configuration is loaded rather than on every script run.
+    pub async fn load(dir: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let root = tokio::fs::canonicalize(dir).await.map_err(|e| {
+            format!(
+                "Failed to open the script modules directory {}: {}",
+                dir.display(),
+                e
+            )
+        })?;
+
+        let mut files = BTreeMap::new();
+        let mut pending = vec![root.clone()];
+        while let Some(dir) = pending.pop() {
+            let mut entries = tokio::fs::read_dir(&dir).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                let path = entry.path();
+                if tokio::fs::metadata(&path).await?.is_dir() {
+                    pending.push(path);
+                } else if matches!(
+                    path.extension().and_then(|ext| ext.to_str()),
+                    Some("js" | "mjs")
+                ) {
+                    let code = tokio::fs::read_to_string(&path).await.map_err(|e| {
+                        format!("Failed to read script module {}: {}", path.display(), e)
+                    })?;
+                    files.insert(path, code);
+                }
+            }
+        }
+
+        let modules = Self {
+            root,
+            files: Arc::new(files),
+        };
+        modules.validate()?;
+        Ok(modules)
+    }
+
+    /// The directory the modules were loaded from, which import specifiers are resolved against.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Registers every module with `loader`, so imports are served from the loaded copies rather
+    /// than read from disk.
+    pub fn register(&self, loader: &SimpleModuleLoader, context: &mut Context) -> JsResult<()> {
+        for (path, code) in self.files.iter() {
+            let module = Module::parse(
+                Source::from_bytes(code.as_bytes()).with_path(path),
+                None,
+                context,
+            )?;
+            loader.insert(path.clone(), module);
+        }
+
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let context = &mut Context::default();
+        for (path, code) in self.files.iter() {
+            Module::parse(
+                Source::from_bytes(code.as_bytes()).with_path(path),
+                None,
+                context,
+            )
+            .map_err(|e| format!("Failed to parse script module {}: {}", path.display(), e))?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,105 @@
+// Installs the `net` and `dns` globals documented in `net.rs` onto the global object, on top of the
+// native primitives passed in as `natives`. This script evaluates to its install function.
+(function (natives) {
+    "use strict";
+
+    const define = (name, value) =>
+        Object.defineProperty(globalThis, name, {
+            value,
+            writable: true,
+            configurable: true,
+            enumerable: false,
+        });
+
+    // Copies binary data to send (an ArrayBuffer or a view onto one) into the plain array of byte
+    // values the native primitives accept. Strings are passed through and sent as UTF-8.
+    const bytesOf = (data) => {
+        if (typeof data === "string") {
+            return data;
+        }
+
+        if (data instanceof ArrayBuffer) {
+            return Array.from(new Uint8Array(data));
+        }
+
+        if (ArrayBuffer.isView(data)) {
+            return Array.from(new Uint8Array(data.buffer, data.byteOffset, data.byteLength));
+        }
+
+        throw new TypeError("The data must be a string, ArrayBuffer, TypedArray or DataView");
+    };
+
+    class Socket {
+        #id;
+        #remoteAddress;
+
+        constructor(handle) {
+            this.#id = handle.id;
+            this.#remoteAddress = handle.remoteAddress;
+        }
+
+        get remoteAddress() {
+            return this.#remoteAddress;
+        }
+
+        // Resolves with the next chunk of at most `maxBytes` received bytes, or `null` once the
+        // peer has closed the connection.
+        async read(maxBytes = 65536) {
+            return natives.read(this.#id, maxBytes);
+        }
+
+        async write(data) {
+            await natives.write(this.#id, bytesOf(data));
+        }
+
+        async close() {
+            await natives.close(this.#id);
+        }
+    }
+
+    class UdpSocket {
+        #id;
+        #remoteAddress;
+
+        constructor(handle) {
+            this.#id = handle.id;
+            this.#remoteAddress = handle.remoteAddress;
+        }
+
+        get remoteAddress() {
+            return this.#remoteAddress;
+        }
+
+        async send(data) {
+            await natives.send(this.#id, bytesOf(data));
+        }
+
+        async receive(maxBytes = 65535) {
+            return natives.receive(this.#id, maxBytes);
+        }
+
+        async close() {
+            await natives.close(this.#id);
+        }
+    }
+
+    define("net", Object.freeze({
+        async connect(host, port, options = {}) {
+            const tls = options?.tls;
+            const serverName = tls ? String(tls.serverName ?? host) : null;
+            const alpn = tls?.alpn ? Array.from(tls.alpn, String) : [];
+            return new Socket(await natives.connect(String(host), port, serverName, alpn));
+        },
+
+        async udp(host, port) {
+            return new UdpSocket(await natives.udp(String(host), port));
+        },
+    }));
+
+    define("dns", Object.freeze({
+        async resolve(name, type = "A", options = {}) {
+            const nameservers = Array.from(options?.nameservers ?? [], String);
+            return natives.resolve(String(name), String(type).toUpperCase(), nameservers);
+        },
+    }));
+})
@@ -0,0 +1,802 @@
+//! Sandboxed network access for scripts implementing custom protocols: `net.connect` (TCP, optionally
+//! wrapped in TLS), `net.udp` and `dns.resolve`.
+//!
+//! Every destination is checked against the probe's [`NetPolicy`] before anything is sent, and a
+//! script without an allow-list can't reach anything through these APIs. The address a hostname
+//! resolves to is checked too, so that a catch-all `*` rule can't be used to reach the agent's own
+//! loopback, link-local or private network. As with `web.js`, the
+//! public API surface lives in `net.js` on top of the primitives defined here; sockets are tracked
+//! by id in the context's [`NetRuntime`] and closed when the run's context is dropped.
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt::Display,
+    net::{IpAddr, SocketAddr},
+    rc::Rc,
+    sync::Arc,
+};
+
+use boa_engine::{
+    Context, IntoJsFunctionCopied, JsData, JsError, JsNativeError, JsObject, JsResult, JsValue,
+    Source, interop::ContextData, js_string,
+    object::{
+        ObjectInitializer,
+        builtins::{JsArray, JsPromise, JsUint8Array},
+    },
+};
+use boa_gc::{Finalize, Trace};
+use futures::lock::Mutex;
+use rustls::pki_types::ServerName;
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadHalf, WriteHalf},
+    net::{TcpStream, UdpSocket, lookup_host},
+};
+use tokio_rustls::TlsConnector;
+
+/// The hosts and ports a script may reach through the `net` and `dns` APIs. Nothing is reachable
+/// unless it matches one of the `allow` rules.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct NetPolicy {
+    /// Rules of the form `host:port`, where the host may be `*` or a `*.example.com` wildcard
+    /// (matching any subdomain) and the port may be `*` or a `low-high` range. IPv6 addresses are
+    /// written in brackets, as in `[::1]:53`.
+    #[serde(default)]
+    pub allow: Vec<NetRule>,
+}
+
+impl NetPolicy {
+    /// Whether the script may connect to `port` on `host`.
+    pub fn permits(&self, host: &str, port: u16) -> bool {
+        self.allow
+            .iter()
+            .any(|rule| rule.matches_host(host) && rule.matches_port(port))
+    }
+
+    /// Whether the script may resolve `host`, which is allowed whenever any rule covers the host.
+    pub fn permits_host(&self, host: &str) -> bool {
+        self.allow.iter().any(|rule| rule.matches_host(host))
+    }
+
+    /// Whether the script may reach `addr`, which `host` resolved to. Public addresses are
+    /// reachable through any rule which permits the host, but loopback, link-local and private
+    /// addresses only through a rule which names the host (or a domain it belongs to) or the address
+    /// itself, since a catch-all `*` rule would otherwise expose the agent's own network.
+    pub fn permits_address(&self, host: &str, addr: SocketAddr) -> bool {
+        if !is_internal(addr.ip()) {
+            return true;
+        }
+
+        let ip = addr.ip().to_string();
+        self.allow.iter().any(|rule| {
+            rule.host != "*"
+                && rule.matches_port(addr.port())
+                && (rule.matches_host(host) || rule.matches_host(&ip))
+        })
+    }
+
+    fn check(&self, api: &str, host: &str, port: u16) -> JsResult<()> {
+        if self.permits(host, port) {
+            Ok(())
+        } else {
+            Err(not_allowed(api, &format!("{}:{port}", bracketed(host))))
+        }
+    }
+
+    fn check_address(&self, api: &str, host: &str, addr: SocketAddr) -> JsResult<()> {
+        if self.permits_address(host, addr) {
+            Ok(())
+        } else {
+            Err(JsNativeError::error()
+                .with_message(format!(
+                    "{api} may not reach '{}' ({addr}) as internal addresses must be named explicitly in the probe's net.allow list",
+                    bracketed(host),
+                ))
+                .into())
+        }
+    }
+}
+
+/// Whether `ip` is a loopback, link-local, private or otherwise non-public address.
+fn is_internal(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                // Carrier-grade NAT's shared address space (100.64.0.0/10).
+                || (a == 100 && (64..128).contains(&b))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_internal(IpAddr::V4(ip)),
+            None => {
+                ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_unique_local()
+                    || ip.is_unicast_link_local()
+            }
+        },
+    }
+}
+
+/// A single `host:port` entry in a [`NetPolicy`]'s allow-list.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct NetRule {
+    host: String,
+    ports: Option<(u16, u16)>,
+}
+
+impl NetRule {
+    fn matches_host(&self, host: &str) -> bool {
+        let host = normalize_host(host);
+        match self.host.as_str() {
+            "*" => true,
+            pattern if pattern.starts_with("*.") => host.ends_with(&pattern[1..]),
+            pattern => pattern == host,
+        }
+    }
+
+    fn matches_port(&self, port: u16) -> bool {
+        self.ports
+            .is_none_or(|(low, high)| (low..=high).contains(&port))
+    }
+}
+
+impl TryFrom<String> for NetRule {
+    type Error = String;
+
+    fn try_from(rule: String) -> Result<Self, Self::Error> {
+        let (host, port) = match rule.strip_prefix('[') {
+            Some(rest) => rest
+                .split_once("]:")
+                .ok_or_else(|| format!("The net rule '{rule}' must be of the form '[address]:port'."))?,
+            None => rule
+                .rsplit_once(':')
+                .ok_or_else(|| format!("The net rule '{rule}' must be of the form 'host:port'."))?,
+        };
+
+        if host.is_empty() {
+            return Err(format!("The net rule '{rule}' does not specify a host."));
+        }
+
+        let parse_port = |port: &str| {
+            port.parse::<u16>()
+                .map_err(|_| format!("The net rule '{rule}' has an invalid port '{port}'."))
+        };
+
+        let ports = match port {
+            "*" => None,
+            range => match range.split_once('-') {
+                Some((low, high)) => {
+                    let (low, high) = (parse_port(low)?, parse_port(high)?);
+                    if low > high {
+                        return Err(format!("The net rule '{rule}' has an empty port range."));
+                    }
+                    Some((low, high))
+                }
+                None => {
+                    let port = parse_port(range)?;
+                    Some((port, port))
+                }
+            },
+        };
+
+        Ok(Self {
+            host: normalize_host(host),
+            ports,
+        })
+    }
+}
+
+impl From<NetRule> for String {
+    fn from(rule: NetRule) -> Self {
+        rule.to_string()
+    }
+}
+
+impl Display for NetRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:", bracketed(&self.host))?;
+        match self.ports {
+            None => write!(f, "*"),
+            Some((low, high)) if low == high => write!(f, "{low}"),
+            Some((low, high)) => write!(f, "{low}-{high}"),
+        }
+    }
+}
+
+fn normalize_host(host: &str) -> String {
+    host.trim_start_matches('[')
+        .trim_end_matches(']')
+        .trim_end_matches('.')
+        .to_ascii_lowercase()
+}
+
+fn bracketed(host: &str) -> String {
+    if host.contains(':') {
+        format!("[{host}]")
+    } else {
+        host.to_string()
+    }
+}
+
+trait Stream: AsyncRead + AsyncWrite + Unpin {}
+impl<T: AsyncRead + AsyncWrite + Unpin> Stream for T {}
+
+/// A connected TCP (or TLS) stream. The halves are locked separately so a script may wait on a
+/// `read` while it `write`s.
+struct StreamSocket {
+    reader: Mutex<ReadHalf<Box<dyn Stream>>>,
+    writer: Mutex<WriteHalf<Box<dyn Stream>>>,
+}
+
+enum Socket {
+    Stream(Rc<StreamSocket>),
+    Datagram(Rc<UdpSocket>),
+}
+
+/// The per-run state behind the `net` and `dns` globals: the probe's policy and its open sockets.
+#[derive(Clone, Trace, Finalize, JsData)]
+struct NetRuntime {
+    #[unsafe_ignore_trace]
+    policy: Rc<NetPolicy>,
+    #[unsafe_ignore_trace]
+    sockets: Rc<RefCell<(u32, HashMap<u32, Socket>)>>,
+}
+
+impl NetRuntime {
+    fn insert(&self, socket: Socket) -> u32 {
+        let mut sockets = self.sockets.borrow_mut();
+        sockets.0 += 1;
+        let id = sockets.0;
+        sockets.1.insert(id, socket);
+        id
+    }
+
+    fn remove(&self, id: u32) -> Option<Socket> {
+        self.sockets.borrow_mut().1.remove(&id)
+    }
+
+    fn stream(&self, id: u32) -> JsResult<Rc<StreamSocket>> {
+        match self.sockets.borrow().1.get(&id) {
+            Some(Socket::Stream(stream)) => Ok(stream.clone()),
+            _ => Err(closed()),
+        }
+    }
+
+    fn datagram(&self, id: u32) -> JsResult<Rc<UdpSocket>> {
+        match self.sockets.borrow().1.get(&id) {
+            Some(Socket::Datagram(socket)) => Ok(socket.clone()),
+            _ => Err(closed()),
+        }
+    }
+}
+
+/// Registers the `net` and `dns` globals in `context`, restricted to the destinations `policy`
+/// allows.
+pub fn register(context: &mut Context, policy: NetPolicy) -> JsResult<()> {
+    context.insert_data(NetRuntime {
+        policy: Rc::new(policy),
+        sockets: Rc::default(),
+    });
+
+    let connect_ = connect.into_js_function_copied(context);
+    let read_ = read.into_js_function_copied(context);
+    let write_ = write.into_js_function_copied(context);
+    let udp_ = udp.into_js_function_copied(context);
+    let send_ = send.into_js_function_copied(context);
+    let receive_ = receive.into_js_function_copied(context);
+    let close_ = close.into_js_function_copied(context);
+    let resolve_ = resolve.into_js_function_copied(context);
+
+    let natives = ObjectInitializer::new(context)
+        .function(connect_, js_string!("connect"), 4)
+        .function(read_, js_string!("read"), 2)
+        .function(write_, js_string!("write"), 2)
+        .function(udp_, js_string!("udp"), 2)
+        .function(send_, js_string!("send"), 2)
+        .function(receive_, js_string!("receive"), 2)
+        .function(close_, js_string!("close"), 1)
+        .function(resolve_, js_string!("resolve"), 3)
+        .build();
+
+    let install = context.eval(Source::from_bytes(include_str!("net.js")))?;
+    let install = install.as_callable().ok_or_else(|| {
+        JsNativeError::typ().with_message("net.js must evaluate to its install function")
+    })?;
+    install.call(&JsValue::undefined(), &[natives.into()], context)?;
+
+    Ok(())
+}
+
+fn not_allowed(api: &str, destination: &str) -> JsError {
+    JsNativeError::error()
+        .with_message(format!(
+            "{api} may not reach '{destination}' as it is not in the probe's net.allow list"
+        ))
+        .into()
+}
+
+fn closed() -> JsError {
+    JsNativeError::error()
+        .with_message("The socket has been closed")
+        .into()
+}
+
+fn net_error(err: impl Display) -> JsError {
+    JsNativeError::error().with_message(err.to_string()).into()
+}
+
+fn port_of(port: &JsValue, context: &mut Context) -> JsResult<u16> {
+    let port = port.to_number(context)?;
+    if port.fract() != 0.0 || !(1.0..=65535.0).contains(&port) {
+        return Err(JsNativeError::range()
+            .with_message(format!("{port} is not a valid port number"))
+            .into());
+    }
+
+    Ok(port as u16)
+}
+
+fn max_bytes_of(size: &JsValue, context: &mut Context) -> JsResult<usize> {
+    Ok((size.to_length(context)? as usize).clamp(1, 1 << 20))
+}
+
+fn strings_of(value: &JsValue, context: &mut Context) -> JsResult<Vec<String>> {
+    let Some(array) = value.as_object() else {
+        return Ok(Vec::new());
+    };
+
+    let length = array.get(js_string!("length"), context)?.to_length(context)?;
+    (0..length)
+        .map(|i| Ok(array.get(i, context)?.to_string(context)?.to_std_string_lossy()))
+        .collect()
+}
+
+/// Reads the data a script sends: a string (sent as UTF-8) or the plain array of bytes `net.js`
+/// builds from binary data.
+fn payload_of(data: &JsValue, context: &mut Context) -> JsResult<Vec<u8>> {
+    match data.as_string() {
+        Some(text) => Ok(text.to_std_string_lossy().into_bytes()),
+        None => super::web::bytes_of(data, context),
+    }
+}
+
+/// Describes a connected socket to `net.js`: its id and the address it connected to.
+fn handle(id: u32, remote: std::net::SocketAddr, context: &mut Context) -> JsResult<JsValue> {
+    let handle = JsObject::with_null_proto();
+    handle.set(js_string!("id"), id, false, context)?;
+    handle.set(
+        js_string!("remoteAddress"),
+        js_string!(remote.to_string()),
+        false,
+        context,
+    )?;
+    Ok(handle.into())
+}
+
+/// Resolves `host`, checking the address it resolves to against `policy` for `api`.
+async fn resolve_addr(policy: &NetPolicy, api: &str, host: &str, port: u16) -> JsResult<SocketAddr> {
+    let addr = lookup_host((host, port))
+        .await
+        .map_err(net_error)?
+        .next()
+        .ok_or_else(|| net_error(format!("Could not resolve the hostname '{host}'.")))?;
+
+    policy.check_address(api, host, addr)?;
+    Ok(addr)
+}
+
+fn connect(
+    ContextData(net): ContextData<NetRuntime>,
+    host: JsValue,
+    port: JsValue,
+    server_name: JsValue,
+    alpn: JsValue,
+    context: &mut Context,
+) -> JsResult<JsValue> {
+    let host = normalize_host(&host.to_string(context)?.to_std_string_lossy());
+    let port = port_of(&port, context)?;
+    net.policy.check("net.connect", &host, port)?;
+
+    let server_name = if server_name.is_null_or_undefined() {
+        None
+    } else {
+        Some(server_name.to_string(context)?.to_std_string_lossy())
+    };
+    let alpn = strings_of(&alpn, context)?;
+
+    Ok(JsPromise::from_async_fn(
+        async move |context| {
+            let addr = resolve_addr(&net.policy, "net.connect", &host, port).await?;
+            let tcp = TcpStream::connect(addr).await.map_err(net_error)?;
+
+            let stream: Box<dyn Stream> = match server_name {
+                Some(server_name) => {
+                    let config = crate::targets::tls_client_config(&alpn).map_err(net_error)?;
+                    let server_name = ServerName::try_from(server_name).map_err(net_error)?;
+                    Box::new(
+                        TlsConnector::from(Arc::new(config))
+                            .connect(server_name, tcp)
+                            .await
+                            .map_err(net_error)?,
+                    )
+                }
+                None => Box::new(tcp),
+            };
+
+            let (reader, writer) = tokio::io::split(stream);
+            let id = net.insert(Socket::Stream(Rc::new(StreamSocket {
+                reader: Mutex::new(reader),
+                writer: Mutex::new(writer),
+            })));
+
+            handle(id, addr, &mut context.borrow_mut())
+        },
+        context,
+    )
+    .into())
+}
+
+fn read(
+    ContextData(net): ContextData<NetRuntime>,
+    id: JsValue,
+    max_bytes: JsValue,
+    context: &mut Context,
+) -> JsResult<JsValue> {
+    let stream = net.stream(id.to_u32(context)?)?;
+    let max_bytes = max_bytes_of(&max_bytes, context)?;
+
+    Ok(JsPromise::from_async_fn(
+        async move |context| {
+            let mut buffer = vec![0; max_bytes];
+            let read = stream
+                .reader
+                .lock()
+                .await
+                .read(&mut buffer)
+                .await
+                .map_err(net_error)?;
+
+            // A read of nothing is the end of the stream.
+            if read == 0 {
+                return Ok(JsValue::null());
+            }
+
+            buffer.truncate(read);
+            Ok(JsUint8Array::from_iter(buffer, &mut context.borrow_mut())?.into())
+        },
+        context,
+    )
+    .into())
+}
+
+fn write(
+    ContextData(net): ContextData<NetRuntime>,
+    id: JsValue,
+    data: JsValue,
+    context: &mut Context,
+) -> JsResult<JsValue> {
+    let stream = net.stream(id.to_u32(context)?)?;
+    let data = payload_of(&data, context)?;
+
+    Ok(JsPromise::from_async_fn(
+        async move |_context| {
+            let mut writer = stream.writer.lock().await;
+            writer.write_all(&data).await.map_err(net_error)?;
+            writer.flush().await.map_err(net_error)?;
+            Ok(JsValue::undefined())
+        },
+        context,
+    )
+    .into())
+}
+
+fn udp(
+    ContextData(net): ContextData<NetRuntime>,
+    host: JsValue,
+    port: JsValue,
+    context: &mut Context,
+) -> JsResult<JsValue> {
+    let host = normalize_host(&host.to_string(context)?.to_std_string_lossy());
+    let port = port_of(&port, context)?;
+    net.policy.check("net.udp", &host, port)?;
+
+    Ok(JsPromise::from_async_fn(
+        async move |context| {
+            let addr = resolve_addr(&net.policy, "net.udp", &host, port).await?;
+            let bind = if addr.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+            let socket = UdpSocket::bind(bind).await.map_err(net_error)?;
+            socket.connect(addr).await.map_err(net_error)?;
+
+            let id = net.insert(Socket::Datagram(Rc::new(socket)));
+            handle(id, addr, &mut context.borrow_mut())
+        },
+        context,
+    )
+    .into())
+}
+
+fn send(
+    ContextData(net): ContextData<NetRuntime>,
+    id: JsValue,
+    data: JsValue,
+    context: &mut Context,
+) -> JsResult<JsValue> {
+    let socket = net.datagram(id.to_u32(context)?)?;
+    let data = payload_of(&data, context)?;
+
+    Ok(JsPromise::from_async_fn(
+        async move |_context| {
+            socket.send(&data).await.map_err(net_error)?;
+            Ok(JsValue::undefined())
+        },
+        context,
+    )
+    .into())
+}
+
+fn receive(
+    ContextData(net): ContextData<NetRuntime>,
+    id: JsValue,
+    max_bytes: JsValue,
+    context: &mut Context,
+) -> JsResult<JsValue> {
+    let socket = net.datagram(id.to_u32(context)?)?;
+    let max_bytes = max_bytes_of(&max_bytes, context)?;
+
+    Ok(JsPromise::from_async_fn(
+        async move |context| {
+            let mut buffer = vec![0; max_bytes];
+            let received = socket.recv(&mut buffer).await.map_err(net_error)?;
+            buffer.truncate(received);
+            Ok(JsUint8Array::from_iter(buffer, &mut context.borrow_mut())?.into())
+        },
+        context,
+    )
+    .into())
+}
+
+fn close(
+    ContextData(net): ContextData<NetRuntime>,
+    id: JsValue,
+    context: &mut Context,
+) -> JsResult<JsValue> {
+    let socket = net.remove(id.to_u32(context)?);
+
+    Ok(JsPromise::from_async_fn(
+        async move |_context| {
+            // Shut a stream down so the peer sees the end of it (and, for TLS, receives a
+            // `close_notify`); datagram sockets need nothing beyond being dropped. A peer which has
+            // already hung up isn't an error for a script which is done with the socket anyway.
+            if let Some(Socket::Stream(stream)) = socket {
+                let _ = stream.writer.lock().await.shutdown().await;
+            }
+            Ok(JsValue::undefined())
+        },
+        context,
+    )
+    .into())
+}
+
+fn resolve(
+    ContextData(net): ContextData<NetRuntime>,
+    name: JsValue,
+    record_type: JsValue,
+    nameservers: JsValue,
+    context: &mut Context,
+) -> JsResult<JsValue> {
+    let name = name.to_string(context)?.to_std_string_lossy();
+    if !net.policy.permits_host(&name) {
+        return Err(not_allowed("dns.resolve", &name));
+    }
+
+    let record_type = record_type.to_string(context)?.to_std_string_lossy();
+    let nameservers = strings_of(&nameservers, context)?;
+    for nameserver in &nameservers {
+        let addr = crate::targets::nameserver_addr(nameserver).map_err(net_error)?;
+        let ip = addr.ip().to_string();
+        net.policy.check("dns.resolve", &ip, addr.port())?;
+        net.policy.check_address("dns.resolve", &ip, addr)?;
+    }
+
+    Ok(JsPromise::from_async_fn(
+        async move |context| {
+            let nameservers = (!nameservers.is_empty()).then_some(nameservers.as_slice());
+            let answers = crate::targets::dns_lookup(&name, &record_type, nameservers)
+                .await
+                .map_err(net_error)?;
+
+            Ok(JsArray::from_iter(
+                answers.into_iter().map(|answer| js_string!(answer).into()),
+                &mut context.borrow_mut(),
+            )
+            .into())
+        },
+        context,
+    )
+    .into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::js::JobQueue;
+    use boa_engine::builtins::promise::PromiseState;
+
+    fn rule(rule: &str) -> NetRule {
+        NetRule::try_from(rule.to_string()).unwrap()
+    }
+
+    fn policy(rules: &[&str]) -> NetPolicy {
+        NetPolicy {
+            allow: rules.iter().map(|r| rule(r)).collect(),
+        }
+    }
+
+    /// Runs `code` (which may `await`) with the `net` and `dns` globals restricted to `policy`,
+    /// returning what it evaluates to or the message it was rejected with.
+    async fn run(policy: NetPolicy, code: &str) -> Result<String, String> {
+        let executor = Rc::new(JobQueue::new());
+        let mut context = crate::js::budgeted()
+            .job_executor(executor.clone())
+            .build()
+            .unwrap();
+        super::super::web::register(&mut context).unwrap();
+        register(&mut context, policy).unwrap();
+
+        let promise = context
+            .eval(Source::from_bytes(&format!("(async () => {{ {code} }})()")))
+            .unwrap()
+            .as_promise()
+            .expect("an async function to return a promise");
+        executor
+            .run_jobs_async(&RefCell::new(&mut context))
+            .await
+            .unwrap();
+
+        match promise.state() {
+            PromiseState::Fulfilled(value) => Ok(value
+                .to_string(&mut context)
+                .unwrap()
+                .to_std_string_lossy()),
+            PromiseState::Rejected(err) => Err(err
+                .to_string(&mut context)
+                .unwrap()
+                .to_std_string_lossy()),
+            PromiseState::Pending => panic!("the script should have finished"),
+        }
+    }
+
+    #[test]
+    fn test_rules() {
+        assert_eq!(rule("Redis.Internal:6379").to_string(), "redis.internal:6379");
+        assert_eq!(rule("[::1]:53").to_string(), "[::1]:53");
+        assert_eq!(rule("*.example.com:8000-8100").to_string(), "*.example.com:8000-8100");
+
+        let policy = policy(&["redis.internal:6379", "*.example.com:*", "[::1]:53"]);
+        assert!(policy.permits("redis.internal", 6379));
+        assert!(!policy.permits("redis.internal", 6380));
+        assert!(policy.permits("api.example.com.", 443));
+        assert!(!policy.permits("example.com", 443), "wildcards only match subdomains");
+        assert!(policy.permits("::1", 53));
+        assert!(policy.permits_host("redis.internal"));
+        assert!(!policy.permits_host("other.internal"));
+
+        let addr = |addr: &str| addr.parse::<SocketAddr>().unwrap();
+        assert!(policy.permits_address("redis.internal", addr("10.0.0.5:6379")), "named hosts may be internal");
+        assert!(policy.permits_address("db.example.com", addr("10.0.0.5:5432")), "as may a named domain's");
+        assert!(policy.permits_address("::1", addr("[::1]:53")));
+
+        let any = NetPolicy { allow: vec![rule("*:*")] };
+        assert!(any.permits_address("example.org", addr("93.184.215.14:443")));
+        for internal in ["127.0.0.1:80", "169.254.169.254:80", "192.168.1.1:80", "100.64.0.1:80", "[fd00::1]:80", "[::ffff:127.0.0.1]:80"] {
+            assert!(!any.permits_address("rebound.example.org", addr(internal)), "{internal} should need an explicit rule");
+        }
+        let named = NetPolicy { allow: vec![rule("*:*"), rule("127.0.0.1:80")] };
+        assert!(named.permits_address("localhost", addr("127.0.0.1:80")), "an address rule allows it");
+
+        for invalid in ["redis.internal", ":80", "host:http", "host:90-80", "[::1]53"] {
+            assert!(NetRule::try_from(invalid.to_string()).is_err(), "{invalid} should be rejected");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_destinations_must_be_allowed() {
+        let err = run(policy(&[]), "await net.connect('127.0.0.1', 80)")
+            .await
+            .unwrap_err();
+        assert!(err.contains("'127.0.0.1:80' as it is not in the probe's net.allow list"), "{err}");
+
+        let err = run(policy(&["example.com:53"]), "await dns.resolve('example.org')")
+            .await
+            .unwrap_err();
+        assert!(err.contains("dns.resolve may not reach 'example.org'"), "{err}");
+
+        let err = run(policy(&["*:*"]), "await net.connect('localhost', 80)")
+            .await
+            .unwrap_err();
+        assert!(err.contains("internal addresses must be named explicitly"), "{err}");
+
+        let err = run(policy(&["*:*"]), "await net.udp('localhost', 70000)")
+            .await
+            .unwrap_err();
+        assert!(err.starts_with("RangeError"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_tcp_round_trip() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buffer = [0; 64];
+            let read = stream.read(&mut buffer).await.unwrap();
+            stream.write_all(b"+").await.unwrap();
+            stream.write_all(&buffer[..read]).await.unwrap();
+        });
+
+        let echoed = run(
+            policy(&[&format!("127.0.0.1:{port}")]),
+            &format!(
+                r#"
+                const socket = await net.connect("127.0.0.1", {port});
+                await socket.write("PING");
+                let reply = "";
+                for (let chunk; (chunk = await socket.read()) !== null;) {{
+                    reply += new TextDecoder().decode(chunk);
+                }}
+                await socket.close();
+                return reply;
+                "#
+            ),
+        )
+        .await
+        .unwrap();
+        assert_eq!(echoed, "+PING");
+    }
+
+    #[tokio::test]
+    async fn test_udp_round_trip() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let port = server.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let mut buffer = [0; 64];
+            let (read, peer) = server.recv_from(&mut buffer).await.unwrap();
+            server.send_to(&buffer[..read], peer).await.unwrap();
+        });
+
+        let echoed = run(
+            policy(&[&format!("127.0.0.1:{port}")]),
+            &format!(
+                r#"
+                const socket = await net.udp("127.0.0.1", {port});
+                await socket.send(new Uint8Array([1, 2, 3]));
+                const reply = await socket.receive();
+                await socket.close();
+                return reply.join(",");
+                "#
+            ),
+        )
+        .await
+        .unwrap();
+        assert_eq!(echoed, "1,2,3");
+    }
+
+    #[tokio::test]
+    #[cfg(not(feature = "pure_tests"))]
+    async fn test_dns_resolve() {
+        let answers = run(
+            policy(&["google.com:*"]),
+            "return (await dns.resolve('google.com', 'MX')).join(',')",
+        )
+        .await
+        .unwrap();
+        assert_eq!(answers, "10 smtp.google.com.");
+    }
+}
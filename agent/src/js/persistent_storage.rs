@@ -0,0 +1,280 @@
+use boa_engine::{
+    Context, IntoJsFunctionCopied, JsData, JsNativeError, JsResult, JsValue, interop::ContextData,
+    js_string, object::ObjectInitializer, property::Attribute,
+};
+use boa_gc::{Finalize, Trace};
+
+use crate::state::{ScriptStorageStore, State, StorageKind};
+
+/// The persistent counterparts to [`super::SessionStorage`]: `localStorage`, kept in this node's
+/// state database, and `clusterStorage`, replicated to every node in the cluster through gossip.
+///
+/// Both are scoped to a single probe and survive config reloads and restarts. `setItem` accepts an
+/// optional third `{ ttl }` argument (in seconds) after which the entry expires, which suits cached
+/// access tokens that every node can then share through `clusterStorage`.
+#[derive(Clone, Trace, Finalize, JsData)]
+pub(crate) struct ScriptStorage {
+    #[unsafe_ignore_trace]
+    state: State,
+    scope: String,
+}
+
+impl std::fmt::Debug for ScriptStorage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ScriptStorage").field("scope", &self.scope).finish()
+    }
+}
+
+impl ScriptStorage {
+    /// Creates the persistent stores for the probe named `scope`.
+    pub fn new(state: State, scope: impl Into<String>) -> Self {
+        Self {
+            state,
+            scope: scope.into(),
+        }
+    }
+
+    /// Exposes this storage as the `localStorage` and `clusterStorage` globals within the provided
+    /// JavaScript context, with the same method surface as `sessionStorage`.
+    pub fn register(self, context: &mut Context) -> JsResult<()> {
+        context.insert_data(self);
+
+        register_store::<false>(context, "localStorage")?;
+        register_store::<true>(context, "clusterStorage")?;
+
+        Ok(())
+    }
+
+    fn kind<const CLUSTER: bool>() -> StorageKind {
+        if CLUSTER {
+            StorageKind::Cluster
+        } else {
+            StorageKind::Local
+        }
+    }
+}
+
+fn register_store<const CLUSTER: bool>(context: &mut Context, name: &str) -> JsResult<()> {
+    let get_item_ = get_item::<CLUSTER>.into_js_function_copied(context);
+    let set_item_ = set_item::<CLUSTER>.into_js_function_copied(context);
+    let remove_item_ = remove_item::<CLUSTER>.into_js_function_copied(context);
+    let clear_ = clear::<CLUSTER>.into_js_function_copied(context);
+    let key_ = key::<CLUSTER>.into_js_function_copied(context);
+    let length_ = length::<CLUSTER>
+        .into_js_function_copied(context)
+        .to_js_function(context.realm());
+
+    let storage = ObjectInitializer::new(context)
+        .function(get_item_, js_string!("getItem"), 1)
+        .function(set_item_, js_string!("setItem"), 2)
+        .function(remove_item_, js_string!("removeItem"), 1)
+        .function(clear_, js_string!("clear"), 0)
+        .function(key_, js_string!("key"), 1)
+        .accessor(
+            js_string!("length"),
+            Some(length_),
+            None,
+            Attribute::ENUMERABLE,
+        )
+        .build();
+
+    context.register_global_property(
+        js_string!(name),
+        storage,
+        Attribute::READONLY | Attribute::ENUMERABLE,
+    )?;
+
+    Ok(())
+}
+
+fn storage_error(err: Box<dyn std::error::Error>) -> boa_engine::JsError {
+    JsNativeError::error().with_message(err.to_string()).into()
+}
+
+/// Reads the `ttl` (in seconds) from `setItem`'s optional options argument.
+fn ttl(options: JsValue, context: &mut Context) -> JsResult<Option<chrono::Duration>> {
+    let Some(options) = options.as_object() else {
+        return Ok(None);
+    };
+
+    let ttl = options.get(js_string!("ttl"), context)?;
+    if ttl.is_undefined() {
+        return Ok(None);
+    }
+
+    let seconds = ttl.to_number(context)?;
+    if !seconds.is_finite() || seconds <= 0.0 {
+        return Err(JsNativeError::range()
+            .with_message("The storage ttl must be a positive number of seconds")
+            .into());
+    }
+
+    chrono::Duration::try_milliseconds((seconds * 1000.0) as i64)
+        .map(Some)
+        .ok_or_else(|| {
+            JsNativeError::range()
+                .with_message("The storage ttl is too large")
+                .into()
+        })
+}
+
+fn get_item<const CLUSTER: bool>(
+    ContextData(storage): ContextData<ScriptStorage>,
+    key: JsValue,
+    context: &mut Context,
+) -> JsResult<JsValue> {
+    let key = key.to_string(context)?.to_std_string_lossy();
+    Ok(storage
+        .state
+        .get_storage_item(ScriptStorage::kind::<CLUSTER>(), &storage.scope, &key)
+        .map_err(storage_error)?
+        .map_or_else(JsValue::null, |value| js_string!(value.as_str()).into()))
+}
+
+fn set_item<const CLUSTER: bool>(
+    ContextData(storage): ContextData<ScriptStorage>,
+    key: JsValue,
+    value: JsValue,
+    options: JsValue,
+    context: &mut Context,
+) -> JsResult<JsValue> {
+    let key = key.to_string(context)?.to_std_string_lossy();
+    let value = value.to_string(context)?.to_std_string_lossy();
+    let ttl = ttl(options, context)?;
+    storage
+        .state
+        .set_storage_item(ScriptStorage::kind::<CLUSTER>(), &storage.scope, &key, &value, ttl)
+        .map_err(storage_error)?;
+    Ok(JsValue::undefined())
+}
+
+fn remove_item<const CLUSTER: bool>(
+    ContextData(storage): ContextData<ScriptStorage>,
+    key: JsValue,
+    context: &mut Context,
+) -> JsResult<JsValue> {
+    let key = key.to_string(context)?.to_std_string_lossy();
+    storage
+        .state
+        .remove_storage_item(ScriptStorage::kind::<CLUSTER>(), &storage.scope, &key)
+        .map_err(storage_error)?;
+    Ok(JsValue::undefined())
+}
+
+fn clear<const CLUSTER: bool>(ContextData(storage): ContextData<ScriptStorage>) -> JsResult<JsValue> {
+    storage
+        .state
+        .clear_storage(ScriptStorage::kind::<CLUSTER>(), &storage.scope)
+        .map_err(storage_error)?;
+    Ok(JsValue::undefined())
+}
+
+fn key<const CLUSTER: bool>(
+    ContextData(storage): ContextData<ScriptStorage>,
+    index: JsValue,
+    context: &mut Context,
+) -> JsResult<JsValue> {
+    let index = index.to_u32(context)? as usize;
+    Ok(storage
+        .state
+        .storage_keys(ScriptStorage::kind::<CLUSTER>(), &storage.scope)
+        .map_err(storage_error)?
+        .get(index)
+        .map_or_else(JsValue::null, |key| js_string!(key.as_str()).into()))
+}
+
+fn length<const CLUSTER: bool>(ContextData(storage): ContextData<ScriptStorage>) -> JsResult<JsValue> {
+    let keys = storage
+        .state
+        .storage_keys(ScriptStorage::kind::<CLUSTER>(), &storage.scope)
+        .map_err(storage_error)?;
+    Ok(JsValue::from(keys.len() as u32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use boa_engine::Source;
+
+    fn context_with_storage(state: &State, scope: &str) -> Context {
+        let mut context = crate::js::budgeted().build().unwrap();
+        ScriptStorage::new(state.clone(), scope).register(&mut context).unwrap();
+        context
+    }
+
+    fn eval(context: &mut Context, code: &str) -> JsValue {
+        context.eval(Source::from_bytes(code)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_persists_across_contexts() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = State::test(dir.path().to_path_buf()).await;
+
+        let mut context = context_with_storage(&state, "probe");
+        eval(&mut context, "localStorage.setItem('token', 'abc'); clusterStorage.setItem('counter', 1)");
+
+        let mut context = context_with_storage(&state, "probe");
+        assert_eq!(
+            eval(&mut context, "localStorage.getItem('token')"),
+            JsValue::from(js_string!("abc"))
+        );
+        assert_eq!(
+            eval(&mut context, "clusterStorage.getItem('counter')"),
+            JsValue::from(js_string!("1"))
+        );
+        assert_eq!(eval(&mut context, "localStorage.getItem('counter')"), JsValue::null());
+        assert_eq!(eval(&mut context, "clusterStorage.length"), JsValue::from(1));
+        assert_eq!(
+            eval(&mut context, "clusterStorage.key(0)"),
+            JsValue::from(js_string!("counter"))
+        );
+
+        let mut other = context_with_storage(&state, "other");
+        assert_eq!(
+            eval(&mut other, "localStorage.getItem('token')"),
+            JsValue::null(),
+            "storage is scoped to the probe"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_remove_and_clear() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = State::test(dir.path().to_path_buf()).await;
+        let mut context = context_with_storage(&state, "probe");
+
+        eval(
+            &mut context,
+            "clusterStorage.setItem('a', '1'); clusterStorage.setItem('b', '2'); clusterStorage.removeItem('a')",
+        );
+        assert_eq!(eval(&mut context, "clusterStorage.getItem('a')"), JsValue::null());
+        assert_eq!(eval(&mut context, "clusterStorage.length"), JsValue::from(1));
+
+        eval(&mut context, "clusterStorage.clear()");
+        assert_eq!(eval(&mut context, "clusterStorage.length"), JsValue::from(0));
+    }
+
+    #[tokio::test]
+    async fn test_ttl() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = State::test(dir.path().to_path_buf()).await;
+        let mut context = context_with_storage(&state, "probe");
+
+        eval(&mut context, "localStorage.setItem('short', 'x', { ttl: 0.001 }); localStorage.setItem('long', 'y', { ttl: 3600 })");
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+
+        assert_eq!(eval(&mut context, "localStorage.getItem('short')"), JsValue::null());
+        assert_eq!(
+            eval(&mut context, "localStorage.getItem('long')"),
+            JsValue::from(js_string!("y"))
+        );
+        assert_eq!(
+            eval(
+                &mut context,
+                "try { localStorage.setItem('bad', 'z', { ttl: -1 }); 'accepted' } catch (e) { e.name }"
+            ),
+            JsValue::from(js_string!("RangeError"))
+        );
+    }
+}
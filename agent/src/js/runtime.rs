@@ -9,19 +9,29 @@ use tracing_batteries::prelude::*;
 
 pub fn setup_runtime(
     context: &mut Context,
+    fetcher: ReqwestFetcher,
     storage: super::SessionStorage,
+    persistent: Option<super::ScriptStorage>,
+    net: super::NetPolicy,
+    results: super::ScriptResults,
     args: Vec<String>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     boa_runtime::register(
         (
             boa_runtime::extensions::ConsoleExtension(super::TraceLogger),
-            boa_runtime::extensions::FetchExtension(ReqwestFetcher::default()),
+            boa_runtime::extensions::FetchExtension(fetcher),
         ),
         None,
         context,
     )?;
 
     storage.register(context)?;
+    if let Some(persistent) = persistent {
+        persistent.register(context)?;
+    }
+    super::web::register(context)?;
+    super::net::register(context, net)?;
+    results.register(context)?;
 
     context.register_global_property(
         js_string!("output"),
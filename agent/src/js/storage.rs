@@ -132,7 +132,7 @@ mod tests {
     use boa_engine::Source;
 
     fn context_with_storage(storage: SessionStorage) -> Context {
-        let mut context = Context::default();
+        let mut context = crate::js::budgeted().build().unwrap();
         storage.register(&mut context).unwrap();
         context
     }
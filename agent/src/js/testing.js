@@ -0,0 +1,115 @@
+// Installs the `test` and `assert` globals documented in `testing.rs` onto the global object, on top
+// of the native primitives passed in as `natives`. This script evaluates to its install function.
+(function (natives) {
+    "use strict";
+
+    const define = (name, value) =>
+        Object.defineProperty(globalThis, name, {
+            value,
+            writable: true,
+            configurable: true,
+            enumerable: false,
+        });
+
+    class AssertionError extends Error {
+        constructor(message) {
+            super(message);
+            this.name = "AssertionError";
+        }
+    }
+
+    const describe = (value) => {
+        if (typeof value === "string") {
+            return JSON.stringify(value);
+        }
+
+        try {
+            return JSON.stringify(value) ?? String(value);
+        } catch {
+            return String(value);
+        }
+    };
+
+    const messageOf = (err) => {
+        if (err instanceof AssertionError) {
+            return err.message;
+        }
+
+        if (err instanceof Error) {
+            return `${err.name}: ${err.message}`;
+        }
+
+        return String(err);
+    };
+
+    const deepEqual = (a, b) => {
+        if (Object.is(a, b)) {
+            return true;
+        }
+
+        if (typeof a !== "object" || typeof b !== "object" || a === null || b === null) {
+            return false;
+        }
+
+        if (Array.isArray(a) !== Array.isArray(b) || Object.getPrototypeOf(a) !== Object.getPrototypeOf(b)) {
+            return false;
+        }
+
+        const keys = Object.keys(a);
+        if (keys.length !== Object.keys(b).length) {
+            return false;
+        }
+
+        return keys.every((key) => Object.prototype.hasOwnProperty.call(b, key) && deepEqual(a[key], b[key]));
+    };
+
+    // The number of `test()` bodies currently running. Assertions made outside of any test are
+    // reported as validations of their own, named after their message.
+    let running = 0;
+    let unnamed = 0;
+
+    const check = (pass, message, fallback) => {
+        if (running === 0) {
+            const name = message === undefined ? `assertion ${++unnamed}` : String(message);
+            natives.record(name, pass, pass ? undefined : String(message ?? fallback()));
+        }
+
+        if (!pass) {
+            throw new AssertionError(String(message ?? fallback()));
+        }
+    };
+
+    const assert = (value, message) => check(!!value, message, () => `expected ${describe(value)} to be truthy`);
+
+    assert.ok = assert;
+
+    assert.equal = (actual, expected, message) =>
+        check(actual === expected, message, () => `expected ${describe(actual)} to equal ${describe(expected)}`);
+
+    assert.notEqual = (actual, expected, message) =>
+        check(actual !== expected, message, () => `expected ${describe(actual)} not to equal ${describe(expected)}`);
+
+    assert.deepEqual = (actual, expected, message) =>
+        check(deepEqual(actual, expected), message, () => `expected ${describe(actual)} to deeply equal ${describe(expected)}`);
+
+    assert.match = (value, pattern, message) =>
+        check(pattern.test(String(value)), message, () => `expected ${describe(value)} to match ${pattern}`);
+
+    assert.fail = (message) => check(false, message, () => "assertion failed");
+
+    define("AssertionError", AssertionError);
+    define("assert", Object.freeze(assert));
+
+    define("test", async function test(name, fn) {
+        name = String(name);
+        running++;
+        try {
+            await fn();
+            natives.record(name, true);
+        } catch (err) {
+            natives.record(name, false, messageOf(err));
+        } finally {
+            running--;
+        }
+    });
+})
@@ -0,0 +1,164 @@
+//! The `test(name, fn)` and `assert` APIs, which let a script report each of the steps it checks as
+//! a validation of its own rather than failing the whole run with a single rejected promise.
+//!
+//! `test` runs `fn` (which may be async) and records a passing validation named `name` once it
+//! completes, or a failing one carrying the error's message if it throws; it never rejects, so one
+//! failing step doesn't stop the rest of the script. `assert(value, message)` and its `ok`,
+//! `equal`, `notEqual`, `deepEqual`, `match` and `fail` variants throw an `AssertionError` when the
+//! assertion doesn't hold. Assertions made outside of a `test` are recorded as validations named
+//! after their message.
+//!
+//! The public API surface lives in `testing.js`, on top of the `record` primitive defined here.
+
+use std::{cell::RefCell, rc::Rc};
+
+use boa_engine::{
+    Context, IntoJsFunctionCopied, JsData, JsNativeError, JsResult, JsValue, Source,
+    interop::ContextData, js_string, object::ObjectInitializer,
+};
+use boa_gc::{Finalize, Trace};
+use grey_api::ValidationResult;
+
+/// The validations a script has recorded through `test` and `assert`, in the order they completed.
+/// Cloned instances share the same list, so the target can read back what the script recorded.
+#[derive(Clone, Debug, Default, Trace, Finalize, JsData)]
+pub(crate) struct ScriptResults {
+    #[unsafe_ignore_trace]
+    results: Rc<RefCell<Vec<(String, ValidationResult)>>>,
+}
+
+impl ScriptResults {
+    /// Exposes the `test`, `assert` and `AssertionError` globals within the provided JavaScript
+    /// context, recording their results into this instance.
+    pub fn register(self, context: &mut Context) -> JsResult<()> {
+        context.insert_data(self);
+
+        let record_ = record.into_js_function_copied(context);
+        let natives = ObjectInitializer::new(context)
+            .function(record_, js_string!("record"), 3)
+            .build();
+
+        let install = context.eval(Source::from_bytes(include_str!("testing.js")))?;
+        let install = install.as_callable().ok_or_else(|| {
+            JsNativeError::typ().with_message("testing.js must evaluate to its install function")
+        })?;
+        install.call(&JsValue::undefined(), &[natives.into()], context)?;
+
+        Ok(())
+    }
+
+    /// Removes and returns the validations recorded so far.
+    pub fn take(&self) -> Vec<(String, ValidationResult)> {
+        self.results.take()
+    }
+}
+
+fn record(
+    ContextData(results): ContextData<ScriptResults>,
+    name: JsValue,
+    pass: JsValue,
+    message: JsValue,
+    context: &mut Context,
+) -> JsResult<JsValue> {
+    let name = name.to_string(context)?.to_std_string_lossy();
+    let mut result = if pass.to_boolean() {
+        ValidationResult::pass()
+    } else {
+        ValidationResult {
+            pass: false,
+            message: None,
+        }
+    };
+
+    if !message.is_null_or_undefined() {
+        result = result.with_message(message.to_string(context)?.to_std_string_lossy());
+    }
+
+    results.results.borrow_mut().push((name, result));
+    Ok(JsValue::undefined())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::js::JobQueue;
+
+    async fn run(code: &str) -> (Vec<(String, ValidationResult)>, JsValue) {
+        let executor = Rc::new(JobQueue::new());
+        let context = &mut crate::js::budgeted()
+            .job_executor(executor.clone())
+            .build()
+            .unwrap();
+
+        let results = ScriptResults::default();
+        results.clone().register(context).unwrap();
+
+        let promise = context
+            .eval(Source::from_bytes(&format!("(async () => {{ {code} }})()")))
+            .unwrap();
+        executor
+            .run_jobs_async(&RefCell::new(&mut *context))
+            .await
+            .unwrap();
+
+        let state = promise
+            .as_promise()
+            .map(|promise| match promise.state() {
+                boa_engine::builtins::promise::PromiseState::Rejected(err) => err
+                    .as_object()
+                    .map(|err| err.get(js_string!("name"), context).unwrap())
+                    .unwrap_or_default(),
+                _ => JsValue::undefined(),
+            })
+            .unwrap_or_default();
+
+        (results.take(), state)
+    }
+
+    #[tokio::test]
+    async fn test_records_tests() {
+        let (results, rejection) = run(r#"
+            await test("passes", async () => {
+                assert.equal(1 + 1, 2);
+                assert.deepEqual({ a: [1, 2] }, { a: [1, 2] });
+                assert.match("hello world", /world/);
+            });
+            await test("fails", () => assert.equal(1, 2));
+            await test("throws", () => { throw new TypeError("bad input"); });
+            await test("custom message", () => assert(false, "the token was missing"));
+        "#)
+        .await;
+
+        assert!(rejection.is_undefined(), "test() never rejects");
+        assert_eq!(
+            results,
+            vec![
+                ("passes".to_string(), ValidationResult::pass()),
+                ("fails".to_string(), ValidationResult::fail("expected 1 to equal 2")),
+                ("throws".to_string(), ValidationResult::fail("TypeError: bad input")),
+                ("custom message".to_string(), ValidationResult::fail("the token was missing")),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_records_top_level_assertions() {
+        let (results, rejection) = run(r#"
+            assert(true, "the service responded");
+            assert.notEqual("a", "b");
+            assert.equal(404, 200, "the status is 200");
+            assert(true, "never reached");
+        "#)
+        .await;
+
+        assert_eq!(rejection, JsValue::from(js_string!("AssertionError")));
+        assert_eq!(
+            results,
+            vec![
+                ("the service responded".to_string(), ValidationResult::pass()),
+                ("assertion 1".to_string(), ValidationResult::pass()),
+                ("the status is 200".to_string(), ValidationResult::fail("the status is 200")),
+            ]
+        );
+    }
+}
@@ -1,4 +1,6 @@
-use boa_engine::{Context, JsError, JsObject, JsResult, JsValue, js_string};
+use boa_engine::{
+    Context, JsError, JsObject, JsResult, JsValue, js_string, object::builtins::JsArray,
+};
 
 use crate::{Sample, SampleValue};
 
@@ -6,17 +8,59 @@ pub trait JsInto<T> {
     fn js_into(&self, context: &mut Context) -> JsResult<T>;
 }
 
+/// How deeply nested objects are flattened into dotted sample fields before the remainder is stored
+/// as its `JSON.stringify(...)` representation instead.
+const MAX_FLATTEN_DEPTH: usize = 16;
+
+/// Converts an object into a sample, flattening nested plain objects into dotted fields so that
+/// `{ http: { status: 200 } }` produces an `http.status` field.
 impl JsInto<Sample> for JsObject {
     fn js_into(&self, context: &mut Context) -> JsResult<Sample> {
         let mut sample: Sample = Sample::default();
+        flatten_into(&mut sample, self, None, 0, context)?;
+        Ok(sample)
+    }
+}
+
+fn flatten_into(
+    sample: &mut Sample,
+    object: &JsObject,
+    prefix: Option<&str>,
+    depth: usize,
+    context: &mut Context,
+) -> JsResult<()> {
+    let keys = object.own_property_keys(context)?;
+    for key in keys {
+        let value = object.get(key.clone(), context)?;
+        let key = match prefix {
+            Some(prefix) => format!("{prefix}.{key}"),
+            None => key.to_string(),
+        };
 
-        let keys = self.own_property_keys(context)?;
-        for key in keys {
-            let value = self.get(key.clone(), context)?;
-            sample.set(key.to_string(), value.js_into(context)?);
+        match value.as_object() {
+            Some(nested) if depth < MAX_FLATTEN_DEPTH && is_plain_object(&nested, context) => {
+                flatten_into(sample, &nested, Some(&key), depth + 1, context)?;
+            }
+            _ => sample.set(key, value.js_into(context)?),
         }
+    }
 
-        Ok(sample)
+    Ok(())
+}
+
+/// Whether `object` is a plain `{ ... }` object (rather than an array, function or class instance
+/// such as a `Date`), which are the objects flattened into dotted fields.
+fn is_plain_object(object: &JsObject, context: &mut Context) -> bool {
+    if object.is_array() || object.is_callable() {
+        return false;
+    }
+
+    match object.prototype() {
+        None => true,
+        Some(prototype) => JsObject::equals(
+            &prototype,
+            &context.intrinsics().constructors().object().prototype(),
+        ),
     }
 }
 
@@ -73,10 +117,57 @@ impl JsInto<SampleValue> for JsObject {
     }
 }
 
+/// The reverse of [`JsInto`], exposing collected samples to scripts such as script checks.
+pub trait ToJs {
+    fn to_js(&self, context: &mut Context) -> JsResult<JsValue>;
+}
+
+/// Converts a sample into an object keyed by its (dotted) field names, as checks refer to them.
+impl ToJs for Sample {
+    fn to_js(&self, context: &mut Context) -> JsResult<JsValue> {
+        let object = JsObject::with_null_proto();
+        for (key, value) in self.fields() {
+            let value = value.to_js(context)?;
+            object.set(js_string!(key), value, false, context)?;
+        }
+
+        Ok(object.into())
+    }
+}
+
+/// Datetimes become `Date`s and durations their length in milliseconds, so that scripts can do
+/// arithmetic on them.
+impl ToJs for SampleValue {
+    fn to_js(&self, context: &mut Context) -> JsResult<JsValue> {
+        Ok(match self {
+            SampleValue::None => JsValue::null(),
+            SampleValue::String(value) => js_string!(value.as_str()).into(),
+            SampleValue::Double(value) => JsValue::from(*value),
+            SampleValue::Int(value) => JsValue::from(*value as f64),
+            SampleValue::Bool(value) => JsValue::from(*value),
+            SampleValue::DateTime(value) => context
+                .intrinsics()
+                .constructors()
+                .date()
+                .constructor()
+                .construct(&[JsValue::from(value.timestamp_millis() as f64)], None, context)?
+                .into(),
+            SampleValue::Duration(value) => JsValue::from(value.num_milliseconds() as f64),
+            SampleValue::List(values) => {
+                let values = values
+                    .iter()
+                    .map(|value| value.to_js(context))
+                    .collect::<JsResult<Vec<_>>>()?;
+                JsArray::from_iter(values, context).into()
+            }
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use boa_engine::{Context, Source};
+    use boa_engine::Source;
 
     #[test]
     fn test_js_primitives_into_sample_value() {
@@ -114,8 +205,55 @@ mod tests {
         assert_eq!(sample, SampleValue::String(r#"{"key1":"value1","key2":42,"key3":true}"#.into()));
     }
 
+    #[test]
+    fn test_nested_objects_flatten_into_sample() {
+        let mut context = crate::js::budgeted().build().unwrap();
+        let object = context
+            .eval(Source::from_bytes(
+                r#"({ "http": { "status": 200, "headers": { "server": "grey" } }, "tags": ["a"], "items": [{ "id": 1 }], "when": new Date(0), "flat.key": true })"#,
+            ))
+            .unwrap();
+
+        let sample: Sample = object.as_object().unwrap().js_into(&mut context).unwrap();
+        assert_eq!(sample.get("http.status"), &SampleValue::Int(200));
+        assert_eq!(sample.get("http.headers.server"), &SampleValue::String("grey".into()));
+        assert_eq!(sample.get("http"), &SampleValue::None);
+        assert_eq!(sample.get("tags"), &SampleValue::List(vec![SampleValue::String("a".into())]));
+        assert_eq!(
+            sample.get("items"),
+            &SampleValue::List(vec![SampleValue::String(r#"{"id":1}"#.into())])
+        );
+        assert_eq!(
+            sample.get("when"),
+            &SampleValue::String(r#""1970-01-01T00:00:00.000Z""#.into())
+        );
+        assert_eq!(sample.get("flat.key"), &SampleValue::Bool(true));
+    }
+
+    #[test]
+    fn test_sample_to_js() {
+        let mut context = crate::js::budgeted().build().unwrap();
+        let sample = Sample::default()
+            .with("http.status", 200)
+            .with("tls.expiry", chrono::DateTime::from_timestamp(86_400, 0).unwrap())
+            .with("tls.remaining", chrono::Duration::seconds(2))
+            .with("tags", vec!["a", "b"]);
+
+        let object = sample.to_js(&mut context).unwrap();
+        context
+            .register_global_property(js_string!("sample"), object, boa_engine::property::Attribute::all())
+            .unwrap();
+
+        let result = context
+            .eval(Source::from_bytes(
+                r#"sample["http.status"] === 200 && sample["tls.expiry"].getTime() === 86400000 && sample["tls.remaining"] === 2000 && sample.tags.join() === "a,b""#,
+            ))
+            .unwrap();
+        assert_eq!(result, JsValue::from(true));
+    }
+
     fn get_sample_value(script: &str) -> SampleValue {
-        let mut context = Context::default();
+        let mut context = crate::js::budgeted().build().unwrap();
         let js_value = context.eval(Source::from_bytes(script)).unwrap();
 
         js_value.js_into(&mut context).unwrap()
@@ -0,0 +1,504 @@
+// Installs the Web APIs documented in `web.rs` onto the global object, on top of the native
+// primitives passed in as `natives`. This script evaluates to its install function.
+(function (natives) {
+    "use strict";
+
+    const define = (name, value) =>
+        Object.defineProperty(globalThis, name, {
+            value,
+            writable: true,
+            configurable: true,
+            enumerable: false,
+        });
+
+    const domError = (name, message) => Object.assign(new Error(message), { name });
+
+    // Copies binary data (an ArrayBuffer or a view onto one) into the plain array of byte values the
+    // native primitives accept.
+    const bytesOf = (data, what = "data") => {
+        if (data instanceof ArrayBuffer) {
+            return Array.from(new Uint8Array(data));
+        }
+
+        if (ArrayBuffer.isView(data)) {
+            return Array.from(new Uint8Array(data.buffer, data.byteOffset, data.byteLength));
+        }
+
+        throw new TypeError(`The ${what} must be an ArrayBuffer, TypedArray or DataView`);
+    };
+
+    const algorithmName = (algorithm) =>
+        String(typeof algorithm === "object" && algorithm !== null ? algorithm.name : algorithm).toUpperCase();
+
+    const notSupported = (algorithm) =>
+        domError("NotSupportedError", `The algorithm '${algorithmName(algorithm)}' is not supported`);
+
+    // --- crypto -------------------------------------------------------------------------------------
+
+    const keyMaterial = new WeakMap();
+
+    class CryptoKey {
+        constructor(type, extractable, algorithm, usages, material) {
+            this.type = type;
+            this.extractable = extractable;
+            this.algorithm = algorithm;
+            this.usages = usages;
+            keyMaterial.set(this, material);
+            Object.freeze(this);
+        }
+    }
+
+    const hmacKey = (key, usage) => {
+        if (!(key instanceof CryptoKey) || key.algorithm.name !== "HMAC") {
+            throw domError("InvalidAccessError", "The key is not an HMAC key");
+        }
+
+        if (!key.usages.includes(usage)) {
+            throw domError("InvalidAccessError", `The key may not be used to ${usage}`);
+        }
+
+        return keyMaterial.get(key);
+    };
+
+    const subtle = Object.freeze({
+        async digest(algorithm, data) {
+            return natives.digest(algorithmName(algorithm), bytesOf(data)).buffer;
+        },
+
+        async importKey(format, keyData, algorithm, extractable, usages) {
+            if (format !== "raw") {
+                throw domError("NotSupportedError", `The key format '${format}' is not supported; use 'raw'`);
+            }
+
+            if (algorithmName(algorithm) !== "HMAC") {
+                throw notSupported(algorithm);
+            }
+
+            const hash = algorithmName(algorithm.hash);
+            const material = bytesOf(keyData, "key data");
+            return new CryptoKey(
+                "secret",
+                !!extractable,
+                { name: "HMAC", hash: { name: hash }, length: material.length * 8 },
+                Array.from(usages ?? []),
+                material,
+            );
+        },
+
+        async exportKey(format, key) {
+            if (format !== "raw") {
+                throw domError("NotSupportedError", `The key format '${format}' is not supported; use 'raw'`);
+            }
+
+            if (!key.extractable) {
+                throw domError("InvalidAccessError", "The key is not extractable");
+            }
+
+            return new Uint8Array(keyMaterial.get(key)).buffer;
+        },
+
+        async sign(algorithm, key, data) {
+            if (algorithmName(algorithm) !== "HMAC") {
+                throw notSupported(algorithm);
+            }
+
+            return natives.hmacSign(key.algorithm.hash.name, hmacKey(key, "sign"), bytesOf(data)).buffer;
+        },
+
+        async verify(algorithm, key, signature, data) {
+            if (algorithmName(algorithm) !== "HMAC") {
+                throw notSupported(algorithm);
+            }
+
+            return natives.hmacVerify(
+                key.algorithm.hash.name,
+                hmacKey(key, "verify"),
+                bytesOf(signature, "signature"),
+                bytesOf(data),
+            );
+        },
+    });
+
+    define("CryptoKey", CryptoKey);
+    define("crypto", Object.freeze({
+        subtle,
+
+        getRandomValues(array) {
+            if (!ArrayBuffer.isView(array) || array instanceof DataView || array instanceof Float32Array || array instanceof Float64Array) {
+                throw new TypeError("getRandomValues expects an integer TypedArray");
+            }
+
+            if (array.byteLength > 65536) {
+                throw domError("QuotaExceededError", "getRandomValues can fill at most 65536 bytes at a time");
+            }
+
+            new Uint8Array(array.buffer, array.byteOffset, array.byteLength).set(natives.randomBytes(array.byteLength));
+            return array;
+        },
+
+        randomUUID() {
+            return natives.randomUUID();
+        },
+    }));
+
+    // --- base64 -------------------------------------------------------------------------------------
+
+    define("atob", (data) => natives.atob(String(data)));
+    define("btoa", (data) => natives.btoa(String(data)));
+
+    // --- text encoding ------------------------------------------------------------------------------
+
+    class TextEncoder {
+        get encoding() {
+            return "utf-8";
+        }
+
+        encode(input = "") {
+            return natives.utf8Encode(String(input));
+        }
+    }
+
+    class TextDecoder {
+        #fatal;
+        #ignoreBOM;
+
+        constructor(label = "utf-8", options = {}) {
+            if (!["utf-8", "utf8", "unicode-1-1-utf-8"].includes(String(label).trim().toLowerCase())) {
+                throw new RangeError(`The encoding '${label}' is not supported; only utf-8 is available`);
+            }
+
+            this.#fatal = !!options.fatal;
+            this.#ignoreBOM = !!options.ignoreBOM;
+        }
+
+        get encoding() {
+            return "utf-8";
+        }
+
+        get fatal() {
+            return this.#fatal;
+        }
+
+        get ignoreBOM() {
+            return this.#ignoreBOM;
+        }
+
+        decode(input) {
+            if (input === undefined) {
+                return "";
+            }
+
+            const text = natives.utf8Decode(bytesOf(input, "input"), this.#fatal);
+            return !this.#ignoreBOM && text.startsWith("\uFEFF") ? text.slice(1) : text;
+        }
+    }
+
+    define("TextEncoder", TextEncoder);
+    define("TextDecoder", TextDecoder);
+
+    // --- URL ----------------------------------------------------------------------------------------
+
+    // application/x-www-form-urlencoded leaves only alphanumerics and `*-._` unescaped, and encodes
+    // spaces as `+`.
+    const formEncode = (value) =>
+        encodeURIComponent(value)
+            .replace(/[!'()~]/g, (c) => "%" + c.charCodeAt(0).toString(16).toUpperCase())
+            .replace(/%20/g, "+");
+
+    const formDecode = (value) => {
+        const spaced = value.replace(/\+/g, " ");
+        try {
+            return decodeURIComponent(spaced);
+        } catch {
+            return spaced;
+        }
+    };
+
+    class URLSearchParams {
+        #entries = [];
+        #onChange = null;
+
+        constructor(init = "") {
+            if (typeof init === "object" && init !== null) {
+                const pairs = typeof init[Symbol.iterator] === "function" ? Array.from(init) : Object.entries(init);
+                this.#entries = pairs.map((pair) => {
+                    const [name, value, ...rest] = Array.from(pair);
+                    if (value === undefined || rest.length > 0) {
+                        throw new TypeError("Each URLSearchParams pair must contain exactly two items");
+                    }
+                    return [String(name), String(value)];
+                });
+            } else {
+                this.#parse(String(init));
+            }
+        }
+
+        #parse(query) {
+            this.#entries = (query.startsWith("?") ? query.slice(1) : query)
+                .split("&")
+                .filter((part) => part.length > 0)
+                .map((part) => {
+                    const separator = part.indexOf("=");
+                    return separator < 0
+                        ? [formDecode(part), ""]
+                        : [formDecode(part.slice(0, separator)), formDecode(part.slice(separator + 1))];
+                });
+        }
+
+        #changed() {
+            this.#onChange?.(this.toString());
+        }
+
+        // Links these params to the URL they were taken from, so changes update its `search` (and
+        // changes to its `search` replace these params).
+        static link(params, onChange) {
+            params.#onChange = onChange;
+        }
+
+        static reset(params, query) {
+            params.#parse(query);
+        }
+
+        get size() {
+            return this.#entries.length;
+        }
+
+        append(name, value) {
+            this.#entries.push([String(name), String(value)]);
+            this.#changed();
+        }
+
+        delete(name, value) {
+            this.#entries = this.#entries.filter(([n, v]) => !(n === String(name) && (value === undefined || v === String(value))));
+            this.#changed();
+        }
+
+        get(name) {
+            return this.#entries.find(([n]) => n === String(name))?.[1] ?? null;
+        }
+
+        getAll(name) {
+            return this.#entries.filter(([n]) => n === String(name)).map(([, v]) => v);
+        }
+
+        has(name, value) {
+            return this.#entries.some(([n, v]) => n === String(name) && (value === undefined || v === String(value)));
+        }
+
+        set(name, value) {
+            name = String(name);
+            const index = this.#entries.findIndex(([n]) => n === name);
+            if (index < 0) {
+                this.#entries.push([name, String(value)]);
+            } else {
+                this.#entries[index] = [name, String(value)];
+                this.#entries = this.#entries.filter(([n], i) => i <= index || n !== name);
+            }
+            this.#changed();
+        }
+
+        sort() {
+            this.#entries.sort(([a], [b]) => (a < b ? -1 : a > b ? 1 : 0));
+            this.#changed();
+        }
+
+        forEach(callback, thisArg) {
+            this.#entries.forEach(([name, value]) => callback.call(thisArg, value, name, this));
+        }
+
+        keys() {
+            return this.#entries.map(([name]) => name)[Symbol.iterator]();
+        }
+
+        values() {
+            return this.#entries.map(([, value]) => value)[Symbol.iterator]();
+        }
+
+        entries() {
+            return this.#entries.map((entry) => [...entry])[Symbol.iterator]();
+        }
+
+        [Symbol.iterator]() {
+            return this.entries();
+        }
+
+        toString() {
+            return this.#entries.map(([name, value]) => `${formEncode(name)}=${formEncode(value)}`).join("&");
+        }
+    }
+
+    class URL {
+        #components;
+        #searchParams;
+
+        constructor(url, base) {
+            this.#components = natives.urlParse(String(url), base === undefined ? undefined : String(base));
+            this.#searchParams = new URLSearchParams(this.#components.search);
+            URLSearchParams.link(this.#searchParams, (query) => {
+                this.#components = natives.urlSet(this.#components.href, "search", query);
+            });
+        }
+
+        static canParse(url, base) {
+            try {
+                new URL(url, base);
+                return true;
+            } catch {
+                return false;
+            }
+        }
+
+        #set(component, value) {
+            this.#components = natives.urlSet(this.#components.href, component, String(value));
+            if (component === "search" || component === "href") {
+                URLSearchParams.reset(this.#searchParams, this.#components.search);
+            }
+        }
+
+        get href() { return this.#components.href; }
+        set href(value) {
+            this.#components = natives.urlParse(String(value));
+            URLSearchParams.reset(this.#searchParams, this.#components.search);
+        }
+
+        get origin() { return this.#components.origin; }
+        get protocol() { return this.#components.protocol; }
+        set protocol(value) { this.#set("protocol", value); }
+        get username() { return this.#components.username; }
+        set username(value) { this.#set("username", value); }
+        get password() { return this.#components.password; }
+        set password(value) { this.#set("password", value); }
+        get host() { return this.#components.host; }
+        set host(value) { this.#set("host", value); }
+        get hostname() { return this.#components.hostname; }
+        set hostname(value) { this.#set("hostname", value); }
+        get port() { return this.#components.port; }
+        set port(value) { this.#set("port", value); }
+        get pathname() { return this.#components.pathname; }
+        set pathname(value) { this.#set("pathname", value); }
+        get search() { return this.#components.search; }
+        set search(value) { this.#set("search", value); }
+        get hash() { return this.#components.hash; }
+        set hash(value) { this.#set("hash", value); }
+
+        get searchParams() {
+            return this.#searchParams;
+        }
+
+        toString() {
+            return this.href;
+        }
+
+        toJSON() {
+            return this.href;
+        }
+    }
+
+    define("URLSearchParams", URLSearchParams);
+    define("URL", URL);
+
+    // --- AbortController ----------------------------------------------------------------------------
+
+    // Signals can only be created and aborted through these helpers (assigned in the class's static
+    // block), so scripts can't construct or trip a signal other than through its controller.
+    const signalKey = Symbol("AbortSignal");
+    let createSignal;
+    let abortSignal;
+
+    class AbortSignal {
+        #aborted = false;
+        #reason = undefined;
+        #listeners = [];
+
+        constructor(key) {
+            if (key !== signalKey) {
+                throw new TypeError("Illegal constructor; use an AbortController to create a signal");
+            }
+        }
+
+        static {
+            createSignal = () => new AbortSignal(signalKey);
+            abortSignal = (signal, reason) => {
+                if (signal.#aborted) {
+                    return signal;
+                }
+
+                signal.#aborted = true;
+                signal.#reason = reason === undefined ? domError("AbortError", "The operation was aborted") : reason;
+
+                const listeners = signal.#listeners;
+                signal.#listeners = [];
+                const event = { type: "abort", target: signal };
+                signal.onabort?.call(signal, event);
+                listeners.forEach((listener) => listener.call(signal, event));
+                return signal;
+            };
+        }
+
+        static abort(reason) {
+            return abortSignal(createSignal(), reason);
+        }
+
+        static timeout(milliseconds) {
+            const signal = createSignal();
+            setTimeout(
+                () => abortSignal(signal, domError("TimeoutError", `The operation timed out after ${milliseconds}ms`)),
+                Number(milliseconds),
+            );
+            return signal;
+        }
+
+        static any(signals) {
+            const signal = createSignal();
+            for (const source of signals) {
+                if (source.aborted) {
+                    return abortSignal(signal, source.reason);
+                }
+
+                source.addEventListener("abort", () => abortSignal(signal, source.reason));
+            }
+            return signal;
+        }
+
+        get aborted() {
+            return this.#aborted;
+        }
+
+        get reason() {
+            return this.#reason;
+        }
+
+        throwIfAborted() {
+            if (this.#aborted) {
+                throw this.#reason;
+            }
+        }
+
+        addEventListener(type, listener) {
+            if (type === "abort" && typeof listener === "function" && !this.#aborted) {
+                this.#listeners.push(listener);
+            }
+        }
+
+        removeEventListener(type, listener) {
+            this.#listeners = this.#listeners.filter((l) => l !== listener);
+        }
+    }
+
+    AbortSignal.prototype.onabort = null;
+
+    class AbortController {
+        #signal = createSignal();
+
+        get signal() {
+            return this.#signal;
+        }
+
+        abort(reason) {
+            abortSignal(this.#signal, reason);
+        }
+    }
+
+    define("AbortSignal", AbortSignal);
+    define("AbortController", AbortController);
+})
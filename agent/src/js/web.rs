@@ -0,0 +1,550 @@
+//! The Web platform APIs scripts commonly need beyond those provided by `boa_runtime`: a WebCrypto
+//! subset (`crypto`), `atob`/`btoa`, `TextEncoder`/`TextDecoder`, `URL`/`URLSearchParams` and
+//! `AbortController`/`AbortSignal`.
+//!
+//! The public API surface lives in `web.js`, which implements the classes and argument handling in
+//! JavaScript on top of the native primitives defined here. Bytes are passed into the primitives as
+//! plain arrays (built with `Array.from`, so no script-level loop counts against the run's loop
+//! budget) and are returned as `Uint8Array`s.
+
+use boa_engine::{
+    Context, IntoJsFunctionCopied, JsNativeError, JsObject, JsResult, JsValue, Source, js_string,
+    object::{ObjectInitializer, builtins::JsUint8Array},
+};
+use hmac::{Hmac, KeyInit, Mac};
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+/// Registers the Web APIs described in the module documentation as globals in `context`.
+pub fn register(context: &mut Context) -> JsResult<()> {
+    let random_bytes_ = random_bytes.into_js_function_copied(context);
+    let random_uuid_ = random_uuid.into_js_function_copied(context);
+    let digest_ = digest.into_js_function_copied(context);
+    let hmac_sign_ = hmac_sign.into_js_function_copied(context);
+    let hmac_verify_ = hmac_verify.into_js_function_copied(context);
+    let atob_ = atob.into_js_function_copied(context);
+    let btoa_ = btoa.into_js_function_copied(context);
+    let utf8_encode_ = utf8_encode.into_js_function_copied(context);
+    let utf8_decode_ = utf8_decode.into_js_function_copied(context);
+    let url_parse_ = url_parse.into_js_function_copied(context);
+    let url_set_ = url_set.into_js_function_copied(context);
+
+    let natives = ObjectInitializer::new(context)
+        .function(random_bytes_, js_string!("randomBytes"), 1)
+        .function(random_uuid_, js_string!("randomUUID"), 0)
+        .function(digest_, js_string!("digest"), 2)
+        .function(hmac_sign_, js_string!("hmacSign"), 3)
+        .function(hmac_verify_, js_string!("hmacVerify"), 4)
+        .function(atob_, js_string!("atob"), 1)
+        .function(btoa_, js_string!("btoa"), 1)
+        .function(utf8_encode_, js_string!("utf8Encode"), 1)
+        .function(utf8_decode_, js_string!("utf8Decode"), 2)
+        .function(url_parse_, js_string!("urlParse"), 2)
+        .function(url_set_, js_string!("urlSet"), 3)
+        .build();
+
+    let install = context.eval(Source::from_bytes(include_str!("web.js")))?;
+    let install = install.as_callable().ok_or_else(|| {
+        JsNativeError::typ().with_message("web.js must evaluate to its install function")
+    })?;
+    install.call(&JsValue::undefined(), &[natives.into()], context)?;
+
+    Ok(())
+}
+
+/// Reads the plain array of byte values the JavaScript side passes for binary data.
+pub(super) fn bytes_of(value: &JsValue, context: &mut Context) -> JsResult<Vec<u8>> {
+    let Some(array) = value.as_object() else {
+        return Err(JsNativeError::typ()
+            .with_message("expected an array of bytes")
+            .into());
+    };
+
+    let length = array.get(js_string!("length"), context)?.to_length(context)? as usize;
+    let mut bytes = Vec::with_capacity(length);
+    for i in 0..length {
+        bytes.push(array.get(i, context)?.to_uint8(context)?);
+    }
+
+    Ok(bytes)
+}
+
+fn uint8_array(bytes: impl IntoIterator<Item = u8>, context: &mut Context) -> JsResult<JsValue> {
+    Ok(JsUint8Array::from_iter(bytes, context)?.into())
+}
+
+fn random_bytes(length: JsValue, context: &mut Context) -> JsResult<JsValue> {
+    let length = length.to_length(context)?;
+    uint8_array((0..length).map(|_| rand::random::<u8>()), context)
+}
+
+fn random_uuid() -> JsValue {
+    js_string!(uuid::Uuid::new_v4().to_string()).into()
+}
+
+fn digest(algorithm: JsValue, data: JsValue, context: &mut Context) -> JsResult<JsValue> {
+    let data = bytes_of(&data, context)?;
+    let digest = match algorithm.to_string(context)?.to_std_string_lossy().as_str() {
+        "SHA-256" => Sha256::digest(&data).to_vec(),
+        "SHA-384" => Sha384::digest(&data).to_vec(),
+        "SHA-512" => Sha512::digest(&data).to_vec(),
+        other => return Err(unsupported_hash(other)),
+    };
+
+    uint8_array(digest, context)
+}
+
+fn hmac_sign(
+    hash: JsValue,
+    key: JsValue,
+    data: JsValue,
+    context: &mut Context,
+) -> JsResult<JsValue> {
+    let hash = hash.to_string(context)?.to_std_string_lossy();
+    let key = bytes_of(&key, context)?;
+    let data = bytes_of(&data, context)?;
+
+    uint8_array(hmac(&hash, &key, &data)?, context)
+}
+
+fn hmac_verify(
+    hash: JsValue,
+    key: JsValue,
+    signature: JsValue,
+    data: JsValue,
+    context: &mut Context,
+) -> JsResult<JsValue> {
+    let hash = hash.to_string(context)?.to_std_string_lossy();
+    let key = bytes_of(&key, context)?;
+    let signature = bytes_of(&signature, context)?;
+    let data = bytes_of(&data, context)?;
+
+    let valid = match hash.as_str() {
+        "SHA-256" => mac::<Hmac<Sha256>>(&key, &data).verify_slice(&signature),
+        "SHA-384" => mac::<Hmac<Sha384>>(&key, &data).verify_slice(&signature),
+        "SHA-512" => mac::<Hmac<Sha512>>(&key, &data).verify_slice(&signature),
+        other => return Err(unsupported_hash(other)),
+    };
+
+    Ok(valid.is_ok().into())
+}
+
+fn hmac(hash: &str, key: &[u8], data: &[u8]) -> JsResult<Vec<u8>> {
+    Ok(match hash {
+        "SHA-256" => mac::<Hmac<Sha256>>(key, data).finalize().into_bytes().to_vec(),
+        "SHA-384" => mac::<Hmac<Sha384>>(key, data).finalize().into_bytes().to_vec(),
+        "SHA-512" => mac::<Hmac<Sha512>>(key, data).finalize().into_bytes().to_vec(),
+        other => return Err(unsupported_hash(other)),
+    })
+}
+
+fn mac<M: Mac + KeyInit>(key: &[u8], data: &[u8]) -> M {
+    let mut mac = <M as KeyInit>::new_from_slice(key).expect("HMAC accepts a key of any length");
+    mac.update(data);
+    mac
+}
+
+fn unsupported_hash(name: &str) -> boa_engine::JsError {
+    JsNativeError::typ()
+        .with_message(format!(
+            "unsupported hash algorithm '{name}'; expected one of SHA-256, SHA-384 or SHA-512"
+        ))
+        .into()
+}
+
+/// Decodes base64 into a "binary string" (one character per byte), following the forgiving base64
+/// rules `atob` uses: ASCII whitespace is ignored and padding is optional.
+fn atob(data: JsValue, context: &mut Context) -> JsResult<JsValue> {
+    use base64::prelude::*;
+
+    let data = data.to_string(context)?.to_std_string_lossy();
+    let mut data: String = data.chars().filter(|c| !c.is_ascii_whitespace()).collect();
+    if data.len() % 4 == 0 {
+        for _ in 0..2 {
+            if data.ends_with('=') {
+                data.pop();
+            }
+        }
+    }
+
+    let bytes = BASE64_STANDARD_NO_PAD
+        .decode(data.as_bytes())
+        .map_err(|e| invalid_character(&format!("the string is not valid base64: {e}")))?;
+
+    Ok(js_string!(bytes.into_iter().map(char::from).collect::<String>()).into())
+}
+
+/// Encodes a "binary string" (whose characters must all be in the range U+0000 to U+00FF) as base64.
+fn btoa(data: JsValue, context: &mut Context) -> JsResult<JsValue> {
+    use base64::prelude::*;
+
+    let data = data.to_string(context)?.to_std_string_lossy();
+    let bytes = data
+        .chars()
+        .map(|c| {
+            u8::try_from(c).map_err(|_| {
+                invalid_character("the string contains characters outside of the Latin1 range")
+            })
+        })
+        .collect::<JsResult<Vec<u8>>>()?;
+
+    Ok(js_string!(BASE64_STANDARD.encode(bytes)).into())
+}
+
+fn invalid_character(message: &str) -> boa_engine::JsError {
+    JsNativeError::error()
+        .with_message(format!("InvalidCharacterError: {message}"))
+        .into()
+}
+
+fn utf8_encode(text: JsValue, context: &mut Context) -> JsResult<JsValue> {
+    // Lone surrogates are replaced with U+FFFD, exactly as `TextEncoder` specifies.
+    let text = text.to_string(context)?.to_std_string_lossy();
+    uint8_array(text.into_bytes(), context)
+}
+
+fn utf8_decode(data: JsValue, fatal: JsValue, context: &mut Context) -> JsResult<JsValue> {
+    let bytes = bytes_of(&data, context)?;
+    let text = if fatal.to_boolean() {
+        String::from_utf8(bytes).map_err(|e| {
+            JsNativeError::typ().with_message(format!("the data is not valid UTF-8: {e}"))
+        })?
+    } else {
+        String::from_utf8_lossy(&bytes).into_owned()
+    };
+
+    Ok(js_string!(text).into())
+}
+
+/// Parses `href` (relative to `base`, when provided) into the components exposed by `URL`.
+fn url_parse(href: JsValue, base: JsValue, context: &mut Context) -> JsResult<JsValue> {
+    let href = href.to_string(context)?.to_std_string_lossy();
+    let url = if base.is_undefined() {
+        reqwest::Url::parse(&href)
+    } else {
+        let base = base.to_string(context)?.to_std_string_lossy();
+        reqwest::Url::parse(&base).and_then(|base| base.join(&href))
+    }
+    .map_err(|e| JsNativeError::typ().with_message(format!("Invalid URL '{href}': {e}")))?;
+
+    url_components(&url, context)
+}
+
+/// Applies the setter for `component` to `href`, returning the updated components. As with the
+/// `URL` setters, a value which can't be applied leaves the URL unchanged.
+fn url_set(
+    href: JsValue,
+    component: JsValue,
+    value: JsValue,
+    context: &mut Context,
+) -> JsResult<JsValue> {
+    let href = href.to_string(context)?.to_std_string_lossy();
+    let component = component.to_string(context)?.to_std_string_lossy();
+    let value = value.to_string(context)?.to_std_string_lossy();
+
+    let mut url = reqwest::Url::parse(&href)
+        .map_err(|e| JsNativeError::typ().with_message(format!("Invalid URL '{href}': {e}")))?;
+
+    match component.as_str() {
+        "protocol" => {
+            let _ = url.set_scheme(value.trim_end_matches(':'));
+        }
+        "username" => {
+            let _ = url.set_username(&value);
+        }
+        "password" => {
+            let _ = url.set_password((!value.is_empty()).then_some(value.as_str()));
+        }
+        "host" => {
+            let (host, port) = match value.rsplit_once(':') {
+                Some((host, port)) if !port.contains(']') => (host, Some(port)),
+                _ => (value.as_str(), None),
+            };
+            if url.set_host(Some(host)).is_ok()
+                && let Some(port) = port
+            {
+                let _ = url.set_port(port.parse().ok());
+            }
+        }
+        "hostname" => {
+            let _ = url.set_host(Some(&value));
+        }
+        "port" => {
+            if value.is_empty() {
+                let _ = url.set_port(None);
+            } else if let Ok(port) = value.parse() {
+                let _ = url.set_port(Some(port));
+            }
+        }
+        "pathname" => url.set_path(&value),
+        "search" => {
+            let query = value.strip_prefix('?').unwrap_or(&value);
+            url.set_query(Some(query).filter(|q| !q.is_empty()));
+        }
+        "hash" => {
+            let fragment = value.strip_prefix('#').unwrap_or(&value);
+            url.set_fragment(Some(fragment).filter(|f| !f.is_empty()));
+        }
+        other => {
+            return Err(JsNativeError::typ()
+                .with_message(format!("'{other}' is not a URL component"))
+                .into());
+        }
+    }
+
+    url_components(&url, context)
+}
+
+fn url_components(url: &reqwest::Url, context: &mut Context) -> JsResult<JsValue> {
+    let hostname = url.host_str().unwrap_or_default().to_string();
+    let port = url.port().map(|port| port.to_string()).unwrap_or_default();
+    let host = if port.is_empty() {
+        hostname.clone()
+    } else {
+        format!("{hostname}:{port}")
+    };
+
+    let components = [
+        ("href", url.as_str().to_string()),
+        ("origin", url.origin().ascii_serialization()),
+        ("protocol", format!("{}:", url.scheme())),
+        ("username", url.username().to_string()),
+        ("password", url.password().unwrap_or_default().to_string()),
+        ("host", host),
+        ("hostname", hostname),
+        ("port", port),
+        ("pathname", url.path().to_string()),
+        (
+            "search",
+            url.query()
+                .filter(|q| !q.is_empty())
+                .map(|q| format!("?{q}"))
+                .unwrap_or_default(),
+        ),
+        (
+            "hash",
+            url.fragment()
+                .filter(|f| !f.is_empty())
+                .map(|f| format!("#{f}"))
+                .unwrap_or_default(),
+        ),
+    ];
+
+    let object = JsObject::with_null_proto();
+    for (key, value) in components {
+        object.set(js_string!(key), js_string!(value), false, context)?;
+    }
+
+    Ok(object.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> Context {
+        let mut context = crate::js::budgeted().build().unwrap();
+        register(&mut context).unwrap();
+        context
+    }
+
+    fn eval(context: &mut Context, code: &str) -> String {
+        context
+            .eval(Source::from_bytes(code))
+            .unwrap()
+            .to_string(context)
+            .unwrap()
+            .to_std_string_lossy()
+    }
+
+    /// Evaluates an expression which produces a promise, returning what it resolves to.
+    fn eval_async(context: &mut Context, code: &str) -> String {
+        eval(
+            context,
+            &format!("globalThis.result = undefined; ({code}).then(r => globalThis.result = r, e => globalThis.result = `${{e.name}}: ${{e.message}}`);"),
+        );
+        context.run_jobs().unwrap();
+        eval(context, "String(globalThis.result)")
+    }
+
+    const HEX: &str = r#"const hex = (buffer) => Array.from(new Uint8Array(buffer), (b) => b.toString(16).padStart(2, "0")).join("");"#;
+
+    #[test]
+    fn test_digest() {
+        let mut context = context();
+        eval(&mut context, HEX);
+
+        assert_eq!(
+            eval_async(
+                &mut context,
+                r#"crypto.subtle.digest("SHA-256", new TextEncoder().encode("abc")).then(hex)"#
+            ),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            eval_async(&mut context, r#"crypto.subtle.digest({ name: "MD5" }, new Uint8Array(1))"#),
+            "TypeError: unsupported hash algorithm 'MD5'; expected one of SHA-256, SHA-384 or SHA-512"
+        );
+    }
+
+    #[test]
+    fn test_hmac_sign_and_verify() {
+        let mut context = context();
+        eval(&mut context, HEX);
+        eval(
+            &mut context,
+            r#"
+            const encoder = new TextEncoder();
+            const message = encoder.encode("The quick brown fox jumps over the lazy dog");
+            const importKey = (usages) => crypto.subtle.importKey(
+                "raw", encoder.encode("key"), { name: "HMAC", hash: "SHA-256" }, false, usages);
+            "#,
+        );
+
+        assert_eq!(
+            eval_async(
+                &mut context,
+                r#"importKey(["sign"]).then((key) => crypto.subtle.sign("HMAC", key, message)).then(hex)"#
+            ),
+            "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+        assert_eq!(
+            eval_async(
+                &mut context,
+                r#"importKey(["sign", "verify"]).then(async (key) =>
+                    [await crypto.subtle.verify("HMAC", key, await crypto.subtle.sign("HMAC", key, message), message),
+                     await crypto.subtle.verify("HMAC", key, new Uint8Array(32), message)].join())"#
+            ),
+            "true,false"
+        );
+        assert_eq!(
+            eval_async(
+                &mut context,
+                r#"importKey(["verify"]).then((key) => crypto.subtle.sign("HMAC", key, message))"#
+            ),
+            "InvalidAccessError: The key may not be used to sign"
+        );
+    }
+
+    #[test]
+    fn test_random_values() {
+        let mut context = context();
+
+        assert_eq!(eval(&mut context, "crypto.getRandomValues(new Uint32Array(8)).length"), "8");
+        assert!(
+            is_v4_uuid(&eval(&mut context, "crypto.randomUUID()")),
+            "randomUUID should produce a v4 UUID"
+        );
+        assert_eq!(
+            eval(&mut context, "crypto.randomUUID() === crypto.randomUUID()"),
+            "false"
+        );
+    }
+
+    fn is_v4_uuid(value: &str) -> bool {
+        uuid::Uuid::parse_str(value).is_ok_and(|uuid| uuid.get_version_num() == 4)
+    }
+
+    #[test]
+    fn test_base64() {
+        let mut context = context();
+
+        assert_eq!(eval(&mut context, r#"btoa("hello, world")"#), "aGVsbG8sIHdvcmxk");
+        assert_eq!(eval(&mut context, r#"atob("aGVsbG8sIHdvcmxk")"#), "hello, world");
+        assert_eq!(eval(&mut context, r#"atob(" aGk ")"#), "hi");
+        assert_eq!(
+            eval(&mut context, r#"try { btoa("✓") } catch (e) { e.message }"#),
+            "InvalidCharacterError: the string contains characters outside of the Latin1 range"
+        );
+    }
+
+    #[test]
+    fn test_text_encoding() {
+        let mut context = context();
+
+        assert_eq!(
+            eval(&mut context, r#"Array.from(new TextEncoder().encode("é✓")).join()"#),
+            "195,169,226,156,147"
+        );
+        assert_eq!(
+            eval(
+                &mut context,
+                r#"new TextDecoder().decode(new Uint8Array([0xEF, 0xBB, 0xBF, 0x68, 0x69]))"#
+            ),
+            "hi"
+        );
+        assert_eq!(
+            eval(
+                &mut context,
+                r#"try { new TextDecoder("utf-8", { fatal: true }).decode(new Uint8Array([0xFF])) } catch (e) { e.name }"#
+            ),
+            "TypeError"
+        );
+    }
+
+    #[test]
+    fn test_url() {
+        let mut context = context();
+
+        assert_eq!(
+            eval(
+                &mut context,
+                r#"
+                const url = new URL("/api/items?page=2&tag=a+b#top", "https://user:pw@Example.com:8443/base/");
+                [url.href, url.origin, url.host, url.pathname, url.search, url.hash, url.searchParams.get("tag")].join(" | ")
+                "#
+            ),
+            "https://user:pw@example.com:8443/api/items?page=2&tag=a+b#top | https://example.com:8443 | example.com:8443 | /api/items | ?page=2&tag=a+b | #top | a b"
+        );
+
+        assert_eq!(
+            eval(
+                &mut context,
+                r#"
+                url.searchParams.set("page", "3");
+                url.searchParams.append("q", "x & y");
+                url.hash = "";
+                url.href
+                "#
+            ),
+            "https://user:pw@example.com:8443/api/items?page=3&tag=a+b&q=x+%26+y"
+        );
+
+        assert_eq!(
+            eval(
+                &mut context,
+                r#"new URLSearchParams({ b: "2", a: "1" }).toString() + " " + URL.canParse("not a url")"#
+            ),
+            "b=2&a=1 false"
+        );
+    }
+
+    #[test]
+    fn test_abort_controller() {
+        let mut context = context();
+        eval(
+            &mut context,
+            r#"
+            const controller = new AbortController();
+            const events = [];
+            controller.signal.addEventListener("abort", (e) => events.push(e.type));
+            const combined = AbortSignal.any([controller.signal]);
+            "#,
+        );
+
+        assert_eq!(eval(&mut context, "controller.signal.aborted"), "false");
+        eval(&mut context, "controller.abort(); controller.abort('again')");
+        assert_eq!(eval(&mut context, "controller.signal.aborted"), "true");
+        assert_eq!(eval(&mut context, "controller.signal.reason.name"), "AbortError");
+        assert_eq!(eval(&mut context, "events.join()"), "abort", "listeners run once");
+        assert_eq!(eval(&mut context, "combined.reason.name"), "AbortError");
+        assert_eq!(
+            eval(&mut context, "try { controller.signal.throwIfAborted(); 'ok' } catch (e) { e.name }"),
+            "AbortError"
+        );
+        assert_eq!(eval(&mut context, "AbortSignal.abort('why').reason"), "why");
+        assert_eq!(
+            eval(&mut context, "try { new AbortSignal(); 'ok' } catch (e) { e.name }"),
+            "TypeError"
+        );
+    }
+}
@@ -0,0 +1,199 @@
+//! Concurrency limits on probe execution: an optional cap on the number of probes running at once
+//! across the agent, plus optional caps per tag value and per target host (see
+//! [`crate::config::ConcurrencyConfig`]). Every [`crate::probe_runner::ProbeRunner`] shares one
+//! [`ConcurrencyLimiter`], so a burst of heavy probes queues rather than starving the rest of the
+//! single-threaded runtime.
+//!
+//! Runs which can't start yet wait in a single queue in arrival order. Whenever a slot frees up it is
+//! handed to the longest-waiting run whose limits all have room, so probes contending for the same
+//! slot are served first come, first served, while a saturated host only holds back its own probes.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use futures::channel::oneshot;
+
+/// The shared queue which hands out slots to probe runs. Cloning it shares the same limits.
+#[derive(Clone, Default)]
+pub struct ConcurrencyLimiter {
+    inner: Arc<Mutex<Inner>>,
+}
+
+#[derive(Default)]
+struct Inner {
+    /// The number of runs currently holding a slot under each limit key.
+    running: HashMap<String, usize>,
+    /// The runs waiting for a slot, oldest first.
+    queue: VecDeque<Waiter>,
+}
+
+struct Waiter {
+    limits: Vec<(String, usize)>,
+    grant: oneshot::Sender<Permit>,
+}
+
+/// A run's claim on a slot under each of its limits, released when dropped.
+pub struct Permit {
+    limiter: ConcurrencyLimiter,
+    keys: Vec<String>,
+}
+
+impl ConcurrencyLimiter {
+    /// Waits until a run subject to `limits` (as produced by
+    /// [`crate::config::ConcurrencyConfig::limits_for`]) may start, returning the permit which holds
+    /// its slot.
+    pub async fn acquire(&self, limits: Vec<(String, usize)>) -> Permit {
+        let granted = {
+            let mut inner = self.inner.lock().unwrap();
+            // Every queued run is waiting on a limit which is currently full, so a run with room under
+            // all of its limits isn't competing with any of them and can start straight away.
+            if inner.has_room(&limits) {
+                return inner.claim(self, &limits);
+            }
+
+            let (grant, granted) = oneshot::channel();
+            inner.queue.push_back(Waiter { limits, grant });
+            granted
+        };
+
+        granted
+            .await
+            .expect("a queued run is only removed from the queue once it has been granted a slot")
+    }
+
+    /// The number of runs waiting for a slot.
+    pub fn queued(&self) -> usize {
+        self.inner.lock().unwrap().queue.len()
+    }
+
+    fn release(&self, keys: &[String]) {
+        let grants = {
+            let mut inner = self.inner.lock().unwrap();
+            for key in keys {
+                if let Some(running) = inner.running.get_mut(key) {
+                    *running = running.saturating_sub(1);
+                    if *running == 0 {
+                        inner.running.remove(key);
+                    }
+                }
+            }
+
+            inner.dispatch(self)
+        };
+
+        // Permits are handed over outside the lock: one whose run stopped waiting (its receiver was
+        // dropped) comes straight back and is released in turn, which needs the lock.
+        for (grant, permit) in grants {
+            let _ = grant.send(permit);
+        }
+    }
+}
+
+impl Inner {
+    fn has_room(&self, limits: &[(String, usize)]) -> bool {
+        limits
+            .iter()
+            .all(|(key, max)| self.running.get(key).copied().unwrap_or_default() < *max)
+    }
+
+    fn claim(&mut self, limiter: &ConcurrencyLimiter, limits: &[(String, usize)]) -> Permit {
+        for (key, _) in limits {
+            *self.running.entry(key.clone()).or_default() += 1;
+        }
+
+        Permit {
+            limiter: limiter.clone(),
+            keys: limits.iter().map(|(key, _)| key.clone()).collect(),
+        }
+    }
+
+    /// Claims a slot for every queued run which now has room, oldest first, dropping any which have
+    /// stopped waiting.
+    fn dispatch(&mut self, limiter: &ConcurrencyLimiter) -> Vec<(oneshot::Sender<Permit>, Permit)> {
+        let mut grants = Vec::new();
+        let mut waiting = VecDeque::with_capacity(self.queue.len());
+        while let Some(waiter) = self.queue.pop_front() {
+            if waiter.grant.is_canceled() {
+                continue;
+            }
+
+            if self.has_room(&waiter.limits) {
+                let permit = self.claim(limiter, &waiter.limits);
+                grants.push((waiter.grant, permit));
+            } else {
+                waiting.push_back(waiter);
+            }
+        }
+
+        self.queue = waiting;
+        grants
+    }
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        self.limiter.release(&self.keys);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(keys: &[(&str, usize)]) -> Vec<(String, usize)> {
+        keys.iter().map(|(key, max)| (key.to_string(), *max)).collect()
+    }
+
+    #[tokio::test]
+    async fn queues_runs_beyond_the_limit() {
+        let limiter = ConcurrencyLimiter::default();
+        let first = limiter.acquire(limits(&[("global", 1)])).await;
+
+        let mut second = Box::pin(limiter.acquire(limits(&[("global", 1)])));
+        assert!(futures::poll!(second.as_mut()).is_pending());
+        assert_eq!(limiter.queued(), 1);
+
+        drop(first);
+        assert_eq!(limiter.queued(), 0);
+        let _second = second.await;
+    }
+
+    #[tokio::test]
+    async fn serves_waiting_runs_in_order() {
+        let limiter = ConcurrencyLimiter::default();
+        let held = limiter.acquire(limits(&[("global", 1)])).await;
+
+        let mut first = Box::pin(limiter.acquire(limits(&[("global", 1)])));
+        let mut second = Box::pin(limiter.acquire(limits(&[("global", 1)])));
+        assert!(futures::poll!(first.as_mut()).is_pending());
+        assert!(futures::poll!(second.as_mut()).is_pending());
+
+        drop(held);
+        assert!(futures::poll!(second.as_mut()).is_pending(), "the later run must keep waiting");
+        let first = first.await;
+
+        drop(first);
+        let _second = second.await;
+    }
+
+    /// A run held back by its own host's limit doesn't block runs against other hosts, and a run
+    /// which gives up waiting doesn't keep a slot.
+    #[tokio::test]
+    async fn limits_are_independent_per_key() {
+        let limiter = ConcurrencyLimiter::default();
+        let a = limiter.acquire(limits(&[("global", 2), ("host:a", 1)])).await;
+
+        let mut blocked = Box::pin(limiter.acquire(limits(&[("global", 2), ("host:a", 1)])));
+        assert!(futures::poll!(blocked.as_mut()).is_pending());
+
+        // Another host still has room under the global limit.
+        let b = limiter.acquire(limits(&[("global", 2), ("host:b", 1)])).await;
+
+        // The waiting run gives up; freeing its host must not leak a slot to nobody.
+        drop(blocked);
+        drop(a);
+        assert_eq!(limiter.queued(), 0);
+        let _a = limiter.acquire(limits(&[("global", 2), ("host:a", 1)])).await;
+        drop(b);
+    }
+}
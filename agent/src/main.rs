@@ -4,8 +4,10 @@ extern crate tracing_batteries;
 
 use std::sync::atomic::AtomicBool;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 
+mod baseline;
+mod change;
 mod checks;
 mod cluster;
 mod config;
@@ -13,8 +15,10 @@ mod cron;
 mod cron_monitor;
 mod engine;
 mod js;
+mod limiter;
 #[macro_use]
 mod macros;
+mod metrics;
 mod notify;
 mod policy;
 mod probe;
@@ -22,8 +26,10 @@ mod probe_runner;
 mod result;
 mod sample;
 mod serializers;
+mod slo;
 mod state;
 mod targets;
+mod telemetry;
 mod api;
 mod utils;
 
@@ -38,6 +44,11 @@ pub const HISTORY_SIZE: usize = 24;
 
 static CANCEL: AtomicBool = AtomicBool::new(false);
 
+/// Counts the memory each script's thread holds, enforcing its `limits.max_heap_size`.
+#[cfg(feature = "scripts")]
+#[global_allocator]
+static ALLOCATOR: js::HeapTracker = js::HeapTracker;
+
 #[tokio::main(flavor = "current_thread")]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // ctrlc::set_handler(|| {
@@ -46,12 +57,18 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let args = Args::parse();
 
+    if let Some(Command::State(command)) = args.command {
+        return run_state_command(&args.config, command).await;
+    }
+
     let telemetry = tracing_batteries::Session::new("grey", version!("v"))
         .with_battery(tracing_batteries::OpenTelemetry::new(""))
         .with_battery(tracing_batteries::Analytics::new(
             "https://analytics.sierrasoftworks.com",
         ));
 
+    let meter_provider = telemetry::init(version!("v"))?;
+
     let state = state::State::new(&args.config).await?;
 
     tracing::info!(
@@ -63,6 +80,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let local_set = &mut tokio::task::LocalSet::new();
     local_set.run_until(engine.run(&CANCEL)).await?;
 
+    if let Some(provider) = meter_provider
+        && let Err(err) = provider.shutdown()
+    {
+        tracing::warn!("Failed to flush OpenTelemetry metrics: {err}");
+    }
     telemetry.shutdown();
 
     Ok(())
@@ -74,4 +96,79 @@ struct Args {
     /// The path to the configuration file which defines the probes to run.
     #[clap(short, long, value_parser)]
     config: String,
+
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Backs up or restores the state database named by the configuration.
+    #[clap(subcommand)]
+    State(StateCommand),
+}
+
+#[derive(Subcommand, Debug)]
+enum StateCommand {
+    /// Writes every probe, cron and incident record in the state database to a portable archive.
+    Export {
+        /// Where to write the archive.
+        path: std::path::PathBuf,
+
+        /// The archive's encoding: `json` or `msgpack`.
+        #[clap(long, default_value = "json")]
+        format: state::ArchiveFormat,
+    },
+    /// Merges the records in an archive (in either encoding) into the state database, by the same
+    /// rules used to merge the state gossiped by peers.
+    Import {
+        /// The archive to read.
+        path: std::path::PathBuf,
+    },
+}
+
+/// Runs a `grey state` subcommand against the state database, which the agent must not be running
+/// against: it holds the database open while it runs.
+async fn run_state_command(config: &str, command: StateCommand) -> Result<(), Box<dyn std::error::Error>> {
+    use state::ArchiveStore;
+
+    match command {
+        StateCommand::Export { path, format } => {
+            // A backup only reads the database, so it is opened read-only rather than as the agent
+            // would open it, which would create a missing database and start a new generation.
+            let config = Config::load_from_path(std::path::Path::new(config)).await?;
+            let archive = state::export_database(&config.state)?;
+            tokio::fs::write(&path, archive.to_vec(format)?)
+                .await
+                .map_err(|e| format!("Failed to write the state archive to '{}': {e}", path.display()))?;
+            println!(
+                "Exported {} probe, {} cron, {} incident and {} incident update records to '{}'.",
+                archive.probes.len(),
+                archive.crons.len(),
+                archive.incidents.len(),
+                archive.incident_updates.len(),
+                path.display()
+            );
+        }
+        StateCommand::Import { path } => {
+            let state = state::State::new(config).await.map_err(|e| {
+                format!("Failed to open the state database ({e}). Stop the agent before running this command.")
+            })?;
+            let data = tokio::fs::read(&path)
+                .await
+                .map_err(|e| format!("Failed to read the state archive '{}': {e}", path.display()))?;
+            let archive = state::StateArchive::from_slice(&data)?;
+            state.import_archive(&archive).await?;
+            println!(
+                "Merged {} probe, {} cron, {} incident and {} incident update records from '{}'.",
+                archive.probes.len(),
+                archive.crons.len(),
+                archive.incidents.len(),
+                archive.incident_updates.len(),
+                path.display()
+            );
+        }
+    }
+
+    Ok(())
 }
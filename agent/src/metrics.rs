@@ -0,0 +1,487 @@
+//! Metrics derived from the pooled probe and cron state and from this node's view of the cluster,
+//! rendered in the Prometheus text exposition format (or OpenMetrics) for the `/metrics` endpoint.
+//!
+//! Every value is computed from state at scrape time rather than being recorded as it happens, so
+//! the metrics of every node in a cluster agree on each probe's samples, while the gossip counters
+//! and peer health describe the node which was scraped. Probe counters are totalled across every
+//! observer; an observer's departure (or its samples ageing out) can make them fall, which
+//! Prometheus treats as a counter reset.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+use chrono::{DateTime, Utc};
+use grey_api::{Cron, CronHealth, Peer, PeerHealth, Probe};
+
+use crate::cluster::GossipStats;
+
+/// The content type of the Prometheus text exposition format.
+pub const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// The content type of the OpenMetrics text format.
+pub const OPENMETRICS_CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+const CRON_HEALTHS: [CronHealth; 6] = [
+    CronHealth::Pending,
+    CronHealth::Running,
+    CronHealth::Succeeded,
+    CronHealth::Failed,
+    CronHealth::Missing,
+    CronHealth::Stuck,
+];
+
+const PEER_HEALTHS: [PeerHealth; 4] = [
+    PeerHealth::Online,
+    PeerHealth::Transitive,
+    PeerHealth::Suspect,
+    PeerHealth::Offline,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricKind {
+    Gauge,
+    Counter,
+    Summary,
+}
+
+impl MetricKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            MetricKind::Gauge => "gauge",
+            MetricKind::Counter => "counter",
+            MetricKind::Summary => "summary",
+        }
+    }
+}
+
+/// A named metric and its samples. A counter's `name` omits the `_total` suffix its samples carry,
+/// as OpenMetrics names the family without it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricFamily {
+    pub name: &'static str,
+    pub help: &'static str,
+    pub kind: MetricKind,
+    pub samples: Vec<MetricSample>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricSample {
+    pub name: String,
+    pub labels: Vec<(String, String)>,
+    pub value: f64,
+}
+
+impl MetricFamily {
+    fn new(name: &'static str, kind: MetricKind, help: &'static str) -> Self {
+        Self {
+            name,
+            help,
+            kind,
+            samples: Vec::new(),
+        }
+    }
+
+    /// Adds a sample of a gauge, or of a counter (which gains its `_total` suffix).
+    fn push(&mut self, labels: Vec<(String, String)>, value: f64) {
+        let name = match self.kind {
+            MetricKind::Counter => format!("{}_total", self.name),
+            _ => self.name.to_string(),
+        };
+        self.samples.push(MetricSample { name, labels, value });
+    }
+
+    /// Adds a summary's sample for the given `quantile`, if it is known.
+    fn push_quantile(&mut self, labels: &[(String, String)], quantile: &str, value: Option<std::time::Duration>) {
+        if let Some(value) = value {
+            let mut labels = labels.to_vec();
+            labels.push(("quantile".to_string(), quantile.to_string()));
+            self.samples.push(MetricSample {
+                name: self.name.to_string(),
+                labels,
+                value: value.as_secs_f64(),
+            });
+        }
+    }
+
+    /// Adds a summary's `_sum` and `_count` samples.
+    fn push_summary(&mut self, labels: Vec<(String, String)>, sum: f64, count: u64) {
+        self.samples.push(MetricSample {
+            name: format!("{}_sum", self.name),
+            labels: labels.clone(),
+            value: sum,
+        });
+        self.samples.push(MetricSample {
+            name: format!("{}_count", self.name),
+            labels,
+            value: count as f64,
+        });
+    }
+}
+
+/// The metrics of the given probes, labelled with each probe's `probe` name and its tags.
+pub fn probe_families(probes: &[Probe], now: DateTime<Utc>) -> Vec<MetricFamily> {
+    let mut up = MetricFamily::new(
+        "grey_probe_up",
+        MetricKind::Gauge,
+        "Whether the probe is passing (1) or failing (0).",
+    );
+    let mut samples = MetricFamily::new(
+        "grey_probe_samples",
+        MetricKind::Counter,
+        "The samples taken of the probe, across every observer.",
+    );
+    let mut successes = MetricFamily::new(
+        "grey_probe_successful_samples",
+        MetricKind::Counter,
+        "The samples of the probe which succeeded, across every observer.",
+    );
+    let mut retries = MetricFamily::new(
+        "grey_probe_retries",
+        MetricKind::Counter,
+        "The retries needed to take the probe's samples, across every observer.",
+    );
+    let mut latency = MetricFamily::new(
+        "grey_probe_latency_seconds",
+        MetricKind::Summary,
+        "The time taken to take the probe's samples, including retries.",
+    );
+    let mut failing = MetricFamily::new(
+        "grey_probe_streak_failing_seconds",
+        MetricKind::Gauge,
+        "How long the probe has been failing, or 0 while it passes.",
+    );
+
+    for probe in probes {
+        let labels = entity_labels("probe", &probe.name, &probe.tags);
+        let total = probe.total();
+        let passing = probe.passing();
+
+        up.push(labels.clone(), if passing { 1.0 } else { 0.0 });
+        samples.push(labels.clone(), total.total_samples as f64);
+        successes.push(labels.clone(), total.successful_samples as f64);
+        retries.push(labels.clone(), total.total_retries as f64);
+        let percentiles = total.latency_percentiles();
+        latency.push_quantile(&labels, "0.5", percentiles.p50);
+        latency.push_quantile(&labels, "0.9", percentiles.p90);
+        latency.push_quantile(&labels, "0.99", percentiles.p99);
+        latency.push_summary(labels.clone(), total.total_latency.as_secs_f64(), total.total_samples);
+
+        let failing_for = (!passing)
+            .then(|| probe.streak.since_at(now, probe.window()))
+            .flatten()
+            .map(|since| (now - since).num_milliseconds().max(0) as f64 / 1000.0)
+            .unwrap_or_default();
+        failing.push(labels, failing_for);
+    }
+
+    vec![up, samples, successes, retries, latency, failing]
+}
+
+/// The metrics of the given crons, labelled with each cron's `cron` name and its tags.
+pub fn cron_families(crons: &[Cron], now: DateTime<Utc>) -> Vec<MetricFamily> {
+    let mut up = MetricFamily::new(
+        "grey_cron_up",
+        MetricKind::Gauge,
+        "Whether the cron is healthy (1) or not (0).",
+    );
+    let mut health = MetricFamily::new(
+        "grey_cron_health",
+        MetricKind::Gauge,
+        "The cron's current health: 1 for the state it is in, 0 for the others.",
+    );
+
+    for cron in crons {
+        let labels = entity_labels("cron", &cron.name, &cron.tags);
+        let current = cron.health(now, cron.window());
+
+        up.push(labels.clone(), if cron.passing(now, cron.window()) { 1.0 } else { 0.0 });
+        for state in CRON_HEALTHS {
+            let mut labels = labels.clone();
+            labels.push(("health".to_string(), state.as_str().to_string()));
+            health.push(labels, if state == current { 1.0 } else { 0.0 });
+        }
+    }
+
+    vec![up, health]
+}
+
+/// The cluster metrics of the serving node: how many peers it knows of in each state and the gossip
+/// traffic it has exchanged. `peers` excludes the serving node itself. The health of each individual
+/// peer is only included when `per_peer` is set, since it names the cluster's members.
+pub fn cluster_families(peers: &[Peer], gossip: &GossipStats, per_peer: bool) -> Vec<MetricFamily> {
+    let mut counts = MetricFamily::new(
+        "grey_cluster_peers",
+        MetricKind::Gauge,
+        "The cluster peers known to this node, by health.",
+    );
+    for state in PEER_HEALTHS {
+        let count = peers.iter().filter(|peer| peer.health == state).count();
+        counts.push(vec![("health".to_string(), state.as_str().to_string())], count as f64);
+    }
+
+    let mut families = vec![counts];
+
+    if per_peer {
+        let mut health = MetricFamily::new(
+            "grey_cluster_peer_health",
+            MetricKind::Gauge,
+            "Each cluster peer's health as seen by this node: 1 for the state it is in, 0 for the others.",
+        );
+        for peer in peers {
+            for state in PEER_HEALTHS {
+                health.push(
+                    vec![
+                        ("peer".to_string(), peer.id.clone()),
+                        ("health".to_string(), state.as_str().to_string()),
+                    ],
+                    if peer.health == state { 1.0 } else { 0.0 },
+                );
+            }
+        }
+        families.push(health);
+    }
+
+    let by_kind = |name, help, counts: BTreeMap<&'static str, u64>| {
+        let mut family = MetricFamily::new(name, MetricKind::Counter, help);
+        for (kind, count) in counts {
+            family.push(vec![("kind".to_string(), kind.to_string())], count as f64);
+        }
+        family
+    };
+    families.push(by_kind(
+        "grey_gossip_messages_sent",
+        "The gossip messages this node has sent, by message kind.",
+        gossip.sent(),
+    ));
+    families.push(by_kind(
+        "grey_gossip_send_failures",
+        "The gossip messages this node failed to send, by message kind.",
+        gossip.send_failures(),
+    ));
+    families.push(by_kind(
+        "grey_gossip_messages_received",
+        "The gossip messages this node has received, by message kind.",
+        gossip.received(),
+    ));
+
+    let mut malformed = MetricFamily::new(
+        "grey_gossip_malformed_messages",
+        MetricKind::Counter,
+        "The inbound gossip datagrams this node could not decode.",
+    );
+    malformed.push(vec![], gossip.malformed() as f64);
+    families.push(malformed);
+
+    families
+}
+
+/// Renders the families in the Prometheus text exposition format, or in OpenMetrics when
+/// `open_metrics` is set.
+pub fn render(families: &[MetricFamily], open_metrics: bool) -> String {
+    let mut out = String::new();
+    for family in families {
+        let name = match family.kind {
+            MetricKind::Counter if !open_metrics => format!("{}_total", family.name),
+            _ => family.name.to_string(),
+        };
+
+        let _ = writeln!(out, "# HELP {name} {}", family.help);
+        let _ = writeln!(out, "# TYPE {name} {}", family.kind.as_str());
+        for sample in &family.samples {
+            out.push_str(&sample.name);
+            if !sample.labels.is_empty() {
+                let labels: Vec<String> = sample
+                    .labels
+                    .iter()
+                    .map(|(key, value)| format!("{key}=\"{}\"", escape_label_value(value)))
+                    .collect();
+                let _ = write!(out, "{{{}}}", labels.join(","));
+            }
+            let _ = writeln!(out, " {}", format_value(sample.value));
+        }
+    }
+
+    if open_metrics {
+        out.push_str("# EOF\n");
+    }
+
+    out
+}
+
+/// The label names the exporter adds to samples itself, which a tag may not shadow: a duplicated
+/// label name makes Prometheus reject the whole scrape.
+const RESERVED_LABELS: [&str; 4] = ["health", "quantile", "kind", "peer"];
+
+/// An entity's labels: its name under `kind` (`probe` or `cron`), then its tags sorted by key. A tag
+/// key is made a valid label name by replacing any other character with `_`; tags which would clash
+/// with the entity's name label, one of the [`RESERVED_LABELS`], or Prometheus' reserved `__` prefix
+/// are left out.
+fn entity_labels(kind: &str, name: &str, tags: &HashMap<String, String>) -> Vec<(String, String)> {
+    let mut labels = vec![(kind.to_string(), name.to_string())];
+
+    let mut tags: Vec<(String, &String)> = tags.iter().map(|(key, value)| (label_name(key), value)).collect();
+    tags.sort();
+    for (key, value) in tags {
+        if key != kind
+            && !key.starts_with("__")
+            && !RESERVED_LABELS.contains(&key.as_str())
+            && !labels.iter().any(|(existing, _)| *existing == key)
+        {
+            labels.push((key, value.clone()));
+        }
+    }
+
+    labels
+}
+
+/// Converts a tag key into a valid label name, matching `[a-zA-Z_][a-zA-Z0-9_]*`.
+fn label_name(key: &str) -> String {
+    let mut name: String = key
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    name
+}
+
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "+Inf" } else { "-Inf" }.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_label_name() {
+        assert_eq!(label_name("service"), "service");
+        assert_eq!(label_name("app.kubernetes.io/name"), "app_kubernetes_io_name");
+        assert_eq!(label_name("1st"), "_1st");
+        assert_eq!(label_name(""), "_");
+    }
+
+    #[test]
+    fn test_entity_labels() {
+        let tags: HashMap<String, String> = [
+            ("team", "Platform"),
+            ("service", "Web"),
+            ("probe", "clash"),
+            ("__name__", "reserved"),
+            ("quantile", "0.5"),
+            ("health", "good"),
+            ("kind", "web"),
+            ("peer", "node-a"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+        assert_eq!(
+            entity_labels("probe", "web.home", &tags),
+            vec![
+                ("probe".to_string(), "web.home".to_string()),
+                ("service".to_string(), "Web".to_string()),
+                ("team".to_string(), "Platform".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let mut up = MetricFamily::new("grey_probe_up", MetricKind::Gauge, "Up.");
+        up.push(vec![("probe".into(), "a \"quoted\"\\name".into())], 1.0);
+        let mut sent = MetricFamily::new("grey_gossip_messages_sent", MetricKind::Counter, "Sent.");
+        sent.push(vec![("kind".into(), "syn".into())], 3.0);
+        let mut latency = MetricFamily::new("grey_probe_latency_seconds", MetricKind::Summary, "Latency.");
+        latency.push_summary(vec![], 1.5, 3);
+        let families = vec![up, sent, latency];
+
+        assert_eq!(
+            render(&families, false),
+            "# HELP grey_probe_up Up.\n\
+             # TYPE grey_probe_up gauge\n\
+             grey_probe_up{probe=\"a \\\"quoted\\\"\\\\name\"} 1\n\
+             # HELP grey_gossip_messages_sent_total Sent.\n\
+             # TYPE grey_gossip_messages_sent_total counter\n\
+             grey_gossip_messages_sent_total{kind=\"syn\"} 3\n\
+             # HELP grey_probe_latency_seconds Latency.\n\
+             # TYPE grey_probe_latency_seconds summary\n\
+             grey_probe_latency_seconds_sum 1.5\n\
+             grey_probe_latency_seconds_count 3\n"
+        );
+
+        let open_metrics = render(&families, true);
+        assert!(open_metrics.contains("# TYPE grey_gossip_messages_sent counter\n"), "{open_metrics}");
+        assert!(open_metrics.contains("grey_gossip_messages_sent_total{kind=\"syn\"} 3\n"), "{open_metrics}");
+        assert!(open_metrics.ends_with("# EOF\n"), "{open_metrics}");
+    }
+
+    #[test]
+    fn test_probe_families() {
+        let now = Utc::now();
+        let mut probe = Probe {
+            name: "web".into(),
+            tags: [("service".to_string(), "Web".to_string())].into_iter().collect(),
+            last_updated: now,
+            history: Vec::new(),
+            observations: HashMap::new(),
+            streak: Default::default(),
+            debounce: None,
+            retired: false,
+            interval: None,
+            blocked_by: Vec::new(),
+        };
+        probe.observations.insert(
+            "node-a".into(),
+            grey_api::Observation {
+                total_samples: 10,
+                successful_samples: 8,
+                total_retries: 2,
+                total_latency: std::time::Duration::from_secs(5),
+                latency_sketch: Default::default(),
+                checks: Default::default(),
+            },
+        );
+        // Failing continuously for the last ten minutes.
+        for minutes in [10, 7, 4, 1, 0] {
+            probe.streak.observe(false, now - chrono::Duration::minutes(minutes), probe.window());
+        }
+
+        let families = probe_families(&[probe], now);
+        let value = |name: &str| {
+            families
+                .iter()
+                .flat_map(|family| family.samples.iter())
+                .find(|sample| sample.name == name)
+                .map(|sample| sample.value)
+        };
+
+        assert_eq!(value("grey_probe_up"), Some(0.0));
+        assert_eq!(value("grey_probe_samples_total"), Some(10.0));
+        assert_eq!(value("grey_probe_successful_samples_total"), Some(8.0));
+        assert_eq!(value("grey_probe_retries_total"), Some(2.0));
+        assert_eq!(value("grey_probe_latency_seconds_sum"), Some(5.0));
+        assert_eq!(value("grey_probe_latency_seconds_count"), Some(10.0));
+        assert_eq!(value("grey_probe_latency_seconds"), None, "no quantiles without a latency sketch");
+        assert_eq!(value("grey_probe_streak_failing_seconds"), Some(600.0));
+        assert_eq!(
+            families[0].samples[0].labels,
+            vec![("probe".to_string(), "web".to_string()), ("service".to_string(), "Web".to_string())]
+        );
+    }
+}
@@ -26,6 +26,11 @@
 //! observed thereafter are notified. Because the converged state is identical on every node,
 //! operators typically configure webhooks on a single node; the `Grey-Webhook-Delivery` header still
 //! lets a consumer de-duplicate if the same webhook is configured on several nodes.
+//!
+//! Service level objectives are re-evaluated on the same cadence, and an `slo.burn_rate_exceeded` or
+//! `slo.budget_exhausted` event is delivered when one starts burning through its error budget or
+//! spends it. Only the onset is notified; the SLO re-arms once it stops burning (or its budget is
+//! replenished as failures age out of the window).
 
 use std::borrow::Cow;
 use std::collections::HashMap;
@@ -33,12 +38,13 @@ use std::time::Duration;
 
 use chrono::{DateTime, Utc};
 use filt_rs::{Filter, FilterValue, Filterable};
-use grey_api::{Cron, Probe, WebhookEvent};
+use grey_api::{Cron, Probe, Slo, WebhookEvent, WebhookEventKind};
 use hmac::{Hmac, KeyInit, Mac};
 use sha2::Sha256;
 use tracing_batteries::prelude::*;
 
 use crate::config::WebhookConfig;
+use crate::slo::evaluate_slos;
 use crate::state::{CronStore, ProbeStore, State};
 
 /// How often the notifier re-derives entity state to look for transitions. Kept short enough that a
@@ -50,13 +56,14 @@ const EVALUATION_INTERVAL: Duration = Duration::from_secs(15);
 const SIGNATURE_HEADER: &str = "Grey-Webhook-Signature";
 /// The event's unique id, for downstream de-duplication of fan-out / retried deliveries.
 const DELIVERY_HEADER: &str = "Grey-Webhook-Delivery";
-/// The event kind (`probe.state_changed` / `cron.state_changed`).
+/// The event kind (e.g. `probe.state_changed` or `slo.budget_exhausted`).
 const EVENT_HEADER: &str = "Grey-Webhook-Event";
 
 /// The last-observed status of an entity, tracked to detect transitions between polls.
 #[derive(Clone)]
 struct Status {
-    /// The derived status token (a probe is `passing`/`failing`; a cron is a `CronHealth` token).
+    /// The derived status token (a probe is `passing`/`degraded`/`failing`; a cron is a `CronHealth`
+    /// token).
     token: String,
     /// Whether that token reads as healthy, carried so an emitted event can report `was_healthy`.
     healthy: bool,
@@ -124,7 +131,7 @@ impl Notifier {
             }
         };
 
-        let events = detect_transitions(
+        let mut events = detect_transitions(
             &mut self.last,
             now,
             &probes,
@@ -133,6 +140,11 @@ impl Notifier {
             &alerting_enabled,
         );
 
+        if !config.slos.is_empty() {
+            let slos = evaluate_slos(&self.state, &config, &config.slos, now).await?;
+            events.extend(detect_slo_transitions(&mut self.last, now, &slos, !config.webhooks.is_empty()));
+        }
+
         if !events.is_empty() {
             self.dispatch(&config.webhooks, &events).await;
         }
@@ -163,7 +175,18 @@ impl Notifier {
 
             for webhook in webhooks {
                 match event_matches(&webhook.filter, event) {
-                    Ok(true) => sends.push(deliver(&self.http, webhook, event, body.clone())),
+                    Ok(true) => {
+                        let body = body.clone();
+                        sends.push(async move {
+                            let result = deliver(&self.http, webhook, event, body).await;
+                            crate::telemetry::record_webhook_delivery(
+                                webhook.label(),
+                                event.event.as_str(),
+                                result.is_ok(),
+                            );
+                            result
+                        });
+                    }
                     Ok(false) => {
                         trace!(name: "webhook.filtered", { webhook = webhook.label(), event.id = event.id, entity = event.entity.name }, "A webhook filter excluded this event.");
                     }
@@ -182,14 +205,18 @@ impl Notifier {
 
 /// Records the current status of every probe and cron against `last`, returning a
 /// [`WebhookEvent`] for each entity that crossed between healthy and unhealthy since the previous
-/// pass.
+/// pass, and a `probe.degraded` event for each probe which moved into or out of `degraded` while
+/// remaining healthy.
 ///
 /// The health axis is read from each entity's debounced, streak-derived health (`probe.passing()` /
 /// `cron.health(now, window)`), so a fault must persist for the entity's configured `alerting.debounce`
 /// before it reads unhealthy and a recovery must hold for that long before it reads healthy — the
 /// flap suppression is entirely in the streak, this function only detects the confirmed crossing.
 ///
-/// `last` is always updated to the current status (so it tracks the pooled view continuously,
+/// An entity which is failing behind a failing dependency (see [`Probe::blocked`]) is skipped, holding
+/// its previous status, so a single upstream outage notifies once rather than once per dependent.
+///
+/// `last` is otherwise always updated to the current status (so it tracks the pooled view continuously,
 /// including token changes that stay within one health class); events are only produced when
 /// `notify` is set, the entity's alerting is `enabled`, the entity already had a recorded baseline,
 /// and its *health* flipped — so the first time an entity is seen it is seeded silently, and
@@ -206,6 +233,11 @@ fn detect_transitions(
 
     for (name, probe) in probes {
         let key = format!("probe:{name}");
+        if probe.blocked() {
+            hold_blocked(last, key);
+            continue;
+        }
+
         // The token and its healthy axis both come from the debounced, streak-derived health.
         let token = probe.status_token();
         let healthy = probe.passing();
@@ -213,15 +245,26 @@ fn detect_transitions(
         if notify
             && enabled(&key)
             && let Some(previous) = last.get(&key)
-            && previous.healthy != healthy
         {
-            events.push(WebhookEvent::for_probe(
-                new_id(),
-                now,
-                probe,
-                previous.token.clone(),
-                previous.healthy,
-            ));
+            if previous.healthy != healthy {
+                events.push(WebhookEvent::for_probe(
+                    new_id(),
+                    now,
+                    probe,
+                    previous.token.clone(),
+                    previous.healthy,
+                ));
+            } else if healthy && (previous.token == "degraded") != (token == "degraded") {
+                // Moving between `passing` and `degraded` stays on the healthy side of the axis, so
+                // it is reported as its own, lower-severity event.
+                events.push(WebhookEvent::for_probe_degraded(
+                    new_id(),
+                    now,
+                    probe,
+                    previous.token.clone(),
+                    previous.healthy,
+                ));
+            }
         }
 
         last.insert(key, Status { token: token.to_string(), healthy });
@@ -229,6 +272,11 @@ fn detect_transitions(
 
     for (name, cron) in crons {
         let key = format!("cron:{name}");
+        if cron.blocked(now) {
+            hold_blocked(last, key);
+            continue;
+        }
+
         // The debounced health folds in the schedule grace / `max_duration` settling windows *and*
         // the configured alerting debounce, so a normal run's `succeeded`/`running` churn is silent —
         // only a confirmed crossing into or out of `failed`/`missing`/`stuck` notifies.
@@ -257,6 +305,69 @@ fn detect_transitions(
     events
 }
 
+/// Records the burn rate and budget status of every evaluated SLO against `last`, returning an
+/// `slo.burn_rate_exceeded` event for each SLO whose burn rate alerts started firing and an
+/// `slo.budget_exhausted` event for each which spent its error budget since the previous pass.
+///
+/// As for probes and crons, an SLO seen for the first time is seeded silently and `notify` only gates
+/// delivery. A return to `ok` updates the baseline without an event, so the SLO re-arms.
+fn detect_slo_transitions(
+    last: &mut HashMap<String, Status>,
+    now: DateTime<Utc>,
+    slos: &[Slo],
+    notify: bool,
+) -> Vec<WebhookEvent> {
+    let mut events = Vec::new();
+
+    for slo in slos {
+        for (kind, key, token, healthy) in [
+            (
+                WebhookEventKind::SloBurnRateExceeded,
+                format!("slo:{}:burn_rate", slo.name),
+                slo.burn_rate_status(),
+                !slo.burning(),
+            ),
+            (
+                WebhookEventKind::SloBudgetExhausted,
+                format!("slo:{}:budget", slo.name),
+                slo.budget_status(),
+                !slo.exhausted(),
+            ),
+        ] {
+            if notify
+                && !healthy
+                && let Some(previous) = last.get(&key)
+                && previous.healthy
+            {
+                events.push(WebhookEvent::for_slo(
+                    new_id(),
+                    now,
+                    kind,
+                    slo,
+                    previous.token.clone(),
+                    previous.healthy,
+                ));
+            }
+
+            last.insert(key, Status { token: token.to_string(), healthy });
+        }
+    }
+
+    events
+}
+
+/// Holds the baseline of an entity which is failing behind a failing dependency, so its failure is
+/// never notified: the upstream outage is what gets reported. Should the entity still be failing once
+/// the dependency recovers, the crossing from its held (healthy) baseline is then notified in its own
+/// right. An entity first seen while blocked is seeded as healthy for the same reason — its failure
+/// has not been reported yet.
+fn hold_blocked(last: &mut HashMap<String, Status>, key: String) {
+    last.entry(key).or_insert_with(|| Status {
+        token: "blocked".to_string(),
+        healthy: true,
+    });
+}
+
 /// A fresh, unique event identifier.
 fn new_id() -> String {
     uuid::Uuid::new_v4().to_string()
@@ -274,7 +385,7 @@ fn event_matches(filter: &Filter, event: &WebhookEvent) -> Result<bool, filt_rs:
             format!("Failed to evaluate the webhook filter '{}'.", filter.raw()),
             &[
                 "Check that the filter expression in your webhook configuration is valid filt-rs syntax.",
-                "Only the fields documented in docs/guide/webhooks.md (event, entity.*, state.*) are available to a webhook filter.",
+                "Only the fields documented in docs/guide/webhooks.md (event, entity.*, state.*, slo.*) are available to a webhook filter.",
             ],
         )
     })
@@ -402,8 +513,9 @@ fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
 
 /// Exposes a [`WebhookEvent`]'s fields to the `filt-rs` filter language. The addressable fields are:
 /// `event`, `entity.type` (alias `entity.kind`), `entity.name`, `entity.tags.<key>` (alias
-/// `tags.<key>`), and the `state.*` summary (`current`, `previous`, `healthy`, `was_healthy`,
-/// `availability`). Unknown keys resolve to null, matching `filt-rs`'s own convention.
+/// `tags.<key>`), the `state.*` summary (`current`, `previous`, `healthy`, `was_healthy`,
+/// `availability`), and for an SLO event `slo.objective` and `slo.budget_remaining`. Unknown keys
+/// resolve to null, matching `filt-rs`'s own convention.
 struct WebhookEventFilter<'a>(&'a WebhookEvent);
 
 impl Filterable for WebhookEventFilter<'_> {
@@ -422,6 +534,16 @@ impl Filterable for WebhookEventFilter<'_> {
                 .availability
                 .map(FilterValue::Number)
                 .unwrap_or(FilterValue::Null),
+            "slo.objective" => event
+                .slo
+                .as_ref()
+                .map(|slo| FilterValue::Number(slo.objective))
+                .unwrap_or(FilterValue::Null),
+            "slo.budget_remaining" => event
+                .slo
+                .as_ref()
+                .map(|slo| FilterValue::Number(slo.budget_remaining()))
+                .unwrap_or(FilterValue::Null),
             k if k.starts_with("entity.tags.") => tag(&event.entity.tags, &k["entity.tags.".len()..]),
             k if k.starts_with("tags.") => tag(&event.entity.tags, &k["tags.".len()..]),
             _ => FilterValue::Null,
@@ -463,12 +585,16 @@ mod tests {
                 failing_since: Some(now - window - chrono::Duration::minutes(1)),
                 failing_until: Some(now),
                 covered_since: None,
+                degraded_since: None,
+                degraded_until: None,
             }
         } else {
             Streak {
                 failing_since: None,
                 failing_until: None,
                 covered_since: Some(now - window - chrono::Duration::minutes(1)),
+                degraded_since: None,
+                degraded_until: None,
             }
         };
         Probe {
@@ -479,6 +605,8 @@ mod tests {
             observations: HashMap::new(),
             streak,
             debounce: None,
+            interval: None,
+            blocked_by: Vec::new(),
             retired: false,
         }
     }
@@ -663,6 +791,8 @@ mod tests {
             failing_since: Some(now),
             failing_until: Some(now),
             covered_since: Some(now - chrono::Duration::hours(1)),
+            degraded_since: None,
+            degraded_until: None,
         });
         assert!(
             detect_transitions(&mut last, now, &fresh_fault, &empty_crons, true, &all_enabled).is_empty(),
@@ -675,6 +805,8 @@ mod tests {
             failing_since: Some(now - window - chrono::Duration::minutes(1)),
             failing_until: Some(now),
             covered_since: Some(now - chrono::Duration::hours(1)),
+            degraded_since: None,
+            degraded_until: None,
         });
         let events = detect_transitions(&mut last, now, &confirmed, &empty_crons, true, &all_enabled);
         assert_eq!(events.len(), 1);
@@ -682,6 +814,43 @@ mod tests {
         assert_eq!(events[0].state.current, "failing");
     }
 
+    /// A passing probe becoming degraded (and recovering from it) delivers `probe.degraded` events,
+    /// while crossing from degraded to failing is an ordinary health transition.
+    #[test]
+    fn notifies_degraded_transitions() {
+        let mut last = HashMap::new();
+        let now = Utc::now();
+        let window = Streak::default_recovery_window();
+        let empty_crons = HashMap::new();
+
+        let mut degraded = probe("web", false);
+        degraded.streak.degraded_since = Some(now - window - chrono::Duration::minutes(1));
+        degraded.streak.degraded_until = Some(now);
+        let degraded = HashMap::from([("web".to_string(), degraded)]);
+        let passing = HashMap::from([("web".to_string(), probe("web", false))]);
+        let failing = HashMap::from([("web".to_string(), probe("web", true))]);
+
+        assert!(detect_transitions(&mut last, now, &passing, &empty_crons, true, &all_enabled).is_empty());
+
+        let events = detect_transitions(&mut last, now, &degraded, &empty_crons, true, &all_enabled);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event, WebhookEventKind::ProbeDegraded);
+        assert_eq!(events[0].state.previous, "passing");
+        assert_eq!(events[0].state.current, "degraded");
+        assert!(events[0].state.healthy && events[0].state.was_healthy);
+
+        let events = detect_transitions(&mut last, now, &failing, &empty_crons, true, &all_enabled);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event, WebhookEventKind::ProbeStateChanged);
+        assert_eq!(events[0].state.previous, "degraded");
+
+        assert_eq!(detect_transitions(&mut last, now, &degraded, &empty_crons, true, &all_enabled).len(), 1);
+        let events = detect_transitions(&mut last, now, &passing, &empty_crons, true, &all_enabled);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event, WebhookEventKind::ProbeDegraded);
+        assert_eq!(events[0].state.current, "passing");
+    }
+
     /// A per-entity `enabled: false` suppresses delivery entirely, but the baseline is still tracked
     /// so re-enabling does not replay the state the entity is already in.
     #[test]
@@ -703,6 +872,46 @@ mod tests {
         assert!(detect_transitions(&mut last, now, &failing, &empty_crons, true, &all_enabled).is_empty());
     }
 
+    /// A probe failing behind a failing dependency is suppressed; only the dependency's own crossing
+    /// notifies. Once the dependency recovers, a dependent which is still failing notifies in its own
+    /// right.
+    #[test]
+    fn suppresses_dependents_of_a_failing_dependency() {
+        let mut last = HashMap::new();
+        let now = Utc::now();
+        let empty_crons = HashMap::new();
+
+        let probes = |lb_failing: bool, web_failing: bool| {
+            let mut web = probe("web", web_failing);
+            if lb_failing {
+                web.blocked_by = vec!["lb".into()];
+            }
+            HashMap::from([
+                ("lb".to_string(), probe("lb", lb_failing)),
+                ("web".to_string(), web),
+            ])
+        };
+
+        assert!(detect_transitions(&mut last, now, &probes(false, false), &empty_crons, true, &all_enabled).is_empty());
+
+        // The load balancer goes down and takes the web probe with it: only the load balancer alerts.
+        let events = detect_transitions(&mut last, now, &probes(true, true), &empty_crons, true, &all_enabled);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].entity.name, "lb");
+
+        // Both recover together: again only the load balancer reports its recovery.
+        let events = detect_transitions(&mut last, now, &probes(false, false), &empty_crons, true, &all_enabled);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].entity.name, "lb");
+
+        // Another outage, but the web probe is still failing after the load balancer recovers.
+        detect_transitions(&mut last, now, &probes(true, true), &empty_crons, true, &all_enabled);
+        let events = detect_transitions(&mut last, now, &probes(false, true), &empty_crons, true, &all_enabled);
+        let names: Vec<&str> = events.iter().map(|e| e.entity.name.as_str()).collect();
+        assert_eq!(names.len(), 2, "unexpected events: {names:?}");
+        assert!(names.contains(&"web"));
+    }
+
     #[test]
     fn filters_match_the_exposed_fields() {
         let event = WebhookEvent::for_probe(
@@ -724,6 +933,57 @@ mod tests {
         assert!(!event_matches(&Filter::new("state.was_healthy == false").unwrap(), &event).unwrap());
     }
 
+    fn slo(total: u64, success: u64, burn_rate: f64) -> Slo {
+        Slo {
+            name: "checkout".into(),
+            description: None,
+            tags: HashMap::new(),
+            objective: 99.0,
+            window: Duration::from_secs(30 * 86400),
+            targets: vec!["checkout.api".into()],
+            observation: grey_api::Observation {
+                total_samples: total,
+                successful_samples: success,
+                total_retries: 0,
+                total_latency: Duration::ZERO,
+                latency_sketch: Default::default(),
+                checks: Default::default(),
+            },
+            burn_rates: vec![grey_api::BurnRate {
+                long_window: Duration::from_secs(6 * 3600),
+                short_window: Duration::from_secs(3600),
+                threshold: 6.0,
+                long_rate: burn_rate,
+                short_rate: burn_rate,
+            }],
+        }
+    }
+
+    /// An SLO notifies once when it starts burning and once when its budget is spent; recovering
+    /// re-arms it silently, and the first pass only seeds the baseline.
+    #[test]
+    fn notifies_on_slo_burn_and_exhaustion() {
+        let mut last = HashMap::new();
+        let now = Utc::now();
+
+        assert!(detect_slo_transitions(&mut last, now, &[slo(1000, 1000, 0.0)], true).is_empty());
+
+        let events = detect_slo_transitions(&mut last, now, &[slo(1000, 995, 8.0)], true);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event, WebhookEventKind::SloBurnRateExceeded);
+        assert_eq!(events[0].state.current, "burning");
+        assert_eq!(events[0].state.previous, "ok");
+        assert!(event_matches(&Filter::new("slo.budget_remaining < 100").unwrap(), &events[0]).unwrap());
+
+        let events = detect_slo_transitions(&mut last, now, &[slo(1000, 980, 8.0)], true);
+        assert_eq!(events.len(), 1, "a burn which continues doesn't notify again");
+        assert_eq!(events[0].event, WebhookEventKind::SloBudgetExhausted);
+
+        assert!(detect_slo_transitions(&mut last, now, &[slo(1000, 1000, 0.0)], true).is_empty());
+        let events = detect_slo_transitions(&mut last, now, &[slo(1000, 995, 8.0)], true);
+        assert_eq!(events.len(), 1, "the burn rate alert re-arms once it clears");
+    }
+
     /// The HMAC matches an independent (OpenSSL-computed) reference vector, confirming the exact
     /// `"<timestamp>.<body>"` construction and hex encoding:
     ///
@@ -1,24 +1,558 @@
+use chrono::{DateTime, Datelike, NaiveTime, TimeZone, Utc, Weekday};
 use serde::{Deserialize, Serialize};
-use std::{fmt::Display, time::Duration};
+use std::{fmt::Display, str::FromStr, time::Duration};
+
+/// The most active-window openings [`Policy::next_run_after`] will step through looking for a
+/// scheduled occurrence that falls inside the window, before concluding the two never intersect.
+const MAX_WINDOW_SEARCH_STEPS: usize = 1000;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Policy {
-    #[serde(with = "humantime_serde")]
-    pub interval: Duration,
+    /// How often the probe runs, as a fixed interval. Exactly one of `interval` / `schedule` must be
+    /// set.
+    #[serde(default, with = "humantime_serde::option")]
+    pub interval: Option<Duration>,
+
+    /// When the probe runs, as a standard 5-field crontab expression evaluated in `timezone` (e.g.
+    /// `0 2 * * *` for nightly at 02:00). Exactly one of `interval` / `schedule` must be set.
+    #[serde(default)]
+    pub schedule: Option<String>,
+
     #[serde(with = "humantime_serde")]
     pub timeout: Duration,
     #[serde(default)]
     pub retries: Option<u8>,
+
+    /// How long to wait before retrying a failed attempt, optionally growing exponentially (and with
+    /// jitter) on each successive retry. Without it, retries follow one another immediately.
+    #[serde(default)]
+    pub retry_delay: Option<RetryDelay>,
+
+    /// Bounds each individual attempt, so a hung attempt can be abandoned and retried while the overall
+    /// `timeout` still has time left. Without it, only the overall `timeout` applies.
+    #[serde(default, with = "humantime_serde::option")]
+    pub attempt_timeout: Option<Duration>,
+
+    /// A `filt-rs` expression deciding whether a failed attempt is retried, evaluated against the failed
+    /// attempt's sample (when the target produced one) along with `error.kind` (`target`, `check` or
+    /// `timeout`), `error.message` and `attempt` (the 1-based attempt number). For example
+    /// `error.kind == "target"` retries connection failures but never a response which failed its
+    /// checks. Without it, every failure is retried.
+    #[serde(default)]
+    pub retry_on: Option<filt_rs::Filter>,
+
+    /// The time of day during which the probe may run, as `HH:MM-HH:MM` in `timezone`. A window whose
+    /// end precedes its start wraps past midnight. Runs falling outside it are skipped entirely, so
+    /// the probe records no samples (and loses no availability) while it is inactive.
+    #[serde(default)]
+    pub active_hours: Option<ActiveHours>,
+
+    /// The days of the week on which the probe may run (e.g. `[mon, tue, wed, thu, fri]`), judged by
+    /// the date in `timezone` at the moment of the run. Empty (the default) allows every day.
+    #[serde(default)]
+    pub active_days: Vec<Weekday>,
+
+    /// The IANA timezone (e.g. `Europe/London`) in which `schedule`, `active_hours` and `active_days`
+    /// are evaluated. Defaults to UTC.
+    #[serde(default)]
+    pub timezone: Option<chrono_tz::Tz>,
+
+    /// How often the probe runs while its streak reads as failing, so that a fault is confirmed (and
+    /// its recovery spotted) sooner than the regular schedule would allow. The probe reverts to its
+    /// regular schedule once the failure clears, and never runs less often than that schedule.
+    #[serde(default, with = "humantime_serde::option")]
+    pub failing_interval: Option<Duration>,
+
+    /// Stretches the `failing_interval` the longer a failure persists, up to a maximum, so a lasting
+    /// outage isn't probed at the accelerated rate indefinitely.
+    #[serde(default)]
+    pub failing_backoff: Option<Backoff>,
+}
+
+impl Policy {
+    /// The schedule this probe runs on, preferring an explicit crontab `schedule` over `interval`.
+    /// (Config validation guarantees exactly one is set; the fallback is purely defensive.)
+    pub fn build_schedule(&self) -> grey_api::CronSchedule {
+        match (&self.schedule, self.interval) {
+            (Some(expr), _) => grey_api::CronSchedule::Cron(expr.clone()),
+            (None, Some(interval)) => grey_api::CronSchedule::Every(interval),
+            (None, None) => grey_api::CronSchedule::Every(Duration::from_secs(60)),
+        }
+    }
+
+    fn tz(&self) -> chrono_tz::Tz {
+        self.timezone.unwrap_or(chrono_tz::UTC)
+    }
+
+    /// Whether the probe may run at `time`: on one of its `active_days`, within its `active_hours`.
+    pub fn is_active_at(&self, time: DateTime<Utc>) -> bool {
+        let local = time.with_timezone(&self.tz());
+
+        (self.active_days.is_empty() || self.active_days.contains(&local.weekday()))
+            && self
+                .active_hours
+                .map(|hours| hours.contains(local.time()))
+                .unwrap_or(true)
+    }
+
+    /// The next time the probe should run, strictly after `from`. Occurrences which fall outside the
+    /// active window are skipped: an interval probe resumes as soon as the window next opens, while a
+    /// crontab probe waits for its first occurrence inside the window. `None` if no such time can be
+    /// found (an unparseable crontab, or a schedule which never lands inside the window).
+    pub fn next_run_after(&self, from: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let schedule = self.build_schedule();
+        let mut cursor = from.with_timezone(&self.tz());
+
+        for _ in 0..MAX_WINDOW_SEARCH_STEPS {
+            let next = schedule.next_due_after_in(&cursor)?.to_utc();
+            if self.is_active_at(next) {
+                return Some(next);
+            }
+
+            let opens = self.next_window_start(next)?;
+            match &schedule {
+                grey_api::CronSchedule::Every(_) => return Some(opens),
+                // Resume the crontab search just before the window opens, so an occurrence at the
+                // very moment it opens is still found.
+                grey_api::CronSchedule::Cron(_) => {
+                    cursor = (opens - chrono::Duration::seconds(1)).with_timezone(&self.tz())
+                }
+            }
+        }
+
+        None
+    }
+
+    /// The next moment strictly after `after` at which the active window opens. `None` if no day in
+    /// the coming week opens a window (only possible if the opening time falls in a DST gap).
+    pub fn next_window_start(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let tz = self.tz();
+        let local = after.with_timezone(&tz);
+        let opening = self
+            .active_hours
+            .map(|hours| hours.start)
+            .unwrap_or(NaiveTime::MIN);
+
+        (0..=7)
+            .filter_map(|offset| local.date_naive().checked_add_days(chrono::Days::new(offset)))
+            .filter_map(|date| tz.from_local_datetime(&date.and_time(opening)).earliest())
+            .map(|opens| opens.to_utc())
+            .find(|opens| *opens > after && self.is_active_at(*opens))
+    }
+
+    /// The accelerated interval in effect at `now` if the probe has a `failing_interval` and its
+    /// streak reads as failing, backed off by how long the current failure episode has lasted. Being
+    /// derived from the cluster-converged streak, every node (and the API) agrees on it without
+    /// tracking any runner-local state.
+    pub fn failing_interval_at(
+        &self,
+        streak: &grey_api::Streak,
+        now: DateTime<Utc>,
+        window: chrono::Duration,
+    ) -> Option<Duration> {
+        let base = self.failing_interval?;
+        if !streak.failing_at(now, window) {
+            return None;
+        }
+
+        let failing_for = streak
+            .failing_since
+            .and_then(|since| (now - since).to_std().ok())
+            .unwrap_or_default();
+
+        Some(match &self.failing_backoff {
+            Some(backoff) => backoff.interval_after(base, failing_for),
+            None => base,
+        })
+    }
+
+    /// The interval the probe is running at, at `now`: its `failing_interval` while failing, otherwise
+    /// its regular `interval`. `None` for a crontab-scheduled probe which isn't failing, since it has
+    /// no fixed interval.
+    pub fn effective_interval(
+        &self,
+        streak: &grey_api::Streak,
+        now: DateTime<Utc>,
+        window: chrono::Duration,
+    ) -> Option<Duration> {
+        self.failing_interval_at(streak, now, window)
+            .or_else(|| self.fixed_interval())
+    }
+
+    /// The probe's regular interval, or `None` if it runs on a crontab schedule instead.
+    pub fn fixed_interval(&self) -> Option<Duration> {
+        match self.build_schedule() {
+            grey_api::CronSchedule::Every(interval) => Some(interval),
+            grey_api::CronSchedule::Cron(_) => None,
+        }
+    }
+}
+
+/// Exponential backoff applied to a repeating interval: each run multiplies the interval by
+/// `multiplier`, until it reaches `max`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Backoff {
+    #[serde(default = "default_backoff_multiplier")]
+    pub multiplier: f64,
+
+    #[serde(with = "humantime_serde")]
+    pub max: Duration,
+}
+
+impl Backoff {
+    /// The interval in effect once `elapsed` has passed since a series of runs starting at `base`
+    /// began, i.e. the interval following the last run to have started by then.
+    pub fn interval_after(&self, base: Duration, elapsed: Duration) -> Duration {
+        let mut interval = base.min(self.max);
+        if self.multiplier <= 1.0 || interval.is_zero() {
+            return interval;
+        }
+
+        let mut waited = Duration::ZERO;
+        while interval < self.max && waited + interval <= elapsed {
+            waited += interval;
+            interval = scale(interval, self.multiplier).min(self.max);
+        }
+
+        interval
+    }
+}
+
+/// The delay between a failed attempt and its retry: `initial` before the first retry, multiplied by
+/// `multiplier` for each retry after that (capped at `max`). With `jitter`, each delay is drawn
+/// uniformly between zero and that value instead, so probes which failed together don't retry in
+/// lockstep.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RetryDelay {
+    #[serde(with = "humantime_serde")]
+    pub initial: Duration,
+
+    #[serde(default = "default_backoff_multiplier")]
+    pub multiplier: f64,
+
+    #[serde(default, with = "humantime_serde::option")]
+    pub max: Option<Duration>,
+
+    #[serde(default)]
+    pub jitter: bool,
+}
+
+impl RetryDelay {
+    /// The delay before the given (1-based) retry.
+    pub fn before_retry(&self, retry: u32) -> Duration {
+        let exponent = retry.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let delay = scale(self.initial, self.multiplier.powi(exponent));
+        let delay = self.max.map_or(delay, |max| delay.min(max));
+
+        if self.jitter {
+            scale(delay, rand::random::<f64>())
+        } else {
+            delay
+        }
+    }
+}
+
+fn default_backoff_multiplier() -> f64 {
+    2.0
+}
+
+/// Scales a duration by `factor`, saturating rather than panicking when the result overflows.
+fn scale(duration: Duration, factor: f64) -> Duration {
+    Duration::try_from_secs_f64(duration.as_secs_f64() * factor).unwrap_or(Duration::MAX)
 }
 
 impl Display for Policy {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.schedule, self.interval) {
+            (Some(expr), _) => write!(f, "schedule: {expr}, ")?,
+            (None, Some(interval)) => write!(f, "interval: {}, ", humantime::format_duration(interval))?,
+            (None, None) => {}
+        }
+
         write!(
             f,
-            "interval: {}, timeout: {}, retries: {}",
-            humantime::format_duration(self.interval),
+            "timeout: {}, retries: {}",
             humantime::format_duration(self.timeout),
             self.retries.unwrap_or(0)
-        )
+        )?;
+
+        if let Some(hours) = &self.active_hours {
+            write!(f, ", active hours: {hours}")?;
+        }
+
+        if !self.active_days.is_empty() {
+            let days = self.active_days.iter().map(Weekday::to_string).collect::<Vec<_>>();
+            write!(f, ", active days: {}", days.join(","))?;
+        }
+
+        if let Some(tz) = &self.timezone {
+            write!(f, ", timezone: {tz}")?;
+        }
+
+        if let Some(delay) = &self.retry_delay {
+            write!(f, ", retry delay: {}", humantime::format_duration(delay.initial))?;
+        }
+
+        if let Some(timeout) = self.attempt_timeout {
+            write!(f, ", attempt timeout: {}", humantime::format_duration(timeout))?;
+        }
+
+        if let Some(retry_on) = &self.retry_on {
+            write!(f, ", retry on: {retry_on}")?;
+        }
+
+        if let Some(interval) = self.failing_interval {
+            write!(f, ", failing interval: {}", humantime::format_duration(interval))?;
+            if let Some(backoff) = &self.failing_backoff {
+                write!(f, " (x{} up to {})", backoff.multiplier, humantime::format_duration(backoff.max))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A daily window of wall-clock time, written as `HH:MM-HH:MM`. The start is inclusive and the end
+/// exclusive; a window whose end precedes its start (e.g. `22:00-06:00`) spans midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ActiveHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl ActiveHours {
+    /// Whether `time` falls within this window.
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            time >= self.start && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+impl FromStr for ActiveHours {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |value: &str| {
+            NaiveTime::parse_from_str(value.trim(), "%H:%M").map_err(|e| {
+                format!("'{}' is not a valid HH:MM time of day ({e}).", value.trim())
+            })
+        };
+
+        let (start, end) = s
+            .split_once('-')
+            .ok_or_else(|| format!("Active hours '{s}' must be written as HH:MM-HH:MM."))?;
+
+        let hours = Self {
+            start: parse(start)?,
+            end: parse(end)?,
+        };
+
+        if hours.start == hours.end {
+            return Err(format!("Active hours '{s}' must not start and end at the same time."));
+        }
+
+        Ok(hours)
+    }
+}
+
+impl TryFrom<String> for ActiveHours {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<ActiveHours> for String {
+    fn from(value: ActiveHours) -> Self {
+        value.to_string()
+    }
+}
+
+impl Display for ActiveHours {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.start.format("%H:%M"), self.end.format("%H:%M"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ts(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value).unwrap().to_utc()
+    }
+
+    fn policy(yaml: &str) -> Policy {
+        serde_yaml::from_str(&format!("timeout: 5s\n{yaml}")).expect("deserialize policy")
+    }
+
+    #[test]
+    fn active_hours_parse_and_wrap_midnight() {
+        let day: ActiveHours = "09:00-17:30".parse().unwrap();
+        assert!(day.contains(NaiveTime::from_hms_opt(9, 0, 0).unwrap()));
+        assert!(day.contains(NaiveTime::from_hms_opt(17, 29, 59).unwrap()));
+        assert!(!day.contains(NaiveTime::from_hms_opt(17, 30, 0).unwrap()));
+        assert_eq!(day.to_string(), "09:00-17:30");
+
+        let night: ActiveHours = "22:00-06:00".parse().unwrap();
+        assert!(night.contains(NaiveTime::from_hms_opt(23, 0, 0).unwrap()));
+        assert!(night.contains(NaiveTime::from_hms_opt(5, 59, 0).unwrap()));
+        assert!(!night.contains(NaiveTime::from_hms_opt(12, 0, 0).unwrap()));
+
+        assert!("9am-5pm".parse::<ActiveHours>().is_err());
+        assert!("09:00".parse::<ActiveHours>().is_err());
+        assert!("09:00-09:00".parse::<ActiveHours>().is_err());
+    }
+
+    #[test]
+    fn window_is_evaluated_in_the_configured_timezone() {
+        let policy = policy(
+            "interval: 1m\nactive_hours: 09:00-17:00\nactive_days: [mon, tue, wed, thu, fri]\ntimezone: America/New_York\n",
+        );
+
+        // Thursday 2026-01-15: 13:00Z is 08:00 in New York (before hours), 14:00Z is 09:00.
+        assert!(!policy.is_active_at(ts("2026-01-15T13:00:00Z")));
+        assert!(policy.is_active_at(ts("2026-01-15T14:00:00Z")));
+        // Saturday is not an active day, even during business hours.
+        assert!(!policy.is_active_at(ts("2026-01-17T15:00:00Z")));
+    }
+
+    #[test]
+    fn interval_probes_resume_when_the_window_opens() {
+        let policy = policy("interval: 5m\nactive_hours: 09:00-17:00\nactive_days: [mon, tue, wed, thu, fri]\n");
+
+        // Inside the window the interval applies as usual.
+        assert_eq!(
+            policy.next_run_after(ts("2026-01-15T10:00:00Z")),
+            Some(ts("2026-01-15T10:05:00Z"))
+        );
+
+        // A run which would land after the window closes on a Friday waits for Monday morning.
+        assert_eq!(
+            policy.next_run_after(ts("2026-01-16T16:58:00Z")),
+            Some(ts("2026-01-19T09:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn crontab_probes_skip_occurrences_outside_the_window() {
+        let policy = policy("schedule: \"0 * * * *\"\nactive_hours: 22:00-02:00\n");
+
+        assert_eq!(
+            policy.next_run_after(ts("2026-01-15T12:30:00Z")),
+            Some(ts("2026-01-15T22:00:00Z"))
+        );
+        assert_eq!(
+            policy.next_run_after(ts("2026-01-15T23:00:00Z")),
+            Some(ts("2026-01-16T00:00:00Z"))
+        );
+        assert_eq!(
+            policy.next_run_after(ts("2026-01-16T01:00:00Z")),
+            Some(ts("2026-01-16T22:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn crontab_schedule_uses_the_policy_timezone() {
+        let policy = policy("schedule: \"0 2 * * *\"\ntimezone: Europe/Berlin\n");
+
+        // 02:00 in Berlin is 01:00Z in winter.
+        assert_eq!(
+            policy.next_run_after(ts("2026-01-15T12:00:00Z")),
+            Some(ts("2026-01-16T01:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn failing_interval_applies_while_the_streak_reads_failing() {
+        let policy = policy("interval: 5m\nfailing_interval: 15s\n");
+        let window = chrono::Duration::minutes(5);
+        let now = ts("2026-01-15T12:00:00Z");
+
+        let mut streak = grey_api::Streak::default();
+        streak.observe(true, now - chrono::Duration::hours(1), window);
+        assert_eq!(policy.failing_interval_at(&streak, now, window), None);
+        assert_eq!(policy.effective_interval(&streak, now, window), Some(Duration::from_secs(300)));
+
+        streak.observe(false, now - chrono::Duration::seconds(30), window);
+        assert_eq!(policy.failing_interval_at(&streak, now, window), Some(Duration::from_secs(15)));
+        assert_eq!(policy.effective_interval(&streak, now, window), Some(Duration::from_secs(15)));
+
+        // Once no failure has been seen for the recovery window, the regular interval resumes.
+        let later = now + chrono::Duration::minutes(10);
+        assert_eq!(policy.effective_interval(&streak, later, window), Some(Duration::from_secs(300)));
+    }
+
+    #[test]
+    fn failing_interval_backs_off_to_its_maximum() {
+        let backoff = Backoff { multiplier: 2.0, max: Duration::from_secs(60) };
+        let base = Duration::from_secs(10);
+
+        // Runs start at 0s, 10s, 30s and 70s, with intervals of 10s, 20s, 40s and then the 60s cap.
+        assert_eq!(backoff.interval_after(base, Duration::from_secs(5)), Duration::from_secs(10));
+        assert_eq!(backoff.interval_after(base, Duration::from_secs(10)), Duration::from_secs(20));
+        assert_eq!(backoff.interval_after(base, Duration::from_secs(29)), Duration::from_secs(20));
+        assert_eq!(backoff.interval_after(base, Duration::from_secs(30)), Duration::from_secs(40));
+        assert_eq!(backoff.interval_after(base, Duration::from_secs(70)), Duration::from_secs(60));
+        assert_eq!(backoff.interval_after(base, Duration::from_secs(86_400)), Duration::from_secs(60));
+
+        let policy = policy("interval: 5m\nfailing_interval: 10s\nfailing_backoff: { max: 1m }\n");
+        let window = chrono::Duration::minutes(5);
+        let now = ts("2026-01-15T12:00:00Z");
+        let mut streak = grey_api::Streak::default();
+        streak.observe(false, now - chrono::Duration::seconds(45), window);
+        streak.observe(false, now - chrono::Duration::seconds(1), window);
+        assert_eq!(policy.failing_interval_at(&streak, now, window), Some(Duration::from_secs(40)));
+    }
+
+    #[test]
+    fn retry_delay_backs_off_exponentially() {
+        let delay = RetryDelay {
+            initial: Duration::from_millis(100),
+            multiplier: 2.0,
+            max: Some(Duration::from_millis(500)),
+            jitter: false,
+        };
+
+        assert_eq!(delay.before_retry(1), Duration::from_millis(100));
+        assert_eq!(delay.before_retry(2), Duration::from_millis(200));
+        assert_eq!(delay.before_retry(3), Duration::from_millis(400));
+        assert_eq!(delay.before_retry(4), Duration::from_millis(500));
+        assert_eq!(delay.before_retry(u32::MAX), Duration::from_millis(500));
+
+        let jittered = RetryDelay { jitter: true, ..delay };
+        for retry in 1..10 {
+            assert!(jittered.before_retry(retry) <= Duration::from_millis(500));
+        }
+    }
+
+    #[test]
+    fn retry_options_deserialize() {
+        let policy = policy(
+            "interval: 1m\nretries: 3\nretry_delay: { initial: 1s, jitter: true }\nattempt_timeout: 2s\nretry_on: error.kind == \"target\"\n",
+        );
+        let delay = policy.retry_delay.expect("a retry delay");
+        assert_eq!(delay.initial, Duration::from_secs(1));
+        assert_eq!(delay.multiplier, 2.0);
+        assert!(delay.jitter);
+        assert_eq!(policy.attempt_timeout, Some(Duration::from_secs(2)));
+        assert_eq!(policy.retry_on.expect("a retry_on filter").raw(), "error.kind == \"target\"");
+    }
+
+    #[test]
+    fn display_describes_the_schedule_and_window() {
+        assert_eq!(
+            policy("interval: 30s\nretries: 2\n").to_string(),
+            "interval: 30s, timeout: 5s, retries: 2"
+        );
+        assert_eq!(
+            policy("schedule: \"0 2 * * *\"\nactive_days: [sat, sun]\nactive_hours: 01:00-03:00\ntimezone: Europe/London\n").to_string(),
+            "schedule: 0 2 * * *, timeout: 5s, retries: 0, active hours: 01:00-03:00, active days: Sat,Sun, timezone: Europe/London"
+        );
     }
 }
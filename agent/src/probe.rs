@@ -1,5 +1,6 @@
-use std::{collections::HashMap, time::Instant};
+use std::collections::HashMap;
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 use crate::{Policy, targets::TargetType, utils::random_start_offset};
@@ -11,12 +12,13 @@ pub struct Probe {
     pub target: TargetType,
     #[serde(default)]
     pub tags: HashMap<String, String>,
-    /// `filt-rs` expression checks evaluated against the whole sample as the way
-    /// to assert on a probe's result. Each expression is parsed at config-load
-    /// time and reported as its own validation. A probe fails as soon as any one
-    /// of its checks does not match.
+    /// Checks evaluated against the whole sample as the way to assert on a
+    /// probe's result: `filt-rs` expressions, or `!Script` checks written in
+    /// JavaScript. Each check is parsed at config-load time and reported as its
+    /// own validation. A probe fails as soon as any one of its checks does not
+    /// match.
     #[serde(default)]
-    pub checks: Vec<filt_rs::Filter>,
+    pub checks: Vec<crate::checks::Check>,
 
     /// A `filt-rs` expression deciding which viewers may see this probe in the API and UI, evaluated
     /// against the requesting viewer's auth context: `auth` (a valid token was presented),
@@ -31,6 +33,27 @@ pub struct Probe {
     /// health hysteresis (see [`crate::config::AlertingConfig`]).
     #[serde(default)]
     pub alerting: crate::config::AlertingConfig,
+
+    /// The probes or crons this probe depends on, such as the load balancer in front of it. While any
+    /// of them is unhealthy, a failure of this probe reads as `blocked` (attributed to the upstream
+    /// outage) rather than `failing`, and no webhook is sent for it.
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+
+    /// Watches one of the probe's sample fields for changes between runs, exposing the
+    /// `change.*` fields to its checks.
+    #[serde(default)]
+    pub change: Option<crate::change::ChangeDetection>,
+
+    /// Keeps this probe's individual results, with their exact timing and check messages, in a
+    /// bounded log served by `/api/v1/probes/{name}/samples`. Disabled by default.
+    #[serde(default)]
+    pub samples: Option<crate::config::SampleLogConfig>,
+
+    /// Learns the probe's usual latency from its history, exposing the `baseline.*` fields to its
+    /// checks so that they can flag an unusually slow run without a fixed threshold.
+    #[serde(default)]
+    pub baseline: Option<crate::baseline::BaselineConfig>,
 }
 
 impl Probe {
@@ -38,17 +61,60 @@ impl Probe {
     pub fn test() -> Self {
         Self {
             name: "test".into(),
-            policy: crate::Policy { interval: std::time::Duration::from_secs(60), timeout: std::time::Duration::from_secs(5), retries: Some(3) },
+            policy: crate::Policy {
+                interval: Some(std::time::Duration::from_secs(60)),
+                schedule: None,
+                timeout: std::time::Duration::from_secs(5),
+                retries: Some(3),
+                retry_delay: None,
+                attempt_timeout: None,
+                retry_on: None,
+                active_hours: None,
+                active_days: Vec::new(),
+                timezone: None,
+                failing_interval: None,
+                failing_backoff: None,
+            },
             target: crate::targets::TargetType::test(),
             tags: HashMap::new(),
-            checks: vec![filt_rs::Filter::new("output.test == true").unwrap()],
+            checks: vec![filt_rs::Filter::new("output.test == true").unwrap().into()],
             visible: crate::config::default_visible_filter(),
             alerting: crate::config::AlertingConfig::default(),
+            depends_on: Vec::new(),
+            change: None,
+            samples: None,
+            baseline: None,
+        }
+    }
+
+    /// When the probe should first run once it is scheduled. An interval probe starts at a random
+    /// offset into its first interval, spreading load across probes which start together, while a
+    /// crontab probe waits for its next occurrence. Either way, a start outside the policy's active
+    /// window is deferred until the window opens.
+    pub fn next_start_time(&self) -> DateTime<Utc> {
+        let now = Utc::now();
+        match self.policy.build_schedule() {
+            grey_api::CronSchedule::Every(interval) => {
+                let start = now
+                    + chrono::Duration::from_std(random_start_offset(interval)).unwrap_or_default();
+                if self.policy.is_active_at(start) {
+                    start
+                } else {
+                    self.policy.next_window_start(start).unwrap_or(start)
+                }
+            }
+            grey_api::CronSchedule::Cron(_) => self.next_run_time(now),
         }
     }
 
-    pub fn next_start_time(&self) -> Instant {
-        Instant::now() + random_start_offset(self.policy.interval)
+    /// When the probe should next run after the run scheduled for `previous`. Interval probes keep a
+    /// fixed cadence from their previous scheduled time (so a slow run doesn't push every later run
+    /// back), skipping ahead to the window's opening when the next run would fall outside it. Should
+    /// the schedule never land inside the window, the runner retries hourly rather than spinning.
+    pub fn next_run_time(&self, previous: DateTime<Utc>) -> DateTime<Utc> {
+        self.policy
+            .next_run_after(previous)
+            .unwrap_or_else(|| previous + chrono::Duration::hours(1))
     }
 }
 
@@ -85,6 +151,65 @@ target: !Http
         );
     }
 
+    #[test]
+    fn start_time_respects_the_schedule_and_window() {
+        let mut probe = Probe::test();
+        let now = Utc::now();
+
+        // Interval probes start within their first interval.
+        let start = probe.next_start_time();
+        assert!(start >= now && start <= now + chrono::Duration::seconds(60));
+
+        // Crontab probes wait for the next occurrence.
+        probe.policy.interval = None;
+        probe.policy.schedule = Some("0 * * * *".into());
+        let start = probe.next_start_time();
+        assert!(start > now && start <= now + chrono::Duration::hours(1));
+        assert_eq!(start.timestamp() % 3600, 0);
+
+        // Whatever the schedule, the first run lands inside the active window.
+        probe.policy.active_hours = Some("03:00-04:00".parse().unwrap());
+        let start = probe.next_start_time();
+        assert!(probe.policy.is_active_at(start), "{start} is outside the window");
+        assert_eq!(probe.next_run_time(start) - start, chrono::Duration::days(1));
+    }
+
+    #[cfg(feature = "scripts")]
+    #[test]
+    fn deserializes_script_checks() {
+        let yaml = format!(
+            "{BASE}checks:\n  - http.status == 200\n  - !Script\n    name: etags match\n    code: return sample[\"http.header.etag\"] === sample[\"http.header.x-etag\"]\n"
+        );
+        let probe: Probe = serde_yaml::from_str(&yaml).expect("deserialize probe");
+        assert_eq!(probe.checks.len(), 2);
+        assert!(matches!(probe.checks[0], crate::checks::Check::Filter(_)));
+        assert!(matches!(probe.checks[1], crate::checks::Check::Script(_)));
+        assert_eq!(probe.checks[1].to_string(), "etags match");
+
+        let yaml = format!("{BASE}checks:\n  - !Script\n    code: return (\n");
+        assert!(serde_yaml::from_str::<Probe>(&yaml).is_err(), "invalid scripts are rejected");
+    }
+
+    #[test]
+    fn deserializes_check_severities() {
+        use crate::checks::CheckSeverity;
+
+        let yaml = format!(
+            "{BASE}checks:\n  - http.status == 200\n  - !Filter\n    filter: http.status < 300\n  - !Filter\n    filter: tls.expires_in > 14d\n    severity: warning\n"
+        );
+        let probe: Probe = serde_yaml::from_str(&yaml).expect("deserialize probe");
+        assert_eq!(
+            probe.checks.iter().map(|check| check.severity()).collect::<Vec<_>>(),
+            vec![CheckSeverity::Critical, CheckSeverity::Critical, CheckSeverity::Warning]
+        );
+        assert_eq!(probe.checks[2].raw(), "tls.expires_in > 14d");
+
+        // Critical checks keep serializing as plain expressions, so they round-trip unchanged.
+        let serialized = serde_yaml::to_string(&probe.checks).unwrap();
+        assert_eq!(serde_yaml::from_str::<Vec<crate::checks::Check>>(&serialized).unwrap(), probe.checks);
+        assert!(serialized.starts_with("- http.status == 200\n"), "{serialized}");
+    }
+
     #[test]
     fn invalid_check_expression_fails_to_deserialize() {
         let yaml = format!("{BASE}checks:\n  - \"http.status >\"\n");
@@ -102,6 +227,8 @@ impl Into<grey_api::Probe> for &Probe {
             observations: HashMap::new(),
             streak: grey_api::Streak::default(),
             debounce: Some(self.alerting.debounce_std()),
+            interval: self.policy.fixed_interval(),
+            blocked_by: Vec::new(),
             retired: false,
         }
     }
@@ -1,40 +1,55 @@
-use grey_api::ValidationResult;
-use std::{
-    sync::{Arc, RwLock, atomic::AtomicBool},
-    time::Instant,
-};
+use grey_api::{LatencyPercentiles, ValidationResult};
+use std::sync::{Arc, RwLock, atomic::AtomicBool};
 use tracing_batteries::prelude::{opentelemetry::trace::Status as OpenTelemetryStatus, *};
 
 use crate::{
-    Probe, checks,
-    result::ProbeResult,
-    state::{ProbeStore, State},
+    Probe, Sample,
+    baseline::{BaselineConfig, LatencyBaseline},
+    checks::{self, CheckSeverity},
+    limiter::ConcurrencyLimiter,
+    result::{ProbeAttempt, ProbeResult},
+    state::{HistoryStore, ProbeStore, SampleStore, State},
 };
 
 const NO_PARENT: Option<tracing::Id> = None;
 
+/// How many hours of the probe's pooled history its `latency.*` check fields are drawn from: the
+/// hourly buckets which started within this window.
+const RECENT_LATENCY_HOURS: usize = 2;
+
 pub struct ProbeRunner {
     probe_name: Arc<String>,
     config: Arc<RwLock<Probe>>,
     state: State,
     cancel: Arc<AtomicBool>,
+    limiter: ConcurrencyLimiter,
 }
 
 impl ProbeRunner {
-    pub fn new(config: Probe, state: State) -> Self {
+    pub fn new(mut config: Probe, state: State) -> Self {
+        config.target.bind_state(&state, &config.name);
         Self {
             probe_name: Arc::new(config.name.clone()),
             config: Arc::new(RwLock::new(config)),
             state,
             cancel: Arc::new(AtomicBool::new(false)),
+            limiter: ConcurrencyLimiter::default(),
         }
     }
 
+    /// Shares `limiter` with the other runners, so this probe's runs count towards (and wait on) the
+    /// configured concurrency limits.
+    pub fn with_limiter(mut self, limiter: ConcurrencyLimiter) -> Self {
+        self.limiter = limiter;
+        self
+    }
+
     pub fn name(&self) -> Arc<String> {
         self.probe_name.clone()
     }
 
-    pub fn update(&self, probe: Probe) {
+    pub fn update(&self, mut probe: Probe) {
+        probe.target.bind_state(&self.state, &probe.name);
         *self.config.write().unwrap() = probe;
     }
 
@@ -62,8 +77,11 @@ impl ProbeRunner {
         let parent_span = Span::current();
 
         while !self.cancel.load(std::sync::atomic::Ordering::Relaxed) {
-            let now = Instant::now();
-            let sleep_time = next_run_time - now;
+            // Schedules are wall-clock based (crontab occurrences, active windows in a timezone), so
+            // the wait is measured against the wall clock too.
+            let sleep_time = (next_run_time - chrono::Utc::now())
+                .to_std()
+                .unwrap_or_default();
             if sleep_time > tokio::time::Duration::from_secs(1) {
                 tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
                 continue;
@@ -77,7 +95,15 @@ impl ProbeRunner {
                 .map_err(|e| format!("Failed to read probe config: {}", e))?
                 .clone();
 
-            next_run_time += probe.policy.interval;
+            let scheduled_at = next_run_time;
+            next_run_time = probe.next_run_time(next_run_time);
+
+            // A run planned before a config reload narrowed the window may now fall outside it. Skip
+            // it rather than record a sample the window says must not count against availability.
+            if !probe.policy.is_active_at(chrono::Utc::now()) {
+                debug!("Skipping probe run outside of its active window.");
+                continue;
+            }
 
             let probe_span = span!(parent: NO_PARENT, tracing::Level::INFO, "probe.schedule.run",
                 %probe.name,
@@ -90,7 +116,7 @@ impl ProbeRunner {
 
             debug!("Starting next probing session...");
             let run_result = self
-                .run_scheduled_execution()
+                .run_scheduled_execution(scheduled_at)
                 .instrument(probe_span.clone())
                 .await;
             match run_result {
@@ -103,25 +129,109 @@ impl ProbeRunner {
                         .record("error", debug(&err));
                 }
             }
+
+            // While the probe reads as failing, run at its (backed-off) failing interval to confirm
+            // the fault and spot its recovery sooner — but never less often than its regular schedule.
+            if let Some(interval) = self.failing_interval(&probe).await {
+                next_run_time = next_run_time.min(scheduled_at + interval);
+            }
         }
 
         Ok(())
     }
 
+    /// The accelerated interval to use for the next run if the probe has a `failing_interval` and its
+    /// cluster-pooled streak currently reads as failing.
+    async fn failing_interval(&self, probe: &Probe) -> Option<chrono::Duration> {
+        probe.policy.failing_interval?;
+
+        let pooled = match self.state.get_probe_state(&probe.name).await {
+            Ok(pooled) => pooled?,
+            Err(err) => {
+                warn!("Failed to read the probe's state to choose its next interval: {}", err);
+                return None;
+            }
+        };
+
+        probe
+            .policy
+            .failing_interval_at(&pooled.streak, chrono::Utc::now(), pooled.window())
+            .and_then(|interval| chrono::Duration::from_std(interval).ok())
+    }
+
+    /// What the probe's earlier runs across every observer say about its latency, which its checks
+    /// can assert on through the `latency.*` and `baseline.*` fields.
+    async fn prior_runs(&self, probe: &Probe) -> PriorRuns {
+        let latency = match self.state.get_probe_state(&probe.name).await {
+            Ok(Some(pooled)) => pooled.recent(RECENT_LATENCY_HOURS).latency_percentiles(),
+            Ok(None) => LatencyPercentiles::default(),
+            Err(err) => {
+                warn!("Failed to read the probe's state to determine its recent latency: {}", err);
+                LatencyPercentiles::default()
+            }
+        };
+
+        let baseline = match &probe.baseline {
+            Some(baseline) => self.fit_baseline(probe, baseline).await,
+            None => None,
+        };
+
+        PriorRuns { latency, baseline }
+    }
+
+    /// Fits the probe's latency baseline to its hourly history tier, which (unlike the pooled state)
+    /// reaches back far enough to hold the same hour of last week.
+    async fn fit_baseline(&self, probe: &Probe, baseline: &BaselineConfig) -> Option<LatencyBaseline> {
+        let now = chrono::Utc::now();
+        let since = BaselineConfig::history_since(now);
+        match self
+            .state
+            .get_probe_history(&probe.name, grey_api::HistoryResolution::Hour, since, now)
+            .await
+        {
+            Ok(history) => baseline.fit(&history, now),
+            Err(err) => {
+                warn!("Failed to read the probe's history to fit its latency baseline: {}", err);
+                None
+            }
+        }
+    }
+
     #[tracing::instrument(name = "probe.run", skip(self), err(Display), fields(
         otel.name=self.probe_name.as_str(),
         probe.name=self.probe_name.as_str(),
         probe.attempts=0,
+        probe.scheduling_delay=EmptyField,
     ))]
-    async fn run_scheduled_execution(&self) -> Result<(), Box<dyn std::error::Error>> {
+    async fn run_scheduled_execution(
+        &self,
+        scheduled_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
         let probe = self
             .config
             .read()
             .map_err(|e| format!("Failed to read probe config: {}", e))?
             .clone();
 
+        // Held until the run's result is stored, so the probe counts towards its concurrency limits
+        // for the whole run.
+        let _permit = self
+            .limiter
+            .acquire(self.state.get_config().concurrency.limits_for(&probe))
+            .await;
+
         let mut sample = ProbeResult::new();
+        // How late the run started, whether from waiting on a concurrency limit or from the runtime
+        // being too busy to wake the runner on time.
+        sample.scheduling_delay = (chrono::Utc::now() - scheduled_at).max(chrono::Duration::zero());
+        Span::current().record(
+            "probe.scheduling_delay",
+            display(humantime::format_duration(
+                sample.scheduling_delay.to_std().unwrap_or_default(),
+            )),
+        );
         let total_attempts = probe.policy.retries.unwrap_or(2);
+        let prior_runs = self.prior_runs(&probe).await;
 
         // Update span with probe details
         Span::current()
@@ -138,19 +248,36 @@ impl ProbeRunner {
                 while !self.cancel.load(std::sync::atomic::Ordering::Relaxed)
                 {
                     sample.start_time = chrono::Utc::now();
+                    let attempt = sample.retries + 1;
                     debug!(
                         "Running probe attempt {}/{}...",
-                        sample.retries + 1, total_attempts,
+                        attempt, total_attempts,
                     );
-                    match self.run_attempt(&probe, &mut sample).await
+                    match self.run_bounded_attempt(&probe, &mut sample, prior_runs).await
                     {
-                        Ok(res) => return Ok(res),
+                        Ok(res) => {
+                            sample.attempts.push(ProbeAttempt::finished(
+                                sample.start_time,
+                                true,
+                                "Attempt completed successfully.",
+                            ));
+                            return Ok(res);
+                        }
                         Err(err) => {
                             debug!("Probe failed: {}", err);
+                            sample.attempts.push(ProbeAttempt::finished(
+                                sample.start_time,
+                                false,
+                                err.to_string(),
+                            ));
                             sample.retries += 1;
                             sample.message = err.to_string();
-                            if sample.retries >= total_attempts {
-                                return Err(err);
+                            if sample.retries >= total_attempts || !should_retry(&probe, &err, attempt) {
+                                return Err(err.into());
+                            }
+
+                            if let Some(delay) = &probe.policy.retry_delay {
+                                tokio::time::sleep(delay.before_retry(sample.retries as u32)).await;
                             }
                         }
                     }
@@ -178,6 +305,12 @@ impl ProbeRunner {
                     total_attempts
                 );
                 warn!("{message}");
+                // Every finished attempt is recorded as it completes, so an attempt whose start isn't
+                // the last one recorded was still in flight (rather than waiting out a retry delay)
+                // when the deadline dropped it.
+                if sample.attempts.last().map(|a| a.start_time) != Some(sample.start_time) {
+                    sample.attempts.push(ProbeAttempt::finished(sample.start_time, false, &message));
+                }
                 sample.message = message.clone();
                 Err(message.into())
             }
@@ -189,7 +322,10 @@ impl ProbeRunner {
         let result = match result {
             Ok(_) => {
                 sample.pass = true;
-                sample.message = "Probe completed successfully.".to_owned();
+                // A degraded run keeps its first failing warning check's message.
+                if !sample.degraded {
+                    sample.message = "Probe completed successfully.".to_owned();
+                }
                 Ok(())
             }
             Err(e) => {
@@ -198,21 +334,113 @@ impl ProbeRunner {
             }
         };
 
+        let sample = sample.finish();
+        crate::telemetry::record_probe_run(
+            self.name().as_str(),
+            &probe.tags,
+            &self.state.node_id().to_string(),
+            sample.pass,
+            sample.duration.to_std().unwrap_or_default(),
+        );
+
+        // The sample log is a debugging aid, so failing to write it must not lose the run's result.
+        if let Some(retention) = &probe.samples
+            && let Err(err) = self
+                .state
+                .record_probe_sample(self.name().as_str(), &sample.to_sample(), retention)
+                .await
+        {
+            warn!("Failed to record a sample for probe '{}': {:?}", self.name(), err);
+        }
+
         self.state
-            .update_probe_state(self.name().as_str(), sample.finish())
+            .update_probe_state(self.name().as_str(), sample)
             .await?;
         result
     }
 
+    /// Runs a single attempt, abandoning it once the policy's `attempt_timeout` (if any) elapses.
+    async fn run_bounded_attempt(
+        &self,
+        probe: &Probe,
+        result: &mut ProbeResult,
+        prior_runs: PriorRuns,
+    ) -> Result<(), AttemptError> {
+        match probe.policy.attempt_timeout {
+            Some(limit) => tokio::time::timeout(limit, self.run_attempt(probe, result, prior_runs))
+                .await
+                .unwrap_or_else(|_| {
+                    Err(AttemptError::Timeout(format!(
+                        "Attempt timed out after {}.",
+                        humantime::format_duration(limit)
+                    )))
+                }),
+            None => self.run_attempt(probe, result, prior_runs).await,
+        }
+    }
+
     #[tracing::instrument(name = "probe.attempt", skip(self), err(Debug), fields(otel.kind=?OpenTelemetrySpanKind::Internal))]
     async fn run_attempt(
         &self,
         probe: &Probe,
         result: &mut ProbeResult,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let sample = probe.target.run(&self.cancel).await?;
+        prior_runs: PriorRuns,
+    ) -> Result<(), AttemptError> {
+        let mut sample = probe
+            .target
+            .run(&self.cancel)
+            .await
+            .map_err(|e| AttemptError::Target(e.to_string()))?;
+        let latency = (chrono::Utc::now() - result.start_time).to_std().unwrap_or_default();
+
+        if let Some(change) = &probe.change {
+            // Retries within a run share its first attempt's start, so that they report the same
+            // change as the attempt which first observed it.
+            let run_started = result
+                .attempts
+                .first()
+                .map_or(result.start_time, |attempt| attempt.start_time);
+            sample = change
+                .observe(&self.state, &probe.name, sample, run_started)
+                .await
+                .map_err(|e| AttemptError::Target(e.to_string()))?;
+        }
+
+        // The percentiles of recent runs (not including this one) let checks catch a degrading tail
+        // which no single run would fail on, such as `latency.p99 < 2s`.
+        let percentile = |latency: Option<std::time::Duration>| {
+            latency.and_then(|latency| chrono::Duration::from_std(latency).ok())
+        };
+        sample = sample
+            .with("latency.p50", percentile(prior_runs.latency.p50))
+            .with("latency.p90", percentile(prior_runs.latency.p90))
+            .with("latency.p99", percentile(prior_runs.latency.p99));
+
+        // Scoring the attempt against the probe's usual latency lets checks catch a run which is slow
+        // for this probe, such as `baseline.anomalous == false`, without a hardcoded threshold.
+        if let Some(baseline) = &probe.baseline {
+            sample = baseline.observe(prior_runs.baseline, latency, sample);
+        }
         debug!(?sample, "Probe sample collected successfully.");
 
+        // Validations the target evaluated itself (a script's `test()`/`assert` calls) are reported
+        // alongside the configured checks. Every one of them is evaluated, so that each check's pass
+        // rate is tracked even while another is failing, and the first failure fails the attempt.
+        let mut first_failure = None;
+        let mut first_warning = None;
+        for (name, validation) in sample.validations() {
+            if !validation.pass && first_failure.is_none() {
+                let message = match &validation.message {
+                    Some(message) => format!("{name}: {message}"),
+                    None => name.clone(),
+                };
+                error!("{message}");
+                first_failure = Some(message);
+            }
+
+            result.validations.insert(name.clone(), validation.clone());
+        }
+
         for check in &probe.checks {
             let name = format!("check {}", check);
             let span = info_span!(
@@ -221,21 +449,17 @@ impl ProbeRunner {
                 check=%check,
                 otel.status_code=?OpenTelemetryStatus::Unset,
                 otel.status_message=EmptyField
-            )
-            .entered();
+            );
 
             // The check expression is the validations map key and the UI shows the pass/fail state,
             // so the public failure message carries only what those can't: the sample fields the
             // check consulted. The raw evaluation error is operator-only — it can expose internal
             // detail and the message is served publicly — so it is kept to telemetry alone.
-            let (failure, otel_detail) = match check.matches(&sample) {
-                Ok(true) => (None, None),
-                Ok(false) => (Some(checks::unmatched_message(check, &sample)), None),
-                Err(e) => (
-                    Some(checks::evaluation_error_message(check, &sample)),
-                    Some(e.to_string()),
-                ),
+            let (failure, otel_detail) = match check.evaluate(&sample).instrument(span.clone()).await {
+                Ok(()) => (None, None),
+                Err(checks::CheckFailure { message, detail }) => (Some(message), detail),
             };
+            let _entered = span.enter();
 
             match failure {
                 None => {
@@ -254,17 +478,120 @@ impl ProbeRunner {
                     };
                     span.record("otel.status_code", "Error")
                         .record("otel.status_message", otel_message.as_str());
-                    error!(check = %check, "{otel_message}");
                     result
                         .validations
                         .insert(check.to_string(), ValidationResult::fail(&message));
-                    let err: Box<dyn std::error::Error> = message.into();
-                    return Err(err);
+
+                    // A failing warning check only degrades the probe, so it never fails (or retries)
+                    // the attempt.
+                    match check.severity() {
+                        CheckSeverity::Critical => {
+                            error!(check = %check, "{otel_message}");
+                            first_failure.get_or_insert(message);
+                        }
+                        CheckSeverity::Warning => {
+                            warn!(check = %check, "{otel_message}");
+                            first_warning.get_or_insert(message);
+                        }
+                    }
                 }
             }
         }
 
-        Ok(())
+        match first_failure {
+            Some(message) => Err(AttemptError::Check { message, sample }),
+            None => {
+                if let Some(message) = first_warning {
+                    result.degraded = true;
+                    result.message = message;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// What a run's checks are told about the probe's earlier runs, read from its pooled state before
+/// the run starts.
+#[derive(Debug, Clone, Copy, Default)]
+struct PriorRuns {
+    /// The latency percentiles of the probe's recent runs.
+    latency: LatencyPercentiles,
+    /// The probe's usual latency, when it configures a `baseline` and has enough history to fit it.
+    baseline: Option<LatencyBaseline>,
+}
+
+/// Why a single probe attempt failed.
+#[derive(Debug)]
+enum AttemptError {
+    /// The target failed to produce a sample at all (e.g. a refused connection or failed lookup).
+    Target(String),
+    /// The target produced a sample, but it failed one of the probe's checks.
+    Check { message: String, sample: Sample },
+    /// The attempt did not finish within the policy's `attempt_timeout`.
+    Timeout(String),
+}
+
+impl AttemptError {
+    /// The `error.kind` a `retry_on` expression sees for this failure.
+    fn kind(&self) -> &'static str {
+        match self {
+            AttemptError::Target(_) => "target",
+            AttemptError::Check { .. } => "check",
+            AttemptError::Timeout(_) => "timeout",
+        }
+    }
+
+    fn message(&self) -> &str {
+        match self {
+            AttemptError::Target(message)
+            | AttemptError::Check { message, .. }
+            | AttemptError::Timeout(message) => message,
+        }
+    }
+
+    /// The fields a `retry_on` expression is evaluated against: the failed attempt's sample (when the
+    /// target produced one), plus the failure's kind and message and the attempt number.
+    fn retry_context(&self, attempt: u8) -> Sample {
+        let sample = match self {
+            AttemptError::Check { sample, .. } => sample.clone(),
+            _ => Sample::default(),
+        };
+
+        sample
+            .with("error.kind", self.kind())
+            .with("error.message", self.message())
+            .with("attempt", attempt as u32)
+    }
+}
+
+impl std::fmt::Display for AttemptError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl std::error::Error for AttemptError {}
+
+/// Whether a failed attempt should be retried under the probe's `retry_on` expression (every failure
+/// is retried when none is configured). An expression which cannot be evaluated does not retry, so a
+/// mistyped condition fails fast rather than retrying everything.
+fn should_retry(probe: &Probe, err: &AttemptError, attempt: u8) -> bool {
+    let Some(retry_on) = &probe.policy.retry_on else {
+        return true;
+    };
+
+    match retry_on.matches(&err.retry_context(attempt)) {
+        Ok(retry) => {
+            if !retry {
+                debug!("Not retrying: the failure does not match the probe's `retry_on` condition.");
+            }
+            retry
+        }
+        Err(e) => {
+            warn!(retry_on = %retry_on, "Failed to evaluate the probe's `retry_on` condition: {e}");
+            false
+        }
     }
 }
 
@@ -288,7 +615,7 @@ mod tests {
         probe.target = TargetType::Hang;
 
         let runner = ProbeRunner::new(probe.clone(), state.clone());
-        let result = runner.run_scheduled_execution().await;
+        let result = runner.run_scheduled_execution(chrono::Utc::now()).await;
         let err = result.expect_err("a timed-out probe must report an error").to_string();
         assert!(err.contains("timed out"), "unexpected error: {err}");
 
@@ -302,4 +629,235 @@ mod tests {
             "no checks ran before the deadline, so no validations should be recorded"
         );
     }
+
+    /// Checks can assert on the latency percentiles of the probe's recent runs.
+    #[tokio::test]
+    async fn checks_see_recent_latency_percentiles() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = State::test(dir.path().to_path_buf()).await;
+
+        for (check, passes) in [("latency.p99 < 1h", true), ("latency.p50 > 1h", false)] {
+            let mut probe = state.get_config().probes[0].clone();
+            probe.checks = vec![filt_rs::Filter::new(check).unwrap().into()];
+
+            let runner = ProbeRunner::new(probe, state.clone());
+            let result = runner.run_scheduled_execution(chrono::Utc::now()).await;
+            assert_eq!(result.is_ok(), passes, "{check}: {result:?}");
+        }
+    }
+
+    /// A probe with a `baseline` exposes the `baseline.*` fields to its checks, and never reads as
+    /// anomalous before it has the history to fit one.
+    #[tokio::test]
+    async fn checks_see_the_latency_baseline() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = State::test(dir.path().to_path_buf()).await;
+
+        let mut probe = state.get_config().probes[0].clone();
+        probe.baseline = Some(serde_yaml::from_str("threshold: 3").unwrap());
+        probe.checks = vec![filt_rs::Filter::new("baseline.anomalous == false").unwrap().into()];
+
+        let runner = ProbeRunner::new(probe, state.clone());
+        runner
+            .run_scheduled_execution(chrono::Utc::now())
+            .await
+            .expect("a probe without enough history is never anomalous");
+    }
+
+    /// A failing check doesn't stop the rest from being evaluated, so each one's pass rate is
+    /// tracked in the probe's history.
+    #[tokio::test]
+    async fn every_check_is_evaluated_and_counted() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = State::test(dir.path().to_path_buf()).await;
+
+        let mut probe = state.get_config().probes[0].clone();
+        probe.checks = ["latency.p50 > 1h", "latency.p99 < 1h"]
+            .into_iter()
+            .map(|check| filt_rs::Filter::new(check).unwrap().into())
+            .collect();
+
+        let runner = ProbeRunner::new(probe.clone(), state.clone());
+        for _ in 0..2 {
+            runner
+                .run_scheduled_execution(chrono::Utc::now())
+                .await
+                .expect_err("the first check fails");
+        }
+
+        let stored = state.get_probe_state(&probe.name).await.unwrap().unwrap();
+        let bucket = stored.history.last().expect("a history bucket to be recorded");
+        assert!(!bucket.validations["latency.p50 > 1h"].pass);
+        assert!(bucket.validations["latency.p99 < 1h"].pass, "later checks still run");
+
+        let counts = bucket.check_counts();
+        assert_eq!(counts["latency.p50 > 1h"], grey_api::CheckCounts { passed: 0, failed: 2 });
+        assert_eq!(counts["latency.p99 < 1h"], grey_api::CheckCounts { passed: 2, failed: 0 });
+    }
+
+    /// A failing warning check passes the run but marks it, and its history bucket, as degraded.
+    #[tokio::test]
+    async fn warning_checks_degrade_rather_than_fail() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = State::test(dir.path().to_path_buf()).await;
+
+        let mut probe = state.get_config().probes[0].clone();
+        probe.checks = vec![
+            filt_rs::Filter::new("latency.p99 < 1h").unwrap().into(),
+            checks::Check::Filter(checks::FilterCheck {
+                filter: filt_rs::Filter::new("latency.p50 > 1h").unwrap(),
+                severity: CheckSeverity::Warning,
+            }),
+        ];
+
+        let runner = ProbeRunner::new(probe.clone(), state.clone());
+        runner
+            .run_scheduled_execution(chrono::Utc::now())
+            .await
+            .expect("a failing warning check doesn't fail the run");
+
+        let stored = state.get_probe_state(&probe.name).await.unwrap().unwrap();
+        let bucket = stored.history.last().expect("a history bucket to be recorded");
+        assert!(bucket.pass && bucket.degraded);
+        assert!(!bucket.validations["latency.p50 > 1h"].pass);
+        assert_eq!(bucket.total().total_retries, 0, "warnings aren't retried");
+        assert!(stored.streak.degraded_until.is_some());
+    }
+
+    /// A probe with a `failing_interval` switches to it once a failure is recorded, and the API DTO
+    /// reports the interval in effect.
+    #[tokio::test]
+    async fn failing_probe_switches_to_its_failing_interval() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = State::test(dir.path().to_path_buf()).await;
+
+        let mut probe = state.get_config().probes[0].clone();
+        probe.policy.timeout = std::time::Duration::from_millis(50);
+        probe.policy.failing_interval = Some(std::time::Duration::from_secs(5));
+        probe.target = TargetType::Hang;
+
+        let mut config = crate::Config::test(&dir.path().to_path_buf());
+        config.probes = vec![probe.clone()];
+        state.set_config_for_test(config);
+
+        let runner = ProbeRunner::new(probe.clone(), state.clone());
+
+        // The test probe has only passed so far, so it runs at its regular interval.
+        assert_eq!(runner.failing_interval(&probe).await, None);
+        let pooled = state.get_probe_state(&probe.name).await.unwrap().unwrap();
+        assert_eq!(pooled.interval, Some(std::time::Duration::from_secs(60)));
+
+        runner.run_scheduled_execution(chrono::Utc::now()).await.expect_err("the probe should fail");
+        assert_eq!(runner.failing_interval(&probe).await, Some(chrono::Duration::seconds(5)));
+        let pooled = state.get_probe_state(&probe.name).await.unwrap().unwrap();
+        assert_eq!(pooled.interval, Some(std::time::Duration::from_secs(5)));
+    }
+
+    /// An `attempt_timeout` abandons each stalled attempt on its own, so every retry gets its turn
+    /// before the overall policy timeout fires.
+    #[tokio::test]
+    async fn attempt_timeout_bounds_each_attempt() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = State::test(dir.path().to_path_buf()).await;
+
+        let mut probe = state.get_config().probes[0].clone();
+        probe.policy.timeout = std::time::Duration::from_secs(5);
+        probe.policy.attempt_timeout = Some(std::time::Duration::from_millis(20));
+        probe.policy.retries = Some(3);
+        probe.target = TargetType::Hang;
+
+        let runner = ProbeRunner::new(probe.clone(), state.clone());
+        let err = runner
+            .run_scheduled_execution(chrono::Utc::now())
+            .await
+            .expect_err("a stalled probe must report an error")
+            .to_string();
+        assert!(err.contains("Attempt timed out"), "unexpected error: {err}");
+
+        let states = state.get_probe_states().await.unwrap();
+        let stored = states.get(&probe.name).expect("the probe state to be stored");
+        let bucket = stored.history.last().expect("a history bucket to be recorded");
+        assert!(!bucket.pass);
+        assert_eq!(bucket.total().total_retries, 3, "every attempt should have run");
+    }
+
+    /// A failure which doesn't match the policy's `retry_on` expression fails the run immediately.
+    #[tokio::test]
+    async fn retry_on_skips_unmatched_failures() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = State::test(dir.path().to_path_buf()).await;
+
+        let mut probe = state.get_config().probes[0].clone();
+        probe.policy.timeout = std::time::Duration::from_secs(5);
+        probe.policy.attempt_timeout = Some(std::time::Duration::from_millis(20));
+        probe.policy.retries = Some(3);
+        probe.policy.retry_on = Some(filt_rs::Filter::new(r#"error.kind != "timeout""#).unwrap());
+        probe.target = TargetType::Hang;
+
+        let runner = ProbeRunner::new(probe.clone(), state.clone());
+        runner
+            .run_scheduled_execution(chrono::Utc::now())
+            .await
+            .expect_err("the probe should fail");
+
+        let states = state.get_probe_states().await.unwrap();
+        let bucket = states[&probe.name].history.last().expect("a history bucket to be recorded");
+        assert_eq!(bucket.total().total_retries, 1, "the timeout should not have been retried");
+    }
+
+    /// The results of a script's `test()` calls are recorded as validations, and a failing one
+    /// fails the probe with the step that failed.
+    #[cfg(feature = "scripts")]
+    #[tokio::test]
+    async fn script_tests_are_reported_as_validations() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = State::test(dir.path().to_path_buf()).await;
+
+        let mut probe = state.get_config().probes[0].clone();
+        probe.policy.retries = Some(1);
+        probe.checks = vec![];
+        probe.target = TargetType::test();
+        if let TargetType::Script(target) = &mut probe.target {
+            target.code = r#"
+                await test("login", () => assert(true));
+                await test("profile", () => assert.equal(404, 200, "the profile should load"));
+            "#
+            .into();
+        }
+
+        let runner = ProbeRunner::new(probe.clone(), state.clone());
+        let err = runner
+            .run_scheduled_execution(chrono::Utc::now())
+            .await
+            .expect_err("the failing test should fail the probe")
+            .to_string();
+        assert!(err.contains("profile: the profile should load"), "unexpected error: {err}");
+
+        let states = state.get_probe_states().await.unwrap();
+        let bucket = states[&probe.name].history.last().expect("a history bucket to be recorded");
+        assert!(!bucket.pass);
+        assert_eq!(bucket.validations.get("login"), Some(&ValidationResult::pass()));
+        assert_eq!(
+            bucket.validations.get("profile"),
+            Some(&ValidationResult::fail("the profile should load"))
+        );
+    }
+
+    #[test]
+    fn retry_context_describes_the_failure() {
+        let err = AttemptError::Check {
+            message: "check failed".into(),
+            sample: Sample::default().with("http.status", 404),
+        };
+
+        let filter = filt_rs::Filter::new(
+            r#"error.kind == "check" && http.status == 404 && attempt == 2"#,
+        )
+        .unwrap();
+        assert!(filter.matches(&err.retry_context(2)).unwrap());
+
+        let err = AttemptError::Target("connection refused".into());
+        let filter = filt_rs::Filter::new(r#"error.message contains "refused""#).unwrap();
+        assert!(filter.matches(&err.retry_context(1)).unwrap());
+    }
 }
@@ -13,8 +13,41 @@ pub struct ProbeResult {
     pub duration: Duration,
     pub retries: u8,
     pub pass: bool,
+    /// Whether the run passed while failing one of the probe's warning checks.
+    #[serde(default)]
+    pub degraded: bool,
     pub message: String,
     pub validations: HashMap<String, ValidationResult>,
+    /// Every attempt made during this run, in order, including the retries which preceded the final
+    /// outcome.
+    #[serde(default)]
+    pub attempts: Vec<ProbeAttempt>,
+    /// How long after its scheduled time this run started, including any time spent waiting on a
+    /// concurrency limit.
+    #[serde(default, with = "crate::serializers::chrono_duration_humantime")]
+    pub scheduling_delay: Duration,
+}
+
+/// The outcome of a single attempt within a probe run.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProbeAttempt {
+    pub start_time: DateTime<Utc>,
+    #[serde(with = "crate::serializers::chrono_duration_humantime")]
+    pub duration: Duration,
+    pub pass: bool,
+    pub message: String,
+}
+
+impl ProbeAttempt {
+    /// Records an attempt which began at `start_time` and has just finished.
+    pub fn finished(start_time: DateTime<Utc>, pass: bool, message: impl Into<String>) -> Self {
+        Self {
+            start_time,
+            duration: Utc::now() - start_time,
+            pass,
+            message: message.into(),
+        }
+    }
 }
 
 impl Default for ProbeResult {
@@ -31,8 +64,11 @@ impl ProbeResult {
             duration: Duration::zero(),
             retries: 0,
             pass: true,
+            degraded: false,
             message: "Test probe".into(),
             validations: HashMap::new(),
+            attempts: Vec::new(),
+            scheduling_delay: Duration::zero(),
         }
     }
 
@@ -42,8 +78,11 @@ impl ProbeResult {
             duration: Duration::zero(),
             retries: 0,
             pass: false,
+            degraded: false,
             message: String::new(),
             validations: HashMap::new(),
+            attempts: Vec::new(),
+            scheduling_delay: Duration::zero(),
         }
     }
 
@@ -60,8 +99,11 @@ impl ProbeResult {
 
         match probe.history.last_mut() {
             Some(last) if last.start_time == start_time => {
-                if last.pass || !self.pass {
+                // The bucket reports its worst run (failing, then degraded, then passing), or the
+                // latest of those which were equally bad.
+                if !self.pass || (last.pass && (self.degraded || !last.degraded)) {
                     last.pass = self.pass;
+                    last.degraded = self.degraded;
                     last.message = self.message.clone();
                     last.validations = self
                         .validations
@@ -74,23 +116,26 @@ impl ProbeResult {
                     .or_insert_with(Default::default);
 
                 observation.add_sample(self.pass, self.retries as u64, std::time::Duration::from_millis(self.duration.num_milliseconds() as u64));
+                observation.add_checks(&self.validations);
             }
             _ => {
+                let mut observation = grey_api::Observation::from_sample(
+                    self.pass,
+                    self.retries as u64,
+                    std::time::Duration::from_millis(self.duration.num_milliseconds() as u64));
+                observation.add_checks(&self.validations);
+
                 probe.history.push(grey_api::ProbeHistoryBucket {
                     start_time,
                     pass: self.pass,
+                    degraded: self.degraded,
                     message: self.message.clone(),
                     validations: self
                         .validations
                         .iter()
                         .map(|(k, v)| (k.clone(), v.clone().elide(256)))
                         .collect(),
-                    observations: vec![(
-                        node_id.to_string(),
-                        grey_api::Observation::from_sample(
-                            self.pass,
-                            self.retries as u64,
-                            std::time::Duration::from_millis(self.duration.num_milliseconds() as u64)))]
+                    observations: vec![(node_id.to_string(), observation)]
                         .into_iter().collect(),
                 });
             }
@@ -99,8 +144,27 @@ impl ProbeResult {
         let observation = probe.observations.entry(node_id.to_string())
             .or_insert_with(Default::default);
         observation.add_sample(self.pass, self.retries as u64, std::time::Duration::from_millis(self.duration.num_milliseconds() as u64));
+        observation.add_checks(&self.validations);
 
         probe.streak.observe(self.pass, sample_time, probe.window());
+        if self.degraded {
+            probe.streak.observe_degraded(sample_time, probe.window());
+        }
+    }
+
+    /// This result as a raw sample for the probe's sample log, which keeps what [`Self::apply`]
+    /// folds away: its exact timing, retries and check messages.
+    pub fn to_sample(&self) -> grey_api::ProbeSample {
+        grey_api::ProbeSample {
+            start_time: self.start_time,
+            duration: self.duration.to_std().unwrap_or_default(),
+            pass: self.pass,
+            degraded: self.degraded,
+            retries: self.retries,
+            message: self.message.clone(),
+            validations: self.validations.clone().into_iter().collect(),
+            scheduling_delay: self.scheduling_delay.to_std().unwrap_or_default(),
+        }
     }
 }
 
@@ -128,8 +192,11 @@ mod tests {
             duration: Duration::zero(),
             retries: 0,
             pass,
+            degraded: false,
             message: String::new(),
             validations: HashMap::new(),
+            attempts: Vec::new(),
+            scheduling_delay: Duration::zero(),
         }
     }
 
@@ -142,6 +209,8 @@ mod tests {
             observations: HashMap::new(),
             streak: grey_api::Streak::default(),
             debounce: None,
+            interval: None,
+            blocked_by: Vec::new(),
             retired: false,
         }
     }
@@ -177,4 +246,29 @@ mod tests {
         assert!(probe.streak.passing_at(recovered, grey_api::Streak::default_recovery_window()));
         assert_eq!(probe.streak.since_at(recovered, grey_api::Streak::default_recovery_window()), Some(failed_at + Duration::minutes(1)));
     }
+
+    /// Each bucket reports its worst run: a degraded run outranks passing ones, and a failure
+    /// outranks both.
+    #[test]
+    fn apply_reports_the_worst_run_per_bucket() {
+        let mut probe = empty_probe();
+        let start = Utc.with_ymd_and_hms(2026, 6, 7, 12, 0, 0).unwrap();
+
+        let mut degraded = result_at(start + Duration::minutes(1), true);
+        degraded.degraded = true;
+        degraded.message = "tls.expires_in = 13d".into();
+
+        result_at(start, true).apply("node", &mut probe);
+        degraded.apply("node", &mut probe);
+        result_at(start + Duration::minutes(2), true).apply("node", &mut probe);
+
+        let bucket = probe.history.last().unwrap();
+        assert!(bucket.pass && bucket.degraded);
+        assert_eq!(bucket.message, degraded.message, "a later passing run doesn't hide the degradation");
+        assert_eq!(probe.streak.degraded_until, Some(start + Duration::minutes(1)));
+
+        result_at(start + Duration::minutes(3), false).apply("node", &mut probe);
+        let bucket = probe.history.last().unwrap();
+        assert!(!bucket.pass && !bucket.degraded);
+    }
 }
@@ -1,11 +1,15 @@
 use std::{borrow::Cow, collections::HashMap, fmt::Display};
 
 use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use grey_api::ValidationResult;
 use serde::{Deserialize, Serialize, de::Visitor};
 
 #[derive(Debug, Clone, Default)]
 pub struct Sample {
     metadata: HashMap<String, SampleValue>,
+    /// Validations the target evaluated itself (such as a script's `test()` and `assert` calls),
+    /// reported alongside the probe's configured checks.
+    validations: Vec<(String, ValidationResult)>,
 }
 
 impl Sample {
@@ -23,6 +27,20 @@ impl Sample {
             .get(&key.to_string())
             .unwrap_or(&SampleValue::None)
     }
+
+    /// The sample's fields and their values, in no particular order.
+    pub fn fields(&self) -> impl Iterator<Item = (&str, &SampleValue)> {
+        self.metadata.iter().map(|(key, value)| (key.as_str(), value))
+    }
+
+    pub fn add_validation<K: ToString>(&mut self, name: K, result: ValidationResult) {
+        self.validations.push((name.to_string(), result));
+    }
+
+    /// The validations the target evaluated itself, in the order they were recorded.
+    pub fn validations(&self) -> &[(String, ValidationResult)] {
+        &self.validations
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -0,0 +1,442 @@
+//! Service level objectives: an availability target over a rolling window for the probes and crons
+//! a tag selector matches, evaluated into a [`grey_api::Slo`] from the targets' observations.
+//!
+//! Probes are measured from their long-term history (see [`crate::state::HistoryStore`]), so windows
+//! are aligned to the history's hourly buckets, and an objective's window falls back to daily or
+//! monthly buckets once it reaches beyond the hourly tier. Crons are measured from their retained
+//! runs, so a cron which runs more often than its last 50 runs span only contributes those runs.
+
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap};
+use std::error::Error;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use filt_rs::{FilterValue, Filterable};
+use grey_api::{BurnRate, HistoryResolution, Mergeable, Observation, ProbeHistoryBucket};
+use serde::{Deserialize, Serialize};
+
+use crate::Config;
+use crate::config::default_visible_filter;
+use crate::state::{CronStore, HistoryStore, State, finest_retained_resolution};
+
+/// A service level objective covering the probes and crons matched by its `selector`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SloConfig {
+    pub name: String,
+
+    #[serde(default)]
+    pub description: Option<String>,
+
+    /// A `filt-rs` expression selecting the probes and crons this objective covers, evaluated against
+    /// each entity's `name`, `type` (`probe` or `cron`) and `tags.<key>`; for example
+    /// `tags.service == "Payments"`.
+    pub selector: filt_rs::Filter,
+
+    /// The target success rate, as a percentage (e.g. `99.9`).
+    pub objective: f64,
+
+    /// The rolling window the objective is measured over. Defaults to 30 days.
+    #[serde(default = "default_window", with = "humantime_serde")]
+    pub window: Duration,
+
+    #[serde(default)]
+    pub tags: HashMap<String, String>,
+
+    /// Which viewers may see this objective in the API and UI, as for a probe's `visible` filter.
+    #[serde(default = "default_visible_filter")]
+    pub visible: filt_rs::Filter,
+
+    /// The multi-window burn rate alerts which deliver an `slo.burn_rate_exceeded` webhook. Defaults
+    /// to a fast burn (6x over 6h and 1h) and a slow burn (1x over 3d and 6h).
+    #[serde(default = "default_alerts")]
+    pub alerts: Vec<BurnRateAlert>,
+}
+
+/// A burn rate alert, firing while the error budget is being spent at least `burn_rate` times faster
+/// than the objective allows over both the `long_window` and the `short_window`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BurnRateAlert {
+    #[serde(with = "humantime_serde")]
+    pub long_window: Duration,
+
+    #[serde(with = "humantime_serde")]
+    pub short_window: Duration,
+
+    pub burn_rate: f64,
+}
+
+fn default_window() -> Duration {
+    Duration::from_secs(30 * 24 * 3600)
+}
+
+fn default_alerts() -> Vec<BurnRateAlert> {
+    vec![
+        BurnRateAlert {
+            long_window: Duration::from_secs(6 * 3600),
+            short_window: Duration::from_secs(3600),
+            burn_rate: 6.0,
+        },
+        BurnRateAlert {
+            long_window: Duration::from_secs(3 * 24 * 3600),
+            short_window: Duration::from_secs(6 * 3600),
+            burn_rate: 1.0,
+        },
+    ]
+}
+
+impl SloConfig {
+    /// Whether the selector matches the given entity. A selector which fails to evaluate matches
+    /// nothing.
+    pub fn selects(&self, entity_type: &str, name: &str, tags: &HashMap<String, String>) -> bool {
+        self.selector
+            .matches(&SloTargetFilter { entity_type, name, tags })
+            .unwrap_or(false)
+    }
+
+    /// Validates the objective and its alerts. The selector is already validated during
+    /// deserialization (it is a parsed [`filt_rs::Filter`]).
+    pub fn validate(&self) -> Result<(), Box<dyn Error>> {
+        if !(self.objective > 0.0 && self.objective < 100.0) {
+            return Err(format!(
+                "SLO '{}' has an `objective` of {}; it must be a percentage between 0 and 100 (exclusive).",
+                self.name, self.objective
+            )
+            .into());
+        }
+
+        if self.window < Duration::from_secs(3600) {
+            return Err(format!("SLO '{}' has a `window` shorter than an hour.", self.name).into());
+        }
+
+        for alert in &self.alerts {
+            if alert.short_window < Duration::from_secs(3600) || alert.short_window >= alert.long_window {
+                return Err(format!(
+                    "SLO '{}' has a burn rate alert whose `short_window` is not between an hour and its `long_window`.",
+                    self.name
+                )
+                .into());
+            }
+
+            if alert.burn_rate.is_nan() || alert.burn_rate <= 0.0 {
+                return Err(format!(
+                    "SLO '{}' has a burn rate alert with a `burn_rate` of {}; it must be positive.",
+                    self.name, alert.burn_rate
+                )
+                .into());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Evaluates every objective in `slos` at `now`, in the order given.
+pub async fn evaluate_slos<'a>(
+    state: &State,
+    config: &Config,
+    slos: impl IntoIterator<Item = &'a SloConfig>,
+    now: DateTime<Utc>,
+) -> Result<Vec<grey_api::Slo>, Box<dyn Error>> {
+    let crons = state.get_cron_states().await?;
+
+    let mut evaluated = Vec::new();
+    for slo in slos {
+        let window_start = since(now, slo.window);
+        let window_resolution = finest_retained_resolution(window_start, now);
+        let burn_windows: BTreeSet<Duration> = slo
+            .alerts
+            .iter()
+            .flat_map(|alert| [alert.long_window, alert.short_window])
+            .collect();
+        let burn_start = burn_windows.last().map(|window| since(now, *window)).unwrap_or(now);
+
+        let mut targets = Vec::new();
+        let mut observation = Observation::default();
+        let mut windows: HashMap<Duration, Observation> = HashMap::new();
+
+        for probe in config.probes.iter().filter(|p| slo.selects("probe", &p.name, &p.tags)) {
+            targets.push(probe.name.clone());
+
+            let history = window_history(state, &probe.name, window_resolution, window_start, now).await?;
+            observation.merge(&observe_buckets(&history, window_resolution.start_of(window_start)));
+
+            // The burn rate windows are measured from the hourly tier, which includes the current hour.
+            let hours = state
+                .get_probe_history(&probe.name, HistoryResolution::Hour, burn_start, now)
+                .await?;
+            for window in &burn_windows {
+                windows.entry(*window).or_default().merge(&observe_buckets(
+                    &hours,
+                    HistoryResolution::Hour.start_of(since(now, *window)),
+                ));
+            }
+        }
+
+        for cron in config.crons.iter().filter(|c| slo.selects("cron", &c.name, &c.tags)) {
+            targets.push(cron.name.clone());
+            let Some(cron) = crons.get(&cron.name) else {
+                continue;
+            };
+
+            observation.merge(&observe_runs(cron, window_start));
+            for window in &burn_windows {
+                windows
+                    .entry(*window)
+                    .or_default()
+                    .merge(&observe_runs(cron, HistoryResolution::Hour.start_of(since(now, *window))));
+            }
+        }
+
+        targets.sort();
+        let rate = |window: &Duration| {
+            windows
+                .get(window)
+                .map(|observation| BurnRate::rate(observation, slo.objective))
+                .unwrap_or_default()
+        };
+        let burn_rates = slo
+            .alerts
+            .iter()
+            .map(|alert| BurnRate {
+                long_window: alert.long_window,
+                short_window: alert.short_window,
+                threshold: alert.burn_rate,
+                long_rate: rate(&alert.long_window),
+                short_rate: rate(&alert.short_window),
+            })
+            .collect();
+
+        evaluated.push(grey_api::Slo {
+            name: slo.name.clone(),
+            description: slo.description.clone(),
+            tags: slo.tags.clone(),
+            objective: slo.objective,
+            window: slo.window,
+            targets,
+            observation,
+            burn_rates,
+        });
+    }
+
+    Ok(evaluated)
+}
+
+/// The named probe's history from `from` to `now`, read from the `resolution` tier. The daily and
+/// monthly tiers are only refreshed by each rollup pass, so the periods still in progress are read
+/// from the finer tiers instead, down to the hourly tier with the pooled recent history laid over it.
+async fn window_history(
+    state: &State,
+    probe_name: &str,
+    resolution: HistoryResolution,
+    from: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> Result<Vec<ProbeHistoryBucket>, Box<dyn Error>> {
+    let tiers: &[HistoryResolution] = match resolution {
+        HistoryResolution::Hour => &[HistoryResolution::Hour],
+        HistoryResolution::Day => &[HistoryResolution::Day, HistoryResolution::Hour],
+        HistoryResolution::Month => &[HistoryResolution::Month, HistoryResolution::Day, HistoryResolution::Hour],
+    };
+
+    let mut from = resolution.start_of(from);
+    let mut buckets = Vec::new();
+    for (i, tier) in tiers.iter().enumerate() {
+        let finest = i + 1 == tiers.len();
+        let until = if finest { now } else { tier.start_of(now) };
+
+        buckets.extend(
+            state
+                .get_probe_history(probe_name, *tier, from, now)
+                .await?
+                .into_iter()
+                .filter(|bucket| bucket.start_time >= from && (finest || bucket.start_time < until)),
+        );
+        from = from.max(until);
+    }
+
+    Ok(buckets)
+}
+
+/// The start of a window of the given length ending at `now`.
+fn since(now: DateTime<Utc>, window: Duration) -> DateTime<Utc> {
+    chrono::Duration::from_std(window)
+        .ok()
+        .and_then(|window| now.checked_sub_signed(window))
+        .unwrap_or(DateTime::<Utc>::MIN_UTC)
+}
+
+/// The sum of every observer's observations in the buckets which start at or after `from`.
+fn observe_buckets(buckets: &[ProbeHistoryBucket], from: DateTime<Utc>) -> Observation {
+    buckets
+        .iter()
+        .filter(|bucket| bucket.start_time >= from)
+        .fold(Observation::default(), |mut acc, bucket| {
+            acc.merge(&bucket.total());
+            acc
+        })
+}
+
+/// A cron's completed runs which started at or after `from`, counting a succeeded run as a
+/// successful sample and a failed, missed or stuck run as a failed one. Runs still in flight haven't
+/// succeeded or failed yet, so they aren't counted.
+fn observe_runs(cron: &grey_api::Cron, from: DateTime<Utc>) -> Observation {
+    let mut observation = Observation::default();
+    for run in cron.runs.iter().filter(|run| run.started_at >= from && !run.is_in_flight()) {
+        observation.add_sample(
+            run.status == grey_api::CronStatus::Succeeded && run.reason.is_none(),
+            0,
+            run.duration.unwrap_or_default(),
+        );
+    }
+
+    observation
+}
+
+/// Exposes a probe or cron to an SLO's `selector`. The addressable fields are `name`, `type` (`probe`
+/// or `cron`) and `tags.<key>`; unknown keys resolve to null.
+struct SloTargetFilter<'a> {
+    entity_type: &'a str,
+    name: &'a str,
+    tags: &'a HashMap<String, String>,
+}
+
+impl Filterable for SloTargetFilter<'_> {
+    fn get(&self, key: &str) -> FilterValue<'_> {
+        match key {
+            "name" => FilterValue::String(Cow::Borrowed(self.name)),
+            "type" => FilterValue::String(Cow::Borrowed(self.entity_type)),
+            k if k.starts_with("tags.") => self
+                .tags
+                .get(&k["tags.".len()..])
+                .map(|value| FilterValue::String(Cow::Borrowed(value)))
+                .unwrap_or(FilterValue::Null),
+            _ => FilterValue::Null,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::result::ProbeResult;
+    use crate::state::ProbeStore;
+
+    fn slo(selector: &str) -> SloConfig {
+        SloConfig {
+            name: "web".into(),
+            description: None,
+            selector: filt_rs::Filter::new(selector).unwrap(),
+            objective: 99.0,
+            window: default_window(),
+            tags: HashMap::new(),
+            visible: default_visible_filter(),
+            alerts: default_alerts(),
+        }
+    }
+
+    #[test]
+    fn test_selects() {
+        let tags = HashMap::from([("service".to_string(), "Web".to_string())]);
+        assert!(slo(r#"tags.service == "Web""#).selects("probe", "web.prod", &tags));
+        assert!(!slo(r#"tags.service == "Web" && type == "cron""#).selects("probe", "web.prod", &tags));
+        assert!(!slo(r#"tags.service == "Payments""#).selects("probe", "web.prod", &tags));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(slo("true").validate().is_ok());
+        assert!(SloConfig { objective: 100.0, ..slo("true") }.validate().is_err());
+        assert!(SloConfig { window: Duration::from_secs(60), ..slo("true") }.validate().is_err());
+
+        let inverted = BurnRateAlert {
+            long_window: Duration::from_secs(3600),
+            short_window: Duration::from_secs(6 * 3600),
+            burn_rate: 6.0,
+        };
+        assert!(SloConfig { alerts: vec![inverted], ..slo("true") }.validate().is_err());
+    }
+
+    #[test]
+    fn test_observe_runs() {
+        let mut cron = grey_api::Cron::from_config(
+            "backup",
+            HashMap::new(),
+            grey_api::CronSchedule::Every(Duration::from_secs(3600)),
+            None,
+            None,
+        );
+        let now = Utc::now();
+        for (offset, status, reason) in [
+            (5, grey_api::CronStatus::Succeeded, None),
+            (4, grey_api::CronStatus::Failed, None),
+            (3, grey_api::CronStatus::Failed, Some(grey_api::CronRunReason::Missed)),
+            (2, grey_api::CronStatus::Succeeded, None),
+            (0, grey_api::CronStatus::Running, None),
+        ] {
+            cron.push_run(grey_api::CronRun {
+                started_at: now - chrono::Duration::hours(offset),
+                status,
+                duration: None,
+                reason,
+            });
+        }
+
+        let observation = observe_runs(&cron, now - chrono::Duration::hours(4));
+        assert_eq!(observation.total_samples, 3, "older and in-flight runs aren't counted");
+        assert_eq!(observation.successful_samples, 1);
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_slos() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = State::test(dir.path().to_path_buf()).await;
+        let config = state.get_config();
+        let probe_name = config.probes[0].name.clone();
+
+        let now = Utc::now();
+        for offset_minutes in [30, 20, 10] {
+            let mut sample = ProbeResult::test();
+            sample.start_time = now - chrono::Duration::minutes(offset_minutes);
+            sample.pass = offset_minutes != 20;
+            state.update_probe_state(&probe_name, sample).await.unwrap();
+        }
+
+        // The objective's 30 day window is measured from the daily tier, which is filled by a rollup.
+        state.rollup_history().await.unwrap();
+
+        let slos = [slo("true"), slo("false")];
+        let evaluated = evaluate_slos(&state, &config, &slos, now).await.unwrap();
+
+        assert_eq!(evaluated[0].targets, vec![probe_name]);
+        assert_eq!(evaluated[0].observation.total_samples, 4, "the test sample and the three above");
+        assert_eq!(evaluated[0].observation.successful_samples, 3);
+        assert!(evaluated[0].exhausted(), "one failure in four spends a 1% budget");
+        assert!(evaluated[0].burning(), "the failure is within every alert's windows");
+
+        assert!(evaluated[1].targets.is_empty());
+        assert_eq!(evaluated[1].budget_remaining(), 100.0);
+        assert!(!evaluated[1].burning());
+    }
+
+    /// Runs since the last rollup count towards a window measured from the daily tier.
+    #[tokio::test]
+    async fn test_evaluate_slos_before_a_rollup() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = State::test(dir.path().to_path_buf()).await;
+        let config = state.get_config();
+        let probe_name = config.probes[0].name.clone();
+
+        state.rollup_history().await.unwrap();
+
+        let now = Utc::now();
+        let mut sample = ProbeResult::test();
+        sample.start_time = now - chrono::Duration::minutes(5);
+        sample.pass = false;
+        state.update_probe_state(&probe_name, sample).await.unwrap();
+
+        let slos = [slo("true")];
+        let evaluated = evaluate_slos(&state, &config, &slos, now).await.unwrap();
+        assert_eq!(evaluated[0].observation.total_samples, 2, "the test sample and the one above");
+        assert_eq!(evaluated[0].observation.successful_samples, 1);
+    }
+}
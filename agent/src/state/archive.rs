@@ -0,0 +1,386 @@
+//! State archives: the [`ArchiveStore`] trait exporting the replicated probe, cron and incident
+//! records in the [`State`] redb store to a portable [`StateArchive`], and merging one back in.
+//!
+//! An archive holds every record exactly as it is stored (including the full history of each probe,
+//! which its gossip diffs trim), alongside the node it is partitioned under. Importing one replays
+//! its records through the gossip apply path, so they merge with the existing state by the same
+//! rules as an update received from a peer: probe records fold together via their CRDT merge, and
+//! crons and incidents resolve by last-writer-wins. As with gossip, only probe records newer than the
+//! stored ones are merged, so importing an old archive never winds back newer observations, and
+//! importing the same archive twice is a no-op.
+
+use std::error::Error;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use grey_api::{Cron, Incident, IncidentUpdate, Probe};
+use redb::{ReadableDatabase, ReadableTable};
+use serde::{Deserialize, Serialize};
+
+use crate::cluster::{ClusterStateDiff, GossipStore, NodeID, Versioned};
+
+use super::{GlobalLwwEntity, INSTANCE_METADATA_TABLE, NODE_ID_KEY, PROBES_TABLE, ReplicatedEntity, State};
+
+/// The version of the archive format written by this agent. Archives from a newer version are
+/// rejected rather than partially imported.
+const ARCHIVE_VERSION: u32 = 1;
+
+/// A portable snapshot of an agent's replicated state.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StateArchive {
+    /// The version of the archive format.
+    pub version: u32,
+    /// When the archive was taken.
+    pub exported_at: DateTime<Utc>,
+    /// The node which exported the archive.
+    pub node_id: String,
+    /// Every node's observations of each probe.
+    #[serde(default)]
+    pub probes: Vec<ArchivedRecord<Probe>>,
+    #[serde(default)]
+    pub crons: Vec<ArchivedRecord<Cron>>,
+    #[serde(default)]
+    pub incidents: Vec<ArchivedRecord<Incident>>,
+    #[serde(default)]
+    pub incident_updates: Vec<ArchivedRecord<IncidentUpdate>>,
+}
+
+/// A single record in a [`StateArchive`].
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ArchivedRecord<T> {
+    /// The node the record is partitioned under: the observer of a probe record, or the last writer
+    /// of a cron or incident.
+    pub node: String,
+    pub record: T,
+}
+
+impl<T> ArchivedRecord<T> {
+    fn node_id(&self) -> Result<NodeID, Box<dyn Error>> {
+        self.node
+            .parse()
+            .map_err(|e| format!("The archive holds a record for an invalid node ID '{}': {e}", self.node).into())
+    }
+}
+
+/// The encodings a [`StateArchive`] can be written in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArchiveFormat {
+    /// Human-readable JSON.
+    #[default]
+    Json,
+    /// Compact MessagePack, the encoding the state database itself uses.
+    MsgPack,
+}
+
+impl ArchiveFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ArchiveFormat::Json => "application/json",
+            ArchiveFormat::MsgPack => "application/msgpack",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ArchiveFormat::Json => "json",
+            ArchiveFormat::MsgPack => "msgpack",
+        }
+    }
+}
+
+impl std::str::FromStr for ArchiveFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(ArchiveFormat::Json),
+            "msgpack" => Ok(ArchiveFormat::MsgPack),
+            other => Err(format!("Unknown archive format '{other}'; expected 'json' or 'msgpack'.")),
+        }
+    }
+}
+
+impl StateArchive {
+    /// Encodes the archive in `format`.
+    pub fn to_vec(&self, format: ArchiveFormat) -> Result<Vec<u8>, Box<dyn Error>> {
+        Ok(match format {
+            ArchiveFormat::Json => serde_json::to_vec_pretty(self)?,
+            ArchiveFormat::MsgPack => rmp_serde::to_vec_named(self)?,
+        })
+    }
+
+    /// Decodes an archive written in either format, telling them apart by the JSON object's opening
+    /// brace.
+    pub fn from_slice(data: &[u8]) -> Result<Self, Box<dyn Error>> {
+        let archive: StateArchive = match data.iter().find(|b| !b.is_ascii_whitespace()) {
+            Some(b'{') => serde_json::from_slice(data)
+                .map_err(|e| format!("Failed to parse the JSON state archive: {e}"))?,
+            _ => rmp_serde::from_slice(data)
+                .map_err(|e| format!("Failed to parse the MessagePack state archive: {e}"))?,
+        };
+
+        if archive.version > ARCHIVE_VERSION {
+            return Err(format!(
+                "The state archive uses version {} of the archive format, but this agent only supports up to version {ARCHIVE_VERSION}; import it with a newer agent.",
+                archive.version
+            )
+            .into());
+        }
+
+        Ok(archive)
+    }
+}
+
+/// Backup and restore of the replicated state.
+#[allow(async_fn_in_trait)]
+pub trait ArchiveStore {
+    /// Snapshots every probe, cron, incident and incident update record into an archive.
+    async fn export_archive(&self) -> Result<StateArchive, Box<dyn Error>>;
+
+    /// Merges the records in `archive` into the store, as though they had been received from the
+    /// peers they are partitioned under.
+    async fn import_archive(&self, archive: &StateArchive) -> Result<(), Box<dyn Error>>;
+}
+
+impl ArchiveStore for State {
+    async fn export_archive(&self) -> Result<StateArchive, Box<dyn Error>> {
+        export_transaction(&self.database.begin_read()?, self.node_id)
+    }
+
+    async fn import_archive(&self, archive: &StateArchive) -> Result<(), Box<dyn Error>> {
+        // Each entity type is applied on its own, since a gossip diff keys its entries by name alone
+        // and so can't hold, say, a probe and a cron which share a partition and a name.
+        let probes = self.newer_probe_records(&archive.probes)?;
+        self.apply(to_diff(&probes, |probe| probe.name.clone(), ReplicatedEntity::Probe)?).await?;
+        self.apply(to_diff(&archive.crons, Cron::id_field, ReplicatedEntity::Cron)?).await?;
+        self.apply(to_diff(&archive.incidents, Incident::id_field, ReplicatedEntity::Incident)?).await?;
+        self.apply(to_diff(&archive.incident_updates, IncidentUpdate::id_field, ReplicatedEntity::IncidentUpdate)?).await?;
+
+        Ok(())
+    }
+}
+
+impl State {
+    /// The probe records which are newer than the stored record for the same observer. A probe
+    /// record's merge replaces each observer's cumulative observations and history buckets wholesale,
+    /// so an older record must not be merged over a newer one; gossip avoids this by only ever sending
+    /// records newer than the version a peer advertises, and an import applies the same check.
+    fn newer_probe_records(
+        &self,
+        records: &[ArchivedRecord<Probe>],
+    ) -> Result<Vec<ArchivedRecord<Probe>>, Box<dyn Error>> {
+        let txn = self.database.begin_read()?;
+        let Ok(table) = txn.open_table(PROBES_TABLE) else {
+            return Ok(records.to_vec());
+        };
+
+        let mut newer = Vec::new();
+        for record in records {
+            let node_id: u128 = record.node_id()?.into();
+            let stored = table
+                .get((node_id, record.record.name.clone()))?
+                .map(|value| value.value().0);
+            if stored.is_none_or(|version| record.record.version() > version) {
+                newer.push(record.clone());
+            }
+        }
+        Ok(newer)
+    }
+}
+
+/// Exports the state database at `path` without opening it for writing, so that taking a backup
+/// neither creates a missing database nor advances its node's generation the way starting the
+/// agent does. The agent must not be running against the database.
+pub fn export_database(path: &Path) -> Result<StateArchive, Box<dyn Error>> {
+    if !path.exists() {
+        return Err(format!("The state database '{}' does not exist.", path.display()).into());
+    }
+
+    let database = redb::ReadOnlyDatabase::open(path)
+        .map_err(|e| {
+            format!(
+                "Failed to open the state database '{}' ({e}). Stop the agent before running this command, or use the /api/v1/admin/state/snapshot endpoint to export the state of a running agent.",
+                path.display()
+            )
+        })?;
+    let txn = database.begin_read()?;
+
+    let node_id = match txn.open_table(INSTANCE_METADATA_TABLE) {
+        Ok(table) => table.get(NODE_ID_KEY)?.map(|id| NodeID::from(id.value())),
+        Err(_) => None,
+    }
+    .ok_or_else(|| format!("The state database '{}' has no node identity; is it a Grey state database?", path.display()))?;
+
+    export_transaction(&txn, node_id)
+}
+
+/// Snapshots every replicated record visible to `txn` into an archive exported by `node_id`.
+fn export_transaction(txn: &redb::ReadTransaction, node_id: NodeID) -> Result<StateArchive, Box<dyn Error>> {
+    let mut probes = Vec::new();
+    // The tables only exist once something has been written to them.
+    if let Ok(table) = txn.open_table(PROBES_TABLE) {
+        for (key, value) in table.iter()?.filter_map(|r| r.ok()) {
+            let (node_id, _name) = key.value();
+            let (_version, data) = value.value();
+            probes.push(ArchivedRecord {
+                node: NodeID::from(node_id).to_string(),
+                record: rmp_serde::from_slice(data)
+                    .map_err(|e| format!("Failed to parse probe state for export: {e:?}"))?,
+            });
+        }
+    }
+
+    Ok(StateArchive {
+        version: ARCHIVE_VERSION,
+        exported_at: Utc::now(),
+        node_id: node_id.to_string(),
+        probes,
+        crons: export_lww(txn)?,
+        incidents: export_lww(txn)?,
+        incident_updates: export_lww(txn)?,
+    })
+}
+
+/// Reads every row of one global-LWW table, partitioned under its `last_writer`.
+fn export_lww<E: GlobalLwwEntity>(txn: &redb::ReadTransaction) -> Result<Vec<ArchivedRecord<E>>, Box<dyn Error>> {
+    let mut records = Vec::new();
+    if let Ok(table) = txn.open_table(E::TABLE) {
+        for (_key, value) in table.iter()?.filter_map(|r| r.ok()) {
+            let (_version, last_writer, data) = value.value();
+            records.push(ArchivedRecord {
+                node: NodeID::from(last_writer).to_string(),
+                record: rmp_serde::from_slice(data)
+                    .map_err(|e| format!("Failed to parse global-LWW state for export: {e:?}"))?,
+            });
+        }
+    }
+    Ok(records)
+}
+
+/// Builds the gossip diff which delivers `records` from the nodes they are partitioned under.
+fn to_diff<T: Clone>(
+    records: &[ArchivedRecord<T>],
+    field: impl Fn(&T) -> String,
+    wrap: impl Fn(T) -> ReplicatedEntity,
+) -> Result<ClusterStateDiff<NodeID, ReplicatedEntity>, Box<dyn Error>> {
+    let mut diff = ClusterStateDiff::new();
+    for record in records {
+        diff.update(record.node_id()?, field(&record.record), wrap(record.record.clone()));
+    }
+    Ok(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{IncidentStore, ProbeStore};
+    use grey_api::{CreateIncident, Impact};
+
+    #[tokio::test]
+    async fn archives_round_trip_between_agents() {
+        let source_dir = tempfile::tempdir().unwrap();
+        let source = State::test(source_dir.path().to_path_buf()).await;
+        source
+            .create_incident(CreateIncident {
+                title: "Outage".into(),
+                impact: Impact::Offline,
+                message: "down".into(),
+                timestamp: None,
+            })
+            .await
+            .unwrap();
+
+        let archive = source.export_archive().await.unwrap();
+        assert_eq!(archive.node_id, source.node_id().to_string());
+        assert_eq!(archive.probes.len(), 1);
+        assert_eq!(archive.incidents.len(), 1);
+        assert_eq!(archive.incident_updates.len(), 1);
+
+        let target_dir = tempfile::tempdir().unwrap();
+        let target = State::test(target_dir.path().to_path_buf()).await;
+        for _ in 0..2 {
+            target.import_archive(&archive).await.unwrap();
+        }
+
+        let restored = target.export_archive().await.unwrap();
+        assert_eq!(restored.probes.len(), 2, "the target's own record is kept alongside the import");
+        assert!(restored.probes.contains(&archive.probes[0]), "importing twice is a no-op");
+        assert_eq!(restored.incidents, archive.incidents);
+        assert_eq!(restored.incident_updates, archive.incident_updates);
+    }
+
+    /// Exporting a database from disk leaves it exactly as it was, and never creates one.
+    #[tokio::test]
+    async fn databases_are_exported_read_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = State::test(dir.path().to_path_buf()).await;
+        let path = state.get_config().state.clone();
+        let node_id = state.node_id();
+        let expected = state.export_archive().await.unwrap();
+        drop(state);
+
+        let generation = || {
+            let database = redb::ReadOnlyDatabase::open(&path).unwrap();
+            let txn = database.begin_read().unwrap();
+            let table = txn.open_table(INSTANCE_METADATA_TABLE).unwrap();
+            table.get(crate::state::GENERATION_KEY).unwrap().map(|v| v.value())
+        };
+        let before = generation();
+
+        let archive = export_database(&path).unwrap();
+        assert_eq!(archive.node_id, node_id.to_string());
+        assert_eq!(archive.probes, expected.probes);
+        assert_eq!(generation(), before, "exporting doesn't start a new generation");
+
+        let missing = dir.path().join("missing.redb");
+        assert!(export_database(&missing).is_err());
+        assert!(!missing.exists(), "a missing database isn't created");
+    }
+
+    /// Restoring an old backup over newer state keeps the newer observations rather than winding them
+    /// back.
+    #[tokio::test]
+    async fn older_probe_records_are_not_imported() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = State::test(dir.path().to_path_buf()).await;
+        let name = state.get_config().probes[0].name.clone();
+        let own = |archive: &StateArchive| {
+            archive
+                .probes
+                .iter()
+                .find(|record| record.node == state.node_id().to_string())
+                .expect("the node's own probe record")
+                .record
+                .clone()
+        };
+
+        let old = state.export_archive().await.unwrap();
+        // The record's version has second granularity once stored, so step past it.
+        tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+        state.update_probe_state(&name, crate::result::ProbeResult::test()).await.unwrap();
+        let newer = own(&state.export_archive().await.unwrap());
+        assert!(newer.total().total_samples > own(&old).total().total_samples);
+
+        state.import_archive(&old).await.unwrap();
+        let restored = own(&state.export_archive().await.unwrap());
+        assert_eq!(restored.total(), newer.total(), "the newer counters survive");
+        assert_eq!(restored.history, newer.history);
+    }
+
+    #[tokio::test]
+    async fn archives_are_encoded_in_either_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = State::test(dir.path().to_path_buf()).await;
+        let archive = state.export_archive().await.unwrap();
+
+        for format in [ArchiveFormat::Json, ArchiveFormat::MsgPack] {
+            let decoded = StateArchive::from_slice(&archive.to_vec(format).unwrap()).unwrap();
+            assert_eq!(decoded, archive, "{format:?}");
+        }
+
+        let future = StateArchive { version: ARCHIVE_VERSION + 1, ..archive };
+        let err = StateArchive::from_slice(&future.to_vec(ArchiveFormat::Json).unwrap()).unwrap_err();
+        assert!(err.to_string().contains("newer agent"), "{err}");
+    }
+}
@@ -0,0 +1,261 @@
+//! Content-change tracking: the [`ChangeStore`] trait recording the hash of the field a probe watches
+//! for drift (see [`crate::change::ChangeDetection`]), implemented over the [`State`] redb store.
+//!
+//! Each probe's latest hash is a [`GlobalLwwEntity`]: a single global row keyed by the probe's name,
+//! replicated through gossip and resolved by last-writer-wins, so a change observed by one node (or
+//! before a restart) isn't reported again by the next run elsewhere.
+
+use std::collections::HashSet;
+use std::error::Error;
+
+use chrono::{DateTime, Utc};
+use redb::{ReadableTable, TableDefinition};
+use serde::{Deserialize, Serialize};
+
+use crate::cluster::Versioned;
+
+use super::{CONTENT_HASHES_TABLE, GlobalLwwEntity, LwwFieldValue, State};
+
+/// The latest hash of the field a probe watches for changes.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ContentHash {
+    pub probe: String,
+    /// The sample field which was hashed, so that watching a different field starts afresh rather
+    /// than reporting a change.
+    #[serde(default)]
+    pub field: String,
+    pub hash: String,
+    /// The hash the content had before it last changed, unless it hasn't changed since it was first
+    /// observed.
+    pub previous_hash: Option<String>,
+    /// When the content took on its current hash.
+    pub since: DateTime<Utc>,
+    pub last_updated: DateTime<Utc>,
+}
+
+/// What a run observed about the content a probe watches.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContentChange {
+    /// Whether the content changed during this run.
+    pub changed: bool,
+    /// The hash the content had before this run, or before the change observed during this run.
+    pub previous_hash: Option<String>,
+    /// When the content took on its current hash.
+    pub since: DateTime<Utc>,
+}
+
+impl Versioned for ContentHash {
+    type Diff = ContentHash;
+
+    fn version(&self) -> u64 {
+        self.last_updated.timestamp_millis() as u64
+    }
+
+    fn diff(&self, version: u64) -> Option<Self::Diff> {
+        if self.version() > version {
+            Some(self.clone())
+        } else {
+            None
+        }
+    }
+
+    fn apply(&mut self, diff: &Self::Diff) {
+        // As for crons, the `(version, last_writer)` tiebreak is applied by the gossip store; this
+        // version-only form is the defensive fallback for the generic path.
+        if diff.version() > self.version() {
+            *self = diff.clone();
+        }
+    }
+}
+
+impl GlobalLwwEntity for ContentHash {
+    type Key = &'static str;
+    const TABLE: TableDefinition<'static, &'static str, LwwFieldValue> = CONTENT_HASHES_TABLE;
+
+    fn id_field(&self) -> String {
+        // The bare probe name is already the field of the probe's own record in its observer's
+        // partition, so the suffix (NUL can't appear in a YAML probe name) keeps the two apart.
+        format!("{}\0content_hash", self.probe)
+    }
+}
+
+/// Storage operations for the hashes of the content probes watch for changes.
+#[allow(async_fn_in_trait)]
+pub trait ChangeStore {
+    /// Records that the named probe observed its `field` holding content with the given `hash`
+    /// during the run which started at `run_started`, and reports whether that content has changed.
+    ///
+    /// A change is reported by every attempt of the run which first observed it, so that a retry
+    /// doesn't hide it, and by no later run. A hash recorded for a different field is disregarded.
+    async fn observe_content_hash(
+        &self,
+        probe: &str,
+        field: &str,
+        hash: &str,
+        run_started: DateTime<Utc>,
+    ) -> Result<ContentChange, Box<dyn Error>>;
+}
+
+impl State {
+    /// Drops the content hashes of probes which no longer appear in `live_probes` and whose hash
+    /// hasn't been written since `threshold`, so that a deleted or renamed probe's row ages out
+    /// like its probe records do. A live probe keeps its hash however long its content is stable.
+    pub(super) fn gc_content_hashes(
+        &self,
+        txn: &redb::WriteTransaction,
+        threshold: DateTime<Utc>,
+        live_probes: &HashSet<String>,
+    ) -> Result<u64, Box<dyn Error>> {
+        let mut dropped = 0u64;
+        txn.open_table(CONTENT_HASHES_TABLE)?.retain(|probe, (version, _writer, _data)| {
+            let last_updated = DateTime::from_timestamp_millis(version as i64).unwrap_or_default();
+            let keep = live_probes.contains(probe) || last_updated >= threshold;
+            dropped += u64::from(!keep);
+            keep
+        })?;
+
+        Ok(dropped)
+    }
+}
+
+impl ChangeStore for State {
+    async fn observe_content_hash(
+        &self,
+        probe: &str,
+        field: &str,
+        hash: &str,
+        run_started: DateTime<Utc>,
+    ) -> Result<ContentChange, Box<dyn Error>> {
+        let now = Utc::now();
+        let txn = self.database.begin_write()?;
+        let change = {
+            let mut table = txn.open_table(CONTENT_HASHES_TABLE)?;
+            let existing: Option<ContentHash> = match table.get(probe)? {
+                Some(value) => {
+                    let (_version, _last_writer, data) = value.value();
+                    Some(
+                        rmp_serde::from_slice(data)
+                            .map_err(|e| format!("Failed to parse content hash: {e:?}"))?,
+                    )
+                }
+                None => None,
+            };
+
+            let (record, change) = match existing.filter(|existing| existing.field == field) {
+                // This run already recorded the current content, so its earlier verdict stands.
+                Some(existing) if existing.hash == hash && existing.since >= run_started => {
+                    let change = ContentChange {
+                        changed: existing.previous_hash.is_some(),
+                        previous_hash: existing.previous_hash.clone(),
+                        since: existing.since,
+                    };
+                    (None, change)
+                }
+                Some(existing) if existing.hash == hash => {
+                    let change = ContentChange {
+                        changed: false,
+                        previous_hash: Some(existing.hash.clone()),
+                        since: existing.since,
+                    };
+                    (None, change)
+                }
+                existing => {
+                    let previous_hash = existing.map(|existing| existing.hash);
+                    let record = ContentHash {
+                        probe: probe.to_string(),
+                        field: field.to_string(),
+                        hash: hash.to_string(),
+                        previous_hash: previous_hash.clone(),
+                        since: now,
+                        last_updated: now,
+                    };
+                    let change = ContentChange {
+                        changed: previous_hash.is_some(),
+                        previous_hash,
+                        since: now,
+                    };
+                    (Some(record), change)
+                }
+            };
+
+            if let Some(record) = record {
+                let bytes = rmp_serde::to_vec_named(&record)
+                    .map_err(|e| format!("Failed to serialize content hash: {e:?}"))?;
+                let own_id: u128 = self.node_id.into();
+                table.insert(probe, (record.version(), own_id, bytes.as_slice()))?;
+            }
+
+            change
+        };
+        txn.commit()?;
+
+        Ok(change)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_observe_content_hash() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = State::test(dir.path().to_path_buf()).await;
+
+        let run = Utc::now();
+        let first = state.observe_content_hash("probe", "http.body", "a", run).await.unwrap();
+        assert!(!first.changed, "the first observation is not a change");
+        assert_eq!(first.previous_hash, None);
+
+        let run = Utc::now() + chrono::Duration::milliseconds(5);
+        let same = state.observe_content_hash("probe", "http.body", "a", run).await.unwrap();
+        assert!(!same.changed);
+        assert_eq!(same.previous_hash.as_deref(), Some("a"));
+        assert_eq!(same.since, first.since);
+
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        let run = Utc::now();
+        let changed = state.observe_content_hash("probe", "http.body", "b", run).await.unwrap();
+        assert!(changed.changed);
+        assert_eq!(changed.previous_hash.as_deref(), Some("a"));
+        assert!(changed.since > first.since);
+
+        let retried = state.observe_content_hash("probe", "http.body", "b", run).await.unwrap();
+        assert_eq!(retried, changed, "a retry within the same run still reports the change");
+
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        let next = state.observe_content_hash("probe", "http.body", "b", Utc::now()).await.unwrap();
+        assert!(!next.changed, "a later run doesn't report the change again");
+        assert_eq!(next.previous_hash.as_deref(), Some("b"));
+    }
+
+    #[tokio::test]
+    async fn watching_another_field_is_not_a_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = State::test(dir.path().to_path_buf()).await;
+
+        state.observe_content_hash("probe", "http.body", "a", Utc::now()).await.unwrap();
+
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        let switched = state.observe_content_hash("probe", "http.header.etag", "b", Utc::now()).await.unwrap();
+        assert!(!switched.changed, "the new field's first observation is not a change");
+        assert_eq!(switched.previous_hash, None);
+    }
+
+    #[tokio::test]
+    async fn gc_drops_hashes_of_deleted_probes() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = State::test(dir.path().to_path_buf()).await;
+
+        state.observe_content_hash("deleted", "http.body", "a", Utc::now()).await.unwrap();
+        state.observe_content_hash("live", "http.body", "a", Utc::now()).await.unwrap();
+
+        let live_probes = ["live".to_string()].into_iter().collect();
+        let txn = state.database.begin_write().unwrap();
+        let dropped = state.gc_content_hashes(&txn, Utc::now() - chrono::Duration::hours(1), &live_probes).unwrap();
+        assert_eq!(dropped, 0, "recently written hashes are kept");
+        let dropped = state.gc_content_hashes(&txn, Utc::now() + chrono::Duration::seconds(1), &live_probes).unwrap();
+        assert_eq!(dropped, 1, "only the deleted probe's hash is dropped");
+        txn.commit().unwrap();
+    }
+}
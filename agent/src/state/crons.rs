@@ -85,8 +85,10 @@ pub trait CronStore {
     ) -> Result<bool, Box<dyn Error>>;
 }
 
-impl CronStore for State {
-    async fn get_cron_states(&self) -> Result<HashMap<String, Cron>, Box<dyn Error>> {
+impl State {
+    /// The cluster-wide record of every cron, seeded from the local configuration and re-stamped with
+    /// its configuration echo fields.
+    pub(super) fn pool_cron_states(&self) -> Result<HashMap<String, Cron>, Box<dyn Error>> {
         let config = self.get_config();
 
         // Seed from local config so a cron renders before its first check-in.
@@ -121,6 +123,15 @@ impl CronStore for State {
 
         Ok(crons)
     }
+}
+
+impl CronStore for State {
+    async fn get_cron_states(&self) -> Result<HashMap<String, Cron>, Box<dyn Error>> {
+        let mut crons = self.pool_cron_states()?;
+        let mut probes = self.pool_probe_states(None)?;
+        self.resolve_dependencies(&mut probes, &mut crons);
+        Ok(crons)
+    }
 
     async fn record_cron_checkin(
         &self,
@@ -133,7 +144,7 @@ impl CronStore for State {
         };
 
         let txn = self.database.begin_write()?;
-        {
+        let finished_run = {
             let mut table = txn.open_table(CRON_TABLE)?;
 
             // Append to the latest global record this node holds (so history accumulates), falling
@@ -154,9 +165,22 @@ impl CronStore for State {
                 name,
                 (cron.version(), self.node_id.into(), rmp_serde::to_vec_named(&cron)?.as_slice()),
             )?;
-        }
+
+            cron.runs.last().filter(|_| checkin.status != CronStatus::Running).map(|run| run.duration)
+        };
         txn.commit()?;
 
+        // Only counted once the run is stored, so a failed write isn't reported as a run.
+        if let Some(duration) = finished_run {
+            crate::telemetry::record_cron_run(
+                name,
+                &cfg.tags,
+                &self.node_id.to_string(),
+                checkin.status.as_str(),
+                duration,
+            );
+        }
+
         Ok(true)
     }
 
@@ -232,6 +256,17 @@ impl CronStore for State {
         }
         txn.commit()?;
 
+        crate::telemetry::record_cron_run(
+            name,
+            &cfg.tags,
+            &self.node_id.to_string(),
+            match reason {
+                CronRunReason::Missed => "missed",
+                CronRunReason::Stuck => "stuck",
+            },
+            None,
+        );
+
         Ok(true)
     }
 }
@@ -262,6 +297,7 @@ mod tests {
             tags: HashMap::new(),
             visible: crate::config::default_visible_filter(),
             alerting: Default::default(),
+            depends_on: Vec::new(),
         }];
         *state.config.write().unwrap() = Arc::new(config);
         state
@@ -0,0 +1,332 @@
+//! Long-term probe history: the [`HistoryStore`] trait keeping tiered, downsampled availability
+//! history in the [`State`] redb store — hourly buckets for a week, daily rollups for 13 months and
+//! monthly rollups beyond that.
+//!
+//! Only the recent hourly history on each probe record is gossiped. The tiers are node-local and are
+//! derived from the pooled records on every GC pass, so each node rolls up the observations of every
+//! observer it has heard from, and a rollup is recomputed from the finer tier for as long as that
+//! tier still holds the whole period. Recomputing (rather than accumulating) keeps the rollups
+//! idempotent however often the pass runs.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+
+use chrono::{DateTime, Utc};
+use grey_api::{HistoryResolution, Mergeable, ProbeHistoryBucket};
+use redb::{ReadableTable, Table};
+use tracing::instrument;
+use tracing_batteries::prelude::*;
+
+use super::{PROBE_HISTORY_TABLE, State};
+
+/// How long the hourly tier is kept, measured from the end of each bucket so that the bucket for
+/// this hour a week ago is still around for the hour-of-week latency baseline.
+const HOURLY_RETENTION_DAYS: i64 = 7;
+
+/// How many months the daily tier is kept; monthly rollups are kept indefinitely.
+const DAILY_RETENTION_MONTHS: u32 = 13;
+
+/// The oldest bucket start still retained at `resolution`, or `None` if it is never dropped.
+fn retained_since(resolution: HistoryResolution, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    match resolution {
+        HistoryResolution::Hour => {
+            Some(now - chrono::Duration::days(HOURLY_RETENTION_DAYS) - chrono::Duration::hours(1))
+        }
+        HistoryResolution::Day => now.checked_sub_months(chrono::Months::new(DAILY_RETENTION_MONTHS)),
+        HistoryResolution::Month => None,
+    }
+}
+
+/// The finest resolution whose tier still holds buckets as old as `from`.
+pub fn finest_retained_resolution(from: DateTime<Utc>, now: DateTime<Utc>) -> HistoryResolution {
+    [HistoryResolution::Hour, HistoryResolution::Day]
+        .into_iter()
+        .find(|resolution| retained_since(*resolution, now).is_some_and(|since| from >= since))
+        .unwrap_or(HistoryResolution::Month)
+}
+
+/// Storage operations for probes' long-term availability history.
+#[allow(async_fn_in_trait)]
+pub trait HistoryStore {
+    /// Folds every probe's pooled recent history into the hourly tier, rolls the hourly tier up into
+    /// days and the daily tier up into months, and drops buckets which have aged out of their tier.
+    async fn rollup_history(&self) -> Result<(), Box<dyn Error>>;
+
+    /// The named probe's history at `resolution`, covering the buckets which start between `from`
+    /// and `to`, oldest first.
+    async fn get_probe_history(
+        &self,
+        probe_name: &str,
+        resolution: HistoryResolution,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<ProbeHistoryBucket>, Box<dyn Error>>;
+}
+
+impl HistoryStore for State {
+    #[instrument(name="state.history.rollup", skip(self), fields(otel.kind = "internal", node.id=%self.node_id), err(Debug))]
+    async fn rollup_history(&self) -> Result<(), Box<dyn Error>> {
+        let now = Utc::now();
+        let probes = self.pool_probe_states(None)?;
+
+        let txn = self.database.begin_write()?;
+        {
+            let mut table = txn.open_table(PROBE_HISTORY_TABLE)?;
+
+            for (name, probe) in &probes {
+                for bucket in &probe.history {
+                    merge_bucket(&mut table, name, HistoryResolution::Hour, bucket)?;
+                }
+
+                for (from, into) in [
+                    (HistoryResolution::Hour, HistoryResolution::Day),
+                    (HistoryResolution::Day, HistoryResolution::Month),
+                ] {
+                    let Some(since) = retained_since(from, now) else {
+                        continue;
+                    };
+
+                    // Only the periods which the finer tier still holds in full are recomputed; older
+                    // rollups were completed while they were recent and are left as they are.
+                    let mut periods: BTreeMap<DateTime<Utc>, Vec<ProbeHistoryBucket>> = BTreeMap::new();
+                    for bucket in read_buckets(&table, name, from, since, now)? {
+                        periods.entry(into.start_of(bucket.start_time)).or_default().push(bucket);
+                    }
+
+                    for (start, buckets) in periods.range(since..) {
+                        if let Some(rollup) = ProbeHistoryBucket::rollup(*start, buckets) {
+                            merge_bucket(&mut table, name, into, &rollup)?;
+                        }
+                    }
+                }
+            }
+
+            let hourly_since = retained_since(HistoryResolution::Hour, now).map(|t| t.timestamp());
+            let daily_since = retained_since(HistoryResolution::Day, now).map(|t| t.timestamp());
+            let mut dropped_buckets = 0u64;
+            table.retain(|(_probe, resolution, start), _data| {
+                let since = match resolution.parse::<HistoryResolution>() {
+                    Ok(HistoryResolution::Hour) => hourly_since,
+                    Ok(HistoryResolution::Day) => daily_since,
+                    _ => None,
+                };
+
+                let keep = since.is_none_or(|since| start >= since);
+                dropped_buckets += u64::from(!keep);
+                keep
+            })?;
+
+            if dropped_buckets > 0 {
+                debug!(name: "state.history.rollup", { dropped_buckets = %dropped_buckets }, "Dropped history buckets which aged out of their tier");
+            }
+        }
+
+        txn.commit()?;
+
+        Ok(())
+    }
+
+    async fn get_probe_history(
+        &self,
+        probe_name: &str,
+        resolution: HistoryResolution,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<ProbeHistoryBucket>, Box<dyn Error>> {
+        let mut buckets = BTreeMap::new();
+
+        {
+            let txn = self.database.begin_read()?;
+            // As for probe state, the table only exists once the first rollup has run.
+            if let Ok(table) = txn.open_table(PROBE_HISTORY_TABLE) {
+                for bucket in read_buckets(&table, probe_name, resolution, from, to)? {
+                    buckets.insert(bucket.start_time, bucket);
+                }
+            }
+        }
+
+        // The hourly tier is only refreshed on each GC pass, so the pooled recent history is laid over
+        // it to include the current hour.
+        if resolution == HistoryResolution::Hour
+            && let Some(probe) = self.pool_probe_states(Some(probe_name))?.remove(probe_name)
+        {
+            for bucket in probe.history {
+                if bucket.start_time < resolution.start_of(from) || bucket.start_time > to {
+                    continue;
+                }
+
+                buckets
+                    .entry(bucket.start_time)
+                    .and_modify(|existing: &mut ProbeHistoryBucket| existing.merge(&bucket))
+                    .or_insert(bucket);
+            }
+        }
+
+        Ok(buckets.into_values().collect())
+    }
+}
+
+/// Reads the buckets of the named probe's `resolution` tier which start between the start of the
+/// bucket containing `from` and `to`.
+fn read_buckets(
+    table: &impl ReadableTable<(&'static str, &'static str, i64), &'static [u8]>,
+    probe_name: &str,
+    resolution: HistoryResolution,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<Vec<ProbeHistoryBucket>, Box<dyn Error>> {
+    let tier = resolution.as_str();
+    let from = resolution.start_of(from).timestamp();
+
+    let mut buckets = Vec::new();
+    for entry in table.range((probe_name, tier, from)..=(probe_name, tier, to.timestamp()))? {
+        let (_key, data) = entry?;
+        buckets.push(
+            rmp_serde::from_slice(data.value())
+                .map_err(|e| format!("Failed to parse probe history bucket: {e:?}"))?,
+        );
+    }
+
+    Ok(buckets)
+}
+
+/// Merges `bucket` into the stored bucket with the same start in the named probe's `resolution`
+/// tier, writing it only if that changes the stored bucket.
+fn merge_bucket(
+    table: &mut Table<'_, (&'static str, &'static str, i64), &'static [u8]>,
+    probe_name: &str,
+    resolution: HistoryResolution,
+    bucket: &ProbeHistoryBucket,
+) -> Result<(), Box<dyn Error>> {
+    let key = (probe_name, resolution.as_str(), bucket.start_time.timestamp());
+
+    let existing: Option<ProbeHistoryBucket> = match table.get(key)? {
+        Some(data) => rmp_serde::from_slice(data.value()).ok(),
+        None => None,
+    };
+
+    let merged = match existing {
+        Some(existing) => {
+            let mut merged = existing.clone();
+            merged.merge(bucket);
+            if merged == existing {
+                return Ok(());
+            }
+            merged
+        }
+        None => bucket.clone(),
+    };
+
+    table.insert(key, rmp_serde::to_vec_named(&merged)?.as_slice())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::result::ProbeResult;
+    use crate::state::ProbeStore;
+
+    /// A probe's samples reach every tier of its history: the current hour straight from the pooled
+    /// record, and the daily and monthly rollups once a rollup pass has run.
+    #[tokio::test]
+    async fn rollups_preserve_observations() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = State::test(dir.path().to_path_buf()).await;
+        let probe_name = state.get_config().probes[0].name.clone();
+
+        let now = Utc::now();
+        for offset_hours in [3, 2, 1] {
+            let mut sample = ProbeResult::test();
+            sample.start_time = now - chrono::Duration::hours(offset_hours);
+            sample.pass = offset_hours != 2;
+            state.update_probe_state(&probe_name, sample).await.unwrap();
+        }
+
+        let from = now - chrono::Duration::days(1);
+        let hours = state
+            .get_probe_history(&probe_name, HistoryResolution::Hour, from, now)
+            .await
+            .unwrap();
+        assert!(hours.len() >= 3, "the pooled hours are visible before any rollup: {hours:?}");
+        assert!(
+            state
+                .get_probe_history(&probe_name, HistoryResolution::Day, from, now)
+                .await
+                .unwrap()
+                .is_empty(),
+            "days only appear once rolled up"
+        );
+
+        state.rollup_history().await.unwrap();
+        // A second pass must not double-count the observations it has already rolled up.
+        state.rollup_history().await.unwrap();
+
+        let total = |buckets: Vec<ProbeHistoryBucket>| {
+            buckets.iter().map(|b| b.total().total_samples).sum::<u64>()
+        };
+        let months = state
+            .get_probe_history(&probe_name, HistoryResolution::Month, from, now)
+            .await
+            .unwrap();
+        let days = state
+            .get_probe_history(&probe_name, HistoryResolution::Day, from, now)
+            .await
+            .unwrap();
+
+        assert_eq!(total(days.clone()), 4, "the day holds the test sample and the three above");
+        assert_eq!(total(months.clone()), 4);
+        assert!(days.iter().any(|d| !d.pass), "a failing hour fails its day");
+        assert_eq!(
+            days[0].observations.keys().collect::<Vec<_>>(),
+            vec![&state.node_id.to_string()],
+            "the per-observer aggregates are preserved"
+        );
+    }
+
+    #[tokio::test]
+    async fn expired_buckets_are_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = State::test(dir.path().to_path_buf()).await;
+
+        let now = Utc::now();
+        let bucket = |start_time| ProbeHistoryBucket {
+            start_time,
+            pass: true,
+            degraded: false,
+            message: String::new(),
+            validations: Default::default(),
+            observations: Default::default(),
+        };
+
+        let txn = state.database.begin_write().unwrap();
+        {
+            let mut table = txn.open_table(PROBE_HISTORY_TABLE).unwrap();
+            let old_hour = HistoryResolution::Hour.start_of(now - chrono::Duration::days(10));
+            let old_day = HistoryResolution::Day.start_of(now - chrono::Duration::days(500));
+            let old_month = HistoryResolution::Month.start_of(now - chrono::Duration::days(900));
+            merge_bucket(&mut table, "old", HistoryResolution::Hour, &bucket(old_hour)).unwrap();
+            merge_bucket(&mut table, "old", HistoryResolution::Day, &bucket(old_day)).unwrap();
+            merge_bucket(&mut table, "old", HistoryResolution::Month, &bucket(old_month)).unwrap();
+
+            let last_week = HistoryResolution::Hour.start_of(now - chrono::Duration::weeks(1));
+            merge_bucket(&mut table, "recent", HistoryResolution::Hour, &bucket(last_week)).unwrap();
+        }
+        txn.commit().unwrap();
+
+        state.rollup_history().await.unwrap();
+
+        let since = now - chrono::Duration::days(1000);
+        for (resolution, kept) in [
+            (HistoryResolution::Hour, 0),
+            (HistoryResolution::Day, 0),
+            (HistoryResolution::Month, 1),
+        ] {
+            let buckets = state.get_probe_history("old", resolution, since, now).await.unwrap();
+            assert_eq!(buckets.len(), kept, "{resolution} buckets past their retention are dropped");
+        }
+
+        let buckets = state.get_probe_history("recent", HistoryResolution::Hour, since, now).await.unwrap();
+        assert_eq!(buckets.len(), 1, "this hour last week is kept until the hour is over");
+    }
+}
@@ -1,4 +1,5 @@
 use std::{
+    collections::{HashMap, HashSet},
     net::SocketAddr,
     path::PathBuf,
     sync::{Arc, Mutex, RwLock},
@@ -11,21 +12,31 @@ use tracing_batteries::prelude::*;
 
 use crate::{
     Config,
-    cluster::{self, ClusterStateDigest, Membership, MembershipConfig, NodeID, Versioned},
+    cluster::{self, ClusterStateDigest, GossipStats, Membership, MembershipConfig, NodeID, Versioned},
 };
 use crate::cluster::GossipStore;
 
 // Probe-state, cron-state and incident storage live in their own sub-modules, as traits implemented
 // over this `State`; the gossip/cluster plumbing remains here in the core store.
+mod archive;
+mod changes;
 mod crons;
+mod history;
 mod incidents;
 mod probes;
 mod replicated;
+mod samples;
+mod storage;
 
+pub use archive::{ArchiveFormat, ArchiveStore, StateArchive, export_database};
+pub use changes::{ChangeStore, ContentChange, ContentHash};
 pub use crons::CronStore;
+pub use history::{HistoryStore, finest_retained_resolution};
 pub use incidents::{CasOutcome, DEFAULT_INCIDENT_PAGE, IncidentStore};
 pub use probes::ProbeStore;
 pub use replicated::{GlobalLwwEntity, LwwFieldValue, ReplicatedEntity};
+pub use samples::SampleStore;
+pub use storage::{ScriptStorageStore, StorageEntry, StorageKind};
 
 // Maps a (NodeID, Probe Name) to a tuple of (Version, MsgPack Snapshot). Shared with the probe and
 // gossip sub-modules. Probes are the one per-observer entity: every node keeps its own observation of
@@ -47,6 +58,29 @@ pub(crate) const INCIDENTS_TABLE: TableDefinition<u64, LwwFieldValue> =
 pub(crate) const INCIDENT_UPDATES_TABLE: TableDefinition<u128, LwwFieldValue> =
     TableDefinition::new("incidents.updates");
 
+// The persistent script stores, keyed by `(probe name, key)`. `localStorage` holds node-local
+// msgpack `StorageEntry` snapshots; `clusterStorage` is another global-LWW table.
+pub(crate) const LOCAL_STORAGE_TABLE: TableDefinition<(&str, &str), &[u8]> =
+    TableDefinition::new("scripts.local_storage");
+pub(crate) const CLUSTER_STORAGE_TABLE: TableDefinition<(&str, &str), LwwFieldValue> =
+    TableDefinition::new("scripts.cluster_storage");
+
+// The latest hash of the field each change-detecting probe watches, keyed by probe name; another
+// global-LWW table.
+pub(crate) const CONTENT_HASHES_TABLE: TableDefinition<&str, LwwFieldValue> =
+    TableDefinition::new("probes.content_hashes");
+
+// Each probe's long-term availability history, keyed by `(probe name, resolution, bucket start)` and
+// valued by a msgpack `ProbeHistoryBucket`. Node-local: the tiers are rolled up from the gossiped
+// probe records rather than being replicated themselves.
+pub(crate) const PROBE_HISTORY_TABLE: TableDefinition<(&str, &str, i64), &[u8]> =
+    TableDefinition::new("probes.history");
+
+// The raw results of each probe which keeps a sample log, keyed by `(probe name, start time in Unix
+// microseconds)` and valued by a msgpack `ProbeSample`. Node-local: each node logs its own runs.
+pub(crate) const PROBE_SAMPLES_TABLE: TableDefinition<(&str, i64), &[u8]> =
+    TableDefinition::new("probes.samples");
+
 // Stores this instance's persistent identity so that a restart resumes the same NodeID (and keeps
 // advertising its existing probe state) rather than appearing as a brand-new node.
 const INSTANCE_METADATA_TABLE: TableDefinition<&str, u128> =
@@ -70,6 +104,9 @@ pub struct State {
     /// **not** persisted to the database — they are rebuilt from seed peers on restart — so this is
     /// shared (read-only for the API) rather than living in redb.
     members: Arc<Membership<NodeID, SocketAddr>>,
+
+    /// Running totals of the gossip traffic this node has exchanged, exported by the metrics endpoint.
+    gossip_stats: Arc<GossipStats>,
 }
 
 impl State {
@@ -121,6 +158,7 @@ impl State {
             node_id,
             database,
             members,
+            gossip_stats: Arc::new(GossipStats::default()),
         })
     }
 
@@ -145,6 +183,16 @@ impl State {
         self.members.clone()
     }
 
+    /// The gossip client's running traffic totals, shared with the metrics endpoint.
+    pub fn gossip_stats(&self) -> Arc<GossipStats> {
+        self.gossip_stats.clone()
+    }
+
+    /// This node's persistent identity, as advertised to its peers.
+    pub fn node_id(&self) -> NodeID {
+        self.node_id
+    }
+
     /// Loads this instance's persistent [`NodeID`] from the database, generating and storing a fresh
     /// one on first run. Persisting the identity means a restart (via [`State::new`]) resumes the
     /// same node — continuing to advertise its probe state — instead of appearing as a new node
@@ -194,7 +242,8 @@ impl State {
     pub async fn reload(&self) -> Result<(), Box<dyn Error>> {
         let last_modified = *self.config_last_modified.lock().unwrap();
         if let Some((config, modified)) =
-            Config::load_if_modified_since(&self.config_path, last_modified).await?
+            Config::load_if_modified_since(&self.config_path, last_modified, &self.get_config().sources)
+                .await?
         {
             info!("Configuration file changed, reloading.");
             *self.config.write().unwrap() = Arc::new(config);
@@ -208,6 +257,44 @@ impl State {
         self.config.read().unwrap().clone()
     }
 
+    /// Stamps `blocked_by` onto every pooled probe and cron: the entries of its configured
+    /// `depends_on` which currently read as unhealthy. A dependency which is itself blocked still
+    /// reads as unhealthy, so an outage is attributed to the entity at the root of the chain.
+    fn resolve_dependencies(
+        &self,
+        probes: &mut HashMap<String, Probe>,
+        crons: &mut HashMap<String, Cron>,
+    ) {
+        let config = self.get_config();
+        let now = chrono::Utc::now();
+
+        let unhealthy: HashSet<String> = probes
+            .iter()
+            .filter(|(_, probe)| !probe.passing())
+            .map(|(name, _)| name.clone())
+            .chain(
+                crons
+                    .iter()
+                    .filter(|(_, cron)| !cron.passing(now, cron.window()))
+                    .map(|(name, _)| name.clone()),
+            )
+            .collect();
+
+        for (name, depends_on) in config.dependencies() {
+            let blocked_by: Vec<String> = depends_on
+                .iter()
+                .filter(|dependency| unhealthy.contains(*dependency))
+                .cloned()
+                .collect();
+
+            if let Some(probe) = probes.get_mut(name) {
+                probe.blocked_by = blocked_by;
+            } else if let Some(cron) = crons.get_mut(name) {
+                cron.blocked_by = blocked_by;
+            }
+        }
+    }
+
     /// Replaces the in-memory configuration. Test-only helper for exercising code paths that read
     /// `get_config()` without going through a config-file reload.
     #[cfg(test)]
@@ -494,6 +581,8 @@ impl GossipStore for State {
         digest_lww::<Cron>(&txn, &mut digest)?;
         digest_lww::<Incident>(&txn, &mut digest)?;
         digest_lww::<IncidentUpdate>(&txn, &mut digest)?;
+        digest_lww::<StorageEntry>(&txn, &mut digest)?;
+        digest_lww::<ContentHash>(&txn, &mut digest)?;
 
         trace!(name: "state.digest", { host.node_id = %self.node_id, digest = %digest }, "Composed new cluster state digest.");
 
@@ -514,6 +603,8 @@ impl GossipStore for State {
         emit_lww_table_diffs::<Cron>(&txn, &digest, &mut delta, ReplicatedEntity::Cron)?;
         emit_lww_table_diffs::<Incident>(&txn, &digest, &mut delta, ReplicatedEntity::Incident)?;
         emit_lww_table_diffs::<IncidentUpdate>(&txn, &digest, &mut delta, ReplicatedEntity::IncidentUpdate)?;
+        emit_lww_table_diffs::<StorageEntry>(&txn, &digest, &mut delta, ReplicatedEntity::StorageEntry)?;
+        emit_lww_table_diffs::<ContentHash>(&txn, &digest, &mut delta, ReplicatedEntity::ContentHash)?;
 
         trace!(name: "state.diff", { host.node_id = %self.node_id, digest = %digest, delta = ?delta }, "Composed new cluster state diff.");
 
@@ -531,6 +622,8 @@ impl GossipStore for State {
             let mut cron_table = txn.open_table(CRON_TABLE)?;
             let mut incident_table = txn.open_table(INCIDENTS_TABLE)?;
             let mut update_table = txn.open_table(INCIDENT_UPDATES_TABLE)?;
+            let mut storage_table = txn.open_table(CLUSTER_STORAGE_TABLE)?;
+            let mut content_hash_table = txn.open_table(CONTENT_HASHES_TABLE)?;
 
             let own_id: u128 = self.node_id.into();
 
@@ -601,6 +694,27 @@ impl GossipStore for State {
                                 update_table.insert(key, (incoming.version(), peer_id, bytes.as_slice()))?;
                             }
                         }
+                        ReplicatedEntity::StorageEntry(incoming) => {
+                            let key = (incoming.scope.as_str(), incoming.key.as_str());
+                            let existing = storage_table
+                                .get(key)?
+                                .map(|g| { let (v, w, _) = g.value(); (v, w) });
+                            if lww_supersedes(existing, (incoming.version(), peer_id)) {
+                                let bytes = rmp_serde::to_vec_named(&incoming)
+                                    .map_err(|e| format!("Failed to serialize storage entry for update: {e:?}"))?;
+                                storage_table.insert(key, (incoming.version(), peer_id, bytes.as_slice()))?;
+                            }
+                        }
+                        ReplicatedEntity::ContentHash(incoming) => {
+                            let existing = content_hash_table
+                                .get(incoming.probe.as_str())?
+                                .map(|g| { let (v, w, _) = g.value(); (v, w) });
+                            if lww_supersedes(existing, (incoming.version(), peer_id)) {
+                                let bytes = rmp_serde::to_vec_named(&incoming)
+                                    .map_err(|e| format!("Failed to serialize content hash for update: {e:?}"))?;
+                                content_hash_table.insert(incoming.probe.as_str(), (incoming.version(), peer_id, bytes.as_slice()))?;
+                            }
+                        }
                     }
                 }
             }
@@ -628,6 +742,8 @@ mod tests {
             observations: HashMap::new(),
             streak: grey_api::Streak::default(),
             debounce: None,
+            interval: None,
+            blocked_by: Vec::new(),
             retired: false,
         }
     }
@@ -699,6 +815,7 @@ mod tests {
             tags: HashMap::new(),
             visible: crate::config::default_visible_filter(),
             alerting: Default::default(),
+            depends_on: Vec::new(),
         }];
         *state.config.write().unwrap() = Arc::new(config);
         state
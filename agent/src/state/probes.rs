@@ -2,7 +2,7 @@
 //! store, plus the cluster [`Versioned`] implementation for probes. Kept separate from the
 //! underlying store, mirroring the incident storage split.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
 
 use grey_api::{Mergeable, Probe};
@@ -14,7 +14,7 @@ use crate::cluster::Versioned;
 use crate::result::ProbeResult;
 
 use super::{
-    PROBES_TABLE, CRON_TABLE, ProbeState, State,
+    PROBES_TABLE, CRON_TABLE, HistoryStore, ProbeState, SampleStore, State,
     gc_lww_table,
 };
 
@@ -24,6 +24,10 @@ pub trait ProbeStore {
     /// The pooled, cluster-merged probe states keyed by probe name.
     async fn get_probe_states(&self) -> Result<HashMap<String, Probe>, Box<dyn Error>>;
 
+    /// The pooled, cluster-merged state of a single probe, or `None` if it is neither configured nor
+    /// observed by any node. Only this probe is pooled, so its `blocked_by` is left unresolved.
+    async fn get_probe_state(&self, probe_name: &str) -> Result<Option<Probe>, Box<dyn Error>>;
+
     /// Persists the configured probe metadata for this node.
     async fn update_probe_config(&self, probe: &crate::Probe) -> Result<(), Box<dyn Error>>;
 
@@ -46,12 +50,15 @@ pub trait ProbeStore {
     async fn gc_loop(&self);
 }
 
-impl ProbeStore for State {
-    async fn get_probe_states(&self) -> Result<HashMap<String, Probe>, Box<dyn Error>> {
+impl State {
+    /// Pools every node's observations of the probes selected by `only` (all probes when `None`) into
+    /// a single record per probe, seeded from the local configuration.
+    pub(super) fn pool_probe_states(&self, only: Option<&str>) -> Result<HashMap<String, Probe>, Box<dyn Error>> {
         let config = self.get_config();
+        let selected = |name: &str| only.is_none_or(|only| only == name);
 
         let mut histories = HashMap::new();
-        for probe in config.probes.iter() {
+        for probe in config.probes.iter().filter(|p| selected(&p.name)) {
             histories.insert(probe.name.clone(), probe.into());
         }
 
@@ -64,6 +71,10 @@ impl ProbeStore for State {
             for entry in table.iter()?.filter_map(|r| r.ok()) {
                 let (key, value) = entry;
                 let (_node_id, probe_name) = key.value();
+                if !selected(&probe_name) {
+                    continue;
+                }
+
                 let (_, data) = value.value();
                 if let Ok(snapshot) = rmp_serde::from_slice::<ProbeState>(data) {
                     // A retired record is an observer's tombstone for a probe it no longer runs; it
@@ -86,14 +97,33 @@ impl ProbeStore for State {
         // The alerting debounce (the streak recovery window) is authoritative locally for display
         // and detection — re-stamp it so a peer's stale config can never override the operator's
         // view. Mirrors the cron config-echo in `get_cron_states`.
+        //
+        // The interval in effect is derived from the pooled streak in the same way the runner derives
+        // it, so the API reports the accelerated failing interval exactly while the runner uses it.
+        let now = chrono::Utc::now();
         for probe in config.probes.iter() {
             if let Some(pooled) = histories.get_mut(&probe.name) {
                 pooled.debounce = Some(probe.alerting.debounce_std());
+                pooled.interval =
+                    probe.policy.effective_interval(&pooled.streak, now, pooled.window());
             }
         }
 
         Ok(histories)
     }
+}
+
+impl ProbeStore for State {
+    async fn get_probe_states(&self) -> Result<HashMap<String, Probe>, Box<dyn Error>> {
+        let mut probes = self.pool_probe_states(None)?;
+        let mut crons = self.pool_cron_states()?;
+        self.resolve_dependencies(&mut probes, &mut crons);
+        Ok(probes)
+    }
+
+    async fn get_probe_state(&self, probe_name: &str) -> Result<Option<Probe>, Box<dyn Error>> {
+        Ok(self.pool_probe_states(Some(probe_name))?.remove(probe_name))
+    }
 
     async fn update_probe_config(&self, probe: &crate::Probe) -> Result<(), Box<dyn Error>> {
         let txn = self.database.begin_write()?;
@@ -258,6 +288,29 @@ impl ProbeStore for State {
             if dropped_crons > 0 {
                 info!(name: "state.gc.summary", { dropped_crons = %dropped_crons }, "Dropped stale cron records");
             }
+
+            // Script storage drops entries as soon as their TTL passes, while the cluster store's
+            // removal tombstones age out on the same expiry as crons. Live entries without a TTL
+            // are kept until the script removes them, unless their probe's records have all been
+            // dropped above and the entries have gone just as long without a write.
+            let live_probes: HashSet<String> = table_fields
+                .iter()?
+                .filter_map(|r| r.ok())
+                .map(|(key, _value)| key.value().1)
+                .collect();
+            let dropped_storage_entries =
+                self.gc_storage(&txn, chrono::Utc::now(), history_expiry_threshold, &live_probes)?;
+
+            if dropped_storage_entries > 0 {
+                info!(name: "state.gc.summary", { dropped_storage_entries = %dropped_storage_entries }, "Dropped expired script storage entries");
+            }
+
+            // A probe's content hash is kept for as long as any node still holds a record of it.
+            let dropped_content_hashes = self.gc_content_hashes(&txn, history_expiry_threshold, &live_probes)?;
+
+            if dropped_content_hashes > 0 {
+                info!(name: "state.gc.summary", { dropped_content_hashes = %dropped_content_hashes }, "Dropped content hashes of deleted probes");
+            }
         }
 
         txn.commit()?;
@@ -271,6 +324,14 @@ impl ProbeStore for State {
                 warn!("Failed to perform state GC: {:?}", err);
             }
 
+            if let Err(err) = self.rollup_history().await {
+                warn!("Failed to roll up probe history: {:?}", err);
+            }
+
+            if let Err(err) = self.prune_probe_samples().await {
+                warn!("Failed to prune probe sample logs: {:?}", err);
+            }
+
             tokio::time::sleep(self.get_config().cluster.gc_interval).await;
         }
     }
@@ -303,6 +364,8 @@ impl Versioned for Probe {
                 observations: self.observations.clone(),
                 streak: self.streak.clone(),
                 debounce: self.debounce,
+                interval: self.interval,
+                blocked_by: self.blocked_by.clone(),
                 retired: self.retired,
             })
         } else {
@@ -331,6 +394,8 @@ mod tests {
             observations: HashMap::new(),
             streak: grey_api::Streak::default(),
             debounce: None,
+            interval: None,
+            blocked_by: Vec::new(),
             retired: false,
         }
     }
@@ -370,6 +435,36 @@ mod tests {
         assert!(stored.retired, "the record must be tombstoned rather than deleted");
     }
 
+    /// A failing probe behind a failing dependency is stamped with the dependency it is blocked by,
+    /// whether the dependency is a probe or a cron.
+    #[tokio::test]
+    async fn failing_dependencies_are_stamped_onto_dependents() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = State::test(dir.path().to_path_buf()).await;
+
+        let mut config = Config::test(&dir.path().to_path_buf());
+        let mut lb = config.probes[0].clone();
+        lb.name = "lb".into();
+        let mut web = config.probes[0].clone();
+        web.name = "web".into();
+        web.depends_on = vec!["lb".into()];
+        config.probes = vec![lb, web];
+        state.set_config_for_test(config);
+
+        // A sustained outage on the load balancer, so its debounced health reads failing.
+        let now = chrono::Utc::now();
+        for offset_mins in [8, 4, 0] {
+            let mut sample = ProbeResult::new();
+            sample.start_time = now - chrono::Duration::minutes(offset_mins);
+            sample.pass = false;
+            state.update_probe_state("lb", sample).await.unwrap();
+        }
+
+        let probes = state.get_probe_states().await.unwrap();
+        assert!(probes["lb"].blocked_by.is_empty());
+        assert_eq!(probes["web"].blocked_by, vec!["lb".to_string()]);
+    }
+
     /// Retirement is per-observer: a peer that still runs the probe keeps it visible here (the
     /// headless-worker topology), and re-adding it locally clears the tombstone without losing the
     /// history that was recorded before the removal.
@@ -430,6 +525,69 @@ mod tests {
         assert!(earlier.diff(earlier.version()).is_none(), "an unchanged probe has nothing to diff");
     }
 
+    /// The gossiped size of a probe record holding two days of hourly buckets from `observers`
+    /// second-by-second observers, each with a latency sketch using every one of its bins and more
+    /// checks than are counted.
+    fn full_record_size(observers: usize) -> usize {
+        let now = chrono::Utc::now();
+
+        let checks: HashMap<String, grey_api::ValidationResult> = (0..grey_api::MAX_CHECKS * 2)
+            .map(|i| {
+                let check = format!("http.body contains \"a reasonably long expected string #{i}\"");
+                (check, grey_api::ValidationResult::pass())
+            })
+            .collect();
+        let mut observation = grey_api::Observation::default();
+        for i in 0..3600 {
+            observation.add_sample(i % 10 != 0, 1, std::time::Duration::from_secs_f64(0.005 * 1.002f64.powi(i)));
+            observation.add_checks(&checks);
+        }
+
+        let observations: HashMap<String, grey_api::Observation> =
+            (0..observers).map(|i| (format!("{i:032x}"), observation.clone())).collect();
+        let mut probe = probe_at("a.fully.populated.probe", now);
+        probe.history = (0..48)
+            .rev()
+            .map(|hours| grey_api::ProbeHistoryBucket {
+                start_time: now - chrono::Duration::hours(hours),
+                pass: true,
+                degraded: false,
+                message: String::new(),
+                validations: HashMap::new(),
+                observations: observations.clone(),
+            })
+            .collect();
+        probe.observations = observations
+            .keys()
+            .map(|observer| {
+                let mut total = grey_api::Observation::default();
+                for bucket in &probe.history {
+                    total.merge(&bucket.observations[observer]);
+                }
+                (observer.clone(), total)
+            })
+            .collect();
+
+        let entry = crate::state::ReplicatedEntity::Probe(probe.diff(0).expect("a diff"));
+        rmp_serde::to_vec(&entry).unwrap().len()
+    }
+
+    /// A probe record is gossiped as a single entry which can't be split across datagrams, so even a
+    /// full one (whose latency sketches and check counts are bounded) must fit a datagram with room
+    /// to spare for the message's metadata, framing and encryption. A node's own record, which only it
+    /// observes, must fit the default message MTU.
+    #[test]
+    fn a_full_probe_record_fits_a_gossip_datagram() {
+        let size = full_record_size(8);
+        assert!(size + 1024 <= crate::cluster::MAX_DATAGRAM_SIZE, "eight observers gossip as {size} bytes");
+
+        let size = full_record_size(1);
+        assert!(
+            size + 1024 <= crate::config::ClusterConfig::default().message_mtu,
+            "a single observer gossips as {size} bytes"
+        );
+    }
+
     /// GC must interpret the stored version as milliseconds; otherwise a millisecond timestamp read
     /// as seconds lands ~50000 years in the future and probes would never expire.
     #[tokio::test]
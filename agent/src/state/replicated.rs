@@ -8,7 +8,8 @@
 //! Two families share this enum but are keyed and merged differently:
 //! - **Per-observer** ([`Probe`]): stored under `(node_id, name)`, the gossip partition is the node
 //!   component of that key, and records merge via their CRDT [`Versioned::apply`].
-//! - **Global last-writer-wins** ([`GlobalLwwEntity`]: [`Cron`], [`Incident`], [`IncidentUpdate`]):
+//! - **Global last-writer-wins** ([`GlobalLwwEntity`]: [`Cron`], [`Incident`], [`IncidentUpdate`],
+//!   [`StorageEntry`], [`ContentHash`]):
 //!   stored as a single row keyed by the entity id alone, the gossip partition is the entity's
 //!   `last_writer` (carried in the redb value, not the key), and conflicts resolve by the total order
 //!   `(version, last_writer)`.
@@ -19,6 +20,8 @@ use serde::{Deserialize, Serialize};
 
 use crate::cluster::Versioned;
 
+use super::{ContentHash, StorageEntry};
+
 /// The redb value shared by every global-LWW entity table: `(version, last_writer, msgpack snapshot)`.
 /// `version` is the entity's wall-clock last-modified time in milliseconds; `last_writer` is the node
 /// that produced this version — the gossip partition the row is advertised under, and the LWW
@@ -54,6 +57,8 @@ pub enum ReplicatedEntity {
     Cron(Cron),
     Incident(Incident),
     IncidentUpdate(IncidentUpdate),
+    StorageEntry(StorageEntry),
+    ContentHash(ContentHash),
 }
 
 impl Versioned for ReplicatedEntity {
@@ -67,6 +72,8 @@ impl Versioned for ReplicatedEntity {
             ReplicatedEntity::Cron(cron) => cron.version(),
             ReplicatedEntity::Incident(incident) => incident.version(),
             ReplicatedEntity::IncidentUpdate(update) => update.version(),
+            ReplicatedEntity::StorageEntry(entry) => entry.version(),
+            ReplicatedEntity::ContentHash(hash) => hash.version(),
         }
     }
 
@@ -80,6 +87,12 @@ impl Versioned for ReplicatedEntity {
             ReplicatedEntity::IncidentUpdate(update) => {
                 update.diff(version).map(ReplicatedEntity::IncidentUpdate)
             }
+            ReplicatedEntity::StorageEntry(entry) => {
+                entry.diff(version).map(ReplicatedEntity::StorageEntry)
+            }
+            ReplicatedEntity::ContentHash(hash) => {
+                hash.diff(version).map(ReplicatedEntity::ContentHash)
+            }
         }
     }
 
@@ -96,6 +109,12 @@ impl Versioned for ReplicatedEntity {
                 ReplicatedEntity::IncidentUpdate(update),
                 ReplicatedEntity::IncidentUpdate(incoming),
             ) => update.apply(incoming),
+            (ReplicatedEntity::StorageEntry(entry), ReplicatedEntity::StorageEntry(incoming)) => {
+                entry.apply(incoming)
+            }
+            (ReplicatedEntity::ContentHash(hash), ReplicatedEntity::ContentHash(incoming)) => {
+                hash.apply(incoming)
+            }
             // A single (node, field) entry never changes entity type, so a mismatched pair cannot
             // occur in practice; ignore it defensively rather than panicking on malformed input.
             _ => {}
@@ -122,6 +141,8 @@ mod tests {
             observations: HashMap::new(),
             streak: Default::default(),
             debounce: None,
+            interval: None,
+            blocked_by: Vec::new(),
             retired: false,
         }
     }
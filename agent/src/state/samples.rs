@@ -0,0 +1,264 @@
+//! Probe sample logs: the [`SampleStore`] trait keeping the raw results of the probes which configure
+//! a `samples` log in the [`State`] redb store, bounded by their count and age limits.
+//!
+//! Sample logs are node-local and are never gossiped: each node keeps the results of the runs it
+//! made itself, which is what makes them useful for pinning down exactly when (and from where) an
+//! intermittent failure happened.
+
+use std::error::Error;
+
+use chrono::{DateTime, Utc};
+use grey_api::ProbeSample;
+use redb::{ReadableDatabase, ReadableTable, Table};
+use tracing::instrument;
+use tracing_batteries::prelude::*;
+
+use super::{PROBE_SAMPLES_TABLE, State};
+use crate::config::SampleLogConfig;
+
+/// Storage operations for probes' raw sample logs.
+#[allow(async_fn_in_trait)]
+pub trait SampleStore {
+    /// Appends `sample` to the named probe's log, then drops whichever of its oldest samples exceed
+    /// the log's `retention` limits.
+    async fn record_probe_sample(
+        &self,
+        probe_name: &str,
+        sample: &ProbeSample,
+        retention: &SampleLogConfig,
+    ) -> Result<(), Box<dyn Error>>;
+
+    /// The named probe's samples which started between `from` and `to`, oldest first. With a `limit`,
+    /// only the most recent `limit` of them are returned.
+    async fn get_probe_samples(
+        &self,
+        probe_name: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        limit: Option<usize>,
+    ) -> Result<Vec<ProbeSample>, Box<dyn Error>>;
+
+    /// Drops the samples of probes which no longer keep a sample log, along with any which have aged
+    /// out of their probe's log since it was last written to.
+    async fn prune_probe_samples(&self) -> Result<(), Box<dyn Error>>;
+}
+
+impl SampleStore for State {
+    async fn record_probe_sample(
+        &self,
+        probe_name: &str,
+        sample: &ProbeSample,
+        retention: &SampleLogConfig,
+    ) -> Result<(), Box<dyn Error>> {
+        let txn = self.database.begin_write()?;
+        {
+            let mut table = txn.open_table(PROBE_SAMPLES_TABLE)?;
+            table.insert(
+                (probe_name, sample.start_time.timestamp_micros()),
+                rmp_serde::to_vec_named(sample)?.as_slice(),
+            )?;
+
+            trim(&mut table, probe_name, retention, Utc::now())?;
+        }
+        txn.commit()?;
+
+        Ok(())
+    }
+
+    async fn get_probe_samples(
+        &self,
+        probe_name: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        limit: Option<usize>,
+    ) -> Result<Vec<ProbeSample>, Box<dyn Error>> {
+        let txn = self.database.begin_read()?;
+        // The table only exists once the first sample has been recorded.
+        let Ok(table) = txn.open_table(PROBE_SAMPLES_TABLE) else {
+            return Ok(Vec::new());
+        };
+
+        let range = (probe_name, from.timestamp_micros())..=(probe_name, to.timestamp_micros());
+        let mut samples = Vec::new();
+        for entry in table.range(range)?.rev() {
+            if limit.is_some_and(|limit| samples.len() >= limit) {
+                break;
+            }
+
+            let (_key, data) = entry?;
+            samples.push(
+                rmp_serde::from_slice::<ProbeSample>(data.value())
+                    .map_err(|e| format!("Failed to parse probe sample: {e:?}"))?,
+            );
+        }
+
+        samples.reverse();
+        Ok(samples)
+    }
+
+    #[instrument(name="state.samples.prune", skip(self), fields(otel.kind = "internal", node.id=%self.node_id), err(Debug))]
+    async fn prune_probe_samples(&self) -> Result<(), Box<dyn Error>> {
+        let config = self.get_config();
+        let now = Utc::now();
+
+        let txn = self.database.begin_write()?;
+        {
+            let mut table = txn.open_table(PROBE_SAMPLES_TABLE)?;
+
+            let mut logged: Vec<String> = Vec::new();
+            for entry in table.iter()? {
+                let (key, _data) = entry?;
+                let (probe_name, _start) = key.value();
+                if logged.last().is_none_or(|last| last != probe_name) {
+                    logged.push(probe_name.to_string());
+                }
+            }
+
+            let mut dropped_samples = 0usize;
+            for probe_name in &logged {
+                let retention = config
+                    .probes
+                    .iter()
+                    .find(|probe| &probe.name == probe_name)
+                    .and_then(|probe| probe.samples.as_ref());
+
+                dropped_samples += match retention {
+                    Some(retention) => trim(&mut table, probe_name, retention, now)?,
+                    None => remove_before(&mut table, probe_name, i64::MAX)?,
+                };
+            }
+
+            if dropped_samples > 0 {
+                debug!(name: "state.samples.prune", { dropped_samples = %dropped_samples }, "Dropped probe samples which aged out of their log");
+            }
+        }
+        txn.commit()?;
+
+        Ok(())
+    }
+}
+
+/// Drops the named probe's samples which exceed `retention` at `now`, oldest first, returning how many
+/// were dropped.
+fn trim(
+    table: &mut Table<(&'static str, i64), &'static [u8]>,
+    probe_name: &str,
+    retention: &SampleLogConfig,
+    now: DateTime<Utc>,
+) -> Result<usize, Box<dyn Error>> {
+    let mut dropped = match retention.retained_since(now) {
+        Some(since) => remove_before(table, probe_name, since.timestamp_micros())?,
+        None => 0,
+    };
+
+    if let Some(count) = retention.count {
+        let stored = table.range((probe_name, i64::MIN)..=(probe_name, i64::MAX))?.count();
+        if stored > count {
+            let excess: Vec<i64> = table
+                .range((probe_name, i64::MIN)..=(probe_name, i64::MAX))?
+                .take(stored - count)
+                .map(|entry| entry.map(|(key, _data)| key.value().1))
+                .collect::<Result<_, _>>()?;
+
+            for start in &excess {
+                table.remove((probe_name, *start))?;
+            }
+            dropped += excess.len();
+        }
+    }
+
+    Ok(dropped)
+}
+
+/// Drops the named probe's samples which started before `before` (in Unix microseconds), returning
+/// how many were dropped.
+fn remove_before(
+    table: &mut Table<(&'static str, i64), &'static [u8]>,
+    probe_name: &str,
+    before: i64,
+) -> Result<usize, Box<dyn Error>> {
+    let expired: Vec<i64> = table
+        .range((probe_name, i64::MIN)..(probe_name, before))?
+        .map(|entry| entry.map(|(key, _data)| key.value().1))
+        .collect::<Result<_, _>>()?;
+
+    for start in &expired {
+        table.remove((probe_name, *start))?;
+    }
+
+    Ok(expired.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::result::ProbeResult;
+
+    fn sample_at(start_time: DateTime<Utc>) -> ProbeSample {
+        let mut result = ProbeResult::test();
+        result.start_time = start_time;
+        result.to_sample()
+    }
+
+    #[tokio::test]
+    async fn logs_are_bounded_by_count_and_age() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = State::test(dir.path().to_path_buf()).await;
+        let now = Utc::now();
+        let minutes_ago = |m: i64| now - chrono::Duration::minutes(m);
+
+        let by_count = SampleLogConfig {
+            count: Some(3),
+            max_age: None,
+        };
+        for m in [5, 4, 3, 2, 1] {
+            state.record_probe_sample("counted", &sample_at(minutes_ago(m)), &by_count).await.unwrap();
+        }
+        let samples = state.get_probe_samples("counted", minutes_ago(60), now, None).await.unwrap();
+        assert_eq!(
+            samples.iter().map(|s| s.start_time).collect::<Vec<_>>(),
+            vec![minutes_ago(3), minutes_ago(2), minutes_ago(1)],
+            "only the newest samples are kept, oldest first"
+        );
+
+        let by_age = SampleLogConfig {
+            count: None,
+            max_age: Some(std::time::Duration::from_secs(150)),
+        };
+        for m in [5, 4, 3, 2, 1] {
+            state.record_probe_sample("aged", &sample_at(minutes_ago(m)), &by_age).await.unwrap();
+        }
+        let samples = state.get_probe_samples("aged", minutes_ago(60), now, None).await.unwrap();
+        assert_eq!(samples.len(), 2, "samples older than the max age are dropped: {samples:?}");
+
+        let samples = state.get_probe_samples("counted", minutes_ago(60), now, Some(1)).await.unwrap();
+        assert_eq!(samples.iter().map(|s| s.start_time).collect::<Vec<_>>(), vec![minutes_ago(1)]);
+        assert!(
+            state.get_probe_samples("counted", minutes_ago(60), minutes_ago(30), None).await.unwrap().is_empty(),
+            "the range is applied"
+        );
+    }
+
+    #[tokio::test]
+    async fn prune_drops_unlogged_probes() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = State::test(dir.path().to_path_buf()).await;
+        let now = Utc::now();
+
+        let retention = SampleLogConfig {
+            count: Some(10),
+            max_age: None,
+        };
+        state.record_probe_sample("removed", &sample_at(now), &retention).await.unwrap();
+
+        state.prune_probe_samples().await.unwrap();
+        assert!(
+            state
+                .get_probe_samples("removed", now - chrono::Duration::hours(1), now, None)
+                .await
+                .unwrap()
+                .is_empty(),
+            "a probe without a sample log keeps no samples"
+        );
+    }
+}
@@ -0,0 +1,464 @@
+//! Script storage: the [`ScriptStorageStore`] trait backing the `localStorage` and `clusterStorage`
+//! globals available to `!Script` probes, implemented over the [`State`] redb store.
+//!
+//! Both stores are scoped per probe and hold string values with optional per-key expiry times.
+//! `localStorage` entries stay on this node. `clusterStorage` entries are a [`GlobalLwwEntity`]: one
+//! global row per `(probe, key)`, replicated through gossip and resolved by last-writer-wins, so
+//! every node running the probe shares the same value. Removing a cluster entry writes a tombstone
+//! so the removal propagates too.
+
+use std::collections::HashSet;
+use std::error::Error;
+
+use chrono::{DateTime, Utc};
+use redb::{ReadableDatabase, ReadableTable, TableDefinition};
+use serde::{Deserialize, Serialize};
+
+use crate::cluster::Versioned;
+
+use super::{
+    CLUSTER_STORAGE_TABLE, GlobalLwwEntity, LOCAL_STORAGE_TABLE, LwwFieldValue, State, lww_supersedes,
+};
+
+/// The maximum serialized size of a single `clusterStorage` entry. Like incident updates, an entry
+/// must fit into a single gossip datagram to replicate, so larger writes are rejected up front.
+pub const MAX_CLUSTER_ENTRY_BYTES: usize = 16 * 1024;
+
+/// Which of a script's persistent stores an operation applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageKind {
+    /// `localStorage`: persisted on this node only.
+    Local,
+    /// `clusterStorage`: replicated to every node in the cluster.
+    Cluster,
+}
+
+/// A single stored value. A `value` of `None` is a tombstone recording that the key was removed.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StorageEntry {
+    /// The probe whose script owns this entry.
+    pub scope: String,
+    pub key: String,
+    pub value: Option<String>,
+    /// When the entry stops being visible to scripts, if it was written with a TTL.
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_updated: DateTime<Utc>,
+}
+
+impl StorageEntry {
+    /// The entry's value, unless it has been removed or has expired as of `now`.
+    fn live_value(&self, now: DateTime<Utc>) -> Option<&str> {
+        match self.expires_at {
+            Some(expires_at) if expires_at <= now => None,
+            _ => self.value.as_deref(),
+        }
+    }
+}
+
+impl Versioned for StorageEntry {
+    type Diff = StorageEntry;
+
+    fn version(&self) -> u64 {
+        self.last_updated.timestamp_millis() as u64
+    }
+
+    fn diff(&self, version: u64) -> Option<Self::Diff> {
+        if self.version() > version {
+            Some(self.clone())
+        } else {
+            None
+        }
+    }
+
+    fn apply(&mut self, diff: &Self::Diff) {
+        // As for crons, the `(version, last_writer)` tiebreak is applied by the gossip store; this
+        // version-only form is the defensive fallback for the generic path.
+        if diff.version() > self.version() {
+            *self = diff.clone();
+        }
+    }
+}
+
+impl GlobalLwwEntity for StorageEntry {
+    type Key = (&'static str, &'static str);
+    const TABLE: TableDefinition<'static, (&'static str, &'static str), LwwFieldValue> =
+        CLUSTER_STORAGE_TABLE;
+
+    fn id_field(&self) -> String {
+        // NUL can't appear in a YAML probe name, so the field is unambiguous.
+        format!("{}\0{}", self.scope, self.key)
+    }
+}
+
+/// Storage operations for the persistent script stores.
+///
+/// Unlike the other stores these are synchronous: they back the synchronous Web Storage API
+/// exposed to scripts, and redb operations never await anyway.
+pub trait ScriptStorageStore {
+    /// The live value of `key` in `scope`, if it is set and hasn't expired.
+    fn get_storage_item(
+        &self,
+        kind: StorageKind,
+        scope: &str,
+        key: &str,
+    ) -> Result<Option<String>, Box<dyn Error>>;
+
+    /// Sets `key` in `scope` to `value`, expiring after `ttl` when one is given.
+    fn set_storage_item(
+        &self,
+        kind: StorageKind,
+        scope: &str,
+        key: &str,
+        value: &str,
+        ttl: Option<chrono::Duration>,
+    ) -> Result<(), Box<dyn Error>>;
+
+    /// Removes `key` from `scope`.
+    fn remove_storage_item(
+        &self,
+        kind: StorageKind,
+        scope: &str,
+        key: &str,
+    ) -> Result<(), Box<dyn Error>>;
+
+    /// The keys with live values in `scope`, in sorted order.
+    fn storage_keys(&self, kind: StorageKind, scope: &str) -> Result<Vec<String>, Box<dyn Error>>;
+
+    /// Removes every key from `scope`.
+    fn clear_storage(&self, kind: StorageKind, scope: &str) -> Result<(), Box<dyn Error>>;
+}
+
+impl State {
+    fn read_storage_entries(
+        &self,
+        kind: StorageKind,
+        scope: &str,
+    ) -> Result<Vec<StorageEntry>, Box<dyn Error>> {
+        let txn = self.database.begin_read()?;
+        let mut entries = Vec::new();
+        match kind {
+            StorageKind::Local => {
+                if let Ok(table) = txn.open_table(LOCAL_STORAGE_TABLE) {
+                    for entry in table.range((scope, "")..)?.filter_map(|r| r.ok()) {
+                        let (key, data) = entry;
+                        if key.value().0 != scope {
+                            break;
+                        }
+                        entries.push(rmp_serde::from_slice(data.value())?);
+                    }
+                }
+            }
+            StorageKind::Cluster => {
+                if let Ok(table) = txn.open_table(CLUSTER_STORAGE_TABLE) {
+                    for entry in table.range((scope, "")..)?.filter_map(|r| r.ok()) {
+                        let (key, value) = entry;
+                        if key.value().0 != scope {
+                            break;
+                        }
+                        let (_version, _last_writer, data) = value.value();
+                        entries.push(rmp_serde::from_slice(data)?);
+                    }
+                }
+            }
+        }
+
+        Ok(entries)
+    }
+
+    fn write_storage_entry(&self, kind: StorageKind, entry: &StorageEntry) -> Result<(), Box<dyn Error>> {
+        let bytes = rmp_serde::to_vec_named(entry)
+            .map_err(|e| format!("Failed to serialize storage entry: {e:?}"))?;
+
+        if kind == StorageKind::Cluster && bytes.len() > MAX_CLUSTER_ENTRY_BYTES {
+            return Err(format!(
+                "clusterStorage entries are limited to {MAX_CLUSTER_ENTRY_BYTES} bytes, but '{}' would take {} bytes.",
+                entry.key,
+                bytes.len()
+            )
+            .into());
+        }
+
+        let txn = self.database.begin_write()?;
+        {
+            let key = (entry.scope.as_str(), entry.key.as_str());
+            match kind {
+                StorageKind::Local => {
+                    let mut table = txn.open_table(LOCAL_STORAGE_TABLE)?;
+                    if entry.value.is_some() {
+                        table.insert(key, bytes.as_slice())?;
+                    } else {
+                        table.remove(key)?;
+                    }
+                }
+                StorageKind::Cluster => {
+                    let mut table = txn.open_table(CLUSTER_STORAGE_TABLE)?;
+                    let own_id: u128 = self.node_id.into();
+                    // A peer's write with a later timestamp (its clock may run ahead of ours) already
+                    // wins everywhere else, so ours must lose here too or the nodes would diverge.
+                    let existing = table.get(key)?.map(|v| {
+                        let (version, last_writer, _data) = v.value();
+                        (version, last_writer)
+                    });
+                    if lww_supersedes(existing, (entry.version(), own_id)) {
+                        table.insert(key, (entry.version(), own_id, bytes.as_slice()))?;
+                    }
+                }
+            }
+        }
+        txn.commit()?;
+
+        Ok(())
+    }
+
+    /// Drops every entry whose TTL has passed from both script stores, along with `clusterStorage`
+    /// tombstones written before `tombstone_threshold`. Expiry times are absolute, so every node
+    /// expires a replicated entry at the same moment and none of them needs a tombstone.
+    ///
+    /// Entries belonging to a probe which no longer appears in `live_probes` are orphans: once they
+    /// too have gone unwritten since `tombstone_threshold` they are dropped, TTL or not, so that
+    /// deleting a probe eventually frees its storage.
+    pub(super) fn gc_storage(
+        &self,
+        txn: &redb::WriteTransaction,
+        now: DateTime<Utc>,
+        tombstone_threshold: DateTime<Utc>,
+        live_probes: &HashSet<String>,
+    ) -> Result<u64, Box<dyn Error>> {
+        let reapable = |data: &[u8]| {
+            rmp_serde::from_slice::<StorageEntry>(data).is_ok_and(|entry| {
+                let stale = entry.last_updated < tombstone_threshold;
+                entry.expires_at.is_some_and(|expires_at| expires_at <= now)
+                    || (entry.value.is_none() && stale)
+                    || (stale && !live_probes.contains(&entry.scope))
+            })
+        };
+
+        let mut dropped = 0u64;
+        txn.open_table(LOCAL_STORAGE_TABLE)?.retain(|_key, data| {
+            let keep = !reapable(data);
+            dropped += u64::from(!keep);
+            keep
+        })?;
+        txn.open_table(CLUSTER_STORAGE_TABLE)?.retain(|_key, (_version, _writer, data)| {
+            let keep = !reapable(data);
+            dropped += u64::from(!keep);
+            keep
+        })?;
+
+        Ok(dropped)
+    }
+}
+
+impl ScriptStorageStore for State {
+    fn get_storage_item(
+        &self,
+        kind: StorageKind,
+        scope: &str,
+        key: &str,
+    ) -> Result<Option<String>, Box<dyn Error>> {
+        let txn = self.database.begin_read()?;
+        let entry: Option<StorageEntry> = match kind {
+            StorageKind::Local => match txn.open_table(LOCAL_STORAGE_TABLE) {
+                Ok(table) => match table.get((scope, key))? {
+                    Some(data) => Some(rmp_serde::from_slice(data.value())?),
+                    None => None,
+                },
+                Err(_) => None,
+            },
+            StorageKind::Cluster => match txn.open_table(CLUSTER_STORAGE_TABLE) {
+                Ok(table) => match table.get((scope, key))? {
+                    Some(value) => Some(rmp_serde::from_slice(value.value().2)?),
+                    None => None,
+                },
+                Err(_) => None,
+            },
+        };
+
+        Ok(entry.and_then(|entry| entry.live_value(Utc::now()).map(str::to_string)))
+    }
+
+    fn set_storage_item(
+        &self,
+        kind: StorageKind,
+        scope: &str,
+        key: &str,
+        value: &str,
+        ttl: Option<chrono::Duration>,
+    ) -> Result<(), Box<dyn Error>> {
+        let now = Utc::now();
+        self.write_storage_entry(
+            kind,
+            &StorageEntry {
+                scope: scope.to_string(),
+                key: key.to_string(),
+                value: Some(value.to_string()),
+                expires_at: ttl.map(|ttl| now + ttl),
+                last_updated: now,
+            },
+        )
+    }
+
+    fn remove_storage_item(
+        &self,
+        kind: StorageKind,
+        scope: &str,
+        key: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        self.write_storage_entry(
+            kind,
+            &StorageEntry {
+                scope: scope.to_string(),
+                key: key.to_string(),
+                value: None,
+                expires_at: None,
+                last_updated: Utc::now(),
+            },
+        )
+    }
+
+    fn storage_keys(&self, kind: StorageKind, scope: &str) -> Result<Vec<String>, Box<dyn Error>> {
+        let now = Utc::now();
+        Ok(self
+            .read_storage_entries(kind, scope)?
+            .into_iter()
+            .filter(|entry| entry.live_value(now).is_some())
+            .map(|entry| entry.key)
+            .collect())
+    }
+
+    fn clear_storage(&self, kind: StorageKind, scope: &str) -> Result<(), Box<dyn Error>> {
+        for key in self.storage_keys(kind, scope)? {
+            self.remove_storage_item(kind, scope, &key)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cluster::{ClusterStateDiff, GossipStore, NodeID};
+    use crate::state::ReplicatedEntity;
+
+    #[tokio::test]
+    async fn local_storage_is_scoped_and_expires() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = State::test(dir.path().to_path_buf()).await;
+
+        state.set_storage_item(StorageKind::Local, "a", "token", "abc", None).unwrap();
+        state
+            .set_storage_item(StorageKind::Local, "a", "stale", "old", Some(chrono::Duration::milliseconds(-1)))
+            .unwrap();
+        state.set_storage_item(StorageKind::Local, "b", "token", "xyz", None).unwrap();
+
+        assert_eq!(state.get_storage_item(StorageKind::Local, "a", "token").unwrap().as_deref(), Some("abc"));
+        assert_eq!(state.get_storage_item(StorageKind::Local, "a", "stale").unwrap(), None, "expired entries are hidden");
+        assert_eq!(state.storage_keys(StorageKind::Local, "a").unwrap(), vec!["token".to_string()]);
+        assert_eq!(
+            state.get_storage_item(StorageKind::Cluster, "a", "token").unwrap(),
+            None,
+            "local entries never appear in cluster storage"
+        );
+
+        state.clear_storage(StorageKind::Local, "a").unwrap();
+        assert!(state.storage_keys(StorageKind::Local, "a").unwrap().is_empty());
+        assert_eq!(state.storage_keys(StorageKind::Local, "b").unwrap(), vec!["token".to_string()]);
+    }
+
+    /// Cluster entries are gossiped under this node's partition, and a peer's newer write (including
+    /// a removal) replaces ours.
+    #[tokio::test]
+    async fn cluster_storage_replicates_through_gossip() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = State::test(dir.path().to_path_buf()).await;
+
+        state.set_storage_item(StorageKind::Cluster, "probe", "token", "ours", None).unwrap();
+
+        let delta = state.diff(crate::cluster::ClusterStateDigest::new()).await.unwrap();
+        let advertised = delta
+            .into_inner()
+            .into_iter()
+            .flat_map(|(_, entries)| entries.into_values())
+            .any(|entity| matches!(entity, ReplicatedEntity::StorageEntry(entry) if entry.value.as_deref() == Some("ours")));
+        assert!(advertised, "the entry should be offered to peers");
+
+        let peer = NodeID::new();
+        let mut diff = ClusterStateDiff::new();
+        let theirs = StorageEntry {
+            scope: "probe".into(),
+            key: "token".into(),
+            value: Some("theirs".into()),
+            expires_at: None,
+            last_updated: Utc::now() + chrono::Duration::seconds(1),
+        };
+        diff.update(peer, theirs.id_field(), ReplicatedEntity::StorageEntry(theirs.clone()));
+        state.apply(diff).await.unwrap();
+        assert_eq!(state.get_storage_item(StorageKind::Cluster, "probe", "token").unwrap().as_deref(), Some("theirs"));
+
+        let mut diff = ClusterStateDiff::new();
+        let removed = StorageEntry {
+            value: None,
+            last_updated: theirs.last_updated + chrono::Duration::seconds(1),
+            ..theirs
+        };
+        diff.update(peer, removed.id_field(), ReplicatedEntity::StorageEntry(removed));
+        state.apply(diff).await.unwrap();
+        assert_eq!(state.get_storage_item(StorageKind::Cluster, "probe", "token").unwrap(), None);
+    }
+
+    /// A local write stamped before a peer's newer write loses to it, as it does on every other node.
+    #[tokio::test]
+    async fn cluster_writes_do_not_overwrite_newer_peer_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = State::test(dir.path().to_path_buf()).await;
+
+        let mut diff = ClusterStateDiff::new();
+        let theirs = StorageEntry {
+            scope: "probe".into(),
+            key: "token".into(),
+            value: Some("theirs".into()),
+            expires_at: None,
+            last_updated: Utc::now() + chrono::Duration::minutes(5),
+        };
+        diff.update(NodeID::new(), theirs.id_field(), ReplicatedEntity::StorageEntry(theirs));
+        state.apply(diff).await.unwrap();
+
+        state.set_storage_item(StorageKind::Cluster, "probe", "token", "ours", None).unwrap();
+        assert_eq!(state.get_storage_item(StorageKind::Cluster, "probe", "token").unwrap().as_deref(), Some("theirs"));
+    }
+
+    #[tokio::test]
+    async fn gc_drops_entries_of_deleted_probes() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = State::test(dir.path().to_path_buf()).await;
+
+        state.set_storage_item(StorageKind::Cluster, "deleted", "token", "abc", None).unwrap();
+        state.set_storage_item(StorageKind::Cluster, "live", "token", "abc", None).unwrap();
+        state.set_storage_item(StorageKind::Local, "deleted", "token", "abc", None).unwrap();
+
+        let live_probes = ["live".to_string()].into_iter().collect();
+        let now = Utc::now();
+
+        let txn = state.database.begin_write().unwrap();
+        let dropped = state.gc_storage(&txn, now, now - chrono::Duration::hours(1), &live_probes).unwrap();
+        assert_eq!(dropped, 0, "recently written entries are kept while their probe may still be starting");
+        let dropped = state.gc_storage(&txn, now, now + chrono::Duration::seconds(1), &live_probes).unwrap();
+        assert_eq!(dropped, 2);
+        txn.commit().unwrap();
+
+        assert_eq!(state.get_storage_item(StorageKind::Cluster, "deleted", "token").unwrap(), None);
+        assert_eq!(state.get_storage_item(StorageKind::Local, "deleted", "token").unwrap(), None);
+        assert_eq!(state.get_storage_item(StorageKind::Cluster, "live", "token").unwrap().as_deref(), Some("abc"));
+    }
+
+    #[tokio::test]
+    async fn cluster_entries_must_fit_in_a_datagram() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = State::test(dir.path().to_path_buf()).await;
+
+        let large = "x".repeat(MAX_CLUSTER_ENTRY_BYTES);
+        assert!(state.set_storage_item(StorageKind::Cluster, "probe", "large", &large, None).is_err());
+        assert!(state.set_storage_item(StorageKind::Local, "probe", "large", &large, None).is_ok());
+    }
+}
@@ -19,24 +19,32 @@ pub struct DnsTarget {
 
 impl Target for DnsTarget {
     async fn run(&self, _cancel: &AtomicBool) -> Result<Sample, Box<dyn std::error::Error>> {
-        let resolver_config = self.resolver_config()?;
-        let lookup = TokioAsyncResolver::tokio(resolver_config, ResolverOpts::default())
-            .lookup(
-                self.domain.as_str(),
-                RecordType::from_str(self.record_type.as_deref().unwrap_or("A"))?,
-            )
-            .await?;
-
-        Ok(Sample::default().with(
-            "dns.answers",
-            lookup
-                .iter()
-                .map(|addr| addr.to_string())
-                .collect::<Vec<String>>(),
-        ))
+        let answers = lookup(
+            &self.domain,
+            self.record_type.as_deref().unwrap_or("A"),
+            self.nameservers.as_deref(),
+        )
+        .await?;
+
+        Ok(Sample::default().with("dns.answers", answers))
     }
 }
 
+/// Resolves the `record_type` records for `domain`, using the given `nameservers` (or the default
+/// public resolvers) and rendering each answer in its presentation format. Shared with the script
+/// runtime's `dns.resolve`.
+pub(crate) async fn lookup(
+    domain: &str,
+    record_type: &str,
+    nameservers: Option<&[String]>,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let lookup = TokioAsyncResolver::tokio(resolver_config(nameservers)?, ResolverOpts::default())
+        .lookup(domain, RecordType::from_str(record_type)?)
+        .await?;
+
+    Ok(lookup.iter().map(|addr| addr.to_string()).collect())
+}
+
 impl Display for DnsTarget {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -48,25 +56,26 @@ impl Display for DnsTarget {
     }
 }
 
-impl DnsTarget {
-    fn resolver_config(&self) -> Result<ResolverConfig, Box<dyn std::error::Error>> {
-        if let Some(nameservers) = &self.nameservers {
-            let mut config = ResolverConfig::new();
-            for ns in nameservers {
-                let ns = match core::net::SocketAddr::from_str(&ns) {
-                    Ok(addr) => Ok(addr),
-                    Err(_) => format!("{ns}:53").parse()
-                }.map_err(|e| format!("Invalid nameserver address '{}': {}", ns, e))?;
-
-                config.add_name_server(trust_dns_resolver::config::NameServerConfig::new(
-                    ns,
-                    trust_dns_resolver::config::Protocol::Udp));
-
-            }
-            Ok(config)
-        } else {
-            Ok(ResolverConfig::default())
+/// Parses a nameserver address, defaulting to port 53 when none is given.
+pub(crate) fn nameserver_addr(ns: &str) -> Result<core::net::SocketAddr, Box<dyn std::error::Error>> {
+    match core::net::SocketAddr::from_str(ns) {
+        Ok(addr) => Ok(addr),
+        Err(_) => format!("{ns}:53").parse()
+    }.map_err(|e| format!("Invalid nameserver address '{}': {}", ns, e).into())
+}
+
+fn resolver_config(nameservers: Option<&[String]>) -> Result<ResolverConfig, Box<dyn std::error::Error>> {
+    if let Some(nameservers) = nameservers {
+        let mut config = ResolverConfig::new();
+        for ns in nameservers {
+            config.add_name_server(trust_dns_resolver::config::NameServerConfig::new(
+                nameserver_addr(ns)?,
+                trust_dns_resolver::config::Protocol::Udp));
+
         }
+        Ok(config)
+    } else {
+        Ok(ResolverConfig::default())
     }
 }
 
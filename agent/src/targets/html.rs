@@ -0,0 +1,304 @@
+use std::collections::{BTreeMap, HashSet};
+
+use futures::StreamExt;
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use tracing_batteries::prelude::*;
+
+use crate::Sample;
+
+/// The most linked assets which are checked at once.
+const ASSET_CONCURRENCY: usize = 8;
+
+/// The most linked assets checked on each run when `max_assets` isn't set, so that a page with
+/// thousands of images doesn't turn every run into thousands of requests.
+const DEFAULT_MAX_ASSETS: usize = 100;
+
+/// The elements whose linked assets are checked when `check_assets` is enabled, and the attribute
+/// holding each one's URL. Only `<link>`s which load a resource for the page itself are included,
+/// so that `canonical`, `alternate` and `preconnect` links don't count as assets.
+const ASSETS: &[(&str, &str)] = &[
+    ("script[src]", "src"),
+    ("img[src]", "src"),
+    (
+        r#"link[href][rel~="stylesheet"], link[href][rel~="icon"], link[href][rel~="preload"], link[href][rel~="modulepreload"], link[href][rel~="manifest"]"#,
+        "href",
+    ),
+];
+
+/// Parses an HTTP target's response as an HTML page (without a browser, so scripts on the page
+/// are never run) and exposes its title, the results of CSS selector queries and any of its linked
+/// assets which fail to load.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HtmlOptions {
+    /// CSS selectors to query the page with, by name. Each is exposed as `http.html.select.<name>`,
+    /// the text of every element it matched, and `http.html.select.<name>.count`.
+    #[serde(default)]
+    pub selectors: BTreeMap<String, String>,
+    /// Whether to request every `<script src>`, `<img src>` and stylesheet, icon or preload
+    /// `<link href>` on the page, reporting those which don't return a 2xx status.
+    #[serde(default)]
+    pub check_assets: bool,
+    /// The most assets requested by `check_assets` on each run. Any beyond it, in the order they
+    /// appear on the page, are counted in `http.html.assets.skipped` rather than requested.
+    #[serde(default = "default_max_assets")]
+    pub max_assets: usize,
+}
+
+fn default_max_assets() -> usize {
+    DEFAULT_MAX_ASSETS
+}
+
+impl Default for HtmlOptions {
+    fn default() -> Self {
+        Self {
+            selectors: BTreeMap::new(),
+            check_assets: false,
+            max_assets: DEFAULT_MAX_ASSETS,
+        }
+    }
+}
+
+impl HtmlOptions {
+    /// Parses the configured selectors, so that a typo is reported when the configuration is loaded
+    /// rather than failing every run of the probe.
+    pub fn validate(&self) -> Result<(), Box<dyn std::error::Error>> {
+        for selector in self.selectors.values() {
+            parse_selector(selector)?;
+        }
+
+        if self.check_assets && self.max_assets == 0 {
+            return Err("`max_assets` is zero, so no assets would be checked; disable `check_assets` instead.".into());
+        }
+
+        Ok(())
+    }
+
+    /// Adds the `http.html.*` fields describing `body`, the page served from `base`, to `sample`.
+    pub async fn inspect(
+        &self,
+        client: &reqwest::Client,
+        base: &reqwest::Url,
+        body: &str,
+        mut sample: Sample,
+    ) -> Result<Sample, Box<dyn std::error::Error>> {
+        // The parsed document isn't `Send`, so everything needed from it is gathered up front.
+        let assets = {
+            let document = Html::parse_document(body);
+
+            let title = parse_selector("title")?;
+            sample = sample.with(
+                "http.html.title",
+                document.select(&title).next().map(|title| text_of(&title)),
+            );
+
+            for (name, selector) in &self.selectors {
+                let parsed = parse_selector(selector)?;
+                let matches = document.select(&parsed).map(|m| text_of(&m)).collect::<Vec<_>>();
+                sample = sample
+                    .with(format!("http.html.select.{name}.count"), matches.len() as i64)
+                    .with(format!("http.html.select.{name}"), matches);
+            }
+
+            if self.check_assets {
+                asset_urls(&document, base)?
+            } else {
+                Vec::new()
+            }
+        };
+
+        if self.check_assets {
+            let skipped = assets.len().saturating_sub(self.max_assets);
+            let checked = assets.len() - skipped;
+            let mut broken = futures::stream::iter(assets.into_iter().take(self.max_assets))
+                .map(|url| check_asset(client, url))
+                .buffer_unordered(ASSET_CONCURRENCY)
+                .filter_map(|failure| async move { failure })
+                .collect::<Vec<_>>()
+                .await;
+            broken.sort();
+
+            sample = sample
+                .with("http.html.assets.checked", checked as i64)
+                .with("http.html.assets.skipped", skipped as i64)
+                .with("http.html.assets.broken.count", broken.len() as i64)
+                .with("http.html.assets.broken", broken);
+        }
+
+        Ok(sample)
+    }
+}
+
+fn parse_selector(selector: &str) -> Result<Selector, Box<dyn std::error::Error>> {
+    Selector::parse(selector)
+        .map_err(|e| format!("The HTML selector '{}' is invalid: {}", selector, e).into())
+}
+
+/// The element's text content, with runs of whitespace collapsed as a browser would render them.
+fn text_of(element: &scraper::ElementRef<'_>) -> String {
+    element
+        .text()
+        .flat_map(str::split_whitespace)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The distinct http(s) URLs of the page's linked assets, resolved against the page's own URL.
+fn asset_urls(
+    document: &Html,
+    base: &reqwest::Url,
+) -> Result<Vec<reqwest::Url>, Box<dyn std::error::Error>> {
+    let mut seen = HashSet::new();
+    let mut urls = Vec::new();
+
+    for (selector, attribute) in ASSETS {
+        let selector = parse_selector(selector)?;
+        for element in document.select(&selector) {
+            let Some(url) = element
+                .value()
+                .attr(attribute)
+                .and_then(|link| base.join(link.trim()).ok())
+            else {
+                continue;
+            };
+
+            if matches!(url.scheme(), "http" | "https") && seen.insert(url.clone()) {
+                urls.push(url);
+            }
+        }
+    }
+
+    Ok(urls)
+}
+
+/// Requests an asset, describing why it is broken if it doesn't return a 2xx status.
+async fn check_asset(client: &reqwest::Client, url: reqwest::Url) -> Option<String> {
+    match client.get(url.clone()).send().await {
+        Ok(response) if response.status().is_success() => None,
+        Ok(response) => {
+            debug!(%url, status = response.status().as_u16(), "A linked asset failed to load.");
+            Some(format!("{} ({})", url, response.status().as_u16()))
+        }
+        Err(err) => {
+            debug!(%url, error = %err, "A linked asset could not be requested.");
+            Some(format!("{} (error)", url))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SampleValue;
+
+    const PAGE: &str = r#"<!doctype html>
+<html>
+  <head>
+    <title> Example
+      Site </title>
+    <link rel="stylesheet" href="/style.css">
+    <link rel="canonical" href="https://example.com/">
+    <script src="app.js"></script>
+  </head>
+  <body>
+    <h1 class="hero">Welcome</h1>
+    <ul><li>One</li><li>Two</li></ul>
+    <img src="/missing.png">
+    <img src="data:image/png;base64,AAAA">
+  </body>
+</html>"#;
+
+    #[tokio::test]
+    async fn test_title_and_selectors() {
+        let options = HtmlOptions {
+            selectors: BTreeMap::from([
+                ("hero".to_string(), "h1.hero".to_string()),
+                ("items".to_string(), "ul > li".to_string()),
+                ("footer".to_string(), "footer".to_string()),
+            ]),
+            ..HtmlOptions::default()
+        };
+
+        let base = reqwest::Url::parse("https://example.com/page").unwrap();
+        let sample = options
+            .inspect(&reqwest::Client::new(), &base, PAGE, Sample::default())
+            .await
+            .unwrap();
+
+        assert_eq!(sample.get("http.html.title"), &SampleValue::from("Example Site"));
+        assert_eq!(sample.get("http.html.select.hero"), &SampleValue::from(vec!["Welcome"]));
+        assert_eq!(sample.get("http.html.select.items"), &SampleValue::from(vec!["One", "Two"]));
+        assert_eq!(sample.get("http.html.select.items.count"), &SampleValue::from(2));
+        assert_eq!(sample.get("http.html.select.footer.count"), &SampleValue::from(0));
+        assert_eq!(sample.get("http.html.assets.broken"), &SampleValue::None);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_selector() {
+        let options = HtmlOptions {
+            selectors: BTreeMap::from([("bad".to_string(), "h1[".to_string())]),
+            ..HtmlOptions::default()
+        };
+
+        let base = reqwest::Url::parse("https://example.com/").unwrap();
+        options
+            .inspect(&reqwest::Client::new(), &base, PAGE, Sample::default())
+            .await
+            .expect_err("an invalid selector should fail the probe");
+    }
+
+    #[test]
+    fn test_validate() {
+        let options = |selector: &str| HtmlOptions {
+            selectors: BTreeMap::from([("name".to_string(), selector.to_string())]),
+            ..HtmlOptions::default()
+        };
+
+        assert!(options("h1.hero").validate().is_ok());
+        assert!(options("h1[").validate().is_err());
+        assert!(
+            HtmlOptions { check_assets: true, max_assets: 0, ..options("h1") }.validate().is_err(),
+            "a cap of zero assets is rejected"
+        );
+
+        let options: HtmlOptions = serde_yaml::from_str("check_assets: true").unwrap();
+        assert_eq!(options.max_assets, DEFAULT_MAX_ASSETS);
+    }
+
+    #[tokio::test]
+    async fn test_max_assets() {
+        let options = HtmlOptions {
+            check_assets: true,
+            max_assets: 0,
+            ..HtmlOptions::default()
+        };
+
+        let base = reqwest::Url::parse("https://example.com/").unwrap();
+        let sample = options
+            .inspect(&reqwest::Client::new(), &base, PAGE, Sample::default())
+            .await
+            .unwrap();
+
+        assert_eq!(sample.get("http.html.assets.checked"), &SampleValue::from(0));
+        assert_eq!(sample.get("http.html.assets.skipped"), &SampleValue::from(3));
+    }
+
+    #[test]
+    fn test_asset_urls() {
+        let base = reqwest::Url::parse("https://example.com/docs/page").unwrap();
+        let urls = asset_urls(&Html::parse_document(PAGE), &base)
+            .unwrap()
+            .into_iter()
+            .map(|url| url.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            urls,
+            vec![
+                "https://example.com/docs/app.js",
+                "https://example.com/missing.png",
+                "https://example.com/style.css",
+            ]
+        );
+    }
+}
@@ -4,6 +4,7 @@ use serde::{Deserialize, Serialize};
 use tracing_batteries::prelude::opentelemetry::trace::SpanKind as OpenTelemetrySpanKind;
 use tracing_batteries::prelude::*;
 
+use super::html::HtmlOptions;
 use crate::{Sample, Target};
 
 lazy_static! {
@@ -33,6 +34,9 @@ pub struct HttpTarget {
     pub body: Option<String>,
     #[serde(default)]
     pub no_verify: bool,
+    /// Parses the response as an HTML page, exposing the `http.html.*` fields.
+    #[serde(default)]
+    pub html: Option<HtmlOptions>,
 }
 
 impl Target for HttpTarget {
@@ -53,11 +57,12 @@ impl Target for HttpTarget {
     async fn run(&self, _cancel: &AtomicBool) -> Result<Sample, Box<dyn std::error::Error>> {
         let method = reqwest::Method::from_str(&self.method)?;
 
-        let mut request = if self.no_verify {
-            CLIENT_NO_VERIFY.request(method, self.url.clone())
+        let client: &reqwest::Client = if self.no_verify {
+            &CLIENT_NO_VERIFY
         } else {
-            CLIENT.request(method, self.url.clone())
+            &CLIENT
         };
+        let mut request = client.request(method, self.url.clone());
 
         let mut headers = self.headers.clone();
 
@@ -93,7 +98,14 @@ impl Target for HttpTarget {
             );
         }
 
-        Ok(sample.with("http.body", response.text().await?))
+        let url = response.url().clone();
+        let body = response.text().await?;
+
+        if let Some(html) = &self.html {
+            sample = html.inspect(client, &url, &body, sample).await?;
+        }
+
+        Ok(sample.with("http.body", body))
     }
 }
 
@@ -127,6 +139,7 @@ mod tests {
             headers: HashMap::new(),
             body: None,
             no_verify: false,
+            html: None,
         };
 
         let cancel = AtomicBool::new(false);
@@ -135,4 +148,54 @@ mod tests {
         assert_eq!(sample.get("http.status"), &200.into());
         assert!(matches!(sample.get("http.body"), SampleValue::String(s) if !s.is_empty()));
     }
+
+    #[tokio::test]
+    async fn test_html() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"<html><head><title>Home</title><link rel="stylesheet" href="/style.css"></head>
+                <body><h1>Welcome</h1><img src="/logo.png"><script src="/missing.js"></script></body></html>"#,
+            ))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/style.css"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("body {}"))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/logo.png"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock_server)
+            .await;
+
+        let target = HttpTarget {
+            url: format!("{}/", mock_server.uri()),
+            method: "GET".to_string(),
+            headers: HashMap::new(),
+            body: None,
+            no_verify: false,
+            html: Some(HtmlOptions {
+                selectors: [("heading".to_string(), "h1".to_string())].into(),
+                check_assets: true,
+                ..HtmlOptions::default()
+            }),
+        };
+
+        let cancel = AtomicBool::new(false);
+
+        let sample = target.run(&cancel).await.unwrap();
+        assert_eq!(sample.get("http.html.title"), &"Home".into());
+        assert_eq!(sample.get("http.html.select.heading"), &vec!["Welcome"].into());
+        assert_eq!(sample.get("http.html.assets.checked"), &3.into());
+        assert_eq!(sample.get("http.html.assets.skipped"), &0.into());
+        assert_eq!(sample.get("http.html.assets.broken.count"), &1.into());
+        assert_eq!(
+            sample.get("http.html.assets.broken"),
+            &vec![format!("{}/missing.js (404)", mock_server.uri())].into()
+        );
+    }
 }
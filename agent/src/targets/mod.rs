@@ -2,15 +2,21 @@ use std::{fmt::Display, future::Future, sync::atomic::AtomicBool};
 
 use serde::{Deserialize, Serialize};
 
-use crate::Sample;
+use crate::{Sample, state::State};
 
 mod dns;
 mod grpc;
+mod html;
 mod http;
 mod script;
 mod tcp;
 mod tls_cert;
 
+#[cfg(feature = "scripts")]
+pub(crate) use dns::{lookup as dns_lookup, nameserver_addr};
+#[cfg(feature = "scripts")]
+pub(crate) use tls_cert::tls_client_config;
+
 pub trait Target: Display {
     fn run(
         &self,
@@ -43,6 +49,32 @@ impl TargetType {
         TargetType::Script(target)
     }
 
+    /// The host this target connects to, used to apply per-host concurrency limits. `None` for
+    /// targets which aren't bound to a single host (DNS lookups and scripts).
+    pub fn host(&self) -> Option<String> {
+        match self {
+            TargetType::Http(target) => url_host(&target.url),
+            TargetType::Grpc(target) => url_host(&target.url),
+            TargetType::Tcp(target) => Some(host_of(&target.host).to_lowercase()),
+            TargetType::TlsCert(target) => Some(host_of(&target.host).to_lowercase()),
+            TargetType::Dns(_) => None,
+            #[cfg(feature = "scripts")]
+            TargetType::Script(_) => None,
+            #[cfg(test)]
+            TargetType::Mock | TargetType::Hang => None,
+        }
+    }
+
+    /// Connects targets which keep persistent state (scripts' `localStorage` and `clusterStorage`)
+    /// to the agent's state store, scoped to the probe named `probe_name`.
+    #[allow(unused_variables)]
+    pub fn bind_state(&mut self, state: &State, probe_name: &str) {
+        #[cfg(feature = "scripts")]
+        if let TargetType::Script(target) = self {
+            target.bind_state(state, probe_name);
+        }
+    }
+
     pub async fn run(&self, cancel: &AtomicBool) -> Result<Sample, Box<dyn std::error::Error>> {
         match self {
             #[cfg(test)]
@@ -95,3 +127,20 @@ impl Target for TargetType {
         }
     }
 }
+
+/// Strips the port (and any IPv6 brackets) from a `host:port` pair.
+fn host_of(host: &str) -> &str {
+    if let Some(rest) = host.strip_prefix('[') {
+        return rest.split(']').next().unwrap_or(rest);
+    }
+
+    host.rsplit_once(':').map(|(host, _)| host).unwrap_or(host)
+}
+
+/// The (lowercased) host named by a URL, if it parses.
+fn url_host(url: &str) -> Option<String> {
+    reqwest::Url::parse(url)
+        .ok()?
+        .host_str()
+        .map(|host| host.to_lowercase())
+}
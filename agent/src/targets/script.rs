@@ -1,51 +1,254 @@
-use boa_engine::{Module, Source, builtins::promise::PromiseState, job::JobExecutor};
+use boa_engine::{
+    JsError, Module, Source, builtins::promise::PromiseState, job::JobExecutor, js_string,
+    module::SimpleModuleLoader,
+};
+use grey_api::ValidationResult;
 use serde::{Deserialize, Serialize};
-use std::{cell::RefCell, fmt::Display, rc::Rc, sync::atomic::AtomicBool};
-use tracing::instrument;
+use std::{
+    cell::RefCell,
+    fmt::Display,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+use tokio::sync::Notify;
+use tracing::{Instrument, instrument};
 use tracing_batteries::prelude::*;
 
-use crate::{Sample, js::JobQueue, targets::Target};
-use crate::js::{JsInto, SessionStorage};
+use crate::{Sample, js::JobQueue, state::State, targets::Target};
+use crate::js::{
+    FetchOptions, JsInto, NetPolicy, ReqwestFetcher, ScriptLimits, ScriptModules, ScriptResults,
+    ScriptStorage, SessionStorage, limit_exceeded, script_error,
+};
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ScriptTarget {
+    /// The script to run, inline. Exactly one of `code` / `file` must be set.
+    #[serde(default)]
     pub code: String,
+    /// A file containing the script to run, relative to the configuration file. Exactly one of
+    /// `code` / `file` must be set.
+    #[serde(default)]
+    pub file: Option<PathBuf>,
     #[serde(default)]
     pub args: Vec<String>,
+    /// The budgets bounding how much work each run of the script may do.
+    #[serde(default)]
+    pub limits: ScriptLimits,
+    /// The hosts and ports the script may reach through the `net` and `dns` APIs.
+    #[serde(default)]
+    pub net: NetPolicy,
+    /// The HTTP client configuration used by the script's `fetch` calls.
+    #[serde(default)]
+    pub fetch: FetchOptions,
+    /// The contents of `file`, read when the configuration is loaded (see [`ScriptTarget::load`]).
+    #[serde(skip)]
+    source: Option<String>,
+    /// The shared modules this script may `import`, when `script_modules` is configured.
+    #[serde(skip)]
+    modules: Option<ScriptModules>,
     /// Runtime cache backing the script's `sessionStorage` global. Clones share the
     /// same store, so state persists across the per-run clones taken by the probe
     /// runner and lasts until a config reload rebuilds this target.
     #[serde(skip)]
     session: SessionStorage,
+    /// The probe's persistent `localStorage` and `clusterStorage`, once the probe runner has bound
+    /// this target to the agent's state (see [`ScriptTarget::bind_state`]).
+    #[serde(skip)]
+    storage: Option<ScriptStorage>,
+    /// Set while a run's thread is still executing the script, shared by the per-run clones so that
+    /// a run which was abandoned without yielding holds up the probe's next run rather than piling up
+    /// threads.
+    #[serde(skip)]
+    running: Arc<AtomicBool>,
 }
 
-/// Equality covers only the configured fields — the session store is runtime cache,
-/// not configuration. The config reloader relies on this comparison to decide when a
-/// probe has changed and needs its target (and therefore its cache) rebuilt.
+/// Equality covers the configured fields and the script and module code they were loaded with —
+/// the session and persistent stores are runtime state, not configuration. The config reloader relies on this
+/// comparison to decide when a probe has changed and needs its target (and therefore its cache)
+/// rebuilt, which is how edits to a script's `file` or to a shared module are picked up.
 impl PartialEq for ScriptTarget {
     fn eq(&self, other: &Self) -> bool {
-        self.code == other.code && self.args == other.args
+        self.code == other.code
+            && self.file == other.file
+            && self.args == other.args
+            && self.limits == other.limits
+            && self.net == other.net
+            && self.fetch == other.fetch
+            && self.source == other.source
+            && self.modules == other.modules
+    }
+}
+
+impl ScriptTarget {
+    /// Reads the script's `file` (resolved relative to `base`) and attaches the shared `modules`,
+    /// validating that exactly one of `code` / `file` is set and building the `fetch` client.
+    pub async fn load(
+        &mut self,
+        base: &Path,
+        modules: Option<ScriptModules>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.source = match (&self.file, self.code.is_empty()) {
+            (Some(_), false) => {
+                return Err("Script targets must declare only one of `code` or `file`.".into());
+            }
+            (None, true) => {
+                return Err("Script targets must declare either `code` or `file`.".into());
+            }
+            (Some(file), true) => {
+                let path = base.join(file);
+                Some(tokio::fs::read_to_string(&path).await.map_err(|e| {
+                    format!("Failed to read script file {}: {}", path.display(), e)
+                })?)
+            }
+            (None, false) => None,
+        };
+
+        self.modules = modules;
+
+        // Each run builds its own client on its own thread, but building one here means invalid
+        // certificates or proxies fail the load rather than every run.
+        self.fetch.client()?;
+        Ok(())
+    }
+
+    /// Gives the script persistent `localStorage` and `clusterStorage`, scoped to `probe_name`.
+    pub fn bind_state(&mut self, state: &State, probe_name: &str) {
+        self.storage = Some(ScriptStorage::new(state.clone(), probe_name));
+    }
+
+    fn code(&self) -> Result<&str, Box<dyn std::error::Error>> {
+        match (&self.file, &self.source) {
+            (None, _) => Ok(&self.code),
+            (Some(_), Some(source)) => Ok(source),
+            (Some(file), None) => {
+                Err(format!("The script file {} has not been loaded.", file.display()).into())
+            }
+        }
     }
 }
 
 impl Target for ScriptTarget {
-    #[instrument("target.script", skip(self, _cancel), err(Debug), fields(script.exit_code = EmptyField))]
-    async fn run(&self, _cancel: &AtomicBool) -> Result<Sample, Box<dyn std::error::Error>> {
-        let code = self.code.clone();
+    #[instrument("target.script", skip(self, cancel), err(Debug), fields(script.exit_code = EmptyField))]
+    async fn run(&self, cancel: &AtomicBool) -> Result<Sample, Box<dyn std::error::Error>> {
+        let code = self.code()?.to_string();
+
+        if self.running.swap(true, Ordering::AcqRel) {
+            return Err("script.limit_exceeded: the script's abandoned previous run is still executing".into());
+        }
+        let running = Running(self.running.clone());
+
+        // The script runs on a thread of its own so that a stretch of synchronous script can't hold
+        // up the agent's other probes, and so that a cancelled or overdue run can be abandoned at once
+        // rather than when the script next yields.
+        let (result_tx, result_rx) = tokio::sync::oneshot::channel();
+        let stop = Arc::new(Notify::new());
+        let target = self.clone();
+        let span = tracing::Span::current();
+        std::thread::Builder::new()
+            .name("grey-script".into())
+            .spawn({
+                let stop = stop.clone();
+                move || {
+                    let evaluate = async {
+                        tokio::select! {
+                            result = target.evaluate(code).instrument(span) => {
+                                result.map_err(|e| e.to_string())
+                            }
+                            _ = stop.notified() => Err("The script was stopped.".to_string()),
+                        }
+                    };
+
+                    let runtime = tokio::runtime::Builder::new_current_thread()
+                        .enable_all()
+                        .build();
+                    let result = match runtime {
+                        Ok(runtime) => tokio::task::LocalSet::new().block_on(&runtime, evaluate),
+                        Err(err) => Err(format!("Failed to start the script's runtime: {err}")),
+                    };
+
+                    // The run is over before its result is reported, so the probe's next run can start.
+                    drop(running);
+                    let _ = result_tx.send(result);
+                }
+            })
+            .map_err(|e| format!("Failed to start the script's thread: {e}"))?;
+
+        tokio::select! {
+            result = result_rx => match result {
+                Ok(result) => Ok(result?),
+                Err(_) => Err("The script's thread exited without reporting its result.".into()),
+            },
+            err = self.interrupted(cancel) => {
+                stop.notify_one();
+                Err(err)
+            }
+        }
+    }
+}
+
+/// Marks a script's run as finished once its thread is done with it.
+struct Running(Arc<AtomicBool>);
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+impl ScriptTarget {
+    /// Runs the script to completion on the current thread, which must be driving a [`LocalSet`].
+    ///
+    /// [`LocalSet`]: tokio::task::LocalSet
+    async fn evaluate(&self, code: String) -> Result<Sample, Box<dyn std::error::Error>> {
         let args = self.args.clone();
 
-        let executor = Rc::new(JobQueue::new());
-        let context = &mut boa_engine::Context::builder()
-            .job_executor(executor.clone())
-            .build()?;
+        let executor = Rc::new(JobQueue::new().with_max_heap_size(self.limits.max_heap_size));
+        let mut builder = self
+            .limits
+            .budget(boa_engine::Context::builder().job_executor(executor.clone()));
+        let loader = match &self.modules {
+            Some(modules) => {
+                let loader = Rc::new(SimpleModuleLoader::new(modules.root())?);
+                builder = builder.module_loader(loader.clone());
+                Some(loader)
+            }
+            None => None,
+        };
+        let context = &mut builder.build()?;
 
-        crate::js::setup_runtime(context, self.session.clone(), args)?;
+        if let (Some(modules), Some(loader)) = (&self.modules, &loader) {
+            modules.register(loader, context)?;
+        }
+
+        // Clients can't be shared between runtimes, so each run (and therefore each runtime) builds
+        // its own.
+        let client = self.fetch.client()?;
+
+        let results = ScriptResults::default();
+        crate::js::setup_runtime(
+            context,
+            ReqwestFetcher::new(client),
+            self.session.clone(),
+            self.storage.clone(),
+            self.net.clone(),
+            results.clone(),
+            args,
+        )?;
+        self.limits.apply(context);
 
         let module = Module::parse(Source::from_bytes(&code), None, context)?;
 
         let promise = module.load_link_evaluate(context);
 
-        executor.run_jobs_async(&RefCell::new(context)).await?;
+        executor
+            .run_jobs_async(&RefCell::new(&mut *context))
+            .await
+            .map_err(script_error)?;
 
         let output = context.eval(Source::from_bytes("output"))?;
 
@@ -55,11 +258,40 @@ impl Target for ScriptTarget {
             Sample::default()
         };
 
+        for (name, validation) in results.take() {
+            sample.add_validation(name, validation);
+        }
+
         match promise.state() {
             PromiseState::Fulfilled(_) => {
-                sample.set("script.exit_code", 0);
+                let failed = sample.validations().iter().any(|(_, v)| !v.pass);
+                sample.set("script.exit_code", if failed { 1 } else { 0 });
             }
             PromiseState::Rejected(err) => {
+                if let Ok(native) = JsError::from_opaque(err.clone()).try_native(context)
+                    && limit_exceeded(&native.into())
+                {
+                    return Err(format!("script.limit_exceeded: {}", err.display()).into());
+                }
+
+                // A failed assertion ends the script, but it is reported as the validation it
+                // failed rather than as an error in the script itself.
+                if is_assertion_error(&err, context)? {
+                    let message = err
+                        .as_object()
+                        .map(|err| err.get(js_string!("message"), context))
+                        .transpose()?
+                        .map(|message| message.display().to_string())
+                        .unwrap_or_default();
+
+                    if sample.validations().iter().all(|(_, v)| v.pass) {
+                        sample.add_validation("assert", ValidationResult::fail(message));
+                    }
+
+                    sample.set("script.exit_code", 1);
+                    return Ok(sample);
+                }
+
                 return Err(err.to_string(context)?.to_std_string_lossy().into());
             }
             PromiseState::Pending => {
@@ -71,6 +303,46 @@ impl Target for ScriptTarget {
     }
 }
 
+/// Whether the script was rejected with the `AssertionError` thrown by a failed `assert`.
+fn is_assertion_error(
+    err: &boa_engine::JsValue,
+    context: &mut boa_engine::Context,
+) -> boa_engine::JsResult<bool> {
+    let Some(err) = err.as_object() else {
+        return Ok(false);
+    };
+
+    let name = err.get(js_string!("name"), context)?;
+    Ok(name
+        .as_string()
+        .is_some_and(|name| name.to_std_string_lossy() == "AssertionError"))
+}
+
+impl ScriptTarget {
+    /// Resolves with the reason the run must stop once it has been cancelled or has outlived its
+    /// `max_duration`.
+    async fn interrupted(&self, cancel: &AtomicBool) -> Box<dyn std::error::Error> {
+        let deadline = self
+            .limits
+            .max_duration
+            .map(|limit| (limit, tokio::time::Instant::now() + limit));
+
+        loop {
+            if cancel.load(Ordering::Relaxed) {
+                return "The script was cancelled.".into();
+            }
+
+            if let Some((limit, deadline)) = deadline
+                && tokio::time::Instant::now() >= deadline
+            {
+                return self.limits.duration_exceeded(limit);
+            }
+
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+}
+
 impl Display for ScriptTarget {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -132,6 +404,65 @@ mod tests {
         assert_eq!(sample.get("abc"), &SampleValue::from("123"));
     }
 
+    #[tokio::test]
+    async fn test_script_nested_outputs() {
+        let target = ScriptTarget {
+            code: r#"output.http = { status: 200, body: { ok: true } };"#.into(),
+            ..Default::default()
+        };
+        let cancel = AtomicBool::new(false);
+
+        let sample = target.run(&cancel).await.expect("no error to be raised");
+        assert_eq!(sample.get("http.status"), &SampleValue::from(200));
+        assert_eq!(sample.get("http.body.ok"), &SampleValue::from(true));
+    }
+
+    #[tokio::test]
+    async fn test_script_tests_and_assertions() {
+        let target = ScriptTarget {
+            code: r#"
+                await test("login", async () => assert.equal(await Promise.resolve(200), 200));
+                await test("profile", () => assert.equal(404, 200, "the profile should load"));
+            "#
+            .into(),
+            ..Default::default()
+        };
+        let cancel = AtomicBool::new(false);
+
+        let sample = target.run(&cancel).await.expect("no error to be raised");
+        assert_eq!(sample.get("script.exit_code"), &SampleValue::from(1));
+        assert_eq!(
+            sample.validations(),
+            &[
+                ("login".to_string(), ValidationResult::pass()),
+                ("profile".to_string(), ValidationResult::fail("the profile should load")),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_script_failed_top_level_assertion() {
+        let target = ScriptTarget {
+            code: r#"assert.equal(1, 1, "one is one"); assert(false, "the token was issued"); output.after = true;"#
+                .into(),
+            ..Default::default()
+        };
+        let cancel = AtomicBool::new(false);
+
+        let sample = target
+            .run(&cancel)
+            .await
+            .expect("a failed assertion is reported as a validation, not an error");
+        assert_eq!(sample.get("after"), &SampleValue::None);
+        assert_eq!(
+            sample.validations(),
+            &[
+                ("one is one".to_string(), ValidationResult::pass()),
+                ("the token was issued".to_string(), ValidationResult::fail("the token was issued")),
+            ]
+        );
+    }
+
     #[tokio::test]
     async fn test_script_args() {
         let target = ScriptTarget {
@@ -288,6 +619,353 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_script_file() {
+        let dir = tempfile::tempdir().unwrap();
+        tokio::fs::write(dir.path().join("probe.js"), "output.answer = 42;")
+            .await
+            .unwrap();
+
+        let mut target = ScriptTarget {
+            file: Some("probe.js".into()),
+            ..Default::default()
+        };
+        let cancel = AtomicBool::new(false);
+
+        target
+            .run(&cancel)
+            .await
+            .expect_err("a script file must be loaded before it can run");
+
+        target.load(dir.path(), None).await.expect("the script file to load");
+        let sample = target.run(&cancel).await.expect("no error to be raised");
+        assert_eq!(sample.get("answer"), &SampleValue::from(42));
+
+        // an edit to the file makes the reloaded target differ, so the reloader rebuilds it
+        tokio::fs::write(dir.path().join("probe.js"), "output.answer = 43;")
+            .await
+            .unwrap();
+        let mut reloaded = target.clone();
+        reloaded.load(dir.path(), None).await.unwrap();
+        assert_ne!(target, reloaded);
+
+        let mut both = ScriptTarget {
+            code: "output.answer = 1;".into(),
+            file: Some("probe.js".into()),
+            ..Default::default()
+        };
+        both.load(dir.path(), None)
+            .await
+            .expect_err("only one of code and file may be set");
+    }
+
+    #[tokio::test]
+    async fn test_script_imports_shared_modules() {
+        let dir = tempfile::tempdir().unwrap();
+        tokio::fs::create_dir_all(dir.path().join("lib")).await.unwrap();
+        tokio::fs::write(
+            dir.path().join("lib/azure.js"),
+            r#"
+            import { resource } from "./defaults.js";
+            export async function getAccessToken() { return `token for ${resource}`; }
+            "#,
+        )
+        .await
+        .unwrap();
+        tokio::fs::write(
+            dir.path().join("lib/defaults.js"),
+            r#"export const resource = "https://management.azure.com/";"#,
+        )
+        .await
+        .unwrap();
+
+        let modules = ScriptModules::load(dir.path())
+            .await
+            .expect("the modules to load");
+
+        let mut target = ScriptTarget {
+            code: r#"
+            import { getAccessToken } from "lib/azure.js";
+            output.token = await getAccessToken();
+            "#
+            .into(),
+            ..Default::default()
+        };
+        target.load(dir.path(), Some(modules)).await.unwrap();
+
+        let cancel = AtomicBool::new(false);
+        let sample = target.run(&cancel).await.expect("no error to be raised");
+        assert_eq!(
+            sample.get("token"),
+            &SampleValue::from("token for https://management.azure.com/")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_script_modules_must_parse() {
+        let dir = tempfile::tempdir().unwrap();
+        tokio::fs::write(dir.path().join("broken.js"), "export function {")
+            .await
+            .unwrap();
+
+        ScriptModules::load(dir.path())
+            .await
+            .expect_err("a module with a syntax error should be rejected");
+    }
+
+    fn limited(code: &str, limits: ScriptLimits) -> ScriptTarget {
+        ScriptTarget {
+            code: code.into(),
+            limits,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_script_loop_limit() {
+        let limits = ScriptLimits {
+            max_loop_iterations: 1000,
+            ..Default::default()
+        };
+        let cancel = AtomicBool::new(false);
+
+        // a runaway loop is stopped whether it runs before or after the script first awaits
+        for code in ["while (true) {}", "await Promise.resolve(); while (true) {}"] {
+            let err = limited(code, limits.clone())
+                .run(&cancel)
+                .await
+                .expect_err("the loop budget should stop the script")
+                .to_string();
+            assert!(err.starts_with("script.limit_exceeded"), "unexpected error: {err}");
+        }
+
+        // ...and a script can't catch its way past the budget
+        let err = limited(
+            "try { while (true) {} } catch (e) { output.caught = true; }",
+            limits.clone(),
+        )
+        .run(&cancel)
+        .await
+        .expect_err("the loop budget should stop the script")
+        .to_string();
+        assert!(err.starts_with("script.limit_exceeded"), "unexpected error: {err}");
+
+        limited("for (let i = 0; i < 100; i++) {}", limits)
+            .run(&cancel)
+            .await
+            .expect("loops within the budget run to completion");
+    }
+
+    #[tokio::test]
+    async fn test_script_recursion_limit() {
+        let target = limited(
+            "function recurse() { return recurse(); } recurse();",
+            ScriptLimits {
+                max_recursion_depth: Some(64),
+                ..Default::default()
+            },
+        );
+        let cancel = AtomicBool::new(false);
+
+        let err = target
+            .run(&cancel)
+            .await
+            .expect_err("the recursion budget should stop the script")
+            .to_string();
+        assert!(err.starts_with("script.limit_exceeded"), "unexpected error: {err}");
+    }
+
+    #[tokio::test]
+    async fn test_script_duration_limit() {
+        let target = limited(
+            "await new Promise((resolve) => setTimeout(resolve, 5000));",
+            ScriptLimits {
+                max_duration: Some(Duration::from_millis(50)),
+                ..Default::default()
+            },
+        );
+        let cancel = AtomicBool::new(false);
+
+        let err = tokio::time::timeout(Duration::from_secs(1), target.run(&cancel))
+            .await
+            .expect("the script should stop at its duration limit")
+            .expect_err("the script should fail")
+            .to_string();
+        assert!(err.starts_with("script.limit_exceeded"), "unexpected error: {err}");
+    }
+
+    #[tokio::test]
+    async fn test_script_duration_limit_stops_synchronous_loops() {
+        // each loop stays within the loop budget, so only the duration limit stops the script
+        let runaway = limited(
+            "for (;;) { for (let i = 0; i < 1000; i++) {} }",
+            ScriptLimits {
+                max_duration: Some(Duration::from_millis(500)),
+                ..Default::default()
+            },
+        );
+        let other = ScriptTarget {
+            code: "await new Promise((resolve) => setTimeout(resolve, 50)); output.done = true;".into(),
+            ..Default::default()
+        };
+        let cancel = AtomicBool::new(false);
+
+        let started = tokio::time::Instant::now();
+        let timed = |target: ScriptTarget| {
+            let cancel = &cancel;
+            async move {
+                let result = target.run(cancel).await;
+                (result, started.elapsed())
+            }
+        };
+
+        let ((runaway, runaway_elapsed), (other, other_elapsed)) = tokio::time::timeout(
+            Duration::from_secs(5),
+            futures::future::join(timed(runaway), timed(other)),
+        )
+        .await
+        .expect("the runaway script should stop at its duration limit");
+
+        let err = runaway.expect_err("the runaway script should fail").to_string();
+        assert!(err.starts_with("script.limit_exceeded"), "unexpected error: {err}");
+        assert!(runaway_elapsed < Duration::from_secs(2), "the runaway script ran for {runaway_elapsed:?}");
+
+        // ...while the other probe's script kept running alongside it
+        let sample = other.expect("the other script should complete");
+        assert_eq!(sample.get("done"), &SampleValue::from(true));
+        assert!(other_elapsed < runaway_elapsed, "the other script was held up by the runaway one");
+    }
+
+    #[tokio::test]
+    async fn test_script_instruction_limit() {
+        let limits = ScriptLimits {
+            max_instructions: 100_000,
+            ..Default::default()
+        };
+        let cancel = AtomicBool::new(false);
+
+        // the budget covers the whole run, not each loop on its own
+        let err = limited(
+            "for (let i = 0; i < 900; i++) { for (let j = 0; j < 900; j++) {} }",
+            limits.clone(),
+        )
+        .run(&cancel)
+        .await
+        .expect_err("the instruction budget should stop the script")
+        .to_string();
+        assert!(err.starts_with("script.limit_exceeded"), "unexpected error: {err}");
+
+        limited("for (let i = 0; i < 100; i++) {}", limits)
+            .run(&cancel)
+            .await
+            .expect("scripts within the budget run to completion");
+    }
+
+    #[tokio::test]
+    async fn test_script_heap_limit() {
+        let target = limited(
+            r#"
+            const chunks = [];
+            for (let i = 0; i < 64; i++) {
+                chunks.push("x".repeat(1024 * 1024));
+                await Promise.resolve();
+            }
+            "#,
+            ScriptLimits {
+                max_heap_size: 32 * 1024 * 1024,
+                ..Default::default()
+            },
+        );
+        let cancel = AtomicBool::new(false);
+
+        let err = target
+            .run(&cancel)
+            .await
+            .expect_err("the heap budget should stop the script")
+            .to_string();
+        assert!(err.starts_with("script.limit_exceeded"), "unexpected error: {err}");
+    }
+
+    #[tokio::test]
+    async fn test_script_waits_for_abandoned_runs() {
+        let target = limited(
+            "for (;;) { for (let i = 0; i < 1000; i++) {} }",
+            ScriptLimits {
+                max_duration: Some(Duration::from_millis(100)),
+                max_instructions: 30_000_000,
+                ..Default::default()
+            },
+        );
+        let cancel = AtomicBool::new(false);
+
+        target.run(&cancel).await.expect_err("the run should be abandoned");
+
+        // the abandoned run is still spinning, so the next one is refused rather than piling up
+        let err = target
+            .clone()
+            .run(&cancel)
+            .await
+            .expect_err("the previous run is still executing")
+            .to_string();
+        assert!(err.contains("previous run"), "unexpected error: {err}");
+
+        // ...until it exhausts its instruction budget
+        let mut waited = Duration::ZERO;
+        while target.running.load(Ordering::Acquire) && waited < Duration::from_secs(60) {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            waited += Duration::from_millis(50);
+        }
+        assert!(!target.running.load(Ordering::Acquire), "the abandoned run never finished");
+    }
+
+    #[tokio::test]
+    async fn test_script_honours_cancellation() {
+        let target = ScriptTarget {
+            code: "await new Promise((resolve) => setTimeout(resolve, 5000));".into(),
+            ..Default::default()
+        };
+        let cancel = AtomicBool::new(true);
+
+        let err = tokio::time::timeout(Duration::from_secs(1), target.run(&cancel))
+            .await
+            .expect("a cancelled script should stop promptly")
+            .expect_err("a cancelled script should fail");
+        assert_eq!(err.to_string(), "The script was cancelled.");
+    }
+
+    #[test]
+    fn test_script_limits_deserialize() {
+        let target: ScriptTarget = serde_yaml::from_str(
+            "code: output.x = 1\nlimits:\n  max_loop_iterations: 500\n  max_duration: 2s\n",
+        )
+        .unwrap();
+        assert_eq!(target.limits.max_loop_iterations, 500);
+        assert_eq!(target.limits.max_duration, Some(Duration::from_secs(2)));
+        assert_eq!(target.limits.max_recursion_depth, None);
+        assert_eq!(target.limits.max_instructions, 100_000_000);
+        assert_eq!(target.limits.max_heap_size, 256 * 1024 * 1024);
+
+        let target: ScriptTarget = serde_yaml::from_str("code: output.x = 1\n").unwrap();
+        assert_eq!(target.limits, ScriptLimits::default());
+    }
+
+    #[test]
+    fn test_script_net_allow_list_deserialize() {
+        let target: ScriptTarget = serde_yaml::from_str(
+            "code: output.x = 1\nnet:\n  allow:\n    - redis.internal:6379\n    - \"*.example.com:*\"\n",
+        )
+        .unwrap();
+        assert!(target.net.permits("redis.internal", 6379));
+        assert!(target.net.permits("api.example.com", 443));
+        assert!(!target.net.permits("redis.internal", 6380));
+
+        let target: ScriptTarget = serde_yaml::from_str("code: output.x = 1\n").unwrap();
+        assert_eq!(target.net, NetPolicy::default());
+
+        serde_yaml::from_str::<ScriptTarget>("code: output.x = 1\nnet:\n  allow:\n    - redis.internal\n")
+            .expect_err("rules without a port should be rejected");
+    }
+
     #[tokio::test]
     async fn test_script_json() {
         let target = ScriptTarget {
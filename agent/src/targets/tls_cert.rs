@@ -23,6 +23,7 @@ use tracing_batteries::prelude::opentelemetry::trace::SpanKind as OpenTelemetryS
 use tracing_batteries::prelude::*;
 use x509_parser::prelude::*;
 
+use super::host_of;
 use crate::{Sample, Target};
 
 lazy_static! {
@@ -44,6 +45,19 @@ lazy_static! {
     };
 }
 
+/// A client configuration which verifies servers against the system's trusted roots, for the
+/// script runtime's `net.connect`, which needs an ordinary verified TLS session rather than the
+/// certificate inspection this target performs.
+#[cfg(feature = "scripts")]
+pub(crate) fn tls_client_config(alpn: &[String]) -> Result<ClientConfig, Box<dyn std::error::Error>> {
+    let mut config = ClientConfig::builder_with_provider(PROVIDER.clone())
+        .with_safe_default_protocol_versions()?
+        .with_root_certificates(NATIVE_ROOTS.clone())
+        .with_no_client_auth();
+    config.alpn_protocols = alpn.iter().map(|p| p.as_bytes().to_vec()).collect();
+    Ok(config)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct TlsCertTarget {
     pub host: String,
@@ -303,15 +317,6 @@ impl ServerCertVerifier for CapturingVerifier {
     }
 }
 
-/// Strips the port (and any IPv6 brackets) from a `host:port` pair.
-fn host_of(host: &str) -> &str {
-    if let Some(rest) = host.strip_prefix('[') {
-        return rest.split(']').next().unwrap_or(rest);
-    }
-
-    host.rsplit_once(':').map(|(host, _)| host).unwrap_or(host)
-}
-
 fn common_name(name: &X509Name<'_>) -> Option<String> {
     name.iter_common_name()
         .next()
@@ -0,0 +1,288 @@
+//! OpenTelemetry metrics, exported over OTLP alongside the traces `tracing_batteries` emits, so a
+//! collector can build dashboards without scraping the status API.
+//!
+//! Unlike the `/metrics` endpoint (see [`crate::metrics`]), which derives its values from the pooled
+//! state when scraped, these instruments are recorded by the node on which each event happens, tagged
+//! with its `node.id`: a probe run by the node which observed it, a cron run by the node which
+//! received its check-in, and a webhook delivery by the node which sent it. Summing those across the
+//! cluster counts each event once. Missed and stuck cron runs are the exception: every node monitors
+//! every cron and records the faults it detects, so they are counted once per node and should be
+//! read from a single `node.id` (or the maximum across nodes) rather than summed.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::time::Duration;
+
+use opentelemetry_otlp::MetricExporter;
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::metrics::SdkMeterProvider;
+use tracing_batteries::prelude::opentelemetry::{
+    KeyValue, global,
+    metrics::{Counter, Histogram, Meter},
+};
+
+/// Bucket boundaries for probe durations, in seconds: a probe is expected to finish within its
+/// timeout, which is rarely more than a minute.
+const PROBE_DURATION_BOUNDARIES: [f64; 14] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0,
+];
+
+/// Bucket boundaries for cron run durations, in seconds: from a few seconds up to a day.
+const CRON_DURATION_BOUNDARIES: [f64; 12] = [
+    1.0, 5.0, 15.0, 30.0, 60.0, 300.0, 900.0, 1800.0, 3600.0, 7200.0, 21600.0, 86400.0,
+];
+
+lazy_static! {
+    static ref INSTRUMENTS: Instruments = Instruments::new(&global::meter("grey"));
+}
+
+struct Instruments {
+    probe_duration: Histogram<f64>,
+    probe_runs: Counter<u64>,
+    cron_duration: Histogram<f64>,
+    cron_runs: Counter<u64>,
+    webhook_deliveries: Counter<u64>,
+}
+
+impl Instruments {
+    fn new(meter: &Meter) -> Self {
+        Self {
+            probe_duration: meter
+                .f64_histogram("grey.probe.duration")
+                .with_unit("s")
+                .with_description("The time taken by each probe run, including retries.")
+                .with_boundaries(PROBE_DURATION_BOUNDARIES.to_vec())
+                .build(),
+            probe_runs: meter
+                .u64_counter("grey.probe.runs")
+                .with_description("The probe runs completed, by `probe.outcome` (`pass` or `fail`).")
+                .build(),
+            cron_duration: meter
+                .f64_histogram("grey.cron.duration")
+                .with_unit("s")
+                .with_description("The time taken by each cron run which reported both its start and its completion.")
+                .with_boundaries(CRON_DURATION_BOUNDARIES.to_vec())
+                .build(),
+            cron_runs: meter
+                .u64_counter("grey.cron.runs")
+                .with_description(
+                    "The cron runs completed or detected, by `cron.outcome` (`succeeded`, `failed`, `missed` or `stuck`).",
+                )
+                .build(),
+            webhook_deliveries: meter
+                .u64_counter("grey.webhook.deliveries")
+                .with_description("The webhook deliveries attempted, by `webhook.outcome` (`success` or `failure`).")
+                .build(),
+        }
+    }
+}
+
+/// Starts exporting metrics over OTLP when an endpoint is configured through the standard
+/// `OTEL_EXPORTER_OTLP_ENDPOINT` (or `OTEL_EXPORTER_OTLP_METRICS_ENDPOINT`) environment variables, as
+/// for traces. The returned provider must be shut down on exit to flush the final export. Without an
+/// endpoint the instruments record into the default no-op provider.
+///
+/// Must be called before anything is recorded, since the instruments bind to the global provider the
+/// first time they are used.
+pub fn init(service_version: String) -> Result<Option<SdkMeterProvider>, Box<dyn Error>> {
+    let configured = ["OTEL_EXPORTER_OTLP_ENDPOINT", "OTEL_EXPORTER_OTLP_METRICS_ENDPOINT"]
+        .iter()
+        .any(|var| std::env::var(var).is_ok_and(|value| !value.is_empty()));
+    if !configured {
+        return Ok(None);
+    }
+
+    let exporter = MetricExporter::builder()
+        .with_tonic()
+        .build()
+        .map_err(|e| format!("Failed to configure the OTLP metrics exporter: {e}"))?;
+
+    let provider = SdkMeterProvider::builder()
+        .with_periodic_exporter(exporter)
+        .with_resource(
+            Resource::builder()
+                .with_service_name("grey")
+                .with_attribute(KeyValue::new("service.version", service_version))
+                .build(),
+        )
+        .build();
+    global::set_meter_provider(provider.clone());
+
+    Ok(Some(provider))
+}
+
+/// Records a completed probe run as observed by `node_id`.
+pub fn record_probe_run(
+    name: &str,
+    tags: &HashMap<String, String>,
+    node_id: &str,
+    pass: bool,
+    duration: Duration,
+) {
+    INSTRUMENTS.record_probe_run(name, tags, node_id, pass, duration);
+}
+
+/// Records a cron run which `node_id` saw finish (`succeeded` or `failed`) or detected as `missed`
+/// or `stuck`. A `duration` is only known for a run which checked in both when it started and when
+/// it finished.
+pub fn record_cron_run(
+    name: &str,
+    tags: &HashMap<String, String>,
+    node_id: &str,
+    outcome: &'static str,
+    duration: Option<Duration>,
+) {
+    INSTRUMENTS.record_cron_run(name, tags, node_id, outcome, duration);
+}
+
+/// Records an attempt to deliver a webhook event.
+pub fn record_webhook_delivery(webhook: &str, event: &'static str, success: bool) {
+    INSTRUMENTS.record_webhook_delivery(webhook, event, success);
+}
+
+impl Instruments {
+    fn record_probe_run(
+        &self,
+        name: &str,
+        tags: &HashMap<String, String>,
+        node_id: &str,
+        pass: bool,
+        duration: Duration,
+    ) {
+        let mut attributes = tag_attributes(tags);
+        attributes.push(KeyValue::new("probe.name", name.to_string()));
+        attributes.push(KeyValue::new("node.id", node_id.to_string()));
+
+        self.probe_duration.record(duration.as_secs_f64(), &attributes);
+
+        attributes.push(KeyValue::new("probe.outcome", if pass { "pass" } else { "fail" }));
+        self.probe_runs.add(1, &attributes);
+    }
+
+    fn record_cron_run(
+        &self,
+        name: &str,
+        tags: &HashMap<String, String>,
+        node_id: &str,
+        outcome: &'static str,
+        duration: Option<Duration>,
+    ) {
+        let mut attributes = tag_attributes(tags);
+        attributes.push(KeyValue::new("cron.name", name.to_string()));
+        attributes.push(KeyValue::new("node.id", node_id.to_string()));
+        attributes.push(KeyValue::new("cron.outcome", outcome));
+
+        if let Some(duration) = duration {
+            self.cron_duration.record(duration.as_secs_f64(), &attributes);
+        }
+        self.cron_runs.add(1, &attributes);
+    }
+
+    fn record_webhook_delivery(&self, webhook: &str, event: &'static str, success: bool) {
+        self.webhook_deliveries.add(
+            1,
+            &[
+                KeyValue::new("webhook.name", webhook.to_string()),
+                KeyValue::new("event.kind", event),
+                KeyValue::new("webhook.outcome", if success { "success" } else { "failure" }),
+            ],
+        );
+    }
+}
+
+/// An entity's tags as `tags.<key>` attributes, the names they go by in SLO selectors and webhook
+/// filters.
+fn tag_attributes(tags: &HashMap<String, String>) -> Vec<KeyValue> {
+    tags.iter()
+        .map(|(key, value)| KeyValue::new(format!("tags.{key}"), value.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry_sdk::metrics::InMemoryMetricExporter;
+    use opentelemetry_sdk::metrics::data::{AggregatedMetrics, MetricData};
+    use tracing_batteries::prelude::opentelemetry::metrics::MeterProvider;
+
+    #[test]
+    fn test_tag_attributes() {
+        let tags = HashMap::from([("service".to_string(), "Web".to_string())]);
+        assert_eq!(
+            tag_attributes(&tags),
+            vec![KeyValue::new("tags.service", "Web")]
+        );
+    }
+
+    #[test]
+    fn test_records_data_points() {
+        let exporter = InMemoryMetricExporter::default();
+        let provider = SdkMeterProvider::builder()
+            .with_periodic_exporter(exporter.clone())
+            .build();
+        let instruments = Instruments::new(&provider.meter("grey"));
+
+        let tags = HashMap::from([("service".to_string(), "Web".to_string())]);
+        instruments.record_probe_run("web", &tags, "node1", true, Duration::from_millis(120));
+        instruments.record_probe_run("web", &tags, "node1", false, Duration::from_millis(80));
+        instruments.record_cron_run("backup", &HashMap::new(), "node1", "succeeded", Some(Duration::from_secs(30)));
+        instruments.record_cron_run("backup", &HashMap::new(), "node1", "missed", None);
+        instruments.record_webhook_delivery("ops", "probe.state_changed", false);
+        provider.force_flush().unwrap();
+
+        let metrics = exporter.get_finished_metrics().unwrap();
+        let metric = |name: &str| {
+            metrics
+                .iter()
+                .flat_map(|resource| resource.scope_metrics())
+                .flat_map(|scope| scope.metrics())
+                .find(|metric| metric.name() == name)
+                .unwrap_or_else(|| panic!("expected the {name} metric to be exported"))
+                .data()
+                .clone()
+        };
+        let sums = |name: &str| match metric(name) {
+            AggregatedMetrics::U64(MetricData::Sum(sum)) => sum
+                .data_points()
+                .map(|point| (point.attributes().cloned().collect::<Vec<_>>(), point.value()))
+                .collect::<Vec<_>>(),
+            other => panic!("expected {name} to be a u64 sum, got {other:?}"),
+        };
+        let has = |attributes: &[KeyValue], key: &'static str, value: &'static str| {
+            attributes.contains(&KeyValue::new(key, value))
+        };
+
+        let runs = sums("grey.probe.runs");
+        assert_eq!(runs.len(), 2, "{runs:?}");
+        for (attributes, value) in &runs {
+            assert_eq!(*value, 1);
+            assert!(has(attributes, "probe.name", "web") && has(attributes, "node.id", "node1"));
+            assert!(has(attributes, "tags.service", "Web"));
+        }
+        assert!(runs.iter().any(|(attributes, _)| has(attributes, "probe.outcome", "fail")));
+
+        match metric("grey.probe.duration") {
+            AggregatedMetrics::F64(MetricData::Histogram(histogram)) => {
+                let point = histogram.data_points().next().expect("a duration data point");
+                assert_eq!(point.count(), 2);
+                assert!((point.sum() - 0.2).abs() < 1e-9, "{}", point.sum());
+            }
+            other => panic!("expected the probe duration to be a histogram, got {other:?}"),
+        }
+
+        let crons = sums("grey.cron.runs");
+        assert_eq!(crons.len(), 2, "{crons:?}");
+        assert!(crons.iter().any(|(attributes, value)| *value == 1 && has(attributes, "cron.outcome", "missed")));
+        match metric("grey.cron.duration") {
+            AggregatedMetrics::F64(MetricData::Histogram(histogram)) => {
+                let point = histogram.data_points().next().expect("a duration data point");
+                assert_eq!(point.count(), 1, "only runs with a known duration are recorded");
+            }
+            other => panic!("expected the cron duration to be a histogram, got {other:?}"),
+        }
+
+        let deliveries = sums("grey.webhook.deliveries");
+        assert_eq!(deliveries.len(), 1);
+        assert!(has(&deliveries[0].0, "webhook.outcome", "failure"));
+    }
+}
@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 use std::time::Duration;
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, TimeZone, Utc};
 use serde::{Deserialize, Serialize};
 
 use crate::Streak;
@@ -31,14 +31,21 @@ impl CronSchedule {
     /// interval`; for a crontab it is the next matching wall-clock time. `None` if a crontab
     /// expression fails to parse (config load rejects those, so this is defensive).
     pub fn next_due_after(&self, from: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.next_due_after_in(&from)
+    }
+
+    /// As [`CronSchedule::next_due_after`], but evaluating a crontab against the wall clock of
+    /// `from`'s timezone rather than UTC — so `0 9 * * *` fires at 09:00 local time across DST
+    /// changes. Probes use this to honour their policy's configured `timezone`.
+    pub fn next_due_after_in<Tz: TimeZone>(&self, from: &DateTime<Tz>) -> Option<DateTime<Tz>> {
         match self {
             CronSchedule::Every(interval) => {
-                Some(from + chrono::Duration::from_std(*interval).ok()?)
+                Some(from.clone() + chrono::Duration::from_std(*interval).ok()?)
             }
             CronSchedule::Cron(expr) => expr
                 .parse::<croner::Cron>()
                 .ok()?
-                .find_next_occurrence(&from, false)
+                .find_next_occurrence(from, false)
                 .ok(),
         }
     }
@@ -276,6 +283,12 @@ pub struct Cron {
     /// and the recovery from it. `None` falls back to [`Streak::default_recovery_window`].
     #[serde(default, with = "humantime_serde::option")]
     pub debounce: Option<Duration>,
+
+    /// The dependencies named in the cron's `depends_on` which currently read as unhealthy (stamped
+    /// by the agent). While any is listed, a failure of this cron is attributed to the upstream
+    /// outage and is not alerted on (see [`Cron::blocked`]).
+    #[serde(default)]
+    pub blocked_by: Vec<String>,
 }
 
 impl Cron {
@@ -299,6 +312,7 @@ impl Cron {
             last_checkin: None,
             streak: Streak::default(),
             debounce: None,
+            blocked_by: Vec::new(),
         }
     }
 
@@ -463,6 +477,12 @@ impl Cron {
         }
     }
 
+    /// Whether the cron is unhealthy at `now` while one of its dependencies is too, so its failure is
+    /// attributed to the upstream outage rather than reported in its own right.
+    pub fn blocked(&self, now: DateTime<Utc>) -> bool {
+        !self.blocked_by.is_empty() && !self.health(now, self.window()).passing()
+    }
+
     /// When the current health state was entered, computed analytically (no sampling loop or streak
     /// register). Takes the already-computed [`Cron::health`] so the state machine — and any crontab
     /// parse it performs — isn't evaluated a second time.
@@ -576,6 +596,8 @@ mod tests {
             failing_since: Some(base),
             failing_until: Some(base + window),
             covered_since: None,
+            degraded_since: None,
+            degraded_until: None,
         };
 
         // Raw health is failed immediately, but the debounced health holds healthy until the window.
@@ -727,6 +749,27 @@ mod tests {
         assert_eq!(c.health(after, win()), CronHealth::Missing);
     }
 
+    #[test]
+    fn crontab_is_evaluated_in_the_given_timezone() {
+        let schedule = CronSchedule::Cron("0 9 * * *".into());
+        let tz = chrono::FixedOffset::east_opt(2 * 3600).unwrap();
+
+        // 06:00Z is 08:00 at +02:00, so the next 09:00 local is 07:00Z the same day.
+        let from = DateTime::parse_from_rfc3339("2026-01-01T06:00:00Z").unwrap().with_timezone(&tz);
+        let next = schedule.next_due_after_in(&from).unwrap();
+        assert_eq!(
+            next.with_timezone(&Utc),
+            DateTime::parse_from_rfc3339("2026-01-01T07:00:00Z").unwrap().with_timezone(&Utc)
+        );
+
+        // The UTC form still evaluates the expression against UTC.
+        let next = schedule.next_due_after(from.with_timezone(&Utc)).unwrap();
+        assert_eq!(
+            next,
+            DateTime::parse_from_rfc3339("2026-01-01T09:00:00Z").unwrap().with_timezone(&Utc)
+        );
+    }
+
     #[test]
     fn invalid_crontab_is_detectable() {
         assert!(every(60).is_valid());
@@ -754,7 +797,7 @@ mod tests {
         // A fresh fault younger than the window has not tripped the debounced health yet, so it
         // still tracks the last run (matching the dot, which is still healthy).
         let base = ts(2_000);
-        c.streak = Streak { failing_since: Some(base), failing_until: Some(base), covered_since: Some(ts(500)) };
+        c.streak = Streak { failing_since: Some(base), failing_until: Some(base), covered_since: Some(ts(500)), degraded_since: None, degraded_until: None };
         assert!(!c.streak.failing_for(base, window));
         assert_eq!(c.last_success(base), Some(ts(1_100)));
 
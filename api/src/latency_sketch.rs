@@ -0,0 +1,255 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Mergeable;
+
+/// The relative accuracy of the quantiles a [`LatencySketch`] reports: each is within 2% of the true
+/// latency at that rank.
+const RELATIVE_ACCURACY: f64 = 0.02;
+
+/// The most bins a sketch keeps. Beyond this the lowest bins are collapsed together, trading accuracy
+/// at the fast end (which matters least for tail latency) for a bounded size on the wire: a sketch is
+/// gossiped with every observation of a probe, so a full one must stay well under a kilobyte. At 2%
+/// accuracy, 128 bins cover latencies spanning a factor of ~165 before any are collapsed.
+const MAX_BINS: usize = 128;
+
+/// Latencies below this many milliseconds are counted as zero.
+const MIN_INDEXABLE_MS: f64 = 0.001;
+
+/// A mergeable summary of a set of latencies which answers quantile queries with bounded relative
+/// error (a [DDSketch](https://arxiv.org/abs/1908.10693)).
+///
+/// Each latency is counted in a logarithmically sized bin, so two sketches merge exactly by summing
+/// their bins, however their samples were split between observers or buckets.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LatencySketch {
+    #[serde(default)]
+    zero: u64,
+    #[serde(default)]
+    bins: BTreeMap<i32, u64>,
+}
+
+impl LatencySketch {
+    fn gamma() -> f64 {
+        (1.0 + RELATIVE_ACCURACY) / (1.0 - RELATIVE_ACCURACY)
+    }
+
+    /// Records a single latency.
+    pub fn add(&mut self, latency: Duration) {
+        let ms = latency.as_secs_f64() * 1000.0;
+        if ms < MIN_INDEXABLE_MS {
+            self.zero += 1;
+        } else {
+            let index = (ms.ln() / Self::gamma().ln()).ceil() as i32;
+            *self.bins.entry(index).or_default() += 1;
+            self.collapse();
+        }
+    }
+
+    /// The number of latencies recorded.
+    pub fn count(&self) -> u64 {
+        self.zero + self.bins.values().sum::<u64>()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count() == 0
+    }
+
+    /// The latency at quantile `q` (between `0.0` and `1.0`), or `None` if nothing was recorded.
+    pub fn quantile(&self, q: f64) -> Option<Duration> {
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+
+        let rank = (q.clamp(0.0, 1.0) * (count - 1) as f64).floor() as u64;
+        if rank < self.zero {
+            return Some(Duration::ZERO);
+        }
+
+        let gamma = Self::gamma();
+        let mut seen = self.zero;
+        for (index, bin) in &self.bins {
+            seen += bin;
+            if seen > rank {
+                let ms = 2.0 * gamma.powi(*index) / (gamma + 1.0);
+                return Some(Duration::from_secs_f64(ms / 1000.0));
+            }
+        }
+
+        None
+    }
+
+    /// The mean and variance of the recorded latencies, in milliseconds (and milliseconds squared),
+    /// estimated from each bin's representative latency. `None` if nothing was recorded.
+    pub fn moments(&self) -> Option<(f64, f64)> {
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+
+        let gamma = Self::gamma();
+        let values = || {
+            self.bins
+                .iter()
+                .map(move |(index, bin)| (2.0 * gamma.powi(*index) / (gamma + 1.0), *bin as f64))
+        };
+
+        let mean = values().map(|(ms, bin)| ms * bin).sum::<f64>() / count as f64;
+        let variance = (self.zero as f64 * mean * mean
+            + values().map(|(ms, bin)| (ms - mean).powi(2) * bin).sum::<f64>())
+            / count as f64;
+        Some((mean, variance))
+    }
+
+    /// The median, 90th and 99th percentile latencies.
+    pub fn percentiles(&self) -> LatencyPercentiles {
+        LatencyPercentiles {
+            p50: self.quantile(0.5),
+            p90: self.quantile(0.9),
+            p99: self.quantile(0.99),
+        }
+    }
+
+    /// Folds the lowest bins together until no more than [`MAX_BINS`] remain.
+    fn collapse(&mut self) {
+        while self.bins.len() > MAX_BINS {
+            if let Some((_, lowest)) = self.bins.pop_first()
+                && let Some(mut next) = self.bins.first_entry()
+            {
+                *next.get_mut() += lowest;
+            }
+        }
+    }
+}
+
+impl Mergeable for LatencySketch {
+    fn merge(&mut self, other: &Self) {
+        self.zero += other.zero;
+        for (index, bin) in &other.bins {
+            *self.bins.entry(*index).or_default() += bin;
+        }
+        self.collapse();
+    }
+}
+
+/// The headline percentiles of a [`LatencySketch`], each `None` when it holds no latencies.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct LatencyPercentiles {
+    #[serde(default, with = "crate::serializers::duration_ms_option")]
+    pub p50: Option<Duration>,
+    #[serde(default, with = "crate::serializers::duration_ms_option")]
+    pub p90: Option<Duration>,
+    #[serde(default, with = "crate::serializers::duration_ms_option")]
+    pub p99: Option<Duration>,
+}
+
+/// The serialized form of a [`LatencySketch`], which is collapsed to [`MAX_BINS`] once read so a peer
+/// (or an older record) can't hand us an unbounded sketch. Only the bins are stored and gossiped: the
+/// percentiles derived from them are left to the API's response types.
+#[derive(Deserialize)]
+struct LatencySketchRepr {
+    #[serde(default)]
+    zero: u64,
+    #[serde(default)]
+    bins: BTreeMap<i32, u64>,
+}
+
+impl<'de> Deserialize<'de> for LatencySketch {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = LatencySketchRepr::deserialize(deserializer)?;
+        let mut sketch = LatencySketch {
+            zero: repr.zero,
+            bins: repr.bins,
+        };
+        sketch.collapse();
+        Ok(sketch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sketch(latencies_ms: impl IntoIterator<Item = u64>) -> LatencySketch {
+        let mut sketch = LatencySketch::default();
+        for ms in latencies_ms {
+            sketch.add(Duration::from_millis(ms));
+        }
+        sketch
+    }
+
+    fn assert_close(actual: Option<Duration>, expected_ms: f64) {
+        let actual = actual.expect("a quantile").as_secs_f64() * 1000.0;
+        assert!(
+            (actual - expected_ms).abs() <= expected_ms * RELATIVE_ACCURACY,
+            "{actual}ms should be within 1% of {expected_ms}ms"
+        );
+    }
+
+    #[test]
+    fn test_quantiles() {
+        let sketch = sketch(1..=1000);
+        assert_eq!(sketch.count(), 1000);
+        assert_close(sketch.quantile(0.5), 500.0);
+        assert_close(sketch.quantile(0.9), 900.0);
+        assert_close(sketch.quantile(0.99), 990.0);
+        assert_close(sketch.quantile(1.0), 1000.0);
+
+        assert_eq!(LatencySketch::default().quantile(0.5), None);
+        assert_eq!(LatencySketch::default().percentiles(), LatencyPercentiles::default());
+    }
+
+    #[test]
+    fn test_moments() {
+        let (mean, variance) = sketch([100, 200, 300]).moments().expect("moments");
+        assert!((mean - 200.0).abs() <= 2.0, "{mean}");
+        assert!((variance.sqrt() - 81.6).abs() <= 2.0, "{}", variance.sqrt());
+
+        let (mean, variance) = sketch([0, 100]).moments().expect("moments");
+        assert!((mean - 50.0).abs() <= 1.0, "{mean}");
+        assert!((variance.sqrt() - 50.0).abs() <= 1.0, "{}", variance.sqrt());
+
+        assert_eq!(LatencySketch::default().moments(), None);
+    }
+
+    #[test]
+    fn test_zero_latencies() {
+        let sketch = sketch([0, 0, 0, 100]);
+        assert_eq!(sketch.quantile(0.5), Some(Duration::ZERO));
+        assert_close(sketch.quantile(1.0), 100.0);
+    }
+
+    #[test]
+    fn test_merge_matches_a_single_sketch() {
+        let mut merged = sketch(1..=500);
+        merged.merge(&sketch(501..=1000));
+        assert_eq!(merged, sketch(1..=1000));
+    }
+
+    #[test]
+    fn test_bins_are_bounded() {
+        let mut sketch = LatencySketch::default();
+        for i in 0..2000 {
+            sketch.add(Duration::from_secs_f64(1.01f64.powi(i) / 1_000_000.0));
+        }
+        assert_eq!(sketch.bins.len(), MAX_BINS);
+        assert_eq!(sketch.count(), 2000);
+        assert_close(sketch.quantile(1.0), 1.01f64.powi(1999) / 1000.0);
+    }
+
+    #[test]
+    fn test_serialization() {
+        let sketch = sketch([10, 20, 30]);
+
+        let json = serde_json::to_value(&sketch).unwrap();
+        assert!(json.get("percentiles").is_none(), "derived percentiles are not serialized: {json}");
+        assert_eq!(serde_json::from_value::<LatencySketch>(json).unwrap(), sketch);
+
+        for packed in [rmp_serde::to_vec(&sketch).unwrap(), rmp_serde::to_vec_named(&sketch).unwrap()] {
+            assert_eq!(rmp_serde::from_slice::<LatencySketch>(&packed).unwrap(), sketch);
+        }
+    }
+}
@@ -3,9 +3,13 @@ mod error;
 mod etag;
 mod identifier;
 mod incident;
+mod latency_sketch;
 mod probe;
+mod probe_history;
 mod probe_history_bucket;
+mod probe_sample;
 mod serializers;
+mod slo;
 mod streak;
 mod ui;
 mod peer;
@@ -17,10 +21,14 @@ pub use error::*;
 pub use etag::*;
 pub use identifier::*;
 pub use incident::*;
+pub use latency_sketch::*;
 pub use observation::*;
 pub use peer::*;
 pub use probe::*;
+pub use probe_history::*;
 pub use probe_history_bucket::*;
+pub use probe_sample::*;
+pub use slo::*;
 pub use streak::*;
 pub use ui::*;
 pub use webhook::*;
@@ -1,5 +1,20 @@
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
-use crate::Mergeable;
+use crate::{CheckCounts, LatencyPercentiles, LatencySketch, Mergeable, ValidationResult};
+
+/// The most checks an [`Observation`] counts. An observation is gossiped with every bucket of its
+/// probe's history, so a script which names its tests dynamically mustn't be able to grow it without
+/// bound: checks beyond the first this many are evaluated as usual but not counted.
+pub const MAX_CHECKS: usize = 32;
+
+/// The key a check's counts are kept under in [`Observation::checks`]: a stable (FNV-1a) hash of the
+/// check's name, so that the name itself isn't repeated in every observation. Names are resolved
+/// again from the validations recorded alongside the observation.
+pub fn check_id(name: &str) -> u32 {
+    name.bytes()
+        .fold(0x811c_9dc5, |hash: u32, byte| (hash ^ byte as u32).wrapping_mul(0x0100_0193))
+}
 
 /// Describes an aggregatable observation from a specific observer
 #[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
@@ -20,6 +35,17 @@ pub struct Observation {
     #[serde(rename = "latency")]
     #[serde(with = "crate::serializers::duration_ms")]
     pub total_latency: std::time::Duration,
+
+    /// The distribution of the samples' latencies, from which their percentiles are drawn. Empty for
+    /// observations recorded by agents which pre-date latency percentiles.
+    #[serde(rename = "latencies", default)]
+    pub latency_sketch: LatencySketch,
+
+    /// How often each of the probe's checks passed and failed across these samples, keyed by the
+    /// [`check_id`] of the check's expression (or the name of a validation its target reported). At
+    /// most [`MAX_CHECKS`] are counted.
+    #[serde(default)]
+    pub checks: BTreeMap<u32, CheckCounts>,
 }
 
 impl Observation {
@@ -37,6 +63,30 @@ impl Observation {
         }
         self.total_retries += retries;
         self.total_latency += latency;
+        self.latency_sketch.add(latency);
+    }
+
+    /// Counts the outcome of each check evaluated for a sample.
+    pub fn add_checks<'a>(&mut self, validations: impl IntoIterator<Item = (&'a String, &'a ValidationResult)>) {
+        for (check, validation) in validations {
+            if let Some(counts) = self.counts_mut(check_id(check)) {
+                counts.add(validation.pass);
+            }
+        }
+    }
+
+    /// How often the named check passed and failed across these samples, if it was counted.
+    pub fn check_counts(&self, check: &str) -> Option<CheckCounts> {
+        self.checks.get(&check_id(check)).copied()
+    }
+
+    /// The counts of the check with the given id, or `None` once [`MAX_CHECKS`] others are counted.
+    fn counts_mut(&mut self, id: u32) -> Option<&mut CheckCounts> {
+        if self.checks.len() >= MAX_CHECKS && !self.checks.contains_key(&id) {
+            return None;
+        }
+
+        Some(self.checks.entry(id).or_default())
     }
 
     /// Calculates the success rate for samples in this observation.
@@ -80,6 +130,17 @@ impl Observation {
 
         std::time::Duration::from_millis((self.total_latency.as_millis() / (self.total_samples as u128)) as u64)
     }
+
+    /// The latency at quantile `q` (between `0.0` and `1.0`) across this observation's samples, or
+    /// `None` when none of them recorded their latency's distribution.
+    pub fn latency_percentile(&self, q: f64) -> Option<std::time::Duration> {
+        self.latency_sketch.quantile(q)
+    }
+
+    /// The median, 90th and 99th percentile latencies of this observation's samples.
+    pub fn latency_percentiles(&self) -> LatencyPercentiles {
+        self.latency_sketch.percentiles()
+    }
 }
 
 impl Mergeable for Observation {
@@ -88,6 +149,12 @@ impl Mergeable for Observation {
         self.successful_samples += other.successful_samples;
         self.total_retries += other.total_retries;
         self.total_latency += other.total_latency;
+        self.latency_sketch.merge(&other.latency_sketch);
+        for (id, counts) in &other.checks {
+            if let Some(existing) = self.counts_mut(*id) {
+                existing.merge(counts);
+            }
+        }
     }
 }
 
@@ -102,6 +169,8 @@ mod tests {
             successful_samples: 8,
             total_retries: 2,
             total_latency: std::time::Duration::from_millis(500),
+            latency_sketch: Default::default(),
+            checks: Default::default(),
         };
 
         let obs2 = Observation {
@@ -109,6 +178,8 @@ mod tests {
             successful_samples: 4,
             total_retries: 1,
             total_latency: std::time::Duration::from_millis(300),
+            latency_sketch: Default::default(),
+            checks: Default::default(),
         };
 
         obs1.merge(&obs2);
@@ -126,6 +197,8 @@ mod tests {
             successful_samples: 8,
             total_retries: 2,
             total_latency: std::time::Duration::from_millis(500),
+            latency_sketch: Default::default(),
+            checks: Default::default(),
         };
 
         assert_eq!(obs.success_rate(), 80.0);
@@ -133,6 +206,93 @@ mod tests {
         assert_eq!(obs.average_latency(), std::time::Duration::from_millis(50));
     }
     
+    #[test]
+    fn test_latency_percentiles() {
+        let mut obs1 = Observation::default();
+        let mut obs2 = Observation::default();
+        for ms in 1..=100 {
+            let obs = if ms % 2 == 0 { &mut obs1 } else { &mut obs2 };
+            obs.add_sample(true, 0, std::time::Duration::from_millis(ms));
+        }
+
+        obs1.merge(&obs2);
+        let p99 = obs1.latency_percentile(0.99).unwrap().as_secs_f64() * 1000.0;
+        assert!((97.0..=100.0).contains(&p99), "{p99}");
+        assert!(obs1.latency_percentiles().p50.is_some());
+        assert_eq!(Observation::default().latency_percentiles().p50, None);
+    }
+
+    #[test]
+    fn test_check_counts() {
+        let mut obs1 = Observation::default();
+        obs1.add_checks(&[
+            ("http.status == 200".to_string(), ValidationResult::pass()),
+            ("http.body contains \"ok\"".to_string(), ValidationResult::fail("http.body = \"error\"")),
+        ].into_iter().collect::<BTreeMap<_, _>>());
+
+        let mut obs2 = Observation::default();
+        for _ in 0..3 {
+            obs2.add_checks(&[("http.status == 200".to_string(), ValidationResult::pass())].into_iter().collect::<BTreeMap<_, _>>());
+        }
+
+        obs1.merge(&obs2);
+        assert_eq!(obs1.check_counts("http.status == 200"), Some(CheckCounts { passed: 4, failed: 0 }));
+        assert_eq!(obs1.check_counts("http.body contains \"ok\"").unwrap().pass_rate(), 0.0);
+        assert_eq!(obs1.check_counts("http.status == 500"), None);
+    }
+
+    #[test]
+    fn test_check_counts_are_bounded() {
+        let mut obs1 = Observation::default();
+        let mut obs2 = Observation::default();
+        for i in 0..MAX_CHECKS * 2 {
+            let obs = if i % 2 == 0 { &mut obs1 } else { &mut obs2 };
+            obs.add_checks(&[(format!("test {i}"), ValidationResult::pass())].into_iter().collect::<BTreeMap<_, _>>());
+        }
+        assert_eq!(obs1.checks.len(), MAX_CHECKS);
+
+        obs1.merge(&obs2);
+        assert_eq!(obs1.checks.len(), MAX_CHECKS);
+        assert_eq!(obs1.check_counts("test 0"), Some(CheckCounts { passed: 1, failed: 0 }));
+        assert_eq!(obs1.check_counts(&format!("test {}", MAX_CHECKS * 2 - 1)), None);
+    }
+
+    #[test]
+    fn test_check_id_is_stable() {
+        // The ids are gossiped between agents, so they must never change between builds.
+        assert_eq!(check_id(""), 0x811c_9dc5);
+        assert_eq!(check_id("a"), 0xe40c_292c);
+        assert_ne!(check_id("http.status == 200"), check_id("http.status == 201"));
+    }
+
+    #[test]
+    fn test_decodes_legacy_observations() {
+        // Observations recorded by agents which pre-date latency percentiles lack the sketch; they
+        // must decode with an empty one in both wire formats.
+        #[derive(Serialize)]
+        struct LegacyObservation {
+            total: u64,
+            success: u64,
+            retry: u64,
+            #[serde(with = "crate::serializers::duration_ms")]
+            latency: std::time::Duration,
+        }
+
+        let legacy = LegacyObservation {
+            total: 10,
+            success: 8,
+            retry: 2,
+            latency: std::time::Duration::from_millis(500),
+        };
+
+        for packed in [rmp_serde::to_vec(&legacy).unwrap(), rmp_serde::to_vec_named(&legacy).unwrap()] {
+            let unpacked: Observation = rmp_serde::from_slice(&packed).unwrap();
+            assert_eq!(unpacked.total_samples, 10);
+            assert!(unpacked.latency_sketch.is_empty());
+            assert!(unpacked.checks.is_empty());
+        }
+    }
+
     #[test]
     fn test_msgpack_roundtrip() {
         let obs = Observation {
@@ -140,6 +300,8 @@ mod tests {
             successful_samples: 8,
             total_retries: 2,
             total_latency: std::time::Duration::from_millis(500),
+            latency_sketch: Default::default(),
+            checks: Default::default(),
         };
 
         let packed = rmp_serde::to_vec(&obs).unwrap();
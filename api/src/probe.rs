@@ -40,6 +40,18 @@ pub struct Probe {
     /// rather than leaving its history stranded on every peer.
     #[serde(default)]
     pub retired: bool,
+
+    /// The interval the probe is currently being run at (a config echo stamped by the agent): its
+    /// accelerated failing interval while it reads as failing, otherwise its regular interval. `None`
+    /// for a probe which runs on a crontab schedule, or for records from agents predating this field.
+    #[serde(default, with = "humantime_serde::option")]
+    pub interval: Option<std::time::Duration>,
+
+    /// The dependencies named in the probe's `depends_on` which currently read as unhealthy (stamped
+    /// by the agent). While any is listed, a failure of this probe is attributed to the upstream
+    /// outage: it reads as `blocked` rather than `failing` and is not alerted on.
+    #[serde(default)]
+    pub blocked_by: Vec<String>,
 }
 
 impl Probe {
@@ -79,10 +91,35 @@ impl Probe {
         self.streak.since_at(chrono::Utc::now(), self.window())
     }
 
-    /// The derived status token used to describe the probe in notifications: `"passing"` or
-    /// `"failing"`. This is the probe analogue of [`crate::CronHealth::as_str`].
+    /// Whether this probe is passing but degraded: one of its warning checks has been failing for its
+    /// debounce window (or, without a streak record, its latest history bucket was degraded).
+    pub fn degraded(&self) -> bool {
+        if self.streak.is_empty() {
+            self.history.last().map(|h| h.pass && h.degraded).unwrap_or(false)
+        } else {
+            self.passing() && self.streak.degraded_for(chrono::Utc::now(), self.window())
+        }
+    }
+
+    /// Whether this probe is failing while one of its dependencies is too, so its failure is
+    /// attributed to the upstream outage rather than reported in its own right.
+    pub fn blocked(&self) -> bool {
+        !self.blocked_by.is_empty() && !self.passing()
+    }
+
+    /// The derived status token used to describe the probe in notifications: `"passing"`,
+    /// `"degraded"` (passing, but failing a warning check), `"failing"`, or `"blocked"` (failing behind
+    /// a failing dependency). This is the probe analogue of [`crate::CronHealth::as_str`].
     pub fn status_token(&self) -> &'static str {
-        if self.passing() { "passing" } else { "failing" }
+        if self.degraded() {
+            "degraded"
+        } else if self.passing() {
+            "passing"
+        } else if self.blocked() {
+            "blocked"
+        } else {
+            "failing"
+        }
     }
 
     /// Calculate recent availability percentage based on successful vs total samples
@@ -170,9 +207,13 @@ mod tests {
                 successful_samples: 9,
                 total_retries: 2,
                 total_latency: std::time::Duration::from_secs(5),
+                latency_sketch: Default::default(),
+                checks: Default::default(),
             })].into_iter().collect(),
             streak: Streak::default(),
             debounce: None,
+            interval: None,
+            blocked_by: Vec::new(),
             retired: false,
         };
 
@@ -186,9 +227,13 @@ mod tests {
                 successful_samples: 4,
                 total_retries: 1,
                 total_latency: std::time::Duration::from_secs(3),
+                latency_sketch: Default::default(),
+                checks: Default::default(),
             })].into_iter().collect(),
             streak: Streak::default(),
             debounce: None,
+            interval: None,
+            blocked_by: Vec::new(),
             retired: false,
         };
 
@@ -214,16 +259,22 @@ mod tests {
                     successful_samples: 9,
                     total_retries: 2,
                     total_latency: std::time::Duration::from_secs(5),
+                    latency_sketch: Default::default(),
+                    checks: Default::default(),
                 }),
                 ("observer2".into(), Observation {
                     total_samples: 5,
                     successful_samples: 4,
                     total_retries: 1,
                     total_latency: std::time::Duration::from_secs(3),
+                    latency_sketch: Default::default(),
+                    checks: Default::default(),
                 }),
             ].into_iter().collect(),
             streak: Streak::default(),
             debounce: None,
+            interval: None,
+            blocked_by: Vec::new(),
             retired: false,
         };
 
@@ -247,16 +298,22 @@ mod tests {
                     successful_samples: 9,
                     total_retries: 2,
                     total_latency: std::time::Duration::from_secs(5),
+                    latency_sketch: Default::default(),
+                    checks: Default::default(),
                 }),
                 ("observer2".into(), Observation {
                     total_samples: 5,
                     successful_samples: 4,
                     total_retries: 1,
                     total_latency: std::time::Duration::from_secs(3),
+                    latency_sketch: Default::default(),
+                    checks: Default::default(),
                 }),
             ].into_iter().collect(),
             streak: Streak::default(),
             debounce: None,
+            interval: None,
+            blocked_by: Vec::new(),
             retired: false,
         };
 
@@ -275,6 +332,8 @@ mod tests {
             observations: HashMap::new(),
             streak: Streak::default(),
             debounce: None,
+            interval: None,
+            blocked_by: Vec::new(),
             retired: false,
         };
 
@@ -285,6 +344,7 @@ mod tests {
         probe.history.push(ProbeHistoryBucket {
             start_time: now,
             pass: false,
+            degraded: false,
             message: "Timeout".into(),
             validations: HashMap::new(),
             observations: HashMap::new(),
@@ -298,6 +358,42 @@ mod tests {
         assert!(probe.passing());
     }
 
+    /// A failing probe with a failing dependency reads as `blocked`; once it passes, the failing
+    /// dependency no longer matters.
+    #[test]
+    fn test_probe_blocked_by_a_dependency() {
+        let now = chrono::Utc::now();
+        let mut probe = Probe {
+            name: "probe".into(),
+            tags: HashMap::new(),
+            last_updated: now,
+            history: vec![ProbeHistoryBucket {
+                start_time: now,
+                pass: false,
+                degraded: false,
+                message: "Timeout".into(),
+                validations: HashMap::new(),
+                observations: HashMap::new(),
+            }],
+            observations: HashMap::new(),
+            streak: Streak::default(),
+            debounce: None,
+            interval: None,
+            blocked_by: Vec::new(),
+            retired: false,
+        };
+        assert!(!probe.blocked());
+        assert_eq!(probe.status_token(), "failing");
+
+        probe.blocked_by = vec!["load-balancer".into()];
+        assert!(probe.blocked());
+        assert_eq!(probe.status_token(), "blocked");
+
+        probe.history[0].pass = true;
+        assert!(!probe.blocked());
+        assert_eq!(probe.status_token(), "passing");
+    }
+
     /// The streak-derived health is debounced by the window: a fault reads failing only once it has
     /// persisted for the whole window, and reads passing again a window after the last failure. This
     /// is evaluated at explicit instants (unlike [`Probe::passing`], which reads the wall clock).
@@ -310,6 +406,8 @@ mod tests {
             failing_since: Some(base),
             failing_until: Some(base + window),
             covered_since: None,
+            degraded_since: None,
+            degraded_until: None,
         };
 
         // The onset is debounced away until the fault is a full window old.
@@ -336,13 +434,19 @@ mod tests {
                 successful_samples: 9,
                 total_retries: 2,
                 total_latency: std::time::Duration::from_secs(5),
+                latency_sketch: Default::default(),
+                checks: Default::default(),
             })].into_iter().collect(),
             streak: Streak {
                 failing_since: Some(chrono::DateTime::from_timestamp(1_699_999_000, 0).unwrap()),
                 failing_until: Some(chrono::DateTime::from_timestamp(1_699_999_900, 0).unwrap()),
                 covered_since: Some(chrono::DateTime::from_timestamp(1_690_000_000, 0).unwrap()),
+                degraded_since: None,
+                degraded_until: None,
             },
             debounce: None,
+            interval: None,
+            blocked_by: Vec::new(),
             retired: false,
         };
 
@@ -375,6 +479,8 @@ mod tests {
                 successful_samples: 9,
                 total_retries: 2,
                 total_latency: std::time::Duration::from_secs(5),
+                latency_sketch: Default::default(),
+                checks: Default::default(),
             })].into_iter().collect(),
         };
 
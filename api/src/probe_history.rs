@@ -0,0 +1,156 @@
+use chrono::{DateTime, Datelike, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+use std::collections::BTreeMap;
+
+use crate::{CheckCounts, LatencyPercentiles, ProbeHistoryBucket};
+
+/// The width of the buckets in a probe's long-term history. Each resolution is kept for a different
+/// span: hourly buckets for a week, daily rollups for 13 months and monthly rollups indefinitely.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HistoryResolution {
+    Hour,
+    Day,
+    Month,
+}
+
+impl HistoryResolution {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HistoryResolution::Hour => "hour",
+            HistoryResolution::Day => "day",
+            HistoryResolution::Month => "month",
+        }
+    }
+
+    /// The start of the bucket containing `time`.
+    pub fn start_of(&self, time: DateTime<Utc>) -> DateTime<Utc> {
+        let aligned = |width: i64| {
+            let ts = time.timestamp();
+            DateTime::from_timestamp(ts - ts.rem_euclid(width), 0).unwrap_or(time)
+        };
+
+        match self {
+            HistoryResolution::Hour => aligned(3600),
+            HistoryResolution::Day => aligned(86400),
+            HistoryResolution::Month => Utc
+                .with_ymd_and_hms(time.year(), time.month(), 1, 0, 0, 0)
+                .single()
+                .unwrap_or(time),
+        }
+    }
+
+    /// The start of the bucket following the one which starts at `start`.
+    pub fn next(&self, start: DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            HistoryResolution::Hour => start + chrono::Duration::hours(1),
+            HistoryResolution::Day => start + chrono::Duration::days(1),
+            HistoryResolution::Month => start
+                .checked_add_months(chrono::Months::new(1))
+                .unwrap_or(DateTime::<Utc>::MAX_UTC),
+        }
+    }
+}
+
+impl std::fmt::Display for HistoryResolution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for HistoryResolution {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hour" => Ok(HistoryResolution::Hour),
+            "day" => Ok(HistoryResolution::Day),
+            "month" => Ok(HistoryResolution::Month),
+            other => Err(format!("'{other}' is not a history resolution, expected 'hour', 'day' or 'month'.")),
+        }
+    }
+}
+
+/// A probe's availability history over a time range, as returned by the
+/// `/api/v1/probes/{name}/history` endpoint.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProbeHistory {
+    pub name: String,
+    pub resolution: HistoryResolution,
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub from: DateTime<Utc>,
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub to: DateTime<Utc>,
+    /// The buckets starting within the range, oldest first. Periods without any samples are omitted.
+    #[serde(default)]
+    pub history: Vec<ProbeHistoryEntry>,
+}
+
+/// A bucket of a probe's history, along with the latency percentiles and per-check pass counts of all
+/// of its observers' samples, and the latency percentiles of each observer's own samples. These are
+/// derived from the bucket's latency sketches when it is served, rather than stored or gossiped.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProbeHistoryEntry {
+    #[serde(flatten)]
+    pub bucket: ProbeHistoryBucket,
+    #[serde(default)]
+    pub latency: LatencyPercentiles,
+    #[serde(default)]
+    pub observer_latency: BTreeMap<String, LatencyPercentiles>,
+    #[serde(default)]
+    pub checks: BTreeMap<String, CheckCounts>,
+}
+
+impl From<ProbeHistoryBucket> for ProbeHistoryEntry {
+    fn from(bucket: ProbeHistoryBucket) -> Self {
+        Self {
+            latency: bucket.latency_percentiles(),
+            observer_latency: bucket
+                .observations
+                .iter()
+                .map(|(observer, observation)| (observer.clone(), observation.latency_percentiles()))
+                .collect(),
+            checks: bucket.check_counts(),
+            bucket,
+        }
+    }
+}
+
+impl std::ops::Deref for ProbeHistoryEntry {
+    type Target = ProbeHistoryBucket;
+
+    fn deref(&self) -> &Self::Target {
+        &self.bucket
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_boundaries() {
+        let time = Utc.with_ymd_and_hms(2024, 12, 31, 14, 37, 23).unwrap();
+
+        assert_eq!(HistoryResolution::Hour.start_of(time), Utc.with_ymd_and_hms(2024, 12, 31, 14, 0, 0).unwrap());
+        assert_eq!(HistoryResolution::Day.start_of(time), Utc.with_ymd_and_hms(2024, 12, 31, 0, 0, 0).unwrap());
+        assert_eq!(HistoryResolution::Month.start_of(time), Utc.with_ymd_and_hms(2024, 12, 1, 0, 0, 0).unwrap());
+
+        assert_eq!(
+            HistoryResolution::Month.next(HistoryResolution::Month.start_of(time)),
+            Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap()
+        );
+        assert_eq!(
+            HistoryResolution::Day.next(HistoryResolution::Day.start_of(time)),
+            Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("day".parse::<HistoryResolution>(), Ok(HistoryResolution::Day));
+        assert!("week".parse::<HistoryResolution>().is_err());
+        assert_eq!(serde_json::to_string(&HistoryResolution::Month).unwrap(), "\"month\"");
+    }
+}
@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-use crate::Mergeable;
+use crate::{LatencyPercentiles, Mergeable};
 use crate::observation::Observation;
 
 /// Probe result from the history endpoint
@@ -21,6 +21,11 @@ pub struct ProbeHistoryBucket {
     /// Observations collected from this probe, keyed by observer ID
     #[serde(default)]
     pub observations: HashMap<String, Observation>,
+
+    /// Whether this bucket passed while failing one of the probe's warning checks. Only meaningful
+    /// while `pass` is set, as a failure supersedes it.
+    #[serde(default)]
+    pub degraded: bool,
 }
 
 impl ProbeHistoryBucket {
@@ -49,19 +54,81 @@ impl ProbeHistoryBucket {
         self.total().average_latency()
     }
 
+    /// The median, 90th and 99th percentile latencies across every observer's samples in this bucket.
+    pub fn latency_percentiles(&self) -> LatencyPercentiles {
+        self.total().latency_percentiles()
+    }
+
+    /// How often each check passed and failed across every observer's samples in this bucket, by
+    /// name. Observations only hold each check's [`crate::check_id`], so the names are taken from the
+    /// bucket's validations: a check its reported sample didn't evaluate has no counts here.
+    pub fn check_counts(&self) -> std::collections::BTreeMap<String, CheckCounts> {
+        let total = self.total();
+        self.validations
+            .keys()
+            .filter_map(|check| Some((check.clone(), total.check_counts(check)?)))
+            .collect()
+    }
+
     /// Calculate retry rate based on attempts (1 attempt = 0 retries, 2 attempts = 1 retry, etc.)
     pub fn retry_rate(&self) -> f64 {
         self.total().retry_rate()
     }
-}
 
-impl Mergeable for ProbeHistoryBucket {
-    fn merge(&mut self, other: &Self) {
-        if self.pass && !other.pass {
-            self.pass = false;
+    /// Whether `other` reads worse than this bucket: failing is worse than degraded, which is worse
+    /// than passing.
+    fn is_worse(&self, other: &Self) -> bool {
+        let severity = |bucket: &Self| match (bucket.pass, bucket.degraded) {
+            (false, _) => 2,
+            (true, true) => 1,
+            (true, false) => 0,
+        };
+
+        severity(other) > severity(self)
+    }
+
+    /// Adopts `other`'s outcome, message and validations if it reads worse than this bucket.
+    fn take_worse(&mut self, other: &Self) {
+        if self.is_worse(other) {
+            self.pass = other.pass;
+            self.degraded = other.degraded;
             self.message = other.message.clone();
             self.validations = other.validations.clone();
         }
+    }
+
+    /// Combines consecutive buckets into a single wider one starting at `start_time`, summing each
+    /// observer's observations and keeping the first failure's (or degradation's) message and
+    /// validations.
+    pub fn rollup<'a>(
+        start_time: chrono::DateTime<chrono::Utc>,
+        buckets: impl IntoIterator<Item = &'a ProbeHistoryBucket>,
+    ) -> Option<Self> {
+        let mut buckets = buckets.into_iter();
+        let mut rollup = Self {
+            start_time,
+            ..buckets.next()?.clone()
+        };
+
+        for bucket in buckets {
+            rollup.take_worse(bucket);
+
+            for (observer, observation) in &bucket.observations {
+                rollup
+                    .observations
+                    .entry(observer.clone())
+                    .or_default()
+                    .merge(observation);
+            }
+        }
+
+        Some(rollup)
+    }
+}
+
+impl Mergeable for ProbeHistoryBucket {
+    fn merge(&mut self, other: &Self) {
+        self.take_worse(other);
         
         self.observations.extend(other.observations.clone());
     }
@@ -102,6 +169,47 @@ impl ValidationResult {
     }
 }
 
+/// How many times a single check passed and failed across a set of samples.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CheckCounts {
+    #[serde(rename = "pass")]
+    pub passed: u64,
+    #[serde(rename = "fail")]
+    pub failed: u64,
+}
+
+impl CheckCounts {
+    /// Counts a single evaluation of the check.
+    pub fn add(&mut self, pass: bool) {
+        if pass {
+            self.passed += 1;
+        } else {
+            self.failed += 1;
+        }
+    }
+
+    /// The number of times the check was evaluated.
+    pub fn total(&self) -> u64 {
+        self.passed + self.failed
+    }
+
+    /// The percentage of evaluations in which the check passed.
+    pub fn pass_rate(&self) -> f64 {
+        if self.total() == 0 {
+            return 100.0;
+        }
+
+        100.0 * self.passed as f64 / self.total() as f64
+    }
+}
+
+impl Mergeable for CheckCounts {
+    fn merge(&mut self, other: &Self) {
+        self.passed += other.passed;
+        self.failed += other.failed;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use chrono::NaiveTime;
@@ -112,24 +220,26 @@ mod tests {
         let mut bucket1 = ProbeHistoryBucket {
             start_time: chrono::Utc::now(),
             pass: true,
+            degraded: false,
             message: "".into(),
             validations: HashMap::new(),
             observations: vec![
-                ("observer1".into(), Observation { total_samples: 5, successful_samples: 5, total_retries: 0, total_latency: std::time::Duration::from_millis(500) }),
-                ("observer2".into(), Observation { total_samples: 5, successful_samples: 4, total_retries: 1, total_latency: std::time::Duration::from_millis(600) }),
+                ("observer1".into(), Observation { total_samples: 5, successful_samples: 5, total_retries: 0, total_latency: std::time::Duration::from_millis(500), latency_sketch: Default::default(), checks: Default::default() }),
+                ("observer2".into(), Observation { total_samples: 5, successful_samples: 4, total_retries: 1, total_latency: std::time::Duration::from_millis(600), latency_sketch: Default::default(), checks: Default::default() }),
             ].into_iter().collect(),
         };
         
         let bucket2 = ProbeHistoryBucket {
             start_time: chrono::Utc::now(),
             pass: false,
+            degraded: false,
             message: "Timeout".into(),
             validations: vec![
                 ("response_time".into(), ValidationResult::fail("Exceeded threshold")),
             ].into_iter().collect(),
             observations: vec![
-                ("observer2".into(), Observation { total_samples: 5, successful_samples: 3, total_retries: 2, total_latency: std::time::Duration::from_millis(700) }),
-                ("observer3".into(), Observation { total_samples: 5, successful_samples: 5, total_retries: 0, total_latency: std::time::Duration::from_millis(400) }),
+                ("observer2".into(), Observation { total_samples: 5, successful_samples: 3, total_retries: 2, total_latency: std::time::Duration::from_millis(700), latency_sketch: Default::default(), checks: Default::default() }),
+                ("observer3".into(), Observation { total_samples: 5, successful_samples: 5, total_retries: 0, total_latency: std::time::Duration::from_millis(400), latency_sketch: Default::default(), checks: Default::default() }),
             ].into_iter().collect(),
         };
         
@@ -143,6 +253,35 @@ mod tests {
         assert_eq!(bucket1.observations.get("observer3").unwrap().total_samples, 5);
     }
     
+    #[test]
+    fn test_probe_history_bucket_rollup() {
+        let day = chrono::DateTime::from_timestamp(1_700_006_400, 0).unwrap();
+        let hour = |offset: i64, pass: bool, observer: &str| ProbeHistoryBucket {
+            start_time: day + chrono::Duration::hours(offset),
+            pass,
+            degraded: false,
+            message: if pass { "".into() } else { format!("Failed at {offset}") },
+            validations: HashMap::new(),
+            observations: vec![
+                (observer.into(), Observation { total_samples: 4, successful_samples: if pass { 4 } else { 2 }, total_retries: 1, total_latency: std::time::Duration::from_millis(400), latency_sketch: Default::default(), checks: Default::default() }),
+            ].into_iter().collect(),
+        };
+
+        let hours = [hour(0, true, "observer1"), hour(1, false, "observer1"), hour(2, false, "observer2")];
+        let rollup = ProbeHistoryBucket::rollup(day, &hours).unwrap();
+
+        assert_eq!(rollup.start_time, day);
+        assert!(!rollup.pass);
+        assert_eq!(rollup.message, "Failed at 1");
+        assert_eq!(rollup.observations.len(), 2);
+        assert_eq!(rollup.observations["observer1"].total_samples, 8);
+        assert_eq!(rollup.observations["observer1"].successful_samples, 6);
+        assert_eq!(rollup.observations["observer2"].total_retries, 1);
+        assert_eq!(rollup.total().total_latency, std::time::Duration::from_millis(1200));
+
+        assert!(ProbeHistoryBucket::rollup(day, &[]).is_none());
+    }
+
     #[test]
     fn test_validation_result_constructors() {
         let pass_result = ValidationResult::pass();
@@ -162,11 +301,12 @@ mod tests {
         let bucket = ProbeHistoryBucket {
             start_time: chrono::Utc::now(),
             pass: true,
+            degraded: false,
             message: "".into(),
             validations: HashMap::new(),
             observations: vec![
-                ("observer1".into(), Observation { total_samples: 10, successful_samples: 8, total_retries: 2, total_latency: std::time::Duration::from_millis(1000) }),
-                ("observer2".into(), Observation { total_samples: 5, successful_samples: 5, total_retries: 0, total_latency: std::time::Duration::from_millis(300) }),
+                ("observer1".into(), Observation { total_samples: 10, successful_samples: 8, total_retries: 2, total_latency: std::time::Duration::from_millis(1000), latency_sketch: Default::default(), checks: Default::default() }),
+                ("observer2".into(), Observation { total_samples: 5, successful_samples: 5, total_retries: 0, total_latency: std::time::Duration::from_millis(300), latency_sketch: Default::default(), checks: Default::default() }),
             ].into_iter().collect(),
         };
         
@@ -181,18 +321,46 @@ mod tests {
         assert_eq!(bucket.average_latency(), std::time::Duration::from_millis(86)); // 1300ms / 15 samples
     }
     
+    #[test]
+    fn test_check_counts() {
+        let observation = |passed: u64, failed: u64| {
+            let mut observation = Observation::default();
+            observation.checks.insert(crate::check_id("http.status == 200"), CheckCounts { passed, failed });
+            observation
+        };
+
+        let bucket = ProbeHistoryBucket {
+            start_time: chrono::Utc::now(),
+            pass: false,
+            degraded: false,
+            message: "".into(),
+            validations: [("http.status == 200".to_string(), ValidationResult::fail("http.status = 500"))].into_iter().collect(),
+            observations: vec![
+                ("observer1".into(), observation(9, 1)),
+                ("observer2".into(), observation(18, 2)),
+            ].into_iter().collect(),
+        };
+
+        let counts = bucket.check_counts()["http.status == 200"];
+        assert_eq!(counts, CheckCounts { passed: 27, failed: 3 });
+        assert_eq!(counts.total(), 30);
+        assert!((counts.pass_rate() - 90.0).abs() < f64::EPSILON);
+        assert_eq!(CheckCounts::default().pass_rate(), 100.0);
+    }
+
     #[test]
     fn test_msgpack_roundtrip() {
         let bucket = ProbeHistoryBucket {
             start_time: chrono::Utc::now().with_time(NaiveTime::from_hms_micro_opt(1, 2, 3, 0).unwrap()).unwrap(),
             pass: true,
+            degraded: false,
             message: "All good".into(),
             validations: vec![
                 ("status_code".into(), ValidationResult::pass()),
                 ("response_time".into(), ValidationResult::fail("Too slow")),
             ].into_iter().collect(),
             observations: vec![
-                ("observer1".into(), Observation { total_samples: 10, successful_samples: 9, total_retries: 1, total_latency: std::time::Duration::from_millis(900) }),
+                ("observer1".into(), Observation { total_samples: 10, successful_samples: 9, total_retries: 1, total_latency: std::time::Duration::from_millis(900), latency_sketch: Default::default(), checks: Default::default() }),
             ].into_iter().collect(),
         };
         
@@ -0,0 +1,82 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::ValidationResult;
+
+/// A single probe run exactly as this node observed it, before it was folded into the probe's
+/// hourly history. Kept in a probe's sample log when one is configured.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProbeSample {
+    pub start_time: DateTime<Utc>,
+    /// How long the run took, including its retries.
+    #[serde(with = "crate::serializers::duration_ms")]
+    pub duration: Duration,
+    pub pass: bool,
+    /// Whether the run passed while failing one of the probe's warning checks.
+    #[serde(default)]
+    pub degraded: bool,
+    pub retries: u8,
+    pub message: String,
+    /// The outcome of each of the probe's checks, keyed by the check.
+    #[serde(default)]
+    pub validations: BTreeMap<String, ValidationResult>,
+    /// How long after its scheduled time the run started.
+    #[serde(default, with = "crate::serializers::duration_ms")]
+    pub scheduling_delay: Duration,
+}
+
+impl ProbeSample {
+    /// The checks which failed during this run, with their messages.
+    pub fn failed_checks(&self) -> impl Iterator<Item = (&str, Option<&str>)> {
+        self.validations
+            .iter()
+            .filter(|(_, result)| !result.pass)
+            .map(|(check, result)| (check.as_str(), result.message.as_deref()))
+    }
+}
+
+/// The raw samples a node has kept for a probe, as returned by the
+/// `/api/v1/probes/{name}/samples` endpoint.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProbeSamples {
+    pub name: String,
+    /// The id of the node which observed (and kept) these samples.
+    pub observer: String,
+    /// The samples starting within the requested range, oldest first.
+    #[serde(default)]
+    pub samples: Vec<ProbeSample>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failed_checks() {
+        let sample = ProbeSample {
+            start_time: Utc::now(),
+            duration: Duration::from_millis(250),
+            pass: false,
+            degraded: false,
+            retries: 2,
+            message: "Probe failed".into(),
+            validations: BTreeMap::from([
+                ("http.status == 200".to_string(), ValidationResult::fail("http.status = 503")),
+                ("http.body != \"\"".to_string(), ValidationResult::pass()),
+            ]),
+            scheduling_delay: Duration::ZERO,
+        };
+
+        assert_eq!(
+            sample.failed_checks().collect::<Vec<_>>(),
+            vec![("http.status == 200", Some("http.status = 503"))]
+        );
+
+        let json = serde_json::to_value(&sample).unwrap();
+        assert_eq!(json["duration"], 250);
+        assert_eq!(serde_json::from_value::<ProbeSample>(json).unwrap(), sample);
+    }
+}
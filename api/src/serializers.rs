@@ -20,3 +20,26 @@ pub mod duration_ms {
         Ok(Duration::from_millis(millis))
     }
 }
+
+/// Serializer for `Option<std::time::Duration>` as milliseconds (u64), or `null`
+pub mod duration_ms_option {
+    use super::*;
+    use std::time::Duration;
+
+    pub fn serialize<S>(duration: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match duration {
+            Some(duration) => serializer.serialize_some(&(duration.as_millis() as u64)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(Option::<u64>::deserialize(deserializer)?.map(Duration::from_millis))
+    }
+}
@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Observation;
+
+/// A service level objective evaluated over its rolling window, as returned by the `/api/v1/slos`
+/// endpoint. The SLI is the proportion of successful samples (probes) and runs (crons) across every
+/// targeted entity, and the error budget is the proportion of them which the objective allows to
+/// fail.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Slo {
+    pub name: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    #[serde(default)]
+    pub tags: HashMap<String, String>,
+
+    /// The target success rate, as a percentage (e.g. `99.9`).
+    pub objective: f64,
+
+    /// The rolling window the objective is measured over.
+    #[serde(with = "humantime_serde")]
+    pub window: Duration,
+
+    /// The names of the probes and crons the objective's selector matched.
+    #[serde(default)]
+    pub targets: Vec<String>,
+
+    /// The samples and runs observed across every target over the window.
+    #[serde(default)]
+    pub observation: Observation,
+
+    /// The multi-window burn rates which alert on this objective.
+    #[serde(default)]
+    pub burn_rates: Vec<BurnRate>,
+}
+
+impl Slo {
+    /// The success rate observed over the window, as a percentage.
+    pub fn attainment(&self) -> f64 {
+        self.observation.success_rate()
+    }
+
+    /// The proportion of samples which the objective allows to fail, as a percentage.
+    pub fn error_budget(&self) -> f64 {
+        100.0 - self.objective
+    }
+
+    /// The proportion of the window's error budget which has not been spent, as a percentage. This
+    /// drops below zero once more samples have failed than the objective allows.
+    pub fn budget_remaining(&self) -> f64 {
+        if self.observation.total_samples == 0 || self.error_budget() <= 0.0 {
+            return 100.0;
+        }
+
+        100.0 * (1.0 - (100.0 - self.attainment()) / self.error_budget())
+    }
+
+    /// Whether the window's error budget has been spent.
+    pub fn exhausted(&self) -> bool {
+        self.observation.total_samples > 0 && self.budget_remaining() <= 0.0
+    }
+
+    /// Whether any of the objective's burn rate alerts is firing.
+    pub fn burning(&self) -> bool {
+        self.burn_rates.iter().any(BurnRate::exceeded)
+    }
+
+    /// `"burning"` while a burn rate alert is firing, otherwise `"ok"`.
+    pub fn burn_rate_status(&self) -> &'static str {
+        if self.burning() { "burning" } else { "ok" }
+    }
+
+    /// `"exhausted"` once the error budget has been spent, otherwise `"ok"`.
+    pub fn budget_status(&self) -> &'static str {
+        if self.exhausted() { "exhausted" } else { "ok" }
+    }
+}
+
+/// A multi-window burn rate alert: the rate at which the error budget is being spent over a long
+/// and a short window, relative to the rate which would spend exactly the whole budget over the
+/// objective's window. It fires only while both windows exceed the threshold, so the long window
+/// confirms a significant spend and the short window confirms it is still ongoing.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BurnRate {
+    #[serde(with = "humantime_serde")]
+    pub long_window: Duration,
+
+    #[serde(with = "humantime_serde")]
+    pub short_window: Duration,
+
+    /// The burn rate at which the alert fires.
+    pub threshold: f64,
+
+    /// The burn rate observed over the long window.
+    pub long_rate: f64,
+
+    /// The burn rate observed over the short window.
+    pub short_rate: f64,
+}
+
+impl BurnRate {
+    /// The burn rate of `observation` against an `objective` percentage: `1.0` spends the error
+    /// budget exactly as fast as the objective allows, while `0.0` spends none of it.
+    pub fn rate(observation: &Observation, objective: f64) -> f64 {
+        let budget = 100.0 - objective;
+        if observation.total_samples == 0 || budget <= 0.0 {
+            return 0.0;
+        }
+
+        (100.0 - observation.success_rate()) / budget
+    }
+
+    /// Whether both windows are burning through the budget at or above the threshold.
+    pub fn exceeded(&self) -> bool {
+        self.long_rate >= self.threshold && self.short_rate >= self.threshold
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn observation(total: u64, success: u64) -> Observation {
+        Observation {
+            total_samples: total,
+            successful_samples: success,
+            total_retries: 0,
+            total_latency: Duration::ZERO,
+            latency_sketch: Default::default(),
+            checks: Default::default(),
+        }
+    }
+
+    fn slo(observation: Observation) -> Slo {
+        Slo {
+            name: "web".into(),
+            description: None,
+            tags: HashMap::new(),
+            objective: 99.0,
+            window: Duration::from_secs(30 * 86400),
+            targets: vec!["web.prod".into()],
+            observation,
+            burn_rates: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_error_budget() {
+        let healthy = slo(observation(1000, 995));
+        assert!((healthy.attainment() - 99.5).abs() < 1e-9);
+        assert!((healthy.error_budget() - 1.0).abs() < 1e-9);
+        assert!((healthy.budget_remaining() - 50.0).abs() < 1e-9);
+        assert!(!healthy.exhausted());
+
+        let spent = slo(observation(1000, 980));
+        assert!((spent.budget_remaining() + 100.0).abs() < 1e-9, "overspending goes negative");
+        assert!(spent.exhausted());
+
+        let empty = slo(Observation::default());
+        assert_eq!(empty.budget_remaining(), 100.0);
+        assert!(!empty.exhausted(), "no samples spend no budget");
+    }
+
+    #[test]
+    fn test_burn_rate() {
+        assert!((BurnRate::rate(&observation(100, 99), 99.0) - 1.0).abs() < 1e-9);
+        assert!((BurnRate::rate(&observation(100, 94), 99.0) - 6.0).abs() < 1e-9);
+        assert_eq!(BurnRate::rate(&Observation::default(), 99.0), 0.0);
+
+        let alert = |long_rate, short_rate| BurnRate {
+            long_window: Duration::from_secs(6 * 3600),
+            short_window: Duration::from_secs(3600),
+            threshold: 6.0,
+            long_rate,
+            short_rate,
+        };
+        assert!(alert(6.0, 10.0).exceeded());
+        assert!(!alert(10.0, 2.0).exceeded(), "a burn which has stopped doesn't fire");
+        assert!(!alert(2.0, 10.0).exceeded(), "a brief spike doesn't fire");
+
+        let mut burning = slo(observation(100, 100));
+        burning.burn_rates = vec![alert(2.0, 10.0), alert(7.0, 8.0)];
+        assert!(burning.burning());
+    }
+
+    #[test]
+    fn test_serialization() {
+        let json = serde_json::to_value(slo(observation(10, 9))).unwrap();
+        assert_eq!(json["window"], "30days");
+        assert_eq!(json["observation"]["total"], 10);
+        assert!(json.get("description").is_none());
+    }
+}
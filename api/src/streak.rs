@@ -2,7 +2,7 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use crate::Mergeable;
 
-/// A cluster-converged record of a probe's pass/fail streaks, expressed as three
+/// A cluster-converged record of a probe's pass/fail (and degraded) streaks, expressed as
 /// independently monotone markers. Every mutation moves the register up the same lattice,
 /// so gossip merges, storage round-trips, and display pooling all use the one [`Streak::join`]
 /// operation — and every node converges on exactly the same value (the join is commutative,
@@ -29,6 +29,17 @@ pub struct Streak {
     /// is what lets rolling restarts inherit the cluster's streak.
     #[serde(default, with = "chrono::serde::ts_milliseconds_option")]
     pub covered_since: Option<DateTime<Utc>>,
+
+    /// When the current (or most recent) degraded episode began: a run which passed its critical
+    /// checks but failed a warning one. Advanced exactly like `failing_since`, but on degraded
+    /// observations.
+    #[serde(default, with = "chrono::serde::ts_milliseconds_option")]
+    pub degraded_since: Option<DateTime<Utc>>,
+
+    /// The most recent degraded observation made by any node, which recovers on its own after the
+    /// recovery window just as `failing_until` does.
+    #[serde(default, with = "chrono::serde::ts_milliseconds_option")]
+    pub degraded_until: Option<DateTime<Utc>>,
 }
 
 impl Streak {
@@ -43,7 +54,11 @@ impl Streak {
     /// Whether this register carries any observations at all (records written by older
     /// agents decode as empty).
     pub fn is_empty(&self) -> bool {
-        self.failing_since.is_none() && self.failing_until.is_none() && self.covered_since.is_none()
+        self.failing_since.is_none()
+            && self.failing_until.is_none()
+            && self.covered_since.is_none()
+            && self.degraded_since.is_none()
+            && self.degraded_until.is_none()
     }
 
     /// Whether a failure has been observed within the last `window` at `now` — the raw (un-debounced)
@@ -81,14 +96,37 @@ impl Streak {
         !self.failing_for(now, window)
     }
 
+    /// Whether a degraded run has been observed within the last `window` at `now`: the raw signal,
+    /// analogous to [`Streak::failing_at`].
+    pub fn degraded_at(&self, now: DateTime<Utc>, window: chrono::Duration) -> bool {
+        self.degraded_until
+            .map(|until| until > now - window)
+            .unwrap_or(false)
+    }
+
+    /// The debounced degraded signal: whether the entity has been continuously degraded for at least
+    /// `window` at `now`, debounced exactly as [`Streak::failing_for`] is. An entity which is
+    /// (debounced) failing reads as failing rather than degraded.
+    pub fn degraded_for(&self, now: DateTime<Utc>, window: chrono::Duration) -> bool {
+        !self.failing_for(now, window)
+            && self.degraded_at(now, window)
+            && self
+                .degraded_since
+                .map(|since| now - since >= window)
+                .unwrap_or(false)
+    }
+
     /// When the debounced state reported at `now` was entered: the failure onset while (debounced)
-    /// failing; otherwise the last failing observation, or — for an entity which has never failed —
-    /// the earliest passing observation.
+    /// failing, or the degradation's onset while degraded; otherwise the last failing or degraded
+    /// observation, or — for an entity which has never been either — the earliest passing
+    /// observation.
     pub fn since_at(&self, now: DateTime<Utc>, window: chrono::Duration) -> Option<DateTime<Utc>> {
         if self.failing_for(now, window) {
             self.failing_since
+        } else if self.degraded_for(now, window) {
+            self.degraded_since
         } else {
-            self.failing_until.or(self.covered_since)
+            self.failing_until.max(self.degraded_until).or(self.covered_since)
         }
     }
 
@@ -115,11 +153,24 @@ impl Streak {
         }
     }
 
-    /// Joins another register into this one: the pointwise join of three monotone
-    /// markers (latest failure onset, latest failing observation, earliest coverage).
+    /// Folds a degraded sample into the register: one which passed its critical checks (and so is
+    /// also observed as passing) but failed a warning check. Like [`Streak::observe`], every write is
+    /// monotone.
+    pub fn observe_degraded(&mut self, time: DateTime<Utc>, window: chrono::Duration) {
+        if !self.degraded_at(time, window) {
+            self.degraded_since = self.degraded_since.max(Some(time));
+        }
+
+        self.degraded_until = self.degraded_until.max(Some(time));
+    }
+
+    /// Joins another register into this one: the pointwise join of its monotone markers (latest
+    /// failure and degradation onsets, latest failing and degraded observations, earliest coverage).
     pub fn join(&mut self, other: &Self) {
         self.failing_since = self.failing_since.max(other.failing_since);
         self.failing_until = self.failing_until.max(other.failing_until);
+        self.degraded_since = self.degraded_since.max(other.degraded_since);
+        self.degraded_until = self.degraded_until.max(other.degraded_until);
         self.covered_since = match (self.covered_since, other.covered_since) {
             (Some(mine), Some(theirs)) => Some(mine.min(theirs)),
             (mine, theirs) => mine.or(theirs),
@@ -151,6 +202,8 @@ mod tests {
             failing_since: failing_since.map(ts),
             failing_until: failing_until.map(ts),
             covered_since: covered_since.map(ts),
+            degraded_since: None,
+            degraded_until: None,
         }
     }
 
@@ -326,6 +379,66 @@ mod tests {
         assert_eq!(with_empty, ab);
     }
 
+    #[test]
+    fn test_degraded_episodes() {
+        let window = win();
+        let w = window.num_seconds();
+        let mut register = streak(None, None, Some(0));
+
+        // A degraded run still passes, so it reads as degraded (not failing) once it has persisted for
+        // the window...
+        for k in 0..=2 {
+            register.observe(true, ts(1_000 + k * (w / 2)), window);
+            register.observe_degraded(ts(1_000 + k * (w / 2)), window);
+        }
+        assert!(register.passing_at(ts(1_000 + w), window));
+        assert!(!register.degraded_for(ts(1_000 + w - 1), window), "onset must not trip before the window");
+        assert!(register.degraded_for(ts(1_000 + w), window));
+        assert_eq!(register.since_at(ts(1_000 + w), window), Some(ts(1_000)));
+
+        // ...a failure takes precedence over it...
+        let mut failing = register.clone();
+        for k in -2..=2 {
+            failing.observe(false, ts(1_000 + k * (w / 2)), window);
+        }
+        assert!(failing.failing_for(ts(1_000 + w), window));
+        assert!(!failing.degraded_for(ts(1_000 + w), window));
+
+        // ...and it recovers on its own, passing since the last degraded observation.
+        assert!(!register.degraded_for(ts(1_000 + 2 * w + 1), window));
+        assert_eq!(register.since_at(ts(1_000 + 2 * w + 1), window), Some(ts(1_000 + w)));
+
+        // The degraded markers join like the failing ones.
+        let mut joined = streak(None, None, Some(0));
+        joined.join(&register);
+        assert_eq!(joined, register);
+    }
+
+    #[test]
+    fn test_decodes_legacy_streaks() {
+        // Streaks gossiped by agents which pre-date degraded probes lack its markers.
+        #[derive(Serialize)]
+        struct LegacyStreak {
+            #[serde(with = "chrono::serde::ts_milliseconds_option")]
+            failing_since: Option<DateTime<Utc>>,
+            #[serde(with = "chrono::serde::ts_milliseconds_option")]
+            failing_until: Option<DateTime<Utc>>,
+            #[serde(with = "chrono::serde::ts_milliseconds_option")]
+            covered_since: Option<DateTime<Utc>>,
+        }
+
+        let legacy = LegacyStreak {
+            failing_since: Some(ts(50_000)),
+            failing_until: Some(ts(50_060)),
+            covered_since: Some(ts(1_000)),
+        };
+
+        for packed in [rmp_serde::to_vec(&legacy).unwrap(), rmp_serde::to_vec_named(&legacy).unwrap()] {
+            let unpacked: Streak = rmp_serde::from_slice(&packed).unwrap();
+            assert_eq!(unpacked, streak(Some(50_000), Some(50_060), Some(1_000)));
+        }
+    }
+
     #[test]
     fn test_msgpack_roundtrip() {
         for register in [
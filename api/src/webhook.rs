@@ -1,5 +1,5 @@
 //! The webhook event payload: the JSON document Grey delivers to a configured endpoint when a probe
-//! or cron changes state.
+//! or cron changes state, or when a service level objective starts burning through its error budget.
 //!
 //! This is a pure DTO (it never references the `filt-rs` filter language or the HTTP machinery — those
 //! live in the agent, which owns dispatch and filtering). It carries a small, stable summary of the
@@ -17,7 +17,7 @@ use std::collections::HashMap;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-use crate::{Cron, Probe};
+use crate::{Cron, Probe, Slo};
 
 /// The schema version stamped onto every [`WebhookEvent`]. Bump this when the payload shape changes
 /// in a way consumers need to discriminate; a consumer can branch on `version` to handle multiple
@@ -29,14 +29,24 @@ fn default_schema_version() -> String {
     WEBHOOK_SCHEMA_VERSION.to_string()
 }
 
-/// The kind of state-change event. The wire value is a dotted `"<entity>.state_changed"` token so a
-/// consumer can route on it directly.
+/// The kind of event. The wire value is a dotted `"<entity>.<change>"` token so a consumer can route
+/// on it directly.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum WebhookEventKind {
     #[serde(rename = "probe.state_changed")]
     ProbeStateChanged,
+    /// A passing probe became degraded (one of its warning checks started failing) or recovered
+    /// from being degraded.
+    #[serde(rename = "probe.degraded")]
+    ProbeDegraded,
     #[serde(rename = "cron.state_changed")]
     CronStateChanged,
+    /// One of an SLO's burn rate alerts started firing.
+    #[serde(rename = "slo.burn_rate_exceeded")]
+    SloBurnRateExceeded,
+    /// An SLO spent the whole error budget for its window.
+    #[serde(rename = "slo.budget_exhausted")]
+    SloBudgetExhausted,
 }
 
 impl WebhookEventKind {
@@ -44,7 +54,10 @@ impl WebhookEventKind {
     pub fn as_str(self) -> &'static str {
         match self {
             WebhookEventKind::ProbeStateChanged => "probe.state_changed",
+            WebhookEventKind::ProbeDegraded => "probe.degraded",
             WebhookEventKind::CronStateChanged => "cron.state_changed",
+            WebhookEventKind::SloBurnRateExceeded => "slo.burn_rate_exceeded",
+            WebhookEventKind::SloBudgetExhausted => "slo.budget_exhausted",
         }
     }
 }
@@ -55,6 +68,7 @@ impl WebhookEventKind {
 pub enum WebhookEntityType {
     Probe,
     Cron,
+    Slo,
 }
 
 impl WebhookEntityType {
@@ -62,6 +76,7 @@ impl WebhookEntityType {
         match self {
             WebhookEntityType::Probe => "probe",
             WebhookEntityType::Cron => "cron",
+            WebhookEntityType::Slo => "slo",
         }
     }
 }
@@ -79,7 +94,7 @@ pub struct WebhookEntity {
 }
 
 /// A compact summary of the transition that triggered the event. `current`/`previous` are the
-/// derived status tokens (a probe is `"passing"`/`"failing"`; a cron is one of the
+/// derived status tokens (a probe is `"passing"`/`"degraded"`/`"failing"`; a cron is one of the
 /// [`crate::CronHealth`] tokens), while `healthy`/`was_healthy` collapse those onto the pass/fail axis
 /// so a consumer can filter on `state.healthy == false` regardless of the specific failure mode.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -95,13 +110,13 @@ pub struct WebhookState {
     /// When the current state was entered, when known.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub since: Option<DateTime<Utc>>,
-    /// The entity's availability over its retained history, as a percentage. Only meaningful for
-    /// probes; omitted for crons.
+    /// The entity's availability over its retained history, as a percentage (for an SLO, its
+    /// attainment over the objective's window). Omitted for crons.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub availability: Option<f64>,
 }
 
-/// A state-change notification for a single probe or cron, as delivered to a webhook endpoint.
+/// A notification for a single probe, cron or SLO, as delivered to a webhook endpoint.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct WebhookEvent {
     /// The payload schema version (`"v1"` today). Lets a consumer discriminate between schema
@@ -113,7 +128,8 @@ pub struct WebhookEvent {
     /// consumer can de-duplicate retried or fan-out deliveries.
     pub id: String,
 
-    /// The event kind (`probe.state_changed` / `cron.state_changed`).
+    /// The event kind (`probe.state_changed`, `probe.degraded`, `cron.state_changed`,
+    /// `slo.burn_rate_exceeded` or `slo.budget_exhausted`).
     pub event: WebhookEventKind,
 
     /// When the event was generated.
@@ -132,6 +148,10 @@ pub struct WebhookEvent {
     /// The full cron snapshot (with runs and the last check-in), for a cron event.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub cron: Option<Cron>,
+
+    /// The evaluated SLO (with its error budget and burn rates), for an SLO event.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slo: Option<Slo>,
 }
 
 impl WebhookEvent {
@@ -165,6 +185,22 @@ impl WebhookEvent {
             },
             probe: Some(probe.clone()),
             cron: None,
+            slo: None,
+        }
+    }
+
+    /// Builds a `probe.degraded` event from the cluster-pooled probe snapshot, for a probe which moved
+    /// into or out of the `degraded` state without crossing the health axis.
+    pub fn for_probe_degraded(
+        id: impl Into<String>,
+        timestamp: DateTime<Utc>,
+        probe: &Probe,
+        previous_token: impl Into<String>,
+        previous_healthy: bool,
+    ) -> Self {
+        Self {
+            event: WebhookEventKind::ProbeDegraded,
+            ..Self::for_probe(id, timestamp, probe, previous_token, previous_healthy)
         }
     }
 
@@ -199,6 +235,47 @@ impl WebhookEvent {
             },
             probe: None,
             cron: Some(cron.clone()),
+            slo: None,
+        }
+    }
+
+    /// Builds an `slo.burn_rate_exceeded` or `slo.budget_exhausted` event from the evaluated SLO,
+    /// given the status it transitioned away from. The status tokens are the SLO's
+    /// [`Slo::burn_rate_status`] or [`Slo::budget_status`] respectively.
+    pub fn for_slo(
+        id: impl Into<String>,
+        timestamp: DateTime<Utc>,
+        event: WebhookEventKind,
+        slo: &Slo,
+        previous_token: impl Into<String>,
+        previous_healthy: bool,
+    ) -> Self {
+        let (current, healthy) = match event {
+            WebhookEventKind::SloBudgetExhausted => (slo.budget_status(), !slo.exhausted()),
+            _ => (slo.burn_rate_status(), !slo.burning()),
+        };
+
+        Self {
+            version: WEBHOOK_SCHEMA_VERSION.to_string(),
+            id: id.into(),
+            event,
+            timestamp,
+            entity: WebhookEntity {
+                entity_type: WebhookEntityType::Slo,
+                name: slo.name.clone(),
+                tags: slo.tags.clone(),
+            },
+            state: WebhookState {
+                current: current.to_string(),
+                previous: previous_token.into(),
+                healthy,
+                was_healthy: previous_healthy,
+                since: None,
+                availability: Some(slo.attainment()),
+            },
+            probe: None,
+            cron: None,
+            slo: Some(slo.clone()),
         }
     }
 }
@@ -227,8 +304,12 @@ mod tests {
                 failing_since: Some(now - window - chrono::Duration::seconds(1)),
                 failing_until: Some(now),
                 covered_since: None,
+                degraded_since: None,
+                degraded_until: None,
             },
             debounce: None,
+            interval: None,
+            blocked_by: Vec::new(),
             retired: false,
         }
     }
@@ -296,6 +377,41 @@ mod tests {
         assert!(json.get("node").is_none(), "events carry no node field");
     }
 
+    #[test]
+    fn slo_event_carries_the_evaluated_slo() {
+        let slo = Slo {
+            name: "checkout".into(),
+            description: None,
+            tags: vec![("team".into(), "Payments".into())].into_iter().collect(),
+            objective: 99.0,
+            window: Duration::from_secs(30 * 86400),
+            targets: vec!["checkout.api".into()],
+            observation: crate::Observation {
+                total_samples: 100,
+                successful_samples: 90,
+                total_retries: 0,
+                total_latency: Duration::ZERO,
+                latency_sketch: Default::default(),
+                checks: Default::default(),
+            },
+            burn_rates: Vec::new(),
+        };
+
+        let event = WebhookEvent::for_slo("evt-3", ts(300), WebhookEventKind::SloBudgetExhausted, &slo, "ok", true);
+
+        assert_eq!(event.entity.entity_type, WebhookEntityType::Slo);
+        assert_eq!(event.entity.tags.get("team").map(String::as_str), Some("Payments"));
+        assert_eq!(event.state.current, "exhausted");
+        assert!(!event.state.healthy);
+        assert_eq!(event.state.availability, Some(90.0));
+        assert!(event.slo.is_some());
+
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["event"], "slo.budget_exhausted");
+        assert_eq!(json["entity"]["type"], "slo");
+        assert!(json.get("probe").is_none());
+    }
+
     /// A freshly built event is stamped with the current schema version, and a payload that predates
     /// the `version` field still deserializes — defaulting to the current version.
     #[test]
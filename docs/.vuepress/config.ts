@@ -97,6 +97,7 @@ export default defineUserConfig({
             '/guide/clustering.md',
             '/guide/crons.md',
             '/guide/webhooks.md',
+            '/guide/slos.md',
             '/guide/telemetry.md',
             '/guide/azure-msi.md',
           ]
@@ -130,6 +130,10 @@ mod browser {
             self.get_json(&format!("{BASE}/crons")).await
         }
 
+        pub async fn slos(&self) -> Result<Vec<grey_api::Slo>, ApiError> {
+            self.get_json(&format!("{BASE}/slos")).await
+        }
+
         /// The first page of publicly visible incidents (hidden drafts excluded), each with its
         /// updates embedded.
         pub async fn incidents(&self) -> Result<Vec<IncidentView>, ApiError> {
@@ -310,6 +314,9 @@ impl ApiClient {
     pub async fn crons(&self) -> Result<Vec<grey_api::Cron>, ApiError> {
         Self::unavailable()
     }
+    pub async fn slos(&self) -> Result<Vec<grey_api::Slo>, ApiError> {
+        Self::unavailable()
+    }
     pub async fn incidents(&self) -> Result<Vec<IncidentView>, ApiError> {
         Self::unavailable()
     }
@@ -25,6 +25,8 @@ pub struct AppProps {
     #[prop_or_default]
     pub crons: Vec<grey_api::Cron>,
     #[prop_or_default]
+    pub slos: Vec<grey_api::Slo>,
+    #[prop_or_default]
     pub incidents: Vec<grey_api::IncidentView>,
     /// The request path, used to seed the router during server-side rendering so a deep link to a
     /// non-home route renders the right page (and hydrates cleanly). Unused on the client, where the
@@ -51,6 +53,7 @@ impl AppProps {
             config,
             probes: Vec::new(),
             crons: Vec::new(),
+            slos: Vec::new(),
             incidents: Vec::new(),
             url: String::new(),
         })
@@ -70,9 +73,10 @@ impl AppProps {
         let probes_data = app_element
             .get_attribute("data-probes")
             .ok_or("#app[data-probes] not found")?;
-        // Incidents and crons are optional: older server renders omit them.
+        // Incidents, crons and SLOs are optional: older server renders omit them.
         let incidents_data = app_element.get_attribute("data-incidents");
         let crons_data = app_element.get_attribute("data-crons");
+        let slos_data = app_element.get_attribute("data-slos");
 
         let config: UiConfig = serde_json::from_str(&config_data)?;
         let probes: Vec<grey_api::Probe> = serde_json::from_str(&probes_data)?;
@@ -82,11 +86,15 @@ impl AppProps {
         let crons: Vec<grey_api::Cron> = crons_data
             .and_then(|data| serde_json::from_str(&data).ok())
             .unwrap_or_default();
+        let slos: Vec<grey_api::Slo> = slos_data
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default();
 
         Ok(Self {
             config,
             probes,
             crons,
+            slos,
             incidents,
             url: String::new(),
         })
@@ -101,6 +109,7 @@ pub fn app(props: &AppProps) -> Html {
     let config_json = serde_json::to_string(&props.config).unwrap_or_default();
     let probes_json = serde_json::to_string(&props.probes).unwrap_or_default();
     let crons_json = serde_json::to_string(&props.crons).unwrap_or_default();
+    let slos_json = serde_json::to_string(&props.slos).unwrap_or_default();
     let incidents_json = serde_json::to_string(&props.incidents).unwrap_or_default();
 
     html! {
@@ -108,12 +117,14 @@ pub fn app(props: &AppProps) -> Html {
             data-config={config_json}
             data-probes={probes_json}
             data-crons={crons_json}
+            data-slos={slos_json}
             data-incidents={incidents_json}
         >
             <StoreProvider
                 config={props.config.clone()}
                 probes={props.probes.clone()}
                 crons={props.crons.clone()}
+                slos={props.slos.clone()}
                 incidents={props.incidents.clone()}
             >
                 { render_router(&props.url) }
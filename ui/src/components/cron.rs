@@ -17,23 +17,30 @@ pub fn cron(props: &CronProps) -> Html {
     let cron = &props.cron;
     let now = chrono::Utc::now();
     let health = cron.health(now, cron.window());
-    let class = cron_class(health);
+    let blocked = cron.blocked(now);
+    // A cron failing behind a failing dependency is greyed out, as the upstream outage is what needs
+    // attention.
+    let class = if blocked { "unknown" } else { cron_class(health) };
 
     // Which run's popover is currently open (on hover).
     let hovered = use_state(|| Option::<usize>::None);
 
-    // "healthy for 5d" / "missed run for 2h" — how long the cron has held its current state.
-    let state_text = cron
-        .since(health)
-        .map(|since| {
-            let held = compact_duration(now.signed_duration_since(since));
-            if health.passing() {
-                format!("healthy for {held}")
-            } else {
-                format!("{} for {held}", health.label().to_lowercase())
-            }
-        })
-        .unwrap_or_else(|| health.label().to_string());
+    // "healthy for 5d" / "missed run for 2h" — how long the cron has held its current state — or
+    // "impacted by lb" while it is blocked.
+    let state_text = if blocked {
+        format!("impacted by {}", cron.blocked_by.join(", "))
+    } else {
+        cron.since(health)
+            .map(|since| {
+                let held = compact_duration(now.signed_duration_since(since));
+                if health.passing() {
+                    format!("healthy for {held}")
+                } else {
+                    format!("{} for {held}", health.label().to_lowercase())
+                }
+            })
+            .unwrap_or_else(|| health.label().to_string())
+    };
 
     let schedule = match &cron.schedule {
         CronSchedule::Every(interval) => chrono::Duration::from_std(*interval)
@@ -238,6 +245,8 @@ mod tests {
             failing_since: Some(now - chrono::Duration::hours(3)),
             failing_until: Some(now - chrono::Duration::minutes(1)),
             covered_since: Some(now - chrono::Duration::days(1)),
+            degraded_since: None,
+            degraded_until: None,
         };
         let html = render(cron).await;
         assert!(html.contains("cron__last-checkin"), "expected the since-last-success time, got: {html}");
@@ -13,6 +13,7 @@ pub mod probe;
 pub mod probe_history;
 pub mod service_list;
 pub mod skeleton;
+pub mod slo_list;
 pub mod status_dot;
 
 pub use banner::{Banner, BannerKind};
@@ -27,4 +28,5 @@ pub use probe::Probe;
 pub use probe_history::ProbeHistory;
 pub use service_list::ServiceList;
 pub use skeleton::IncidentBlockSkeleton;
+pub use slo_list::SloList;
 pub use status_dot::StatusDot;
@@ -16,12 +16,23 @@ pub fn probe(props: &ProbeProps) -> Html {
 
     // Key the status off the currently observed (debounced) state so a recovery is reflected once it
     // settles, using the recent average only to grade how severe an ongoing failure is.
-    let probe_class = probe_class(props.probe.passing(), recent_availability);
+    // A probe failing behind a failing dependency is greyed out, since the upstream outage is what
+    // needs attention.
+    let probe_class = if props.probe.blocked() {
+        "unknown"
+    } else if props.probe.degraded() {
+        "warn"
+    } else {
+        probe_class(props.probe.passing(), recent_availability)
+    };
 
-    // How long the probe has held its current state, e.g. "healthy for 5d" or "unhealthy for 17m".
+    // How long the probe has held its current state, e.g. "healthy for 5d", "degraded for 2h" or
+    // "unhealthy for 17m".
     let streak_text = props.probe.since().map(|since| {
         let held_for = compact_duration(chrono::Utc::now().signed_duration_since(since));
-        if props.probe.passing() {
+        if props.probe.degraded() {
+            format!("degraded for {held_for}")
+        } else if props.probe.passing() {
             format!("healthy for {held_for}")
         } else {
             format!("unhealthy for {held_for}")
@@ -49,7 +60,9 @@ pub fn probe(props: &ProbeProps) -> Html {
                     }
                 </div>
                 
-                if let Some(streak_text) = streak_text {
+                if props.probe.blocked() {
+                    <div class="probe__blocked">{format!("impacted by {}", props.probe.blocked_by.join(", "))}</div>
+                } else if let Some(streak_text) = streak_text {
                     <div class="probe__streak">{streak_text}</div>
                 }
                 <div class="probe__availability">{availability(props.probe.availability())}</div>
@@ -80,6 +93,10 @@ mod tests {
     }
 
     async fn render(streak: Streak) -> String {
+        render_blocked(streak, Vec::new()).await
+    }
+
+    async fn render_blocked(streak: Streak, blocked_by: Vec<String>) -> String {
         let probe = grey_api::Probe {
             name: "probe".into(),
             tags: Default::default(),
@@ -88,6 +105,8 @@ mod tests {
             observations: Default::default(),
             streak,
             debounce: None,
+            interval: None,
+            blocked_by,
             retired: false,
         };
         yew::ServerRenderer::<Harness>::with_props(move || HarnessProps { probe })
@@ -118,6 +137,33 @@ mod tests {
         assert!(html.contains("unhealthy for 17m"), "expected the unhealthy streak text, got: {html}");
     }
 
+    #[tokio::test]
+    async fn test_shows_degraded_streak_duration() {
+        let mut streak = Streak::default();
+        let now = chrono::Utc::now();
+        for minutes_ago in (2..=17).rev().step_by(3) {
+            let at = now - chrono::Duration::minutes(minutes_ago);
+            streak.observe(true, at, Streak::default_recovery_window());
+            streak.observe_degraded(at, Streak::default_recovery_window());
+        }
+
+        let html = render(streak).await;
+        assert!(html.contains("degraded for 17m"), "expected the degraded streak text, got: {html}");
+    }
+
+    #[tokio::test]
+    async fn test_shows_the_failing_dependency() {
+        let mut streak = Streak::default();
+        let now = chrono::Utc::now();
+        for minutes_ago in (2..=17).rev().step_by(3) {
+            streak.observe(false, now - chrono::Duration::minutes(minutes_ago), Streak::default_recovery_window());
+        }
+
+        let html = render_blocked(streak, vec!["lb".into()]).await;
+        assert!(html.contains("impacted by lb"), "expected the blocking dependency, got: {html}");
+        assert!(!html.contains("unhealthy for"), "expected no streak text, got: {html}");
+    }
+
     #[tokio::test]
     async fn test_omits_streak_text_for_legacy_records() {
         // Records from older agents carry no streak observations at all.
@@ -113,7 +113,15 @@ pub fn probe_history(props: &ProbeHistoryProps) -> Html {
                 let is_current = index + 1 == props.samples.len();
                 let current_streak = (is_current && !props.streak.is_empty()).then_some(&props.streak);
                 let current_passing = current_streak.map(|s| s.healthy_at(Utc::now(), props.window));
-                let sample_class = sample_class(current_passing, sample.max_availability());
+                // A segment which passed while failing a warning check is shown as degraded.
+                let degraded = match current_streak {
+                    Some(streak) => streak.degraded_for(Utc::now(), props.window),
+                    None => sample.pass && sample.degraded,
+                };
+                let sample_class = match sample_class(current_passing, sample.max_availability()) {
+                    "ok" if degraded => "warn",
+                    class => class,
+                };
 
                 // Serialize the entire ProbeResult to JSON
                 let probe_result_json = serde_json::to_string(sample).unwrap_or_default();
@@ -150,6 +158,11 @@ pub fn probe_history(props: &ProbeHistoryProps) -> Html {
     }
 }
 
+/// Formats a latency to the millisecond, as the average latency is.
+fn rounded_latency(latency: std::time::Duration) -> String {
+    humantime::format_duration(std::time::Duration::from_millis(latency.as_millis() as u64)).to_string()
+}
+
 fn render_tooltip(probe_result: &ProbeHistoryBucket, streak: Option<&grey_api::Streak>, window: chrono::Duration, include_observers: bool) -> Html {
     let (status_text, status_class) = match streak {
         Some(streak) => {
@@ -159,9 +172,14 @@ fn render_tooltip(probe_result: &ProbeHistoryBucket, streak: Option<&grey_api::S
                 .since_at(now, window)
                 .map(|t| format!(" for {}", compact_duration(now - t)))
                 .unwrap_or_default();
-            let label = if healthy { "Passing" } else { "Failing" };
-            (format!("{label}{since}"), pass_class(healthy))
+            if streak.degraded_for(now, window) {
+                (format!("Degraded{since}"), "warn")
+            } else {
+                let label = if healthy { "Passing" } else { "Failing" };
+                (format!("{label}{since}"), pass_class(healthy))
+            }
         }
+        _ if probe_result.pass && probe_result.degraded => ("Degraded".to_string(), "warn"),
         _ => (
             (if probe_result.max_availability() == 100.0 { "Passed" } else { "Failed" }).to_string(),
             pass_class(probe_result.pass),
@@ -182,6 +200,18 @@ fn render_tooltip(probe_result: &ProbeHistoryBucket, streak: Option<&grey_api::S
         humantime::format_duration(overall_stats.average_latency())
     );
 
+    // Percentiles are only known for buckets recorded by agents which keep latency distributions.
+    let percentiles = overall_stats.latency_percentiles();
+    let percentiles_text = match (percentiles.p50, percentiles.p90, percentiles.p99) {
+        (Some(p50), Some(p90), Some(p99)) => Some(format!(
+            "p50 {} · p90 {} · p99 {}",
+            rounded_latency(p50),
+            rounded_latency(p90),
+            rounded_latency(p99)
+        )),
+        _ => None,
+    };
+
     let mut relevant_observations = probe_result.observations.iter().collect::<Vec<_>>();
     relevant_observations.sort_by(|a, b| a.1.success_rate().partial_cmp(&b.1.success_rate()).unwrap_or(std::cmp::Ordering::Equal)); // (|(_, obs)| obs.success_rate());
     relevant_observations.truncate(probe_result.validations.len().max(3));
@@ -204,6 +234,12 @@ fn render_tooltip(probe_result: &ProbeHistoryBucket, streak: Option<&grey_api::S
                     <span class="tooltip__label">{"Latency:"}</span>
                     <span>{duration_text}</span>
                 </div>
+                if let Some(percentiles_text) = percentiles_text {
+                    <div class="tooltip__row">
+                        <span class="tooltip__label">{"Percentiles:"}</span>
+                        <span>{percentiles_text}</span>
+                    </div>
+                }
                 <div class="tooltip__row">
                     <span class="tooltip__label">{"Availability:"}</span>
                     <span>{format!("{} ± {:.1}%", availability(overall_stats.success_rate()), overall_stats.success_rate_error_margin())}</span>
@@ -253,6 +289,10 @@ fn render_tooltip(probe_result: &ProbeHistoryBucket, streak: Option<&grey_api::S
                                         <div class="tooltip__section-entry-header">
                                             <StatusDot class={validation_class} />
                                             <span class="tooltip__section-entry-name">{name}</span>
+                                            // Pass rates are only known for buckets recorded by agents which count each check.
+                                            if let Some(counts) = overall_stats.check_counts(name) {
+                                                <span class="tooltip__section-entry-message">{format!("passed {}", availability(counts.pass_rate()))}</span>
+                                            }
                                         </div>
                                         if let Some(ref msg) = validation.message {
                                             <div class="tooltip__section-entry-details">
@@ -291,6 +331,7 @@ mod tests {
         let bucket = ProbeHistoryBucket {
             start_time: chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
             pass: true,
+            degraded: false,
             message: String::new(),
             validations: Default::default(),
             observations: Default::default(),
@@ -311,6 +352,41 @@ mod tests {
         assert!(html.contains("Passing"), "expected the streak status, got: {html}");
     }
 
+    #[tokio::test]
+    async fn test_tooltip_shows_check_pass_rates() {
+        let mut observation = grey_api::Observation::default();
+        observation.checks.insert(grey_api::check_id("http.status == 200"), grey_api::CheckCounts { passed: 9, failed: 1 });
+
+        let bucket = ProbeHistoryBucket {
+            start_time: chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+            pass: false,
+            degraded: false,
+            message: String::new(),
+            validations: [("http.status == 200".to_string(), grey_api::ValidationResult::fail("http.status = 500"))].into_iter().collect(),
+            observations: [("observer1".to_string(), observation)].into_iter().collect(),
+        };
+        let html = yew::ServerRenderer::<Harness>::with_props(move || HarnessProps { bucket, streak: Default::default() })
+            .render()
+            .await;
+        assert!(html.contains("passed 90%"), "expected the check's pass rate, got: {html}");
+    }
+
+    #[tokio::test]
+    async fn test_tooltip_shows_degraded_buckets() {
+        let bucket = ProbeHistoryBucket {
+            start_time: chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+            pass: true,
+            degraded: true,
+            message: "tls.expires_in = 13d".into(),
+            validations: Default::default(),
+            observations: Default::default(),
+        };
+        let html = yew::ServerRenderer::<Harness>::with_props(move || HarnessProps { bucket, streak: Default::default() })
+            .render()
+            .await;
+        assert!(html.contains("Degraded"), "expected the degraded status, got: {html}");
+    }
+
     #[tokio::test]
     async fn test_tooltip_omits_streak_row_for_legacy_records() {
         let html = render(grey_api::Streak::default()).await;
@@ -137,6 +137,8 @@ mod tests {
             observations: Default::default(),
             streak: Default::default(),
             debounce: None,
+            interval: None,
+            blocked_by: Vec::new(),
             retired: false,
         }
     }
@@ -0,0 +1,140 @@
+use super::StatusDot;
+use crate::contexts::use_store;
+use crate::formatters::{availability, compact_duration};
+use crate::styles::slo_class;
+use yew::prelude::*;
+
+/// The status page's service level objectives: each SLO's attainment against its objective, the
+/// share of its error budget left, and any burn rate alert which is firing. Renders nothing when no
+/// SLOs are configured.
+#[function_component(SloList)]
+pub fn slo_list() -> Html {
+    let store = use_store();
+    if store.slos().is_empty() {
+        return html! {};
+    }
+
+    html! {
+        <div class="section slos">
+            <div class="service__title">
+                <h2 class="service__name">{"Service Level Objectives"}</h2>
+            </div>
+            {for store.slos().iter().map(|slo| {
+                let class = slo_class(slo);
+                let window = chrono::Duration::from_std(slo.window)
+                    .map(compact_duration)
+                    .unwrap_or_default();
+
+                // The fastest burn among the alerts which are firing, e.g. "burning 8.2x budget over 1h".
+                let burning = slo
+                    .burn_rates
+                    .iter()
+                    .filter(|burn| burn.exceeded())
+                    .max_by(|a, b| a.short_rate.total_cmp(&b.short_rate))
+                    .map(|burn| {
+                        let over = chrono::Duration::from_std(burn.short_window)
+                            .map(compact_duration)
+                            .unwrap_or_default();
+                        format!("burning {:.1}x budget over {over}", burn.short_rate)
+                    });
+
+                let remaining = slo.budget_remaining();
+
+                html! {
+                    <div class="slo">
+                        <div class="slo__title">
+                            <div class="slo__name-section">
+                                <StatusDot class={class} />
+                                <h3 class="slo__name">{&slo.name}</h3>
+                                <span class="slo__objective">
+                                    {format!("{} over {window}", availability(slo.objective))}
+                                </span>
+                            </div>
+                            if let Some(burning) = burning {
+                                <span class={classes!("slo__burn", class)}>{burning}</span>
+                            }
+                            <span class="slo__attainment">{availability(slo.attainment())}</span>
+                        </div>
+                        if let Some(description) = &slo.description {
+                            <p class="slo__description">{description}</p>
+                        }
+                        <div class="slo__budget" title={format!("{remaining:.1}% of the error budget remaining")}>
+                            <div
+                                class={classes!("slo__budget-fill", class)}
+                                style={format!("width:{:.1}%", remaining.clamp(0.0, 100.0))}
+                            ></div>
+                        </div>
+                        <div class="slo__budget-label">
+                            {format!("{:.0}% of error budget remaining", remaining.max(0.0))}
+                        </div>
+                    </div>
+                }
+            })}
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contexts::StoreProvider;
+    use std::time::Duration;
+
+    #[derive(Properties, PartialEq)]
+    struct HarnessProps {
+        slos: Vec<grey_api::Slo>,
+    }
+
+    #[function_component(Harness)]
+    fn harness(props: &HarnessProps) -> Html {
+        html! {
+            <StoreProvider slos={props.slos.clone()}>
+                <SloList />
+            </StoreProvider>
+        }
+    }
+
+    async fn render(slos: Vec<grey_api::Slo>) -> String {
+        yew::ServerRenderer::<Harness>::with_props(move || HarnessProps { slos })
+            .render()
+            .await
+    }
+
+    #[tokio::test]
+    async fn renders_budget_and_burn_rate() {
+        let html = render(vec![grey_api::Slo {
+            name: "checkout".into(),
+            description: Some("Customers can pay for their orders".into()),
+            tags: Default::default(),
+            objective: 99.0,
+            window: Duration::from_secs(30 * 86400),
+            targets: vec!["checkout.api".into()],
+            observation: grey_api::Observation {
+                total_samples: 1000,
+                successful_samples: 995,
+                total_retries: 0,
+                total_latency: Duration::ZERO,
+                latency_sketch: Default::default(),
+                checks: Default::default(),
+            },
+            burn_rates: vec![grey_api::BurnRate {
+                long_window: Duration::from_secs(6 * 3600),
+                short_window: Duration::from_secs(3600),
+                threshold: 6.0,
+                long_rate: 7.0,
+                short_rate: 8.0,
+            }],
+        }])
+        .await;
+
+        assert!(html.contains("checkout"), "the SLO should render: {html}");
+        assert!(html.contains("50% of error budget remaining"), "{html}");
+        assert!(html.contains("burning 8.0x budget"), "{html}");
+    }
+
+    #[tokio::test]
+    async fn renders_nothing_without_slos() {
+        let html = render(vec![]).await;
+        assert!(!html.contains("Service Level Objectives"), "{html}");
+    }
+}
@@ -13,7 +13,7 @@ use std::rc::Rc;
 
 use grey_api::{
     AdminUser, ApiError, CreateIncident, CreateUpdate, Cron, Identifier, IncidentUpdateId,
-    IncidentView, Peer, Probe, PutIncident, PutUpdate, UiConfig,
+    IncidentView, Peer, Probe, PutIncident, PutUpdate, Slo, UiConfig,
 };
 use yew::prelude::*;
 
@@ -48,6 +48,7 @@ pub struct StoreState {
     pub incidents: Vec<IncidentView>,
     pub probes: Vec<Probe>,
     pub crons: Vec<Cron>,
+    pub slos: Vec<Slo>,
     /// The most recent background-fetch failure, surfaced to the user as a dismissible banner.
     pub error: Option<ApiError>,
 }
@@ -57,6 +58,7 @@ pub struct StoreState {
 pub enum Action {
     SetProbes(Vec<Probe>),
     SetCrons(Vec<Cron>),
+    SetSlos(Vec<Slo>),
     SetPeers(Vec<Peer>),
     SetIncidents(Vec<IncidentView>),
     /// Insert or replace a single incident (after an admin create or edit), without waiting for the
@@ -85,6 +87,7 @@ impl Reducible for StoreState {
         match action {
             Action::SetProbes(probes) => next.probes = probes,
             Action::SetCrons(crons) => next.crons = crons,
+            Action::SetSlos(slos) => next.slos = slos,
             Action::SetPeers(peers) => next.peers = peers,
             Action::SetIncidents(mut incidents) => {
                 sort_incidents(&mut incidents);
@@ -139,6 +142,10 @@ impl Store {
         &self.state.crons
     }
 
+    pub fn slos(&self) -> &[Slo] {
+        &self.state.slos
+    }
+
     pub fn peers(&self) -> &[Peer] {
         &self.state.peers
     }
@@ -258,6 +265,8 @@ pub struct StoreProviderProps {
     #[prop_or_default]
     pub crons: Vec<Cron>,
     #[prop_or_default]
+    pub slos: Vec<Slo>,
+    #[prop_or_default]
     pub peers: Vec<Peer>,
     #[prop_or_default]
     pub incidents: Vec<IncidentView>,
@@ -287,6 +296,7 @@ pub fn store_provider(props: &StoreProviderProps) -> Html {
         let config = props.config.clone();
         let probes = props.probes.clone();
         let crons = props.crons.clone();
+        let slos = props.slos.clone();
         let peers = props.peers.clone();
         let incidents = props.incidents.clone();
         let user = props.user.clone();
@@ -301,6 +311,7 @@ pub fn store_provider(props: &StoreProviderProps) -> Html {
                 incidents,
                 probes,
                 crons,
+                slos,
                 error: None,
             }
         }
@@ -354,7 +365,7 @@ pub fn store_provider(props: &StoreProviderProps) -> Html {
         let reload = props.config.reload_interval;
         let focus = use_focus_tracker();
 
-        // Probes, crons and incidents: fetch immediately when the page was rendered without their
+        // Probes, crons, SLOs and incidents: fetch immediately when the page was rendered without their
         // data (a minimal/un-hydrated render), otherwise the first fetch lands after one interval.
         {
             let state = state.clone();
@@ -367,6 +378,7 @@ pub fn store_provider(props: &StoreProviderProps) -> Html {
                         if !seeded {
                             state.dispatch(load_probes(&client).await);
                             state.dispatch(load_crons(&client).await);
+                            state.dispatch(load_slos(&client).await);
                             state.dispatch(load_incidents(&client).await);
                         }
                         loop {
@@ -376,6 +388,7 @@ pub fn store_provider(props: &StoreProviderProps) -> Html {
                             focus.active().await;
                             state.dispatch(load_probes(&client).await);
                             state.dispatch(load_crons(&client).await);
+                            state.dispatch(load_slos(&client).await);
                             state.dispatch(load_incidents(&client).await);
                         }
                     });
@@ -422,6 +435,7 @@ pub fn store_provider(props: &StoreProviderProps) -> Html {
                     wasm_bindgen_futures::spawn_local(async move {
                         state.dispatch(load_probes(&client).await);
                         state.dispatch(load_crons(&client).await);
+                        state.dispatch(load_slos(&client).await);
                     });
                 }
                 || ()
@@ -526,6 +540,17 @@ async fn load_crons(client: &ApiClient) -> Action {
     }
 }
 
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+async fn load_slos(client: &ApiClient) -> Action {
+    match client.slos().await {
+        Ok(slos) => Action::SetSlos(slos),
+        Err(err) => {
+            gloo::console::error!(format!("Failed to fetch SLOs: {err}"));
+            Action::SetError(err)
+        }
+    }
+}
+
 #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
 async fn load_incidents(client: &ApiClient) -> Action {
     match client.incidents().await {
@@ -274,6 +274,8 @@ fn probe(
         observations,
         streak: streak(now, shape),
         debounce: None,
+        interval: None,
+        blocked_by: Vec::new(),
         retired: false,
     }
 }
@@ -296,18 +298,17 @@ fn history(now: DateTime<Utc>, shape: Shape, seed: u64) -> Vec<ProbeHistoryBucke
                 .map(|(observer, base_latency)| {
                     let total_samples = 60;
                     let failed = (total_samples as f64 * failure_rate).round() as u64;
-                    let jitter = rng.below(30);
-                    (
-                        (*observer).to_string(),
-                        Observation {
-                            total_samples,
-                            successful_samples: total_samples - failed.min(total_samples),
-                            total_retries: failed / 2,
-                            total_latency: Duration::from_millis(
-                                (base_latency + jitter) * total_samples,
-                            ),
-                        },
-                    )
+                    let mut observation = Observation::default();
+                    for sample in 0..total_samples {
+                        // An occasional slow sample gives the tail percentiles something to show.
+                        let tail = if rng.below(20) == 0 { base_latency * 3 } else { 0 };
+                        observation.add_sample(
+                            sample >= failed,
+                            u64::from(sample < failed / 2),
+                            Duration::from_millis(base_latency + rng.below(30) + tail),
+                        );
+                    }
+                    ((*observer).to_string(), observation)
                 })
                 .collect();
 
@@ -315,6 +316,7 @@ fn history(now: DateTime<Utc>, shape: Shape, seed: u64) -> Vec<ProbeHistoryBucke
             ProbeHistoryBucket {
                 start_time,
                 pass,
+                degraded: false,
                 message: if pass {
                     String::new()
                 } else {
@@ -359,16 +361,22 @@ fn streak(now: DateTime<Utc>, shape: Shape) -> Streak {
             failing_since: Some(now - Delta::hours(5)),
             failing_until: Some(now - Delta::hours(5) + Delta::seconds(20)),
             covered_since: Some(now - Delta::days(23)),
+            degraded_since: None,
+            degraded_until: None,
         },
         Shape::Recovered => Streak {
             failing_since: Some(now - Delta::hours(21)),
             failing_until: Some(now - Delta::hours(16)),
             covered_since: Some(now - Delta::days(23)),
+            degraded_since: None,
+            degraded_until: None,
         },
         Shape::Failing => Streak {
             failing_since: Some(now - Delta::minutes(94)),
             failing_until: Some(now - Delta::seconds(20)),
             covered_since: Some(now - Delta::days(23)),
+            degraded_since: None,
+            degraded_until: None,
         },
     }
 }
@@ -460,6 +468,8 @@ fn missed_cron(now: DateTime<Utc>) -> Cron {
         failing_since: Some(now - Delta::hours(2)),
         failing_until: Some(now - Delta::minutes(3)),
         covered_since: Some(now - Delta::days(30)),
+        degraded_since: None,
+        degraded_until: None,
     };
     cron
 }
@@ -495,6 +505,8 @@ fn failed_cron(now: DateTime<Utc>) -> Cron {
         failing_since: Some(now - Delta::hours(5)),
         failing_until: Some(now - Delta::minutes(1)),
         covered_since: Some(now - Delta::days(56)),
+        degraded_since: None,
+        degraded_until: None,
     };
     cron
 }
@@ -9,8 +9,10 @@ mod cluster;
 mod cron;
 mod incident;
 mod probe;
+mod slo;
 
 pub use cluster::*;
 pub use cron::*;
 pub use incident::*;
 pub use probe::*;
+pub use slo::*;
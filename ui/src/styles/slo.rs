@@ -0,0 +1,11 @@
+/// The colour class for an SLO: `error` (red) once its error budget is spent, `warn` (orange) while
+/// a burn rate alert is firing, and `ok` (green) otherwise.
+pub fn slo_class(slo: &grey_api::Slo) -> &'static str {
+    if slo.exhausted() {
+        "error"
+    } else if slo.burning() {
+        "warn"
+    } else {
+        "ok"
+    }
+}
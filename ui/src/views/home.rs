@@ -3,10 +3,10 @@ use grey_api::Impact;
 use yew::prelude::*;
 
 use crate::components::incidents::worst_impact;
-use crate::components::{Banner, BannerKind, IncidentsSection, ServiceList};
+use crate::components::{Banner, BannerKind, IncidentsSection, ServiceList, SloList};
 use crate::contexts::use_store;
 
-/// The status page: a top-line banner, the probe list, and recent/active incidents. The top-line
+/// The status page: a top-line banner, the probe list, any SLOs, and recent/active incidents. The top-line
 /// status reflects the worst active incident when there is one, otherwise it is derived from probe
 /// health.
 #[function_component(HomeView)]
@@ -47,6 +47,7 @@ pub fn home_view() -> Html {
             <div class="content">
                 <Banner kind={banner_kind} text={status_text.to_string()} />
                 <ServiceList />
+                <SloList />
             </div>
 
             <IncidentsSection incidents={recent_incidents} />